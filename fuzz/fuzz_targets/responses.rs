@@ -0,0 +1,34 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use std::str;
+use swish_api::amount::Amount;
+use swish_api::fuzzing;
+
+/// The statuses Swish is known to respond with, plus a few it isn't.
+const STATUSES: [u16; 14] = [
+    200, 201, 204, 400, 401, 403, 404, 409, 415, 422, 429, 500, 503, 999,
+];
+
+fuzz_target!(|data: &[u8]| {
+    let (header, body) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let status = STATUSES[usize::from(*header) % STATUSES.len()];
+    let strict = header & 0x80 != 0;
+
+    let errors = vec![
+        fuzzing::fetch_payment(status, body, strict).err(),
+        fuzzing::fetch_refund(status, body, strict).err(),
+    ];
+    for error in errors.iter().flatten() {
+        let _ = error.to_string();
+        let _ = error.category();
+        let _ = error.localized_message();
+        let _ = error.response_body();
+    }
+
+    if let Ok(amount) = str::from_utf8(body) {
+        let _ = amount.parse::<Amount>();
+    }
+});
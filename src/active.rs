@@ -0,0 +1,167 @@
+//! # The active module
+//!
+//! Contains the recovery from Swish refusing a payment request because
+//! the payer already has an active one (RP06), e.g. when the payer
+//! reloads the checkout before answering the first request.
+//!
+use alias::PhoneAlias;
+use client::{CreatedPayment, Payment, PaymentParams, Status, SwishBoxFuture, SwishClient};
+use error::{ErrorCode, SwishClientError};
+use event::PaymentEvent;
+use futures::{future, stream, Future};
+use ids::PaymentId;
+use polling::PollPolicy;
+use store::{StoredPayment, SwishStore};
+
+/// What to do with the active payment request of a payer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivePaymentAction {
+    /// Cancel it, so that a new one can be created.
+    Cancel,
+    /// Wait for the payer to answer it, polling it with the policy.
+    Await(PollPolicy),
+}
+
+/// Whether Swish refused to create a payment request because
+/// the payer already has an active one (RP06).
+///
+/// # Arguments
+///
+/// * `error` - The error of creating the payment.
+pub fn is_active_payment_conflict(error: &SwishClientError) -> bool {
+    error.swish_error_codes().contains(&&ErrorCode::RP06)
+}
+
+impl SwishClient {
+    /// Finds the payment request of a payer that the payer may still answer.
+    ///
+    /// The newest payment of the payer in the store is fetched from Swish,
+    /// and is only returned if it's still created. Otherwise the store is
+    /// updated with what happened to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `payer_alias` - The phone number of the payer.
+    /// * `store` - The [`SwishStore`](../store/trait.SwishStore.html) the payments are saved in.
+    ///
+    /// # Returns
+    /// A Future with the active [`Payment`](../client/struct.Payment.html), if there is one.
+    pub fn find_active_payment<'a, S: SwishStore + ?Sized>(
+        &'a self,
+        payer_alias: &PhoneAlias,
+        store: &'a S,
+    ) -> SwishBoxFuture<'a, Option<Payment>> {
+        let stored = match store.active_payment_for(payer_alias, self.clock().now()) {
+            Ok(Some(stored)) => stored,
+            Ok(None) => return Box::new(future::ok(None)),
+            Err(error) => return Box::new(future::err(SwishClientError::Store(error))),
+        };
+        Box::new(self.get_payment(&stored.id).map(move |payment| {
+            if payment
+                .status
+                .as_ref()
+                .is_none_or(|status| *status == Status::Created)
+            {
+                Some(payment)
+            } else {
+                update_store(store, &PaymentEvent::from(&payment));
+                None
+            }
+        }))
+    }
+
+    /// Cancels or awaits the payment request of a payer that the payer may still answer.
+    ///
+    /// # Arguments
+    ///
+    /// * `payer_alias` - The phone number of the payer.
+    /// * `store` - The [`SwishStore`](../store/trait.SwishStore.html) the payments are saved in.
+    /// * `action` - The [`ActivePaymentAction`](enum.ActivePaymentAction.html) to take.
+    ///
+    /// # Returns
+    /// A Future with the final [`PaymentEvent`](../event/enum.PaymentEvent.html) of the
+    /// payment, or `None` if the payer had no active payment request.
+    pub fn resolve_active_payment<'a, S: SwishStore + ?Sized>(
+        &'a self,
+        payer_alias: &PhoneAlias,
+        store: &'a S,
+        action: ActivePaymentAction,
+    ) -> SwishBoxFuture<'a, Option<PaymentEvent>> {
+        Box::new(self.find_active_payment(payer_alias, store).and_then(
+            move |payment| -> SwishBoxFuture<'a, Option<PaymentEvent>> {
+                let payment_id = match payment {
+                    Some(payment) => PaymentId::from(payment.id),
+                    None => return Box::new(future::ok(None)),
+                };
+                let event: SwishBoxFuture<'a, PaymentEvent> = match action {
+                    ActivePaymentAction::Cancel => Box::new(
+                        self.cancel_payment(&payment_id)
+                            .map(|payment| PaymentEvent::from(&payment)),
+                    ),
+                    ActivePaymentAction::Await(policy) => {
+                        self.await_payment(payment_id, stream::empty::<_, ()>(), policy)
+                    }
+                };
+                Box::new(event.map(move |event| {
+                    update_store(store, &event);
+                    Some(event)
+                }))
+            },
+        ))
+    }
+
+    /// Creates a payment request and saves it in the store. If the payer already
+    /// has an active payment request, it's resolved with the action and the
+    /// payment request is created again.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - [`PaymentParams`](../client/struct.PaymentParams.html) with a payer alias.
+    /// * `store` - The [`SwishStore`](../store/trait.SwishStore.html) the payments are saved in.
+    /// * `action` - The [`ActivePaymentAction`](enum.ActivePaymentAction.html) to take.
+    ///
+    /// # Returns
+    /// A Future with the [`CreatedPayment`](../client/struct.CreatedPayment.html).
+    /// The RP06 error is returned if the active payment request isn't in the store.
+    pub fn create_payment_resolving<'a, S: SwishStore + ?Sized>(
+        &'a self,
+        params: PaymentParams<'a>,
+        store: &'a S,
+        action: ActivePaymentAction,
+    ) -> SwishBoxFuture<'a, CreatedPayment> {
+        let retry_params = params.clone();
+        let saved_params = params.clone();
+        let created = self.create_payment(params).or_else(
+            move |error| -> SwishBoxFuture<'a, CreatedPayment> {
+                let payer_alias = match retry_params.payer_alias {
+                    Some(ref payer_alias) if is_active_payment_conflict(&error) => {
+                        payer_alias.clone()
+                    }
+                    _ => return Box::new(future::err(error)),
+                };
+                Box::new(
+                    self.resolve_active_payment(&payer_alias, store, action)
+                        .and_then(move |event| -> SwishBoxFuture<'a, CreatedPayment> {
+                            match event {
+                                Some(_) => self.create_payment(retry_params),
+                                None => Box::new(future::err(error)),
+                            }
+                        }),
+                )
+            },
+        );
+        Box::new(created.and_then(move |created| {
+            store
+                .save_payment(StoredPayment::new(&created, &saved_params))
+                .map(|()| created)
+                .map_err(SwishClientError::Store)
+        }))
+    }
+}
+
+/// Updates the store with an event, which fails if e.g. a callback got there first.
+fn update_store<S: SwishStore + ?Sized>(store: &S, event: &PaymentEvent) {
+    if let Err(_error) = store.update_status(event) {
+        log_debug!("Couldn't store the event of {}: {}", event.id(), _error);
+    }
+}
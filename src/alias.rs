@@ -0,0 +1,172 @@
+//! # The alias module
+//!
+//! Contains the aliases, i.e. the Swish numbers, of the parties of a payment.
+//!
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use validation::ValidationError;
+
+/// The mobile number a payer is registered with in Swish,
+/// normalized to the country code followed by the number without its leading zero.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::alias::PhoneAlias;
+///
+/// let alias = PhoneAlias::new("070-123 45 67").unwrap();
+///
+/// assert_eq!(alias.as_str(), "46701234567");
+/// assert!(PhoneAlias::new("070-123 45 6x").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PhoneAlias(String);
+
+impl PhoneAlias {
+    /// [`PhoneAlias`]: struct.PhoneAlias.html
+    ///
+    /// Creates a new [`PhoneAlias`], stripping spaces and dashes and
+    /// converting Swedish numbers such as `0701234567` to `46701234567`.
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - A mobile number, either with its country code or a Swedish one.
+    ///
+    /// # Returns
+    /// The [`PhoneAlias`], or a `ValidationError` if it isn't a valid mobile number.
+    pub fn new(number: &str) -> Result<Self, ValidationError> {
+        let digits: String = number
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect();
+        let digits = digits.trim_start_matches('+');
+
+        let normalized = if let Some(international) = digits.strip_prefix("00") {
+            international.to_owned()
+        } else if let Some(swedish) = digits.strip_prefix('0') {
+            format!("46{}", swedish)
+        } else {
+            digits.to_owned()
+        };
+
+        let valid = (8..=15).contains(&normalized.len())
+            && !normalized.starts_with('0')
+            && normalized.chars().all(|c| c.is_ascii_digit());
+        if !valid {
+            return Err(ValidationError::InvalidFormat {
+                field: "payerAlias",
+                expected: "a mobile number of 8 to 15 digits starting with the country code",
+            });
+        }
+        Ok(PhoneAlias(normalized))
+    }
+
+    /// Gets the normalized number as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for PhoneAlias {
+    type Err = ValidationError;
+
+    fn from_str(number: &str) -> Result<Self, Self::Err> {
+        PhoneAlias::new(number)
+    }
+}
+
+impl AsRef<str> for PhoneAlias {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for PhoneAlias {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for PhoneAlias {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// The Swish number of a merchant, which is 10 digits starting with `123`.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::alias::MerchantAlias;
+///
+/// assert!(MerchantAlias::new("1231181189").is_ok());
+/// assert!(MerchantAlias::new("0701234567").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MerchantAlias(String);
+
+impl MerchantAlias {
+    /// [`MerchantAlias`]: struct.MerchantAlias.html
+    ///
+    /// Creates a new [`MerchantAlias`].
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - The Swish number of the merchant, e.g. `1231181189`.
+    ///
+    /// # Returns
+    /// The [`MerchantAlias`], or a `ValidationError` if it isn't a valid Swish number.
+    pub fn new(number: &str) -> Result<Self, ValidationError> {
+        let valid = number.len() == 10
+            && number.starts_with("123")
+            && number.chars().all(|c| c.is_ascii_digit());
+        if !valid {
+            return Err(ValidationError::InvalidFormat {
+                field: "payeeAlias",
+                expected: "a Swish number of 10 digits starting with 123",
+            });
+        }
+        Ok(MerchantAlias(number.to_owned()))
+    }
+
+    /// Gets the Swish number as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for MerchantAlias {
+    type Err = ValidationError;
+
+    fn from_str(number: &str) -> Result<Self, Self::Err> {
+        MerchantAlias::new(number)
+    }
+}
+
+impl AsRef<str> for MerchantAlias {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for MerchantAlias {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for MerchantAlias {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for MerchantAlias {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let number = String::deserialize(deserializer)?;
+        MerchantAlias::new(&number).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,188 @@
+//! # The amount module
+//!
+//! Contains the amount of a payment or a refund, which is kept in
+//! öre so that no precision is lost between the caller and Swish.
+//!
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::str;
+use validation::ValidationError;
+
+/// An amount of money, kept as a whole number of öre.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::amount::Amount;
+///
+/// let amount = Amount::from_ore(10050);
+///
+/// assert_eq!(amount, Amount::from_kronor(100) + Amount::from_ore(50));
+/// assert_eq!(amount.as_ore(), 10050);
+/// assert_eq!(amount.to_string(), "100.50");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(i64);
+
+impl Amount {
+    /// [`Amount`]: struct.Amount.html
+    ///
+    /// Creates a new [`Amount`] from a number of öre.
+    ///
+    /// # Arguments
+    ///
+    /// * `ore` - The amount in öre, e.g. `10050` for 100.50 SEK.
+    pub const fn from_ore(ore: i64) -> Self {
+        Amount(ore)
+    }
+
+    /// [`Amount`]: struct.Amount.html
+    ///
+    /// Creates a new [`Amount`] from a whole number of kronor.
+    ///
+    /// # Arguments
+    ///
+    /// * `kronor` - The amount in kronor, e.g. `100` for 100.00 SEK.
+    pub const fn from_kronor(kronor: i64) -> Self {
+        Amount(kronor * 100)
+    }
+
+    /// Gets the amount as a number of öre.
+    pub fn as_ore(&self) -> i64 {
+        self.0
+    }
+
+    /// Gets the amount in kronor, as sent to Swish.
+    pub fn as_f64(&self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+}
+
+impl From<i64> for Amount {
+    fn from(ore: i64) -> Self {
+        Amount::from_ore(ore)
+    }
+}
+
+impl From<Amount> for i64 {
+    fn from(amount: Amount) -> Self {
+        amount.as_ore()
+    }
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, other: Amount) -> Amount {
+        Amount(self.0 + other.0)
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, other: Amount) -> Amount {
+        Amount(self.0 - other.0)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let ore = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, ore / 100, ore % 100)
+    }
+}
+
+impl str::FromStr for Amount {
+    type Err = ValidationError;
+
+    /// Parses an amount of kronor exactly, e.g. `100`, `100.5` or `100,50`.
+    /// Decimals beyond the öre are only allowed when they're zeros.
+    fn from_str(amount: &str) -> Result<Self, Self::Err> {
+        let invalid = ValidationError::InvalidFormat {
+            field: "amount",
+            expected: "a number of kronor with at most two decimals",
+        };
+        let amount = amount.trim();
+        let (negative, amount) = match amount.strip_prefix('-') {
+            Some(amount) => (true, amount),
+            None => (false, amount),
+        };
+        let mut parts = amount.splitn(2, ['.', ',']);
+        let kronor = parts.next().unwrap_or("");
+        let decimals = parts.next().unwrap_or("");
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if kronor.is_empty() || !is_digits(kronor) || !is_digits(decimals) {
+            return Err(invalid);
+        }
+        if decimals.bytes().skip(2).any(|b| b != b'0') {
+            return Err(invalid);
+        }
+
+        let ore = decimals
+            .bytes()
+            .chain("00".bytes())
+            .take(2)
+            .fold(0, |ore, b| ore * 10 + i64::from(b - b'0'));
+        let ore = kronor
+            .parse::<i64>()
+            .ok()
+            .and_then(|kronor| kronor.checked_mul(100))
+            .and_then(|kronor| kronor.checked_add(ore))
+            .ok_or(invalid)?;
+        Ok(Amount(if negative { -ore } else { ore }))
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.as_f64())
+    }
+}
+
+/// Reads an amount of kronor sent either as a JSON number or as a string.
+struct AmountVisitor;
+
+impl<'de> Visitor<'de> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an amount of kronor as a number or a string")
+    }
+
+    fn visit_i64<E: de::Error>(self, kronor: i64) -> Result<Amount, E> {
+        kronor
+            .checked_mul(100)
+            .map(Amount)
+            .ok_or_else(|| E::custom(format!("invalid amount {}", kronor)))
+    }
+
+    fn visit_u64<E: de::Error>(self, kronor: u64) -> Result<Amount, E> {
+        i64::try_from(kronor)
+            .map_err(|_| E::custom(format!("invalid amount {}", kronor)))
+            .and_then(|kronor| self.visit_i64(kronor))
+    }
+
+    fn visit_f64<E: de::Error>(self, kronor: f64) -> Result<Amount, E> {
+        if !kronor.is_finite() {
+            return Err(E::custom(format!("invalid amount {}", kronor)));
+        }
+        Ok(Amount((kronor * 100.0).round() as i64))
+    }
+
+    fn visit_str<E: de::Error>(self, amount: &str) -> Result<Amount, E> {
+        amount
+            .parse()
+            .map_err(|_| E::custom(format!("invalid amount {:?}", amount)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
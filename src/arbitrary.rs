@@ -0,0 +1,393 @@
+//! # The arbitrary module
+//!
+//! Implements [proptest](https://docs.rs/proptest)'s `Arbitrary` for the params,
+//! responses and callbacks of the Swish API, so that property tests can generate
+//! them without writing their own strategies. It's only built with the `proptest` feature.
+//!
+//! The params only contain values Swish accepts, and the payments, refunds and
+//! callbacks are consistent with their status, e.g. only paid ones have a date paid
+//! and only failed ones have an error code.
+//!
+//! # Example
+//!
+//! ```
+//! #[macro_use]
+//! extern crate proptest;
+//! extern crate swish_api;
+//!
+//! use proptest::prelude::*;
+//! use swish_api::params::OwnedPaymentParams;
+//!
+//! proptest! {
+//!     fn test_params_are_valid(params in any::<OwnedPaymentParams>()) {
+//!         prop_assert!(params.as_params().validate().is_ok());
+//!     }
+//! }
+//! # fn main() { test_params_are_valid(); }
+//! ```
+//!
+use alias::{MerchantAlias, PhoneAlias};
+use amount::Amount;
+use callback::{CallbackUrl, PaymentCallback, RefundCallback};
+use client::{CreatedRefund, Currency, Payment, Refund, Status};
+use error::ErrorCode;
+use params::{OwnedPaymentParams, OwnedRefundParams};
+use proptest::prelude::*;
+use validation::{AMOUNT_MAX, AMOUNT_MIN};
+
+/// The codes Swish documents, which arbitrary error codes are picked from.
+const ERROR_CODES: [&str; 32] = [
+    "FF08",
+    "RP03",
+    "BE18",
+    "RP01",
+    "PA02",
+    "AM06",
+    "AM02",
+    "AM03",
+    "RP02",
+    "RP06",
+    "ACMT03",
+    "ACMT01",
+    "ACMT07",
+    "PA01",
+    "RF02",
+    "FF10",
+    "RP04",
+    "RP08",
+    "RP09",
+    "RF03",
+    "RF04",
+    "RF06",
+    "RF07",
+    "RF08",
+    "RF09",
+    "TM01",
+    "DS24",
+    "VR01",
+    "VR02",
+    "BANKIDCL",
+    "BANKIDONGOING",
+    "BANKIDUNKN",
+];
+
+/// Implements `Arbitrary` for a type, generating it with the given strategy.
+macro_rules! arbitrary {
+    ($type:ty, $strategy:expr) => {
+        impl Arbitrary for $type {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                $strategy.boxed()
+            }
+        }
+    };
+}
+
+arbitrary!(
+    Amount,
+    (AMOUNT_MIN.as_ore()..=AMOUNT_MAX.as_ore()).prop_map(Amount::from_ore)
+);
+arbitrary!(
+    PhoneAlias,
+    "46[0-9]{9}".prop_map(|number| PhoneAlias::new(&number).unwrap())
+);
+arbitrary!(
+    MerchantAlias,
+    "123[0-9]{7}".prop_map(|number| MerchantAlias::new(&number).unwrap())
+);
+arbitrary!(
+    CallbackUrl,
+    "https://[a-z]{1,20}\\.example\\.com/[a-z]{0,20}"
+        .prop_map(|url| CallbackUrl::new(&url).unwrap())
+);
+arbitrary!(Currency, Just(Currency::SEK));
+arbitrary!(
+    ErrorCode,
+    proptest::sample::select(&ERROR_CODES[..]).prop_map(ErrorCode::from_code)
+);
+arbitrary!(Status, prop_oneof![payment_status(), refund_status()]);
+arbitrary!(
+    CreatedRefund,
+    id().prop_map(|id| CreatedRefund {
+        location: format!(
+            "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/refunds/{}",
+            id
+        ),
+        id,
+    })
+);
+arbitrary!(
+    OwnedPaymentParams,
+    (
+        proptest::option::of(payee_payment_reference()),
+        any::<Option<PhoneAlias>>(),
+        any::<Option<MerchantAlias>>(),
+        any::<Amount>(),
+        proptest::option::of(message()),
+        any::<CallbackUrl>(),
+    )
+        .prop_map(
+            |(payee_payment_reference, payer_alias, payee_alias, amount, message, callback_url)| {
+                OwnedPaymentParams {
+                    payee_payment_reference,
+                    payer_alias,
+                    payee_alias,
+                    amount,
+                    message,
+                    callback_url: Some(callback_url),
+                }
+            }
+        )
+);
+arbitrary!(
+    OwnedRefundParams,
+    (
+        proptest::option::of(payee_payment_reference()),
+        id(),
+        any::<Option<MerchantAlias>>(),
+        any::<PhoneAlias>(),
+        any::<Amount>(),
+        proptest::option::of(message()),
+        any::<CallbackUrl>(),
+    )
+        .prop_map(
+            |(
+                payer_payment_reference,
+                original_payment_reference,
+                payer_alias,
+                payee_alias,
+                amount,
+                message,
+                callback_url,
+            )| {
+                OwnedRefundParams {
+                    payer_payment_reference,
+                    original_payment_reference,
+                    payment_reference: None,
+                    payer_alias,
+                    payee_alias: payee_alias.as_str().to_owned(),
+                    amount,
+                    message,
+                    callback_url: Some(callback_url),
+                }
+            }
+        )
+);
+arbitrary!(
+    Payment,
+    (outcome(payment_status()), transfer()).prop_map(
+        |((status, date_created, date_paid, error), transfer)| Payment {
+            id: transfer.id,
+            amount: transfer.amount,
+            payee_payment_reference: transfer.reference,
+            payment_reference: date_paid.as_ref().and(Some(transfer.payment_reference)),
+            callback_url: Some(transfer.callback_url.as_str().to_owned()),
+            payer_alias: Some(transfer.phone_alias.as_str().to_owned()),
+            payee_alias: Some(transfer.merchant_alias.as_str().to_owned()),
+            message: transfer.message,
+            status: Some(status),
+            date_created,
+            currency: Currency::SEK,
+            date_paid,
+            error_code: error.clone(),
+            error_message: error.map(|code| code.description().to_owned()),
+            extra: Default::default(),
+        }
+    )
+);
+arbitrary!(
+    Refund,
+    (outcome(refund_status()), transfer()).prop_map(
+        |((status, date_created, date_paid, error), transfer)| Refund {
+            id: transfer.id,
+            amount: transfer.amount,
+            payer_payment_reference: transfer.reference,
+            original_payment_reference: Some(transfer.payment_reference),
+            payment_reference: None,
+            callback_url: Some(transfer.callback_url.as_str().to_owned()),
+            payer_alias: Some(transfer.merchant_alias.as_str().to_owned()),
+            payee_alias: Some(transfer.phone_alias.as_str().to_owned()),
+            message: transfer.message,
+            status: Some(status),
+            date_created,
+            currency: Currency::SEK,
+            date_paid,
+            error_code: error.clone(),
+            error_message: error.map(|code| code.description().to_owned()),
+            additional_information: None,
+            extra: Default::default(),
+        }
+    )
+);
+arbitrary!(
+    PaymentCallback,
+    any::<Payment>().prop_map(|payment| PaymentCallback {
+        id: payment.id,
+        payee_payment_reference: payment.payee_payment_reference,
+        payment_reference: payment.payment_reference,
+        callback_url: payment.callback_url,
+        payer_alias: payment.payer_alias,
+        payee_alias: payment.payee_alias,
+        amount: payment.amount,
+        currency: payment.currency,
+        message: payment.message,
+        status: payment.status.unwrap_or(Status::Created),
+        date_created: payment.date_created,
+        date_paid: payment.date_paid,
+        error_code: payment.error_code,
+        error_message: payment.error_message,
+        extra: payment.extra,
+    })
+);
+arbitrary!(
+    RefundCallback,
+    any::<Refund>().prop_map(|refund| RefundCallback {
+        id: refund.id,
+        payment_reference: refund.payment_reference,
+        payer_payment_reference: refund.payer_payment_reference,
+        original_payment_reference: refund.original_payment_reference,
+        callback_url: refund.callback_url,
+        payer_alias: refund.payer_alias,
+        payee_alias: refund.payee_alias,
+        amount: refund.amount,
+        currency: refund.currency,
+        message: refund.message,
+        status: refund.status.unwrap_or(Status::Created),
+        date_created: refund.date_created,
+        date_paid: refund.date_paid,
+        error_code: refund.error_code,
+        error_message: refund.error_message,
+        additional_information: refund.additional_information,
+        extra: refund.extra,
+    })
+);
+
+/// Generates ids like the ones Swish gives payments and refunds,
+/// which are 32 uppercase hexadecimal digits.
+pub fn id() -> impl Strategy<Value = String> {
+    "[0-9A-F]{32}"
+}
+
+/// Generates messages that Swish accepts for payments and refunds.
+pub fn message() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9åäöÅÄÖ :;.,?!()\\-\"']{0,50}"
+}
+
+/// Generates payee payment references that Swish accepts.
+pub fn payee_payment_reference() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9åäöÅÄÖ]{1,35}"
+}
+
+/// Generates timestamps formatted like the ones Swish returns.
+pub fn timestamp() -> impl Strategy<Value = String> {
+    (
+        2015..2030u32,
+        1..=12u32,
+        1..=28u32,
+        0..24u32,
+        0..60u32,
+        0..60u32,
+        0..1000u32,
+    )
+        .prop_map(|(year, month, day, hour, minute, second, millis)| {
+            format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+                year, month, day, hour, minute, second, millis
+            )
+        })
+}
+
+/// Generates the statuses Swish gives payments.
+pub fn payment_status() -> impl Strategy<Value = Status> {
+    prop_oneof![
+        Just(Status::Created),
+        Just(Status::Paid),
+        Just(Status::Declined),
+        Just(Status::Cancelled),
+        Just(Status::Error),
+    ]
+}
+
+/// Generates the statuses Swish gives refunds.
+pub fn refund_status() -> impl Strategy<Value = Status> {
+    prop_oneof![
+        Just(Status::Created),
+        Just(Status::Validated),
+        Just(Status::Initiated),
+        Just(Status::Debited),
+        Just(Status::Paid),
+        Just(Status::Error),
+    ]
+}
+
+/// What's shared by the payments and refunds that are generated.
+#[derive(Debug, Clone)]
+struct Transfer {
+    id: String,
+    payment_reference: String,
+    reference: Option<String>,
+    amount: Amount,
+    message: Option<String>,
+    phone_alias: PhoneAlias,
+    merchant_alias: MerchantAlias,
+    callback_url: CallbackUrl,
+}
+
+fn transfer() -> impl Strategy<Value = Transfer> {
+    (
+        id(),
+        id(),
+        proptest::option::of(payee_payment_reference()),
+        any::<Amount>(),
+        proptest::option::of(message()),
+        any::<PhoneAlias>(),
+        any::<MerchantAlias>(),
+        any::<CallbackUrl>(),
+    )
+        .prop_map(
+            |(
+                id,
+                payment_reference,
+                reference,
+                amount,
+                message,
+                phone_alias,
+                merchant_alias,
+                callback_url,
+            )| Transfer {
+                id,
+                payment_reference,
+                reference,
+                amount,
+                message,
+                phone_alias,
+                merchant_alias,
+                callback_url,
+            },
+        )
+}
+
+/// Generates a status with the date created, and the date paid
+/// or error code that's consistent with it.
+fn outcome<S>(
+    status: S,
+) -> impl Strategy<Value = (Status, String, Option<String>, Option<ErrorCode>)>
+where
+    S: Strategy<Value = Status>,
+{
+    (status, timestamp(), any::<ErrorCode>()).prop_map(|(status, date_created, error)| {
+        let date_paid = if status == Status::Paid {
+            Some(date_created.clone())
+        } else {
+            None
+        };
+        let error = if status == Status::Error {
+            Some(error)
+        } else {
+            None
+        };
+        (status, date_created, date_paid, error)
+    })
+}
@@ -0,0 +1,122 @@
+//! The parsing of the command line, which is words followed by
+//! `--name value` options and `--name` switches.
+//!
+use std::collections::HashMap;
+use std::time::Duration;
+use swish_api::amount::Amount;
+use swish_api::callback::CallbackUrl;
+use EXIT_USAGE;
+
+/// The arguments of a command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    /// Parses the arguments, of which the switches are the
+    /// options that are never followed by a value.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I, switches: &[&str]) -> Self {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => match name.find('=') {
+                    Some(index) => {
+                        let (name, value) = name.split_at(index);
+                        parsed
+                            .options
+                            .insert(name.to_owned(), Some(value[1..].to_owned()));
+                    }
+                    None if switches.contains(&name) => {
+                        parsed.options.insert(name.to_owned(), None);
+                    }
+                    None => {
+                        parsed.options.insert(name.to_owned(), args.next());
+                    }
+                },
+                None => parsed.positional.push(arg),
+            }
+        }
+        parsed
+    }
+
+    /// Gets the positional argument at the index.
+    pub fn positional(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(String::as_str)
+    }
+
+    /// Gets the value of an option, falling back to an environment variable.
+    pub fn value(&self, name: &str, env: &str) -> Option<String> {
+        match self.options.get(name) {
+            Some(value) => value.clone(),
+            None => ::std::env::var(env).ok(),
+        }
+    }
+
+    /// Gets the value of an option.
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .get(name)
+            .and_then(|value| value.as_ref().map(String::as_str))
+    }
+
+    /// Whether a switch is set.
+    pub fn switch(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    /// Gets an option that's a number of seconds.
+    ///
+    /// # Returns
+    /// The duration, or the exit code if the option isn't a number.
+    pub fn seconds(&self, name: &str, default: u64) -> Result<Duration, i32> {
+        match self.option(name).map(str::parse) {
+            None => Ok(Duration::from_secs(default)),
+            Some(Ok(seconds)) => Ok(Duration::from_secs(seconds)),
+            Some(Err(_)) => {
+                eprintln!("error: --{} must be a number of seconds", name);
+                Err(EXIT_USAGE)
+            }
+        }
+    }
+
+    /// Gets an option that's an amount of kronor.
+    ///
+    /// # Returns
+    /// The amount, or the exit code if the option isn't an amount.
+    pub fn kronor(&self, name: &str) -> Result<Option<Amount>, i32> {
+        match self.option(name).map(str::parse) {
+            None => Ok(None),
+            Some(Ok(amount)) => Ok(Some(amount)),
+            Some(Err(error)) => {
+                eprintln!("error: --{}: {}", name, error);
+                Err(EXIT_USAGE)
+            }
+        }
+    }
+
+    /// Gets the `--callback-url` option, or the `SWISH_CALLBACK_URL` environment variable.
+    ///
+    /// # Returns
+    /// The URL, or the exit code if it's missing or invalid.
+    pub fn callback_url(&self) -> Result<CallbackUrl, i32> {
+        match self
+            .value("callback-url", "SWISH_CALLBACK_URL")
+            .map(|url| CallbackUrl::new(&url))
+        {
+            Some(Ok(callback_url)) => Ok(callback_url),
+            Some(Err(error)) => {
+                eprintln!("error: {}", error);
+                Err(EXIT_USAGE)
+            }
+            None => {
+                eprintln!(
+                    "error: the callback URL is missing, set --callback-url or SWISH_CALLBACK_URL"
+                );
+                Err(EXIT_USAGE)
+            }
+        }
+    }
+}
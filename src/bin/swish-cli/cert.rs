@@ -0,0 +1,170 @@
+//! The `cert` commands.
+//!
+use args::Args;
+use openssl::asn1::Asn1Time;
+use openssl::nid::Nid;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::{PKey, Private};
+use openssl::x509::{X509NameRef, X509};
+use std::fs;
+use {EXIT_FAILURE, EXIT_USAGE};
+
+const USAGE: &str =
+    "usage: swish-cli cert info <path> [--passphrase <secret>] [--merchant <number>]";
+
+/// The certificate of a merchant, with its private key if the bundle has one.
+struct Bundle {
+    certificate: X509,
+    key: Option<PKey<Private>>,
+}
+
+/// Runs a `cert` command.
+pub fn run(args: &Args) -> i32 {
+    match (args.positional(1), args.positional(2)) {
+        (Some("info"), Some(path)) => info(args, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            EXIT_USAGE
+        }
+    }
+}
+
+/// Prints what's in a certificate bundle, and whether it's one
+/// the merchant can call Swish with.
+fn info(args: &Args, path: &str) -> i32 {
+    let passphrase = args
+        .value("passphrase", "SWISH_PASSPHRASE")
+        .unwrap_or_default();
+    let bundle = match fs::read(path)
+        .map_err(|error| error.to_string())
+        .and_then(|bundle| load(&bundle, &passphrase))
+    {
+        Ok(bundle) => bundle,
+        Err(error) => {
+            eprintln!("error: couldn't load {}: {}", path, error);
+            return EXIT_FAILURE;
+        }
+    };
+    let certificate = &bundle.certificate;
+    let mut problems = Vec::new();
+
+    println!("subject:     {}", name(certificate.subject_name()));
+    println!("issuer:      {}", name(certificate.issuer_name()));
+    match certificate
+        .serial_number()
+        .to_bn()
+        .and_then(|serial| serial.to_hex_str())
+    {
+        Ok(serial) => println!("serial:      {}", serial),
+        Err(error) => problems.push(format!("the serial number couldn't be read: {}", error)),
+    }
+    println!("valid from:  {}", certificate.not_before());
+    println!("valid until: {}", certificate.not_after());
+    match Asn1Time::days_from_now(0) {
+        Ok(ref now) if certificate.not_before() > now => {
+            problems.push("the certificate isn't valid yet".to_owned())
+        }
+        Ok(ref now) if certificate.not_after() < now => {
+            problems.push("the certificate has expired".to_owned())
+        }
+        Ok(_) => {}
+        Err(error) => problems.push(format!("the validity couldn't be checked: {}", error)),
+    }
+    match bundle.key {
+        Some(ref key) => match certificate.public_key().map(|public| public.public_eq(key)) {
+            Ok(true) => println!("private key: matches the certificate"),
+            _ => problems.push("the private key doesn't match the certificate".to_owned()),
+        },
+        None => problems.push("the bundle has no private key".to_owned()),
+    }
+
+    // Swish issues the certificate of a merchant to its Swish number.
+    let common_name = common_name(certificate);
+    match args.value("merchant", "SWISH_MERCHANT") {
+        Some(ref merchant) if common_name.as_ref() == Some(merchant) => {
+            println!("merchant:    matches {}", merchant)
+        }
+        Some(merchant) => problems.push(format!(
+            "the certificate is issued to {}, not the merchant {}",
+            common_name.as_deref().unwrap_or("no one"),
+            merchant
+        )),
+        None => {
+            println!("merchant:    not configured, set --merchant or SWISH_MERCHANT to check it")
+        }
+    }
+
+    for problem in &problems {
+        eprintln!("error: {}", problem);
+    }
+    if problems.is_empty() {
+        0
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+/// Loads a PKCS#12 bundle, or a PEM one with the certificate first and an optional private key.
+fn load(bundle: &[u8], passphrase: &str) -> Result<Bundle, String> {
+    if let Ok(pkcs12) = Pkcs12::from_der(bundle) {
+        let parsed = pkcs12.parse2(passphrase).map_err(|error| {
+            // OpenSSL 3 doesn't decrypt the RC2 of older bundles without its legacy provider.
+            let hint = if error
+                .errors()
+                .iter()
+                .any(|error| error.reason() == Some("unsupported"))
+            {
+                ", it may be encrypted with a legacy algorithm, which \
+                 `openssl pkcs12 -legacy` can convert"
+            } else {
+                ""
+            };
+            format!(
+                "the PKCS#12 bundle couldn't be decrypted: {}{}",
+                error, hint
+            )
+        })?;
+        return match parsed.cert {
+            Some(certificate) => Ok(Bundle {
+                certificate,
+                key: parsed.pkey,
+            }),
+            None => Err("the PKCS#12 bundle has no certificate".to_owned()),
+        };
+    }
+
+    let certificate = X509::stack_from_pem(bundle)
+        .map_err(|error| error.to_string())?
+        .into_iter()
+        .next()
+        .ok_or("it's neither a PKCS#12 bundle nor a PEM certificate")?;
+    let key = if String::from_utf8_lossy(bundle).contains("PRIVATE KEY-----") {
+        let key = PKey::private_key_from_pem_passphrase(bundle, passphrase.as_bytes())
+            .map_err(|error| format!("the private key couldn't be decrypted: {}", error))?;
+        Some(key)
+    } else {
+        None
+    };
+    Ok(Bundle { certificate, key })
+}
+
+/// Formats a name like `C=SE, O=5560997982, CN=1234679304`.
+fn name(name: &X509NameRef) -> String {
+    let entries: Vec<_> = name
+        .entries()
+        .map(|entry| {
+            let field = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry.data().to_string().unwrap_or_default();
+            format!("{}={}", field, value)
+        })
+        .collect();
+    entries.join(", ")
+}
+
+fn common_name(certificate: &X509) -> Option<String> {
+    certificate
+        .subject_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()
+        .and_then(|entry| entry.data().to_string().ok())
+}
@@ -0,0 +1,31 @@
+//! The building of the client the commands call Swish with.
+//!
+use args::Args;
+use native_tls::TlsConnector;
+use swish_api::prelude::*;
+use tokio_core::reactor::Handle;
+
+/// Builds a client from the `--merchant`, `--cert`, `--passphrase` and `--api-url`
+/// options, or the `SWISH_MERCHANT`, `SWISH_CERT`, `SWISH_PASSPHRASE` and
+/// `SWISH_API_URL` environment variables. Without a certificate, the client
+/// connects without one, e.g. to a simulator.
+pub fn build(args: &Args, handle: Handle) -> Result<SwishClient, String> {
+    let merchant = args
+        .value("merchant", "SWISH_MERCHANT")
+        .ok_or("the Swish number of the merchant is missing, set --merchant or SWISH_MERCHANT")?;
+    let merchant = MerchantAlias::new(&merchant).map_err(|error| error.to_string())?;
+    let passphrase = args
+        .value("passphrase", "SWISH_PASSPHRASE")
+        .unwrap_or_default();
+
+    let builder = match args.value("cert", "SWISH_CERT") {
+        Some(cert) => SwishClientBuilder::new(merchant, &cert, &passphrase, handle),
+        None => SwishClientBuilder::new(merchant, "", "", handle)
+            .tls_connector(TlsConnector::new().map_err(|error| error.to_string())?),
+    };
+    let builder = match args.value("api-url", "SWISH_API_URL") {
+        Some(api_url) => builder.api_url(&api_url),
+        None => builder,
+    };
+    builder.try_build().map_err(|error| error.to_string())
+}
@@ -0,0 +1,200 @@
+//! The `doctor` command.
+//!
+use args::Args;
+use client;
+use futures::stream;
+use hyper::StatusCode;
+use std::cmp;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use swish_api::event::PaymentEvent;
+use swish_api::params::{OwnedPaymentParams, OwnedRefundParams};
+use swish_api::polling::PollPolicy;
+use swish_api::prelude::*;
+use tokio_core::reactor::Core;
+use EXIT_FAILURE;
+
+/// The payer of the payment, which MSS accepts any Swedish mobile number for.
+const PAYER: &str = "46701234567";
+/// The callback URLs used unless one is given. MSS doesn't need to reach them.
+const PAYMENT_CALLBACK_URL: &str = "https://example.com/api/swishcb/paymentrequests";
+const REFUND_CALLBACK_URL: &str = "https://example.com/api/swishcb/refunds";
+
+/// Creates a payment in MSS, waits for it to be paid, refunds it and waits for
+/// the refund to be paid, printing the outcome of every step as it finishes.
+/// The steps after one that fails are skipped.
+pub fn run(args: &Args) -> i32 {
+    let timeout = match args.seconds("timeout", 60) {
+        Ok(timeout) => timeout,
+        Err(code) => return code,
+    };
+    let amount = match args.kronor("amount") {
+        Ok(amount) => amount.unwrap_or_else(|| Amount::from_kronor(1)),
+        Err(code) => return code,
+    };
+    let mut core = Core::new().expect("a reactor can be created");
+    let mut doctor = Doctor { failed: false };
+    let payer = args.option("payer").unwrap_or(PAYER);
+
+    let client = doctor.step("configuration", || {
+        let client = client::build(args, core.handle())?;
+        let merchant = args.value("merchant", "SWISH_MERCHANT").unwrap_or_default();
+        let cert = args
+            .value("cert", "SWISH_CERT")
+            .unwrap_or_else(|| "no certificate".to_owned());
+        Ok((client, format!("merchant {}, {}", merchant, cert)))
+    });
+
+    let created = doctor.after(client.as_ref(), "create payment", |client| {
+        let params = OwnedPaymentParams {
+            payee_payment_reference: Some("doctor".to_owned()),
+            payer_alias: Some(PhoneAlias::new(payer).map_err(|error| error.to_string())?),
+            payee_alias: None,
+            amount,
+            message: Some("swish-cli doctor".to_owned()),
+            callback_url: Some(callback_url(args, PAYMENT_CALLBACK_URL)?),
+        };
+        let created = core
+            .run(client.create_payment(params.as_params()))
+            .map_err(|error| error.to_string())?;
+        let expires_at = created.expires_at();
+        Ok(((PaymentId::from(created.id.clone()), expires_at), created.id))
+    });
+
+    let paid = doctor.after(
+        created.and_then(|created| Some((client.as_ref()?, created))),
+        "await payment",
+        |(client, (payment_id, expires_at))| {
+            let policy = PollPolicy::new().interval(Duration::from_secs(1));
+            let expires_at = cmp::min(expires_at, SystemTime::now() + timeout);
+            let event = core
+                .run(client.await_payment_until(
+                    payment_id,
+                    expires_at,
+                    stream::empty::<_, ()>(),
+                    policy,
+                ))
+                .map_err(|error| error.to_string())?;
+            match event {
+                PaymentEvent::Paid {
+                    payment_reference: Some(payment_reference),
+                    amount,
+                    ..
+                } => Ok(((payment_reference, amount), "PAID".to_owned())),
+                event => Err(format!("the payment wasn't paid: {:?}", event)),
+            }
+        },
+    );
+
+    let refund_id = doctor.after(
+        paid.and_then(|paid| Some((client.as_ref()?, paid))),
+        "create refund",
+        |(client, (payment_reference, amount))| {
+            let params = OwnedRefundParams {
+                payer_payment_reference: Some("doctor".to_owned()),
+                original_payment_reference: payment_reference,
+                payment_reference: None,
+                payer_alias: None,
+                payee_alias: payer.to_owned(),
+                amount,
+                message: Some("swish-cli doctor".to_owned()),
+                callback_url: Some(callback_url(args, REFUND_CALLBACK_URL)?),
+            };
+            let created = core
+                .run(client.create_refund(params.as_params()))
+                .map_err(|error| error.to_string())?;
+            Ok((RefundId::from(created.id.as_str()), created.id))
+        },
+    );
+
+    doctor.after(
+        refund_id.and_then(|refund_id| Some((client.as_ref()?, refund_id))),
+        "await refund",
+        |(client, refund_id)| {
+            let started = Instant::now();
+            loop {
+                match core.run(client.get_refund(&refund_id)) {
+                    Ok(refund) => match PaymentEvent::from(&refund) {
+                        PaymentEvent::RefundPaid { .. } => return Ok(((), "PAID".to_owned())),
+                        event if event.is_final() => {
+                            return Err(format!("the refund wasn't paid: {:?}", event))
+                        }
+                        _ => {}
+                    },
+                    // The refund may not be found yet, or Swish may be unreachable for a while.
+                    Err(ref error)
+                        if error.is_retryable()
+                            || error.http_status() == Some(StatusCode::NOT_FOUND) => {}
+                    Err(error) => return Err(error.to_string()),
+                }
+                if started.elapsed() >= timeout {
+                    return Err("timed out waiting for the refund".to_owned());
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
+        },
+    );
+    doctor.finish()
+}
+
+/// Runs the steps, and keeps track of whether one has failed.
+struct Doctor {
+    failed: bool,
+}
+
+impl Doctor {
+    /// Runs a step and prints its outcome, with how long it took.
+    ///
+    /// # Returns
+    /// What the step got, or `None` if it failed.
+    fn step<T, F>(&mut self, name: &str, step: F) -> Option<T>
+    where
+        F: FnOnce() -> Result<(T, String), String>,
+    {
+        let started = Instant::now();
+        let outcome = step();
+        let elapsed = started.elapsed().as_secs_f64();
+        match outcome {
+            Ok((value, detail)) => {
+                println!("ok      {:<16} {} ({:.1}s)", name, detail, elapsed);
+                Some(value)
+            }
+            Err(error) => {
+                println!("FAILED  {:<16} {} ({:.1}s)", name, error, elapsed);
+                self.failed = true;
+                None
+            }
+        }
+    }
+
+    /// Runs a step with what the previous step got, or skips it if that one failed.
+    fn after<P, T, F>(&mut self, previous: Option<P>, name: &str, step: F) -> Option<T>
+    where
+        F: FnOnce(P) -> Result<(T, String), String>,
+    {
+        match previous {
+            Some(previous) => self.step(name, || step(previous)),
+            None => {
+                println!("skipped {}", name);
+                None
+            }
+        }
+    }
+
+    /// Gets the exit code, which is 0 if every step succeeded.
+    fn finish(self) -> i32 {
+        if self.failed {
+            EXIT_FAILURE
+        } else {
+            0
+        }
+    }
+}
+
+/// Gets the callback URL given, or else the default one.
+fn callback_url(args: &Args, default: &str) -> Result<CallbackUrl, String> {
+    let url = args
+        .value("callback-url", "SWISH_CALLBACK_URL")
+        .unwrap_or_else(|| default.to_owned());
+    CallbackUrl::new(&url).map_err(|error| error.to_string())
+}
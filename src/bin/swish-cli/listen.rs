@@ -0,0 +1,115 @@
+//! The `listen` command.
+//!
+use args::Args;
+use futures::sync::mpsc;
+use futures::{Future, Stream};
+use serde_json;
+use std::net::{IpAddr, SocketAddr};
+use swish_api::callback::Callback;
+use swish_api::callback_server::CallbackServer;
+use swish_api::prelude::*;
+use swish_api::webhook::WebhookHarness;
+use tokio_core::reactor::Core;
+use {EXIT_FAILURE, EXIT_USAGE};
+
+const USAGE: &str = "usage: swish-cli listen --port <port> [--host <address>] [--forward <url>]";
+
+/// Receives callbacks on plain http and prints them as they arrive,
+/// forwarding each of them if it's given a URL to forward them to.
+pub fn run(args: &Args) -> i32 {
+    let port = match args.option("port").map(str::parse::<u16>) {
+        Some(Ok(port)) => port,
+        _ => {
+            eprintln!("{}", USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    let host = match args.option("host").unwrap_or("127.0.0.1").parse::<IpAddr>() {
+        Ok(host) => host,
+        Err(error) => {
+            eprintln!("error: --host: {}", error);
+            return EXIT_USAGE;
+        }
+    };
+    let mut core = Core::new().expect("a reactor can be created");
+    let handle = core.handle();
+    let forward = match args.option("forward") {
+        Some(url) => match WebhookHarness::new(url, &handle) {
+            Ok(harness) => Some((url.to_owned(), harness)),
+            Err(error) => {
+                eprintln!("error: --forward: {}", error);
+                return EXIT_USAGE;
+            }
+        },
+        None => None,
+    };
+
+    let server = match CallbackServer::bind(&SocketAddr::new(host, port), &handle) {
+        Ok(server) => server,
+        Err(error) => {
+            eprintln!("error: couldn't listen on {}:{}: {}", host, port, error);
+            return EXIT_FAILURE;
+        }
+    };
+    let server = match args
+        .value("merchant", "SWISH_MERCHANT")
+        .map(|merchant| MerchantAlias::new(&merchant))
+    {
+        Some(Ok(merchant)) => server.merchant(merchant),
+        Some(Err(error)) => {
+            eprintln!("error: {}", error);
+            return EXIT_USAGE;
+        }
+        None => server,
+    };
+    match server.local_addr() {
+        Ok(addr) => eprintln!("listening for callbacks on http://{}/", addr),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return EXIT_FAILURE;
+        }
+    }
+
+    let (sender, receiver) = mpsc::unbounded();
+    handle.spawn(
+        server
+            .serve(sender)
+            .map_err(|error| eprintln!("error: the listener failed: {}", error)),
+    );
+    let printed = receiver.for_each(|callback| {
+        print(&callback);
+        if let Some((ref url, ref harness)) = forward {
+            let url = url.clone();
+            handle.spawn(harness.deliver_callback(&callback).then(move |delivered| {
+                match delivered {
+                    Ok(statuses) => eprintln!("forwarded to {}: {}", url, statuses[0]),
+                    Err(error) => eprintln!("error: couldn't forward to {}: {}", url, error),
+                }
+                Ok(())
+            }));
+        }
+        Ok(())
+    });
+    let _ = core.run(printed);
+    0
+}
+
+/// Prints a callback, with a line telling what happened followed by its JSON.
+fn print(callback: &Callback) {
+    let (kind, status, json) = match *callback {
+        Callback::Payment(ref callback) => (
+            "PAYMENT",
+            &callback.status,
+            serde_json::to_string_pretty(callback),
+        ),
+        Callback::Refund(ref callback) => (
+            "REFUND",
+            &callback.status,
+            serde_json::to_string_pretty(callback),
+        ),
+    };
+    println!("{} {} {}", kind, status, callback.id());
+    if let Ok(json) = json {
+        println!("{}", json);
+    }
+}
@@ -0,0 +1,87 @@
+//! # swish-cli
+//!
+//! A command line client of the Swish API, for scripts and for trying out
+//! a Swish setup. It's only built with the `cli` feature.
+//!
+//! The merchant and the certificate are given with the `--merchant`, `--cert`,
+//! `--passphrase` and `--api-url` options, or the `SWISH_MERCHANT`, `SWISH_CERT`,
+//! `SWISH_PASSPHRASE` and `SWISH_API_URL` environment variables.
+//!
+extern crate futures;
+extern crate hyper;
+extern crate native_tls;
+extern crate openssl;
+extern crate qrcode;
+extern crate serde_json;
+extern crate swish_api;
+extern crate tokio_core;
+
+mod args;
+mod cert;
+mod client;
+mod doctor;
+mod listen;
+mod payment;
+mod qr;
+mod refund;
+
+use args::Args;
+use std::env;
+use std::process;
+
+/// The exit code of a command that failed, e.g. because Swish couldn't be reached.
+pub const EXIT_FAILURE: i32 = 1;
+/// The exit code of a command that was called wrongly.
+pub const EXIT_USAGE: i32 = 64;
+
+const USAGE: &str = "usage: swish-cli <command> [options]
+
+commands:
+    cert info <path>     Prints the subject, serial and validity of the certificate in a
+                         PKCS#12 or PEM bundle, and whether it's issued to the merchant,
+                         exiting with 1 if it can't be used to call Swish
+    doctor               Checks the setup end to end against the Swish test environment,
+                         creating a payment of --amount or 1 kr, awaiting it, refunding
+                         it and awaiting the refund, and exits with 1 if a step fails
+    listen --port <port> Receives callbacks on plain http, on --host or 127.0.0.1, and
+                         prints them as they arrive, forwarding them to --forward <url>
+    payment create       Creates a payment of --amount, whose outcome is posted to
+                         --callback-url [env: SWISH_CALLBACK_URL]. It's sent to the
+                         Swish app of --payer, or else its request token is printed,
+                         or rendered as a QR code to scan with the Swish app with --qr
+    payment wait <id>    Polls a payment until it's paid, declined, failed or cancelled,
+                         exiting with 0, 2, 3 or 4, and 5 when it times out
+    refund create <id>   Refunds a payment after showing its amount, payer and what's
+                         left to refund, and asking to go ahead unless --yes is given.
+                         --amount is the refund, --refunded what's been refunded before
+                         and --callback-url [env: SWISH_CALLBACK_URL] where it's posted
+
+options:
+    --merchant <number>      The Swish number of the merchant [env: SWISH_MERCHANT]
+    --cert <path>            The PKCS#12 certificate of the merchant [env: SWISH_CERT]
+    --passphrase <secret>    The passphrase of the certificate [env: SWISH_PASSPHRASE]
+    --api-url <url>          The URL of the Swish API, defaults to the test environment
+                             [env: SWISH_API_URL]";
+
+/// The options that are never followed by a value.
+const SWITCHES: [&str; 3] = ["help", "qr", "yes"];
+
+fn main() {
+    let args = Args::parse(env::args().skip(1), &SWITCHES);
+    let code = match args.positional(0) {
+        _ if args.switch("help") => {
+            println!("{}", USAGE);
+            0
+        }
+        Some("cert") => cert::run(&args),
+        Some("doctor") => doctor::run(&args),
+        Some("listen") => listen::run(&args),
+        Some("payment") => payment::run(&args),
+        Some("refund") => refund::run(&args),
+        _ => {
+            eprintln!("{}", USAGE);
+            EXIT_USAGE
+        }
+    };
+    process::exit(code);
+}
@@ -0,0 +1,227 @@
+//! The `payment` commands.
+//!
+use args::Args;
+use client;
+use hyper::StatusCode;
+use qr;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+use swish_api::event::PaymentEvent;
+use swish_api::params::OwnedPaymentParams;
+use swish_api::prelude::*;
+use tokio_core::reactor::Core;
+use {EXIT_FAILURE, EXIT_USAGE};
+
+/// The exit codes of `payment wait`, besides `EXIT_FAILURE`.
+pub const EXIT_PAID: i32 = 0;
+pub const EXIT_DECLINED: i32 = 2;
+pub const EXIT_ERROR: i32 = 3;
+pub const EXIT_CANCELLED: i32 = 4;
+pub const EXIT_TIMED_OUT: i32 = 5;
+
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+const USAGE: &str = "usage: swish-cli payment create --amount <kronor> --callback-url <url> \
+                     [--payer <phone number> | --qr] [--message <message>] \
+                     [--reference <reference>]
+       swish-cli payment wait <id> [--interval <secs>] [--timeout <secs>]";
+
+/// Runs a `payment` command.
+pub fn run(args: &Args) -> i32 {
+    match args.positional(1) {
+        Some("create") => create(args),
+        Some("wait") => wait(args),
+        _ => {
+            eprintln!("{}", USAGE);
+            EXIT_USAGE
+        }
+    }
+}
+
+/// Creates an e-commerce payment if it's given a payer, and an m-commerce
+/// payment otherwise, whose request token is printed, or rendered as a QR code.
+fn create(args: &Args) -> i32 {
+    let amount = match args.kronor("amount") {
+        Ok(Some(amount)) => amount,
+        Ok(None) => {
+            eprintln!("{}", USAGE);
+            return EXIT_USAGE;
+        }
+        Err(code) => return code,
+    };
+    let callback_url = match args.callback_url() {
+        Ok(callback_url) => callback_url,
+        Err(code) => return code,
+    };
+    let payer_alias = match args.option("payer").map(PhoneAlias::new) {
+        Some(Ok(_)) if args.switch("qr") => {
+            eprintln!("error: --qr is for m-commerce payments, which have no payer");
+            return EXIT_USAGE;
+        }
+        Some(Ok(payer_alias)) => Some(payer_alias),
+        Some(Err(error)) => {
+            eprintln!("error: {}", error);
+            return EXIT_USAGE;
+        }
+        None => None,
+    };
+    let mut core = Core::new().expect("a reactor can be created");
+    let client = match client::build(args, core.handle()) {
+        Ok(client) => client,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return EXIT_FAILURE;
+        }
+    };
+
+    let params = OwnedPaymentParams {
+        payee_payment_reference: args.option("reference").map(str::to_owned),
+        payer_alias: None,
+        payee_alias: None,
+        amount,
+        message: args.option("message").map(str::to_owned),
+        callback_url: Some(callback_url),
+    };
+    let created = match payer_alias {
+        Some(payer_alias) => core
+            .run(client.create_ecommerce_payment(payer_alias, params.as_params()))
+            .map(|payment| (payment.id, None)),
+        None => core
+            .run(client.create_mcommerce_payment(params.as_params()))
+            .map(|payment| (payment.id, Some(payment.request_token))),
+    };
+    match created {
+        Ok((id, request_token)) => {
+            println!("CREATED {}", id);
+            if let Some(request_token) = request_token {
+                if args.switch("qr") {
+                    println!("{}", qr::render(request_token.as_str()));
+                } else {
+                    println!("TOKEN {}", request_token);
+                }
+            }
+            0
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Polls a payment until it has a final status, and exits with a code telling which.
+fn wait(args: &Args) -> i32 {
+    let id = match args.positional(2) {
+        Some(id) => PaymentId::from(id),
+        None => {
+            eprintln!("{}", USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    let interval = match args.seconds("interval", 2) {
+        Ok(interval) => interval,
+        Err(code) => return code,
+    };
+    let timeout = match args.seconds("timeout", 210) {
+        Ok(timeout) => timeout,
+        Err(code) => return code,
+    };
+    let mut core = Core::new().expect("a reactor can be created");
+    let client = match client::build(args, core.handle()) {
+        Ok(client) => client,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return EXIT_FAILURE;
+        }
+    };
+
+    let started = Instant::now();
+    let mut frame = 0;
+    loop {
+        let status = match core.run(client.get_payment(&id)) {
+            Ok(payment) => {
+                let event = PaymentEvent::from(&payment);
+                if event.is_final() {
+                    eprint!("\r");
+                    return finish(&event);
+                }
+                payment
+                    .status
+                    .map_or_else(String::new, |status| status.to_string())
+            }
+            // The payment may not be found yet, or Swish may be unreachable for a while.
+            Err(ref error)
+                if error.is_retryable() || error.http_status() == Some(StatusCode::NOT_FOUND) =>
+            {
+                error.to_string()
+            }
+            Err(error) => {
+                eprintln!("\rerror: {}", error);
+                return EXIT_FAILURE;
+            }
+        };
+
+        let next_poll = Instant::now() + interval;
+        while Instant::now() < next_poll {
+            if started.elapsed() >= timeout {
+                eprintln!("\rtimed out waiting for payment {}", id);
+                return EXIT_TIMED_OUT;
+            }
+            eprint!(
+                "\r{} waiting for payment {} ({})",
+                SPINNER[frame % 4],
+                id,
+                status
+            );
+            let _ = io::stderr().flush();
+            frame += 1;
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+/// Prints the final event of a payment, and gets the exit code of it.
+fn finish(event: &PaymentEvent) -> i32 {
+    match *event {
+        PaymentEvent::Paid {
+            ref payment_id,
+            amount,
+            ref payment_reference,
+            ..
+        } => {
+            println!(
+                "PAID {} {} SEK {}",
+                payment_id,
+                amount,
+                payment_reference.as_ref().map_or("", String::as_str)
+            );
+            EXIT_PAID
+        }
+        PaymentEvent::Declined { ref payment_id } => {
+            println!("DECLINED {}", payment_id);
+            EXIT_DECLINED
+        }
+        PaymentEvent::Cancelled { ref payment_id } => {
+            println!("CANCELLED {}", payment_id);
+            EXIT_CANCELLED
+        }
+        PaymentEvent::Errored {
+            ref payment_id,
+            ref error_code,
+            ref error_message,
+        } => {
+            println!(
+                "ERROR {} {} {}",
+                payment_id,
+                error_code.as_ref().map_or("", |code| code.as_str()),
+                error_message.as_ref().map_or("", String::as_str)
+            );
+            EXIT_ERROR
+        }
+        ref event => {
+            println!("{:?}", event);
+            EXIT_FAILURE
+        }
+    }
+}
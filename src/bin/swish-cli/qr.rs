@@ -0,0 +1,19 @@
+//! The rendering of QR codes in the terminal.
+//!
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+
+/// Renders the QR code the Swish app opens an m-commerce payment with,
+/// which is the request token prefixed with `D`.
+///
+/// The code is drawn with half blocks, two modules a character, for a terminal
+/// with a dark background, on which the dark modules are left blank.
+pub fn render(request_token: &str) -> String {
+    QrCode::new(format!("D{}", request_token))
+        .expect("a request token fits in a QR code")
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build()
+}
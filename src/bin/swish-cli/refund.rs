@@ -0,0 +1,126 @@
+//! The `refund` commands.
+//!
+use args::Args;
+use client;
+use std::io::{self, BufRead, Write};
+use swish_api::eligibility;
+use swish_api::params::OwnedRefundParams;
+use swish_api::prelude::*;
+use tokio_core::reactor::Core;
+use {EXIT_FAILURE, EXIT_USAGE};
+
+const USAGE: &str = "usage: swish-cli refund create <payment id> --amount <kronor> \
+                     --callback-url <url> [--refunded <kronor>] [--message <message>] \
+                     [--reference <reference>] [--yes]";
+
+/// Runs a `refund` command.
+pub fn run(args: &Args) -> i32 {
+    match args.positional(1) {
+        Some("create") => create(args),
+        _ => {
+            eprintln!("{}", USAGE);
+            EXIT_USAGE
+        }
+    }
+}
+
+/// Refunds a payment, once what's refunded has been confirmed.
+fn create(args: &Args) -> i32 {
+    let (id, amount, refunded) = match (
+        args.positional(2),
+        args.kronor("amount"),
+        args.kronor("refunded"),
+    ) {
+        (Some(id), Ok(Some(amount)), Ok(refunded)) => (id, amount, refunded.unwrap_or_default()),
+        (_, Err(code), _) | (_, _, Err(code)) => return code,
+        _ => {
+            eprintln!("{}", USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    let callback_url = match args.callback_url() {
+        Ok(callback_url) => callback_url,
+        Err(code) => return code,
+    };
+    let mut core = Core::new().expect("a reactor can be created");
+    let client = match client::build(args, core.handle()) {
+        Ok(client) => client,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return EXIT_FAILURE;
+        }
+    };
+
+    let payment = match core.run(client.get_payment(&PaymentId::from(id))) {
+        Ok(payment) => payment,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return EXIT_FAILURE;
+        }
+    };
+    let remaining = ::std::cmp::max(payment.amount - refunded, Amount::from_ore(0));
+    eprintln!("payment:    {}", payment.id);
+    eprintln!("amount:     {} SEK", payment.amount);
+    eprintln!(
+        "payer:      {}",
+        payment
+            .payer_alias
+            .as_ref()
+            .map_or("unknown", String::as_str)
+    );
+    eprintln!("refundable: {} SEK", remaining);
+    eprintln!("refund:     {} SEK", amount);
+
+    if let Err(reasons) = eligibility::check_refund(&payment, amount, refunded) {
+        for reason in reasons {
+            eprintln!("error: {}", reason);
+        }
+        return EXIT_FAILURE;
+    }
+    let (payment_reference, payer_alias) = match (payment.payment_reference, payment.payer_alias) {
+        (Some(payment_reference), Some(payer_alias)) => (payment_reference, payer_alias),
+        _ => {
+            eprintln!("error: the payment has no payment reference or payer to refund");
+            return EXIT_FAILURE;
+        }
+    };
+    if !args.switch("yes") && !confirm(&format!("Refund {} SEK to {}?", amount, payer_alias)) {
+        eprintln!("aborted, nothing was refunded");
+        return EXIT_FAILURE;
+    }
+
+    let params = OwnedRefundParams {
+        payer_payment_reference: args.option("reference").map(str::to_owned),
+        original_payment_reference: payment_reference,
+        payment_reference: None,
+        payer_alias: None,
+        payee_alias: payer_alias,
+        amount,
+        message: args.option("message").map(str::to_owned),
+        callback_url: Some(callback_url),
+    };
+    match core.run(client.create_refund(params.as_params())) {
+        Ok(refund) => {
+            println!("CREATED {}", refund.id);
+            0
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Asks a yes or no question on the terminal, which is no unless answered yes.
+fn confirm(question: &str) -> bool {
+    eprint!("{} [y/N] ", question);
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    match io::stdin().lock().read_line(&mut answer) {
+        Ok(_) => {
+            let answer = answer.trim().to_lowercase();
+            answer == "y" || answer == "yes"
+        }
+        Err(_) => false,
+    }
+}
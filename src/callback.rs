@@ -0,0 +1,277 @@
+//! # The callback module
+//!
+//! Contains the URL Swish sends the outcome of a payment or a refund to,
+//! and the payloads it sends there.
+//!
+use amount::Amount;
+use client::{Currency, Status};
+use error::{ErrorCode, SwishClientError};
+use event::PaymentEvent;
+use hyper::Uri;
+use serde::ser::{Serialize, Serializer};
+use serde_json;
+use std::fmt;
+use std::str::FromStr;
+use validation::ValidationError;
+
+/// The maximum number of characters of a callback URL.
+pub const CALLBACK_URL_MAX_LENGTH: usize = 2048;
+
+/// The largest body of a callback that's accepted, in bytes.
+pub const CALLBACK_MAX_BODY_LENGTH: usize = 64 * 1024;
+
+/// The URL Swish posts the outcome of a payment or a refund to,
+/// which Swish requires to be https.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::callback::CallbackUrl;
+///
+/// assert!(CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").is_ok());
+/// assert!(CallbackUrl::new("http://example.com/api/swishcb/paymentrequests").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallbackUrl(String);
+
+impl CallbackUrl {
+    /// [`CallbackUrl`]: struct.CallbackUrl.html
+    ///
+    /// Creates a new [`CallbackUrl`].
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - An absolute https URL.
+    ///
+    /// # Returns
+    /// The [`CallbackUrl`], or a `ValidationError` if it isn't an https URL
+    /// or is longer than Swish allows.
+    pub fn new(url: &str) -> Result<Self, ValidationError> {
+        let field = "callbackUrl";
+        if url.len() > CALLBACK_URL_MAX_LENGTH {
+            return Err(ValidationError::TooLong {
+                field,
+                max: CALLBACK_URL_MAX_LENGTH,
+            });
+        }
+
+        let is_https = url
+            .parse::<Uri>()
+            .map(|uri| uri.scheme_str() == Some("https") && uri.host().is_some())
+            .unwrap_or(false);
+        if !is_https {
+            return Err(ValidationError::InvalidFormat {
+                field,
+                expected: "an absolute https URL",
+            });
+        }
+        Ok(CallbackUrl(url.to_owned()))
+    }
+
+    /// Gets the URL as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for CallbackUrl {
+    type Err = ValidationError;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        CallbackUrl::new(url)
+    }
+}
+
+impl AsRef<str> for CallbackUrl {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for CallbackUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for CallbackUrl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// The outcome of a payment, as Swish posts it to the callback URL of the payment.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::callback::PaymentCallback;
+/// use swish_api::client::Status;
+///
+/// let body = br#"{
+///     "id": "AB23D7406ECE4542A80152D909EF9F6B",
+///     "payeePaymentReference": "0123456789",
+///     "paymentReference": null,
+///     "callbackUrl": "https://example.com/api/swishcb/paymentrequests",
+///     "payerAlias": "46701234567",
+///     "payeeAlias": "1231181189",
+///     "amount": 100.00,
+///     "currency": "SEK",
+///     "message": "Kingston USB Flash Drive 8 GB",
+///     "status": "DECLINED",
+///     "dateCreated": "2015-02-19T22:01:53+01:00",
+///     "datePaid": null,
+///     "errorCode": null,
+///     "errorMessage": null
+/// }"#;
+///
+/// let callback = PaymentCallback::from_slice(body).unwrap();
+///
+/// assert_eq!(callback.status, Status::Declined);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentCallback {
+    pub id: String,
+    pub payee_payment_reference: Option<String>,
+    /// The reference of the payment at the bank of the payer, once it's paid.
+    pub payment_reference: Option<String>,
+    pub callback_url: Option<String>,
+    pub payer_alias: Option<String>,
+    pub payee_alias: Option<String>,
+    pub amount: Amount,
+    pub currency: Currency,
+    pub message: Option<String>,
+    pub status: Status,
+    pub date_created: String,
+    pub date_paid: Option<String>,
+    /// Set when the status is `ERROR`.
+    pub error_code: Option<ErrorCode>,
+    pub error_message: Option<String>,
+
+    /// Fields sent by Swish that aren't known by this crate yet.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl PaymentCallback {
+    /// [`PaymentCallback`]: struct.PaymentCallback.html
+    ///
+    /// Parses the body of a request Swish sent to the callback URL of a payment.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The json body of the request.
+    ///
+    /// # Returns
+    /// The [`PaymentCallback`], or a `SwishClientError` if the body isn't one.
+    pub fn from_slice(body: &[u8]) -> Result<Self, SwishClientError> {
+        serde_json::from_slice(body).map_err(SwishClientError::from)
+    }
+}
+
+/// The outcome of a refund, as Swish posts it to the callback URL of the refund.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefundCallback {
+    pub id: String,
+    /// The reference of the refund at the bank of the merchant, once it's paid.
+    pub payment_reference: Option<String>,
+    pub payer_payment_reference: Option<String>,
+    /// Read from both `originalPaymentReference` and the
+    /// `originalpaymentReference` spelling of the Swish API.
+    #[serde(alias = "originalpaymentReference")]
+    pub original_payment_reference: Option<String>,
+    pub callback_url: Option<String>,
+    pub payer_alias: Option<String>,
+    pub payee_alias: Option<String>,
+    pub amount: Amount,
+    pub currency: Currency,
+    pub message: Option<String>,
+    pub status: Status,
+    pub date_created: String,
+    pub date_paid: Option<String>,
+    /// Set when the status is `ERROR`.
+    pub error_code: Option<ErrorCode>,
+    pub error_message: Option<String>,
+    pub additional_information: Option<String>,
+
+    /// Fields sent by Swish that aren't known by this crate yet.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl RefundCallback {
+    /// [`RefundCallback`]: struct.RefundCallback.html
+    ///
+    /// Parses the body of a request Swish sent to the callback URL of a refund.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The json body of the request.
+    ///
+    /// # Returns
+    /// The [`RefundCallback`], or a `SwishClientError` if the body isn't one.
+    pub fn from_slice(body: &[u8]) -> Result<Self, SwishClientError> {
+        serde_json::from_slice(body).map_err(SwishClientError::from)
+    }
+}
+
+/// A payment or a refund callback, for callback URLs that receive both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Callback {
+    Payment(PaymentCallback),
+    Refund(RefundCallback),
+}
+
+impl Callback {
+    /// [`Callback`]: enum.Callback.html
+    ///
+    /// Parses the body of a request Swish sent to a callback URL.
+    /// It's seen as a refund if it refers to an original payment.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The json body of the request.
+    ///
+    /// # Returns
+    /// The [`Callback`], or a `SwishClientError` if the body isn't one.
+    pub fn from_slice(body: &[u8]) -> Result<Self, SwishClientError> {
+        let value: serde_json::Value = serde_json::from_slice(body)?;
+        let is_refund = ["originalPaymentReference", "originalpaymentReference"]
+            .iter()
+            .any(|field| value.get(field).is_some());
+        if is_refund {
+            serde_json::from_value(value).map(Callback::Refund)
+        } else {
+            serde_json::from_value(value).map(Callback::Payment)
+        }
+        .map_err(SwishClientError::from)
+    }
+
+    /// Gets the id of the payment or refund.
+    pub fn id(&self) -> &str {
+        match *self {
+            Callback::Payment(ref callback) => &callback.id,
+            Callback::Refund(ref callback) => &callback.id,
+        }
+    }
+
+    /// Gets the alias of the merchant the callback is for, i.e. the payee
+    /// of a payment or the payer of a refund.
+    pub fn merchant_alias(&self) -> Option<&str> {
+        match *self {
+            Callback::Payment(ref callback) => callback.payee_alias.as_ref(),
+            Callback::Refund(ref callback) => callback.payer_alias.as_ref(),
+        }
+        .map(String::as_str)
+    }
+
+    /// Gets the [`PaymentEvent`](../event/enum.PaymentEvent.html) of the callback.
+    pub fn event(&self) -> PaymentEvent {
+        match *self {
+            Callback::Payment(ref callback) => PaymentEvent::from(callback),
+            Callback::Refund(ref callback) => PaymentEvent::from(callback),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! # The callback_axum module
+//!
+//! Contains [axum](https://docs.rs/axum) extractors of the callbacks Swish posts
+//! to the callback URL of a payment or a refund, so that a callback handler can be
+//! mounted in an axum app. It's only built with the `axum` feature.
+//!
+//! A body that isn't a callback is rejected with `400 Bad Request`, and one
+//! larger than [`CALLBACK_MAX_BODY_LENGTH`](../callback/constant.CALLBACK_MAX_BODY_LENGTH.html)
+//! with `413 Payload Too Large`. Answer a callback with a 2xx status once it's
+//! handled, as Swish retries it otherwise.
+//!
+//! # Example
+//!
+//! ```
+//! extern crate axum;
+//! extern crate swish_api;
+//!
+//! use axum::http::StatusCode;
+//! use axum::routing::post;
+//! use axum::Router;
+//! use std::future::{self, Ready};
+//! use swish_api::callback_axum::{SwishCallback, SwishPaymentCallback};
+//!
+//! fn payment_callback(SwishPaymentCallback(callback): SwishPaymentCallback) -> Ready<StatusCode> {
+//!     println!("{} is {}", callback.id, callback.status);
+//!     future::ready(StatusCode::OK)
+//! }
+//!
+//! fn any_callback(SwishCallback(callback): SwishCallback) -> Ready<StatusCode> {
+//!     println!("{:?}", callback.event());
+//!     future::ready(StatusCode::OK)
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route("/swish/payments", post(payment_callback))
+//!     .route("/swish/callbacks", post(any_callback));
+//! ```
+//!
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use callback::{Callback, PaymentCallback, RefundCallback, CALLBACK_MAX_BODY_LENGTH};
+use error::SwishClientError;
+use futures_util::FutureExt;
+use std::error;
+use std::fmt;
+use std::future::Future;
+
+/// Why a callback was rejected, which is answered with the status of
+/// [`status`](#method.status).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CallbackRejection {
+    /// The body couldn't be read, e.g. because it's larger than the body limit of the app.
+    Body(BytesRejection),
+    /// The body is larger than
+    /// [`CALLBACK_MAX_BODY_LENGTH`](../callback/constant.CALLBACK_MAX_BODY_LENGTH.html).
+    TooLarge,
+    /// The body isn't a callback.
+    Invalid(SwishClientError),
+}
+
+impl CallbackRejection {
+    /// Gets the status the rejection is answered with.
+    pub fn status(&self) -> StatusCode {
+        match *self {
+            CallbackRejection::Body(ref rejection) => rejection.status(),
+            CallbackRejection::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            CallbackRejection::Invalid(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl fmt::Display for CallbackRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CallbackRejection::Body(ref rejection) => {
+                write!(f, "the body couldn't be read: {}", rejection)
+            }
+            CallbackRejection::TooLarge => write!(f, "the body is too large"),
+            CallbackRejection::Invalid(ref error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for CallbackRejection {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            CallbackRejection::Body(ref rejection) => Some(rejection),
+            CallbackRejection::TooLarge => None,
+            CallbackRejection::Invalid(ref error) => Some(error),
+        }
+    }
+}
+
+impl IntoResponse for CallbackRejection {
+    fn into_response(self) -> Response {
+        log_debug!("Rejected a callback: {}", self);
+        (self.status(), self.to_string()).into_response()
+    }
+}
+
+/// Parses a callback from the body of a request.
+fn parse<T>(
+    body: Result<Bytes, BytesRejection>,
+    from_slice: fn(&[u8]) -> Result<T, SwishClientError>,
+) -> Result<T, CallbackRejection> {
+    let body = body.map_err(CallbackRejection::Body)?;
+    if body.len() > CALLBACK_MAX_BODY_LENGTH {
+        return Err(CallbackRejection::TooLarge);
+    }
+    from_slice(&body).map_err(CallbackRejection::Invalid)
+}
+
+macro_rules! callback_extractor {
+    ($(#[$meta:meta])* pub struct $name:ident(pub $callback:ident);) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name(pub $callback);
+
+        impl<S: Send + Sync> FromRequest<S> for $name {
+            type Rejection = CallbackRejection;
+
+            fn from_request(
+                request: Request,
+                state: &S,
+            ) -> impl Future<Output = Result<Self, CallbackRejection>> + Send {
+                Bytes::from_request(request, state)
+                    .map(|body| parse(body, $callback::from_slice).map($name))
+            }
+        }
+    };
+}
+
+callback_extractor!(
+    /// Extracts a [`Callback`](../callback/enum.Callback.html) of a payment
+    /// or a refund, for callback URLs that receive both.
+    pub struct SwishCallback(pub Callback);
+);
+
+callback_extractor!(
+    /// Extracts the [`PaymentCallback`](../callback/struct.PaymentCallback.html)
+    /// posted to the callback URL of a payment.
+    pub struct SwishPaymentCallback(pub PaymentCallback);
+);
+
+callback_extractor!(
+    /// Extracts the [`RefundCallback`](../callback/struct.RefundCallback.html)
+    /// posted to the callback URL of a refund.
+    pub struct SwishRefundCallback(pub RefundCallback);
+);
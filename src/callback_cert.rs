@@ -0,0 +1,216 @@
+//! # The callback_cert module
+//!
+//! Contains the verification of the client certificate Swish presents
+//! when it posts a callback, to make sure the callback comes from Swish.
+//!
+//! The certificate is verified against the Swish CA, which can be downloaded
+//! from the Swish developer portal. When the TLS is terminated in front of
+//! the application, the terminator has to forward the certificate presented
+//! by the client, e.g. nginx with `$ssl_client_cert` in a header.
+//!
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::stack::Stack;
+use openssl::x509::store::{X509Store, X509StoreBuilder};
+use openssl::x509::{X509PurposeId, X509StoreContext, X509};
+use std::error;
+use std::fmt;
+
+/// A certificate couldn't be verified to be one of Swish.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CertificateError {
+    /// The certificates couldn't be read.
+    Parse(ErrorStack),
+    /// No certificate was given.
+    Missing,
+    /// The certificate isn't issued by the Swish CA for clients, or isn't valid anymore.
+    Untrusted(String),
+    /// The certificate is issued by the Swish CA, but to someone else.
+    CommonName {
+        expected: String,
+        actual: Option<String>,
+    },
+}
+
+impl fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CertificateError::Parse(ref err) => {
+                write!(f, "couldn't read the certificates: {}", err)
+            }
+            CertificateError::Missing => write!(f, "no certificate was given"),
+            CertificateError::Untrusted(ref reason) => {
+                write!(f, "the certificate isn't trusted: {}", reason)
+            }
+            CertificateError::CommonName {
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "the certificate is issued to {}, not {}",
+                actual.as_deref().unwrap_or("no one"),
+                expected
+            ),
+        }
+    }
+}
+
+impl error::Error for CertificateError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            CertificateError::Parse(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorStack> for CertificateError {
+    fn from(error: ErrorStack) -> CertificateError {
+        CertificateError::Parse(error)
+    }
+}
+
+/// Verifies the client certificates presented by callbacks.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::callback_cert::CallbackCertVerifier;
+///
+/// let ca = include_bytes!("../tests/certs/swish_root_ca.pem");
+/// let verifier = CallbackCertVerifier::from_pem(ca).unwrap();
+///
+/// let chain = include_bytes!("../tests/certs/swish_callback_chain.pem");
+/// assert!(verifier.verify_pem(chain).is_ok());
+/// ```
+pub struct CallbackCertVerifier {
+    store: X509Store,
+    common_name: Option<String>,
+}
+
+impl fmt::Debug for CallbackCertVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CallbackCertVerifier")
+            .field("common_name", &self.common_name)
+            .finish()
+    }
+}
+
+impl CallbackCertVerifier {
+    /// [`CallbackCertVerifier`]: struct.CallbackCertVerifier.html
+    ///
+    /// Creates a new [`CallbackCertVerifier`] trusting the given CA certificates.
+    ///
+    /// # Arguments
+    ///
+    /// * `ca` - The PEM encoded Swish CA, which may hold several certificates.
+    ///
+    /// # Returns
+    /// The [`CallbackCertVerifier`], or a `CertificateError` if the CA couldn't be read.
+    pub fn from_pem(ca: &[u8]) -> Result<Self, CertificateError> {
+        let certificates = X509::stack_from_pem(ca)?;
+        if certificates.is_empty() {
+            return Err(CertificateError::Missing);
+        }
+
+        let mut store = X509StoreBuilder::new()?;
+        for certificate in certificates {
+            store.add_cert(certificate)?;
+        }
+        store.set_purpose(X509PurposeId::SSL_CLIENT)?;
+
+        Ok(CallbackCertVerifier {
+            store: store.build(),
+            common_name: None,
+        })
+    }
+
+    /// Also requires the certificate to be issued to the given common name.
+    ///
+    /// # Arguments
+    ///
+    /// * `common_name` - The common name of the certificate Swish uses for callbacks.
+    pub fn common_name(mut self, common_name: &str) -> Self {
+        self.common_name = Some(common_name.to_owned());
+        self
+    }
+
+    /// Verifies a PEM encoded client certificate.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain` - The certificate, followed by any intermediate certificates.
+    ///
+    /// # Returns
+    /// `Ok` if the certificate is issued by the Swish CA,
+    /// or a `CertificateError` describing why it isn't trusted.
+    pub fn verify_pem(&self, chain: &[u8]) -> Result<(), CertificateError> {
+        let mut certificates = X509::stack_from_pem(chain)?.into_iter();
+        match certificates.next() {
+            Some(certificate) => self.verify(&certificate, certificates),
+            None => Err(CertificateError::Missing),
+        }
+    }
+
+    /// Verifies a DER encoded client certificate.
+    ///
+    /// # Arguments
+    ///
+    /// * `certificate` - The certificate.
+    /// * `intermediates` - The intermediate certificates presented with it.
+    ///
+    /// # Returns
+    /// `Ok` if the certificate is issued by the Swish CA,
+    /// or a `CertificateError` describing why it isn't trusted.
+    pub fn verify_der(
+        &self,
+        certificate: &[u8],
+        intermediates: &[&[u8]],
+    ) -> Result<(), CertificateError> {
+        let intermediates = intermediates
+            .iter()
+            .map(|intermediate| X509::from_der(intermediate))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.verify(&X509::from_der(certificate)?, intermediates)
+    }
+
+    fn verify<I>(&self, certificate: &X509, intermediates: I) -> Result<(), CertificateError>
+    where
+        I: IntoIterator<Item = X509>,
+    {
+        let mut chain = Stack::new()?;
+        for intermediate in intermediates {
+            chain.push(intermediate)?;
+        }
+
+        let mut context = X509StoreContext::new()?;
+        let verified = context.init(&self.store, certificate, &chain, |context| {
+            Ok(if context.verify_cert()? {
+                Ok(())
+            } else {
+                Err(context.error().error_string().to_owned())
+            })
+        })?;
+        verified.map_err(CertificateError::Untrusted)?;
+
+        match self.common_name {
+            Some(ref expected) => {
+                let actual = certificate
+                    .subject_name()
+                    .entries_by_nid(Nid::COMMONNAME)
+                    .next()
+                    .and_then(|entry| entry.data().to_string().ok());
+                if actual.as_ref() == Some(expected) {
+                    Ok(())
+                } else {
+                    Err(CertificateError::CommonName {
+                        expected: expected.clone(),
+                        actual,
+                    })
+                }
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! # The callback_dedup module
+//!
+//! Contains the store that keeps track of the callbacks already received,
+//! as Swish may deliver the same callback more than once.
+//!
+use callback::Callback;
+use client::Status;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+
+/// The number of callbacks remembered by
+/// [`InMemoryDedupStore::default`](struct.InMemoryDedupStore.html).
+pub const DEFAULT_DEDUP_CAPACITY: usize = 10_000;
+
+/// Identifies a delivery of a callback, by the id of
+/// the payment or refund and the status it was delivered with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallbackKey {
+    pub id: String,
+    pub status: Status,
+}
+
+impl<'a> From<&'a Callback> for CallbackKey {
+    fn from(callback: &'a Callback) -> Self {
+        let status = match *callback {
+            Callback::Payment(ref callback) => &callback.status,
+            Callback::Refund(ref callback) => &callback.status,
+        };
+        CallbackKey {
+            id: callback.id().to_owned(),
+            status: status.clone(),
+        }
+    }
+}
+
+/// Keeps track of the callbacks already received.
+///
+/// Implement this with e.g. a database table when several instances receive
+/// callbacks, so that a callback delivered to two of them is only handled once.
+pub trait CallbackDedupStore: fmt::Debug + Send + Sync {
+    /// Records that a callback was received.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The [`CallbackKey`](struct.CallbackKey.html) of the callback.
+    ///
+    /// # Returns
+    /// `true` the first time the key is recorded, and `false` for duplicates.
+    fn insert(&self, key: &CallbackKey) -> bool;
+}
+
+/// Remembers the last `capacity` callbacks in memory,
+/// which is enough for a single instance receiving callbacks.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::callback::Callback;
+/// use swish_api::callback_dedup::{CallbackDedupStore, CallbackKey, InMemoryDedupStore};
+///
+/// let body = br#"{
+///     "id": "AB23D7406ECE4542A80152D909EF9F6B",
+///     "amount": 100.00,
+///     "currency": "SEK",
+///     "status": "PAID",
+///     "dateCreated": "2015-02-19T22:01:53+01:00"
+/// }"#;
+/// let key = CallbackKey::from(&Callback::from_slice(body).unwrap());
+///
+/// let store = InMemoryDedupStore::default();
+/// assert!(store.insert(&key));
+/// assert!(!store.insert(&key));
+/// ```
+#[derive(Debug)]
+pub struct InMemoryDedupStore {
+    capacity: usize,
+    keys: Mutex<(HashSet<CallbackKey>, VecDeque<CallbackKey>)>,
+}
+
+impl InMemoryDedupStore {
+    /// [`InMemoryDedupStore`]: struct.InMemoryDedupStore.html
+    ///
+    /// Creates a new [`InMemoryDedupStore`].
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of callbacks to remember, after which the oldest are forgotten.
+    pub fn new(capacity: usize) -> Self {
+        InMemoryDedupStore {
+            capacity: capacity.max(1),
+            keys: Mutex::new((HashSet::new(), VecDeque::new())),
+        }
+    }
+}
+
+impl Default for InMemoryDedupStore {
+    fn default() -> Self {
+        InMemoryDedupStore::new(DEFAULT_DEDUP_CAPACITY)
+    }
+}
+
+impl CallbackDedupStore for InMemoryDedupStore {
+    fn insert(&self, key: &CallbackKey) -> bool {
+        let mut keys = match self.keys.lock() {
+            Ok(keys) => keys,
+            // Handling a callback twice is better than not at all.
+            Err(_) => return true,
+        };
+        let (ref mut seen, ref mut order) = *keys;
+        if !seen.insert(key.clone()) {
+            return false;
+        }
+        order.push_back(key.clone());
+        while order.len() > self.capacity {
+            if let Some(oldest) = order.pop_front() {
+                seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,223 @@
+//! # The callback_ip module
+//!
+//! Contains the IP addresses Swish posts callbacks from, for deployments
+//! that only accept callbacks from Swish at the network layer.
+//!
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use validation::ValidationError;
+
+/// The addresses and ranges Swish posts callbacks from in production,
+/// as published in the Swish integration guide.
+pub const SWISH_CALLBACK_RANGES: [&str; 3] =
+    ["213.132.115.94/32", "35.228.51.224/28", "34.140.166.128/28"];
+
+/// A range of IP addresses, written in CIDR notation.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::callback_ip::IpRange;
+///
+/// let range: IpRange = "35.228.51.224/28".parse().unwrap();
+///
+/// assert!(range.contains("35.228.51.230".parse().unwrap()));
+/// assert!(!range.contains("35.228.51.240".parse().unwrap()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// [`IpRange`]: struct.IpRange.html
+    ///
+    /// Creates a new [`IpRange`].
+    ///
+    /// # Arguments
+    ///
+    /// * `network` - The first address of the range.
+    /// * `prefix_len` - The number of leading bits shared by the addresses of the range.
+    ///
+    /// # Returns
+    /// The [`IpRange`], or a `ValidationError` if the prefix is longer than the address
+    /// or the address isn't the first one of the range.
+    pub fn new(network: IpAddr, prefix_len: u8) -> Result<Self, ValidationError> {
+        let error = ValidationError::InvalidFormat {
+            field: "ipRange",
+            expected: "an IP address or a range in CIDR notation",
+        };
+        let (bits, max_len) = to_bits(network);
+        if prefix_len > max_len || bits & !mask(prefix_len, max_len) != 0 {
+            return Err(error);
+        }
+        Ok(IpRange {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Gets the first address of the range.
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    /// Gets the number of leading bits shared by the addresses of the range.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Whether the address is in the range.
+    /// IPv4 addresses mapped to IPv6, as given by dual-stack listeners, are seen as IPv4.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let (network, max_len) = to_bits(self.network);
+        let (ip, ip_max_len) = to_bits(unmap(ip));
+        max_len == ip_max_len && ip & mask(self.prefix_len, max_len) == network
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = ValidationError;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let error = ValidationError::InvalidFormat {
+            field: "ipRange",
+            expected: "an IP address or a range in CIDR notation",
+        };
+        let mut parts = range.trim().splitn(2, '/');
+        let network: IpAddr = parts
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .map_err(|_| error.clone())?;
+        let prefix_len = match parts.next() {
+            Some(prefix_len) => prefix_len.parse().map_err(|_| error)?,
+            None => to_bits(network).1,
+        };
+        IpRange::new(network, prefix_len)
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// The IP addresses callbacks are accepted from.
+///
+/// Defaults to the ranges published by Swish, which can be extended
+/// or replaced e.g. from configuration if Swish changes them.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::callback_ip::CallbackSources;
+///
+/// let sources = CallbackSources::swish().with("10.0.0.0/8".parse().unwrap());
+/// assert!(sources.contains("10.1.2.3".parse().unwrap()));
+///
+/// let sources: CallbackSources = "192.0.2.1, 198.51.100.0/24".parse().unwrap();
+/// assert!(!sources.contains("213.132.115.94".parse().unwrap()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackSources {
+    ranges: Vec<IpRange>,
+}
+
+impl CallbackSources {
+    /// [`CallbackSources`]: struct.CallbackSources.html
+    ///
+    /// Creates new [`CallbackSources`] accepting only the given ranges.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranges` - The ranges callbacks are accepted from.
+    pub fn new(ranges: Vec<IpRange>) -> Self {
+        CallbackSources { ranges }
+    }
+
+    /// The [`SWISH_CALLBACK_RANGES`](constant.SWISH_CALLBACK_RANGES.html) published by Swish.
+    pub fn swish() -> Self {
+        CallbackSources::new(
+            SWISH_CALLBACK_RANGES
+                .iter()
+                .map(|range| range.parse().expect("the Swish ranges are valid"))
+                .collect(),
+        )
+    }
+
+    /// Also accepts callbacks from the given range.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The range to accept.
+    pub fn with(mut self, range: IpRange) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Gets the ranges callbacks are accepted from.
+    pub fn ranges(&self) -> &[IpRange] {
+        &self.ranges
+    }
+
+    /// Whether callbacks are accepted from the address.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+}
+
+impl Default for CallbackSources {
+    fn default() -> Self {
+        CallbackSources::swish()
+    }
+}
+
+/// Parses a comma separated list of ranges.
+impl FromStr for CallbackSources {
+    type Err = ValidationError;
+
+    fn from_str(ranges: &str) -> Result<Self, Self::Err> {
+        ranges
+            .split(',')
+            .filter(|range| !range.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(CallbackSources::new)
+    }
+}
+
+/// Whether the address is one Swish publishes that it posts callbacks from.
+///
+/// # Arguments
+///
+/// * `ip` - The address a callback was received from.
+pub fn is_swish_source(ip: IpAddr) -> bool {
+    CallbackSources::swish().contains(ip)
+}
+
+fn unmap(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        ip => ip,
+    }
+}
+
+/// Gets the address as bits, together with the number of bits of its kind.
+fn to_bits(ip: IpAddr) -> (u128, u8) {
+    match ip {
+        IpAddr::V4(v4) => (u128::from(u32::from(v4)), 32),
+        IpAddr::V6(v6) => (u128::from(v6), 128),
+    }
+}
+
+/// Gets the mask of a prefix of the given length.
+fn mask(prefix_len: u8, max_len: u8) -> u128 {
+    let all = u128::MAX >> (128 - u32::from(max_len));
+    all.checked_shl(u32::from(max_len - prefix_len))
+        .unwrap_or(0)
+        & all
+}
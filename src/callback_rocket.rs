@@ -0,0 +1,153 @@
+//! # The callback_rocket module
+//!
+//! Contains [Rocket](https://rocket.rs) data guards of the callbacks Swish posts
+//! to the callback URL of a payment or a refund, so that a callback handler can be
+//! mounted in a Rocket app. It's only built with the `rocket` feature.
+//!
+//! A body that isn't a callback is answered with `400 Bad Request`, and one
+//! larger than [`CALLBACK_MAX_BODY_LENGTH`](../callback/constant.CALLBACK_MAX_BODY_LENGTH.html)
+//! with `413 Payload Too Large`. Answer a callback with a 2xx status once it's
+//! handled, as Swish retries it otherwise.
+//!
+//! # Example
+//!
+//! ```edition2021
+//! #[macro_use]
+//! extern crate rocket;
+//! extern crate swish_api;
+//!
+//! use rocket::http::Status;
+//! use swish_api::callback_rocket::SwishPaymentCallback;
+//!
+//! #[post("/swish/callback", data = "<callback>")]
+//! fn swish_callback(callback: SwishPaymentCallback) -> Status {
+//!     println!("{} is {}", callback.0.id, callback.0.status);
+//!     Status::Ok
+//! }
+//!
+//! # fn main() {
+//! let rocket = rocket::build().mount("/", routes![swish_callback]);
+//! # }
+//! ```
+//!
+use callback::{Callback, PaymentCallback, RefundCallback, CALLBACK_MAX_BODY_LENGTH};
+use error::SwishClientError;
+use futures_util::FutureExt;
+use rocket::data::{ByteUnit, Capped, Data, FromData, Outcome};
+use rocket::http::Status;
+use rocket::outcome::Outcome::{Error, Success};
+use rocket::Request;
+use std::error;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+
+/// Why a callback was rejected, which is answered with the status of
+/// [`status`](#method.status).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CallbackRejection {
+    /// The body couldn't be read.
+    Body(io::Error),
+    /// The body is larger than
+    /// [`CALLBACK_MAX_BODY_LENGTH`](../callback/constant.CALLBACK_MAX_BODY_LENGTH.html).
+    TooLarge,
+    /// The body isn't a callback.
+    Invalid(SwishClientError),
+}
+
+impl CallbackRejection {
+    /// Gets the status the rejection is answered with.
+    pub fn status(&self) -> Status {
+        match *self {
+            CallbackRejection::TooLarge => Status::PayloadTooLarge,
+            CallbackRejection::Body(_) | CallbackRejection::Invalid(_) => Status::BadRequest,
+        }
+    }
+}
+
+impl fmt::Display for CallbackRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CallbackRejection::Body(ref error) => write!(f, "the body couldn't be read: {}", error),
+            CallbackRejection::TooLarge => write!(f, "the body is too large"),
+            CallbackRejection::Invalid(ref error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for CallbackRejection {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            CallbackRejection::Body(ref error) => Some(error),
+            CallbackRejection::TooLarge => None,
+            CallbackRejection::Invalid(ref error) => Some(error),
+        }
+    }
+}
+
+/// Parses a callback from the body of a request.
+fn parse<'r, T>(
+    body: io::Result<Capped<Vec<u8>>>,
+    from_slice: fn(&[u8]) -> Result<T, SwishClientError>,
+) -> Outcome<'r, T, CallbackRejection> {
+    let rejection = match body {
+        Ok(ref body) if !body.is_complete() => CallbackRejection::TooLarge,
+        Ok(body) => match from_slice(&body) {
+            Ok(callback) => return Success(callback),
+            Err(error) => CallbackRejection::Invalid(error),
+        },
+        Err(error) => CallbackRejection::Body(error),
+    };
+    log_debug!("Rejected a callback: {}", rejection);
+    Error((rejection.status(), rejection))
+}
+
+// `FromData` is an `async_trait`, whose expansion is written out by hand
+// as `async` isn't available in the 2015 edition.
+macro_rules! callback_guard {
+    ($(#[$meta:meta])* pub struct $name:ident(pub $callback:ident);) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name(pub $callback);
+
+        impl<'r> FromData<'r> for $name {
+            type Error = CallbackRejection;
+
+            fn from_data<'life0, 'async_trait>(
+                _request: &'r Request<'life0>,
+                data: Data<'r>,
+            ) -> Pin<Box<dyn Future<Output = Outcome<'r, Self>> + Send + 'async_trait>>
+            where
+                'r: 'async_trait,
+                'life0: 'async_trait,
+                Self: 'async_trait,
+            {
+                Box::pin(
+                    data.open(ByteUnit::from(CALLBACK_MAX_BODY_LENGTH))
+                        .into_bytes()
+                        .map(|body| parse(body, $callback::from_slice).map($name)),
+                )
+            }
+        }
+    };
+}
+
+callback_guard!(
+    /// Guards a [`Callback`](../callback/enum.Callback.html) of a payment
+    /// or a refund, for callback URLs that receive both.
+    pub struct SwishCallback(pub Callback);
+);
+
+callback_guard!(
+    /// Guards the [`PaymentCallback`](../callback/struct.PaymentCallback.html)
+    /// posted to the callback URL of a payment.
+    pub struct SwishPaymentCallback(pub PaymentCallback);
+);
+
+callback_guard!(
+    /// Guards the [`RefundCallback`](../callback/struct.RefundCallback.html)
+    /// posted to the callback URL of a refund.
+    pub struct SwishRefundCallback(pub RefundCallback);
+);
@@ -0,0 +1,344 @@
+//! # The callback_server module
+//!
+//! Contains a server that receives the callbacks Swish posts to the callback URL
+//! of a payment or a refund, and hands them on to a
+//! [`CallbackHandler`](trait.CallbackHandler.html).
+//!
+//! Swish only posts callbacks to https URLs, so give the server a `TlsAcceptor`
+//! unless the TLS is terminated in front of it, e.g. by a load balancer.
+//!
+use alias::MerchantAlias;
+pub use callback::CALLBACK_MAX_BODY_LENGTH;
+use callback::Callback;
+use callback_dedup::{CallbackDedupStore, CallbackKey, InMemoryDedupStore};
+use callback_ip::CallbackSources;
+use error::SwishClientError;
+use futures::sync::mpsc::UnboundedSender;
+use futures::{future, Async, Future, Poll, Stream};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use hyper_tls::MaybeHttpsStream;
+use native_tls::{HandshakeError, MidHandshakeTlsStream, TlsAcceptor, TlsStream};
+use std::fmt;
+use std::io;
+use std::mem;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio_core::net::{TcpListener, TcpStream};
+use tokio_core::reactor::Handle;
+
+/// The future returned by [`CallbackServer::serve`](struct.CallbackServer.html#method.serve),
+/// which runs until the listener fails.
+pub type CallbackServerFuture = Box<dyn Future<Item = (), Error = io::Error>>;
+
+/// Receives every valid callback posted to a
+/// [`CallbackServer`](struct.CallbackServer.html).
+///
+/// It's implemented for closures and for the sending half of a channel.
+pub trait CallbackHandler: Send + Sync + 'static {
+    /// Called with each [`Callback`](../callback/enum.Callback.html) that's received.
+    fn handle(&self, callback: Callback);
+}
+
+impl<F> CallbackHandler for F
+where
+    F: Fn(Callback) + Send + Sync + 'static,
+{
+    fn handle(&self, callback: Callback) {
+        self(callback)
+    }
+}
+
+/// Callbacks received after the receiver is dropped are discarded.
+impl CallbackHandler for UnboundedSender<Callback> {
+    fn handle(&self, callback: Callback) {
+        let _ = self.unbounded_send(callback);
+    }
+}
+
+/// A server that receives the callbacks of Swish.
+///
+/// # Example
+///
+/// ```no_run
+/// extern crate futures;
+/// extern crate swish_api;
+/// extern crate tokio_core;
+///
+/// use futures::sync::mpsc;
+/// use futures::{Future, Stream};
+/// use swish_api::callback_server::CallbackServer;
+/// use tokio_core::reactor::Core;
+///
+/// let mut core = Core::new().unwrap();
+/// let (sender, receiver) = mpsc::unbounded();
+///
+/// let server = CallbackServer::bind(&"0.0.0.0:8443".parse().unwrap(), &core.handle()).unwrap();
+/// core.handle()
+///     .spawn(server.serve(sender).map_err(|error| eprintln!("{}", error)));
+///
+/// core.run(receiver.for_each(|callback| {
+///     println!("{:?}", callback.event());
+///     Ok(())
+/// }))
+/// .unwrap();
+/// ```
+pub struct CallbackServer {
+    listener: TcpListener,
+    handle: Handle,
+    tls_acceptor: Option<TlsAcceptor>,
+    merchant: Option<MerchantAlias>,
+    sources: Option<CallbackSources>,
+    dedup_store: Arc<dyn CallbackDedupStore>,
+}
+
+impl CallbackServer {
+    /// [`CallbackServer`]: struct.CallbackServer.html
+    ///
+    /// Creates a new [`CallbackServer`] listening on the given address.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on.
+    /// * `handle` - The tokio-core handle the connections are handled on.
+    ///
+    /// # Returns
+    /// The [`CallbackServer`], or an `io::Error` if the address couldn't be bound.
+    pub fn bind(addr: &SocketAddr, handle: &Handle) -> io::Result<Self> {
+        Ok(CallbackServer {
+            listener: TcpListener::bind(addr, handle)?,
+            handle: handle.clone(),
+            tls_acceptor: None,
+            merchant: None,
+            sources: None,
+            dedup_store: Arc::new(InMemoryDedupStore::default()),
+        })
+    }
+
+    /// Serves https using the given acceptor, instead of plain http.
+    ///
+    /// # Arguments
+    ///
+    /// * `tls_acceptor` - An acceptor with the certificate of the callback URL.
+    pub fn tls(mut self, tls_acceptor: TlsAcceptor) -> Self {
+        self.tls_acceptor = Some(tls_acceptor);
+        self
+    }
+
+    /// Only accepts callbacks for the given merchant, i.e. where it's
+    /// the payee of a payment or the payer of a refund.
+    ///
+    /// # Arguments
+    ///
+    /// * `merchant` - The Swish number of the merchant.
+    pub fn merchant(mut self, merchant: MerchantAlias) -> Self {
+        self.merchant = Some(merchant);
+        self
+    }
+
+    /// Only accepts connections from the given addresses, and closes any other
+    /// connection without answering. Don't use this behind a proxy, as the
+    /// connections then come from the proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - The [`CallbackSources`](../callback_ip/struct.CallbackSources.html)
+    ///   to accept connections from, e.g. the ones published by Swish.
+    pub fn sources(mut self, sources: CallbackSources) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    /// Sets the store keeping track of the callbacks already received.
+    /// Defaults to an [`InMemoryDedupStore`](../callback_dedup/struct.InMemoryDedupStore.html).
+    ///
+    /// # Arguments
+    ///
+    /// * `dedup_store` - The [`CallbackDedupStore`](../callback_dedup/trait.CallbackDedupStore.html)
+    ///   to use.
+    pub fn dedup_store<S: CallbackDedupStore + 'static>(mut self, dedup_store: S) -> Self {
+        self.dedup_store = Arc::new(dedup_store);
+        self
+    }
+
+    /// Gets the address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serves callbacks until the listener fails.
+    ///
+    /// Valid callbacks are answered with `200 OK` and handed to the handler,
+    /// unless they have been received before. Anything else is answered
+    /// with an error status, and isn't handed on.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The [`CallbackHandler`](trait.CallbackHandler.html)
+    ///   receiving the callbacks.
+    ///
+    /// # Returns
+    /// A [`CallbackServerFuture`](type.CallbackServerFuture.html) that has to be run
+    /// for the server to accept connections.
+    pub fn serve<H: CallbackHandler>(self, handler: H) -> CallbackServerFuture {
+        let CallbackServer {
+            listener,
+            handle,
+            tls_acceptor,
+            merchant,
+            sources,
+            dedup_store,
+        } = self;
+        let receiver = Arc::new(Receiver {
+            handler,
+            merchant,
+            dedup_store,
+        });
+        let http = Http::new();
+
+        Box::new(listener.incoming().for_each(move |(stream, addr)| {
+            if let Some(ref sources) = sources {
+                if !sources.contains(addr.ip()) {
+                    log_debug!("Closed a callback connection from {}", addr);
+                    return Ok(());
+                }
+            }
+
+            let stream: Box<dyn Future<Item = MaybeHttpsStream<TcpStream>, Error = io::Error>> =
+                match tls_acceptor {
+                    Some(ref tls_acceptor) => Box::new(
+                        Accepting::Start(tls_acceptor.clone(), stream).map(MaybeHttpsStream::from),
+                    ),
+                    None => Box::new(future::ok(MaybeHttpsStream::Http(stream))),
+                };
+
+            let receiver = receiver.clone();
+            let http = http.clone();
+            let connection = stream.map_err(|_| ()).and_then(move |stream| {
+                let service = service_fn(move |request| receive(request, receiver.clone()));
+                http.serve_connection(stream, service).map_err(|_| ())
+            });
+            handle.spawn(connection);
+            Ok(())
+        }))
+    }
+}
+
+/// Why a callback wasn't accepted.
+#[derive(Debug)]
+enum Rejection {
+    Body(::hyper::Error),
+    TooLarge,
+    Invalid(SwishClientError),
+    OtherMerchant,
+}
+
+impl Rejection {
+    fn status(&self) -> StatusCode {
+        match *self {
+            Rejection::Body(_) | Rejection::Invalid(_) => StatusCode::BAD_REQUEST,
+            Rejection::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Rejection::OtherMerchant => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rejection::Body(ref error) => write!(f, "the body couldn't be read: {}", error),
+            Rejection::TooLarge => write!(f, "the body is too large"),
+            Rejection::Invalid(ref error) => write!(f, "{}", error),
+            Rejection::OtherMerchant => write!(f, "it's for another merchant"),
+        }
+    }
+}
+
+/// What's shared by the requests posted to the server.
+struct Receiver<H> {
+    handler: H,
+    merchant: Option<MerchantAlias>,
+    dedup_store: Arc<dyn CallbackDedupStore>,
+}
+
+/// Receives a single request posted to the server.
+fn receive<H: CallbackHandler>(
+    request: Request<Body>,
+    receiver: Arc<Receiver<H>>,
+) -> Box<dyn Future<Item = Response<Body>, Error = ::hyper::Error> + Send> {
+    if request.method() != Method::POST {
+        return Box::new(future::ok(respond(StatusCode::METHOD_NOT_ALLOWED)));
+    }
+
+    let received = request
+        .into_body()
+        .map_err(Rejection::Body)
+        .fold(Vec::new(), |mut body, chunk| {
+            if body.len() + chunk.len() > CALLBACK_MAX_BODY_LENGTH {
+                return Err(Rejection::TooLarge);
+            }
+            body.extend_from_slice(&chunk);
+            Ok(body)
+        })
+        .and_then(move |body| {
+            let callback = Callback::from_slice(&body).map_err(Rejection::Invalid)?;
+            match receiver.merchant {
+                Some(ref merchant) if callback.merchant_alias() != Some(merchant.as_str()) => {
+                    Err(Rejection::OtherMerchant)
+                }
+                _ => Ok((callback, receiver)),
+            }
+        })
+        .then(|received| {
+            Ok(match received {
+                Ok((callback, receiver)) => {
+                    if receiver.dedup_store.insert(&CallbackKey::from(&callback)) {
+                        receiver.handler.handle(callback);
+                    } else {
+                        log_debug!("Received callback {} again", callback.id());
+                    }
+                    respond(StatusCode::OK)
+                }
+                Err(rejection) => {
+                    log_debug!("Rejected a callback: {}", rejection);
+                    respond(rejection.status())
+                }
+            })
+        });
+    Box::new(received)
+}
+
+fn respond(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// The TLS handshake of a connection to the server.
+enum Accepting {
+    Start(TlsAcceptor, TcpStream),
+    Handshaking(MidHandshakeTlsStream<TcpStream>),
+    Done,
+}
+
+impl Future for Accepting {
+    type Item = TlsStream<TcpStream>;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let result = match mem::replace(self, Accepting::Done) {
+            Accepting::Start(tls_acceptor, stream) => tls_acceptor.accept(stream),
+            Accepting::Handshaking(handshake) => handshake.handshake(),
+            Accepting::Done => panic!("polled a finished handshake"),
+        };
+        match result {
+            Ok(stream) => Ok(Async::Ready(stream)),
+            Err(HandshakeError::WouldBlock(handshake)) => {
+                *self = Accepting::Handshaking(handshake);
+                Ok(Async::NotReady)
+            }
+            Err(HandshakeError::Failure(error)) => Err(io::Error::other(error)),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! # The callback_warp module
+//!
+//! Contains [warp](https://docs.rs/warp) filters that extract the callbacks Swish
+//! posts to the callback URL of a payment or a refund, so that a callback handler
+//! can be mounted in a warp server. It's only built with the `warp` feature.
+//!
+//! A body larger than [`CALLBACK_MAX_BODY_LENGTH`](../callback/constant.CALLBACK_MAX_BODY_LENGTH.html)
+//! is rejected with `413 Payload Too Large`, and one that isn't a callback with an
+//! [`InvalidCallback`](struct.InvalidCallback.html), which warp answers with
+//! `500 Internal Server Error` unless the filter is recovered with
+//! [`recover_invalid_callback`](fn.recover_invalid_callback.html).
+//! Answer a callback with a 2xx status once it's handled, as Swish retries it otherwise.
+//!
+//! # Example
+//!
+//! ```
+//! extern crate swish_api;
+//! extern crate warp;
+//!
+//! use swish_api::callback::PaymentCallback;
+//! use swish_api::callback_warp;
+//! use warp::http::StatusCode;
+//! use warp::Filter;
+//!
+//! let callbacks = warp::post()
+//!     .and(warp::path!("swish" / "callback"))
+//!     .and(callback_warp::payment_callback())
+//!     .map(|callback: PaymentCallback| {
+//!         println!("{} is {}", callback.id, callback.status);
+//!         StatusCode::OK
+//!     })
+//!     .recover(callback_warp::recover_invalid_callback);
+//! ```
+//!
+use callback::{Callback, PaymentCallback, RefundCallback, CALLBACK_MAX_BODY_LENGTH};
+use error::SwishClientError;
+use std::future::{self, Ready};
+use warp::http::StatusCode;
+use warp::hyper::body::Bytes;
+use warp::reject::{self, Reject, Rejection};
+use warp::reply::{self, WithStatus};
+use warp::Filter;
+
+/// The rejection of a body that isn't a callback.
+#[derive(Debug)]
+pub struct InvalidCallback(pub SwishClientError);
+
+impl Reject for InvalidCallback {}
+
+/// Extracts a body of at most `CALLBACK_MAX_BODY_LENGTH` bytes with `from_slice`.
+fn callback_body<T: Send + 'static>(
+    from_slice: fn(&[u8]) -> Result<T, SwishClientError>,
+) -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
+    warp::body::content_length_limit(CALLBACK_MAX_BODY_LENGTH as u64)
+        .and(warp::body::bytes())
+        .and_then(move |body: Bytes| {
+            future::ready(from_slice(&body).map_err(|error| reject::custom(InvalidCallback(error))))
+        })
+}
+
+/// Creates a filter that extracts a [`Callback`](../callback/enum.Callback.html)
+/// of a payment or a refund, for callback URLs that receive both.
+pub fn callback() -> impl Filter<Extract = (Callback,), Error = Rejection> + Clone {
+    callback_body(Callback::from_slice)
+}
+
+/// Creates a filter that extracts the
+/// [`PaymentCallback`](../callback/struct.PaymentCallback.html)
+/// posted to the callback URL of a payment.
+pub fn payment_callback() -> impl Filter<Extract = (PaymentCallback,), Error = Rejection> + Clone
+{
+    callback_body(PaymentCallback::from_slice)
+}
+
+/// Creates a filter that extracts the
+/// [`RefundCallback`](../callback/struct.RefundCallback.html)
+/// posted to the callback URL of a refund.
+pub fn refund_callback() -> impl Filter<Extract = (RefundCallback,), Error = Rejection> + Clone {
+    callback_body(RefundCallback::from_slice)
+}
+
+/// Answers an [`InvalidCallback`](struct.InvalidCallback.html) with `400 Bad Request`,
+/// and passes any other rejection on. Meant for `Filter::recover`.
+///
+/// # Arguments
+///
+/// * `rejection` - The rejection of the filter.
+pub fn recover_invalid_callback(
+    rejection: Rejection,
+) -> Ready<Result<WithStatus<String>, Rejection>> {
+    future::ready(match rejection.find::<InvalidCallback>() {
+        Some(InvalidCallback(error)) => {
+            log_debug!("Rejected a callback: {}", error);
+            Ok(reply::with_status(error.to_string(), StatusCode::BAD_REQUEST))
+        }
+        None => Err(rejection),
+    })
+}
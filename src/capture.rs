@@ -0,0 +1,69 @@
+//! # The capture module
+//!
+//! Records the raw requests and responses exchanged with the Swish API,
+//! which makes disputes with Swish support resolvable without packet captures.
+//!
+//! Captured exchanges contain personal data, such as payer aliases,
+//! and should be handled accordingly.
+//!
+use hyper::header::HeaderMap;
+use hyper::{Method, StatusCode, Uri};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use timing::RequestTimings;
+
+/// A request sent to the Swish API and the response it got, if any.
+#[derive(Debug, Clone)]
+pub struct CapturedExchange {
+    pub correlation_id: String,
+    pub method: Method,
+    pub uri: Uri,
+    pub request_headers: HeaderMap,
+    /// The exact serialized request body, empty for GET requests.
+    pub request_body: String,
+    /// `None` when the request failed before a response was received.
+    pub response: Option<CapturedResponse>,
+    /// Where the time of the request was spent,
+    /// `None` when the request failed before a response was received.
+    pub timings: Option<RequestTimings>,
+}
+
+/// A response from the Swish API.
+#[derive(Debug, Clone)]
+pub struct CapturedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+/// Keeps the last `capacity` exchanges.
+#[derive(Debug)]
+pub(crate) struct CaptureBuffer {
+    capacity: usize,
+    exchanges: Mutex<VecDeque<CapturedExchange>>,
+}
+
+impl CaptureBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        CaptureBuffer {
+            capacity,
+            exchanges: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub(crate) fn record(&self, exchange: CapturedExchange) {
+        if let Ok(mut exchanges) = self.exchanges.lock() {
+            while exchanges.len() >= self.capacity.max(1) {
+                exchanges.pop_front();
+            }
+            exchanges.push_back(exchange);
+        }
+    }
+
+    pub(crate) fn exchanges(&self) -> Vec<CapturedExchange> {
+        self.exchanges
+            .lock()
+            .map(|exchanges| exchanges.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,313 @@
+//! # The chaos module
+//!
+//! Contains a proxy that injects failures into the calls a client makes to the
+//! Swish API, e.g. to a [`SwishSimulator`](../simulator/struct.SwishSimulator.html),
+//! so that retries and circuit breakers can be tested against how the client
+//! actually behaves when Swish is slow, unreachable or broken.
+//! It's only built with the `chaos` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! extern crate futures;
+//! extern crate hyper;
+//! extern crate swish_api;
+//! extern crate tokio_core;
+//!
+//! use futures::Future;
+//! use hyper::StatusCode;
+//! use std::time::Duration;
+//! use swish_api::chaos::{ChaosProxy, ChaosSchedule, Fault};
+//! use tokio_core::reactor::Core;
+//!
+//! let core = Core::new().unwrap();
+//! let schedule = ChaosSchedule::new()
+//!     .then(Fault::Status(StatusCode::SERVICE_UNAVAILABLE))
+//!     .then(Fault::Latency(Duration::from_secs(5)))
+//!     .then_pass()
+//!     .repeat();
+//! let proxy = ChaosProxy::bind(
+//!     &"127.0.0.1:0".parse().unwrap(),
+//!     &core.handle(),
+//!     "http://127.0.0.1:8080/swish-cpcapi/api/v1/",
+//!     schedule,
+//! )
+//! .unwrap();
+//! // Give this to `SwishClientBuilder::api_url`.
+//! let api_url = proxy.api_url().unwrap();
+//! core.handle()
+//!     .spawn(proxy.serve().map_err(|error| eprintln!("{}", error)));
+//! ```
+//!
+use futures::sync::oneshot;
+use futures::{future, Future, Stream};
+use hyper::client::HttpConnector;
+use hyper::header::{CONTENT_LENGTH, HOST};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Client, Request, Response, StatusCode, Uri};
+use hyper_tls::HttpsConnector;
+use native_tls::TlsConnector;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio_core::net::TcpListener;
+use tokio_core::reactor::Handle;
+
+/// The future returned by [`ChaosProxy::serve`](struct.ChaosProxy.html#method.serve),
+/// which runs until the listener fails.
+pub type ChaosProxyFuture = Box<dyn Future<Item = (), Error = io::Error>>;
+
+/// A failure injected into a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// The call is forwarded after the delay.
+    Latency(Duration),
+    /// The connection is closed without a response.
+    Reset,
+    /// The call is answered with the status, without being forwarded.
+    Status(StatusCode),
+    /// The call is forwarded, but only the first half of the body of the response is returned.
+    MalformedBody,
+}
+
+/// The failures injected into the calls made through a
+/// [`ChaosProxy`](struct.ChaosProxy.html), in order.
+///
+/// Once the schedule is used up, the calls are forwarded
+/// unless the schedule repeats.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChaosSchedule {
+    faults: Vec<Option<Fault>>,
+    repeat: bool,
+}
+
+impl ChaosSchedule {
+    /// [`ChaosSchedule`]: struct.ChaosSchedule.html
+    ///
+    /// Creates a new [`ChaosSchedule`] that forwards every call.
+    pub fn new() -> Self {
+        ChaosSchedule::default()
+    }
+
+    /// Injects a fault into the next call of the schedule.
+    ///
+    /// # Arguments
+    ///
+    /// * `fault` - The [`Fault`](enum.Fault.html) to inject.
+    pub fn then(mut self, fault: Fault) -> Self {
+        self.faults.push(Some(fault));
+        self
+    }
+
+    /// Forwards the next call of the schedule.
+    pub fn then_pass(mut self) -> Self {
+        self.faults.push(None);
+        self
+    }
+
+    /// Starts the schedule over once it's used up.
+    pub fn repeat(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+
+    /// Gets the fault of the call with the given number, counting from 0.
+    fn fault(&self, call: usize) -> Option<Fault> {
+        let call = match self.faults.len() {
+            0 => return None,
+            len if self.repeat => call % len,
+            _ => call,
+        };
+        self.faults.get(call).cloned().and_then(|fault| fault)
+    }
+}
+
+/// A proxy in front of the Swish API that injects failures into the calls made through it.
+pub struct ChaosProxy {
+    listener: TcpListener,
+    handle: Handle,
+    upstream: Uri,
+    schedule: ChaosSchedule,
+    tls_connector: Option<TlsConnector>,
+}
+
+impl ChaosProxy {
+    /// [`ChaosProxy`]: struct.ChaosProxy.html
+    ///
+    /// Creates a new [`ChaosProxy`] listening on the given address.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on, e.g. `127.0.0.1:0` for any free port.
+    /// * `handle` - The tokio-core handle the connections are handled on.
+    /// * `upstream` - The URL of the Swish API the calls are forwarded to.
+    /// * `schedule` - The [`ChaosSchedule`](struct.ChaosSchedule.html) of the failures.
+    ///
+    /// # Returns
+    /// The [`ChaosProxy`], or an `io::Error` if the address couldn't be bound
+    /// or the upstream URL is invalid.
+    pub fn bind(
+        addr: &SocketAddr,
+        handle: &Handle,
+        upstream: &str,
+        schedule: ChaosSchedule,
+    ) -> io::Result<Self> {
+        let upstream = upstream
+            .parse::<Uri>()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        Ok(ChaosProxy {
+            listener: TcpListener::bind(addr, handle)?,
+            handle: handle.clone(),
+            upstream,
+            schedule,
+            tls_connector: None,
+        })
+    }
+
+    /// Connects to the upstream with the given connector, e.g. one
+    /// with the client certificate to forward calls to Swish.
+    ///
+    /// # Arguments
+    ///
+    /// * `tls_connector` - A configured `native_tls::TlsConnector`.
+    pub fn tls_connector(mut self, tls_connector: TlsConnector) -> Self {
+        self.tls_connector = Some(tls_connector);
+        self
+    }
+
+    /// Gets the address the proxy is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Gets the URL of the Swish API through the proxy, to give to
+    /// [`SwishClientBuilder::api_url`](../client/struct.SwishClientBuilder.html#method.api_url).
+    pub fn api_url(&self) -> io::Result<String> {
+        self.local_addr()
+            .map(|addr| format!("http://{}{}", addr, self.upstream.path()))
+    }
+
+    /// Forwards calls until the listener fails.
+    ///
+    /// # Returns
+    /// A [`ChaosProxyFuture`](type.ChaosProxyFuture.html) that has to be run
+    /// for the proxy to accept connections.
+    pub fn serve(self) -> ChaosProxyFuture {
+        let ChaosProxy {
+            listener,
+            handle,
+            upstream,
+            schedule,
+            tls_connector,
+        } = self;
+        let mut http_connector = HttpConnector::new(1);
+        http_connector.enforce_http(false);
+        let tls_connector = match tls_connector.map_or_else(TlsConnector::new, Ok) {
+            Ok(tls_connector) => tls_connector,
+            Err(error) => return Box::new(future::err(io::Error::other(error))),
+        };
+        let client = Client::builder().build(HttpsConnector::from((http_connector, tls_connector)));
+        let chaos = Arc::new(Chaos {
+            client,
+            upstream,
+            schedule,
+            calls: Mutex::new(0),
+        });
+
+        let http = Http::new();
+        Box::new(listener.incoming().for_each(move |(stream, _)| {
+            let chaos = chaos.clone();
+            let service = service_fn(move |request| chaos.call(request));
+            handle.spawn(http.serve_connection(stream, service).map_err(|_| ()));
+            Ok(())
+        }))
+    }
+}
+
+type ChaosFuture = Box<dyn Future<Item = Response<Body>, Error = io::Error> + Send>;
+
+/// What's shared by the calls made through the proxy.
+struct Chaos {
+    client: Client<HttpsConnector<HttpConnector>, Body>,
+    upstream: Uri,
+    schedule: ChaosSchedule,
+    calls: Mutex<usize>,
+}
+
+impl Chaos {
+    /// Injects the next fault of the schedule into a call.
+    fn call(&self, request: Request<Body>) -> ChaosFuture {
+        let fault = {
+            let mut calls = self.calls.lock().unwrap();
+            let fault = self.schedule.fault(*calls);
+            *calls += 1;
+            fault
+        };
+        match fault {
+            None => self.forward(request),
+            Some(Fault::Latency(delay)) => {
+                let forwarded = self.forward(request);
+                Box::new(sleep(delay).and_then(|()| forwarded))
+            }
+            Some(Fault::Reset) => Box::new(future::err(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "the chaos proxy reset the connection",
+            ))),
+            Some(Fault::Status(status)) => {
+                let reason = status.canonical_reason().unwrap_or_default();
+                let mut response = Response::new(Body::from(reason));
+                *response.status_mut() = status;
+                Box::new(future::ok(response))
+            }
+            Some(Fault::MalformedBody) => Box::new(self.forward(request).and_then(|response| {
+                let (mut parts, body) = response.into_parts();
+                parts.headers.remove(CONTENT_LENGTH);
+                body.concat2().map_err(io::Error::other).map(|body| {
+                    let body = body[..body.len() / 2].to_vec();
+                    Response::from_parts(parts, Body::from(body))
+                })
+            })),
+        }
+    }
+
+    /// Forwards a call to the upstream.
+    fn forward(&self, request: Request<Body>) -> ChaosFuture {
+        let (mut parts, body) = request.into_parts();
+        let path = parts
+            .uri
+            .path_and_query()
+            .map_or("/", |path| path.as_str())
+            .to_owned();
+        let mut uri = Uri::builder();
+        if let Some(scheme) = self.upstream.scheme_part() {
+            uri.scheme(scheme.clone());
+        }
+        if let Some(authority) = self.upstream.authority_part() {
+            uri.authority(authority.clone());
+        }
+        parts.uri = match uri.path_and_query(path.as_str()).build() {
+            Ok(uri) => uri,
+            Err(error) => return Box::new(future::err(io::Error::other(error))),
+        };
+        parts.headers.remove(HOST);
+
+        Box::new(
+            self.client
+                .request(Request::from_parts(parts, body))
+                .map_err(io::Error::other),
+        )
+    }
+}
+
+/// Resolves after the delay, without tying up the reactor.
+fn sleep(delay: Duration) -> Box<dyn Future<Item = (), Error = io::Error> + Send> {
+    let (sender, receiver) = oneshot::channel();
+    thread::spawn(move || {
+        thread::sleep(delay);
+        let _ = sender.send(());
+    });
+    Box::new(receiver.map_err(io::Error::other))
+}
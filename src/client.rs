@@ -2,57 +2,211 @@
 //!
 //! This is the client that's used to make calls to the Swish API.
 //!
-use error::{RequestError, SwishClientError};
+use alias::{MerchantAlias, PhoneAlias};
+use amount::Amount;
+use callback::CallbackUrl;
+use capture::{CaptureBuffer, CapturedExchange, CapturedResponse};
+use clock::{Clock, SystemClock};
+use config::SwishConfig;
+#[cfg(feature = "chrono")]
+use chrono::{self, DateTime, FixedOffset};
+pub use connector::ConnectionTask;
+use connector::{HttpsKind, SharedExecutor, SwishConnector};
+use dns::{DnsResolver, SwishResolver};
+use error::{
+    ConfigError, ErrorCode, ErrorCollection, ErrorResponse, Language, RequestContext, RequestError,
+    Resource, SwishClientError,
+};
+use futures::future::Executor;
 use futures::stream::Stream;
+use futures::future::Either;
 use futures::{future, Future};
 use hyper::client::HttpConnector;
-use hyper::header::{self, HeaderValue, CONTENT_TYPE, LOCATION};
+use hyper::header::{self, HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, LOCATION, USER_AGENT};
 use hyper::Client as HttpClient;
 use hyper::StatusCode;
 use hyper::{self, Body, Request, Uri};
 use hyper_tls::HttpsConnector;
+use ids::{PaymentRef, PaymentRequestToken, RefundRef};
+pub use native_tls::Protocol;
+use metrics::{Endpoint, MetricsSink, Outcome};
 use native_tls::{Identity, TlsConnector};
-use serde::de::DeserializeOwned;
-use serde::Serialize;
+#[cfg(feature = "otel")]
+use otel::RequestSpan;
+use params::{Missing, PaymentParamsBuilder, RefundParamsBuilder};
+use redact;
+use reporter::{ErrorReport, ErrorReporter};
+use retry::{self, RetryPolicy};
+use secret::{FileSecretProvider, SecretProvider, SecretString};
+use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+use serde::{Serialize, Serializer};
 use serde_json;
-use std::error;
+use stats::{ClientStats, StatsRecorder};
+use std::collections::HashMap;
 use std::fmt;
-use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io;
-use std::io::Read;
-use std::path::Path;
+use std::net::IpAddr;
 use std::str;
-use tokio_core::reactor::Handle;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+use timing::{ConnectTimings, RequestTimings};
+use uuid::Uuid;
+use validation::{self, ValidationError};
+use tokio_core::reactor::{Handle, Timeout};
 
 /// The client used to make call to the Swish API.
 #[derive(Debug)]
 pub struct SwishClient {
-    merchant_swish_number: String,
+    merchant_swish_number: MerchantAlias,
     swish_api_url: String,
-    passphrase: String,
-    cert_path: String,
+    secret_provider: Box<dyn SecretProvider>,
+    min_tls_version: Option<Protocol>,
+    max_tls_version: Option<Protocol>,
+    connector: Option<Connector>,
+    resolve_overrides: Arc<HashMap<String, Vec<IpAddr>>>,
+    dns_resolver: Option<Arc<dyn DnsResolver>>,
+    dns_threads: usize,
+    executor: Option<SharedExecutor>,
+    default_headers: HeaderMap,
+    metrics_sink: Option<Arc<dyn MetricsSink>>,
+    correlation_id_header: HeaderName,
+    capture: Option<Arc<CaptureBuffer>>,
+    error_reporter: Option<Arc<dyn ErrorReporter>>,
+    strict: bool,
+    language: Language,
+    default_callback_url: Option<CallbackUrl>,
+    payee_payment_reference_prefix: Option<String>,
+    stats: Arc<StatsRecorder>,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    clock: Arc<dyn Clock>,
+    http_client: Mutex<Option<HttpClient<SwishConnector, Body>>>,
     handle: Handle,
 }
 
+/// Builder used to configure a [`SwishClient`].
+///
+/// [`SwishClient`]: struct.SwishClient.html
+#[derive(Debug)]
+pub struct SwishClientBuilder {
+    merchant_swish_number: MerchantAlias,
+    secret_provider: Box<dyn SecretProvider>,
+    min_tls_version: Option<Protocol>,
+    max_tls_version: Option<Protocol>,
+    connector: Option<Connector>,
+    resolve_overrides: HashMap<String, Vec<IpAddr>>,
+    dns_resolver: Option<Arc<dyn DnsResolver>>,
+    dns_threads: usize,
+    executor: Option<SharedExecutor>,
+    default_headers: HeaderMap,
+    metrics_sink: Option<Arc<dyn MetricsSink>>,
+    correlation_id_header: HeaderName,
+    debug_capture: Option<usize>,
+    error_reporter: Option<Arc<dyn ErrorReporter>>,
+    strict: bool,
+    language: Language,
+    default_callback_url: Option<CallbackUrl>,
+    payee_payment_reference_prefix: Option<String>,
+    swish_api_url: String,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    clock: Arc<dyn Clock>,
+    handle: Handle,
+}
+
+/// A user supplied connector that replaces the one
+/// the client would otherwise build from the certificate.
+#[derive(Debug, Clone)]
+enum Connector {
+    Tls(TlsConnector),
+    Https(HttpsConnector<HttpConnector>),
+}
+
+/// How long Swish lets the payer answer a payment request,
+/// after which the payment fails.
+pub const PAYMENT_REQUEST_TTL: Duration = Duration::from_secs(3 * 60);
+
 /// This is what will be returned when a payment is
 /// successfully created at Swish.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreatedPayment {
     pub id: String,
     pub location: String,
     pub request_token: Option<String>,
+    /// When Swish responded that the payment was created, which is required
+    /// in JSON as the payment request expires relative to it.
+    pub created_at: SystemTime,
+}
+
+/// This is what will be returned when an m-commerce
+/// payment is successfully created at Swish.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedMCommercePayment {
+    pub id: String,
+    pub location: String,
+    pub request_token: PaymentRequestToken,
+    /// When Swish responded that the payment was created, which is required
+    /// in JSON as the payment request expires relative to it.
+    pub created_at: SystemTime,
+}
+
+macro_rules! expiring_payment {
+    ($payment:ident) => {
+        impl $payment {
+            /// Gets when the payment request expires, unless the payer has answered it.
+            pub fn expires_at(&self) -> SystemTime {
+                self.created_at + PAYMENT_REQUEST_TTL
+            }
+
+            /// Whether the payment request had expired at the given time,
+            /// e.g. the time of a [`Clock`](../clock/trait.Clock.html).
+            ///
+            /// # Arguments
+            ///
+            /// * `now` - The time to check at.
+            pub fn is_expired_at(&self, now: SystemTime) -> bool {
+                now >= self.expires_at()
+            }
+        }
+
+        /// Payments are the same if they're the same request, so `created_at`,
+        /// which is the time of the response, isn't compared or hashed.
+        impl PartialEq for $payment {
+            fn eq(&self, other: &Self) -> bool {
+                self.id == other.id
+                    && self.location == other.location
+                    && self.request_token == other.request_token
+            }
+        }
+
+        impl Eq for $payment {}
+
+        impl Hash for $payment {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.id.hash(state);
+                self.location.hash(state);
+                self.request_token.hash(state);
+            }
+        }
+    };
 }
 
+expiring_payment!(CreatedPayment);
+expiring_payment!(CreatedMCommercePayment);
+
 /// This is all the data that's returned from the
 /// Swish API when fetching a payment.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Payment {
     pub id: String,
-    pub amount: f64,
+    pub amount: Amount,
     #[serde(rename = "payeePaymentReference")]
     pub payee_payment_reference: Option<String>,
     #[serde(rename = "paymentReference")]
     pub payment_reference: Option<String>,
+    #[serde(rename = "callbackUrl")]
+    pub callback_url: Option<String>,
     #[serde(rename = "payerAlias")]
     pub payer_alias: Option<String>,
     #[serde(rename = "payeeAlias")]
@@ -68,45 +222,131 @@ pub struct Payment {
 
     // Errors can occur
     #[serde(rename = "errorCode")]
-    pub error_code: Option<String>,
+    pub error_code: Option<ErrorCode>,
     #[serde(rename = "errorMessage")]
     pub error_message: Option<String>,
+
+    /// Fields returned by Swish that aren't known by this crate yet.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// The status of an operation.
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+///
+/// New statuses may be added by Swish, which are kept as `Unknown`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum Status {
-    #[serde(rename = "CREATED")]
+    /// The payment request was created.
     Created,
-    #[serde(rename = "PAID")]
+    /// The payment or refund was paid.
     Paid,
-    #[serde(rename = "ERROR")]
+    /// The payment or refund failed, see the error code.
     Error,
-    #[serde(rename = "VALIDATED")]
+    /// The refund was validated.
     Validated,
-    #[serde(rename = "INITIATED")]
+    /// The refund was initiated.
     Initiated,
+    /// The payer declined the payment.
+    Declined,
+    /// The payment request was cancelled.
+    Cancelled,
+    /// The refund was debited from the merchant, but not yet paid out.
+    Debited,
+    /// A status that isn't known by this crate yet.
+    Unknown(String),
+}
+
+impl Status {
+    /// Gets the status as it's named by the Swish API, e.g. `PAID`.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Status::Created => "CREATED",
+            Status::Paid => "PAID",
+            Status::Error => "ERROR",
+            Status::Validated => "VALIDATED",
+            Status::Initiated => "INITIATED",
+            Status::Declined => "DECLINED",
+            Status::Cancelled => "CANCELLED",
+            Status::Debited => "DEBITED",
+            Status::Unknown(ref status) => status,
+        }
+    }
+
+    /// Parses a status returned by Swish, keeping unknown statuses as `Unknown`.
+    pub fn from_name(status: &str) -> Status {
+        match status {
+            "CREATED" => Status::Created,
+            "PAID" => Status::Paid,
+            "ERROR" => Status::Error,
+            "VALIDATED" => Status::Validated,
+            "INITIATED" => Status::Initiated,
+            "DECLINED" => Status::Declined,
+            "CANCELLED" => Status::Cancelled,
+            "DEBITED" => Status::Debited,
+            _ => Status::Unknown(status.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl str::FromStr for Status {
+    type Err = ValidationError;
+
+    /// Parses a known status by its Swish name, ignoring case.
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match Status::from_name(&status.to_ascii_uppercase()) {
+            Status::Unknown(_) => Err(ValidationError::InvalidFormat {
+                field: "status",
+                expected: "one of CREATED, PAID, ERROR, VALIDATED, INITIATED, DECLINED, CANCELLED or DEBITED",
+            }),
+            status => Ok(status),
+        }
+    }
+}
+
+impl Serialize for Status {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let status = String::deserialize(deserializer)?;
+        Ok(Status::from_name(&status))
+    }
 }
 
 /// Params used to create a new payment.
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PaymentParams<'a> {
+    /// Prefixed with the reference prefix of the client, if it has one.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payee_payment_reference: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub payer_alias: Option<&'a str>,
-    pub payee_alias: &'a str,
+    pub payer_alias: Option<PhoneAlias>,
+    /// Defaults to the merchant alias of the client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payee_alias: Option<MerchantAlias>,
 
-    pub amount: f64,
+    pub amount: Amount,
     currency: Currency,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<&'a str>,
-    pub callback_url: &'a str,
+    /// Defaults to the default callback URL of the client, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<CallbackUrl>,
 }
 
 /// Params used to create a new refund.
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RefundParams<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -114,46 +354,235 @@ pub struct RefundParams<'a> {
     pub original_payment_reference: &'a str,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payment_reference: Option<&'a str>,
-    pub payer_alias: &'a str,
+    /// Defaults to the merchant alias of the client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payer_alias: Option<MerchantAlias>,
     pub payee_alias: &'a str,
-    pub amount: f64,
+    pub amount: Amount,
     currency: Currency,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<&'a str>,
-    pub callback_url: &'a str,
+    /// Defaults to the default callback URL of the client, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<CallbackUrl>,
+}
+
+impl<'a> PaymentParams<'a> {
+    /// [`PaymentParamsBuilder`]: ../params/struct.PaymentParamsBuilder.html
+    ///
+    /// Creates a [`PaymentParamsBuilder`], which makes sure that
+    /// every field Swish requires is set.
+    pub fn builder() -> PaymentParamsBuilder<'a, Missing, Missing> {
+        PaymentParamsBuilder::new()
+    }
+
+    /// Validates the params against the constraints of the Swish API.
+    /// This is done before every payment is created, but can also
+    /// be done up front, e.g. to show all the problems of a form at once.
+    /// A missing callback URL isn't an error here, as the client may have
+    /// a default one, but creating the payment fails without either.
+    ///
+    /// # Returns
+    /// A Result that contains every `ValidationError` if the params are invalid.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        if let Some(reference) = self.payee_payment_reference {
+            errors.extend(validation::validate_payee_payment_reference(reference).err());
+        }
+        if let Some(message) = self.message {
+            errors.extend(validation::validate_message(message).err());
+        }
+        errors.extend(validation::validate_amount(self.amount).err());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl<'a> RefundParams<'a> {
+    /// [`RefundParamsBuilder`]: ../params/struct.RefundParamsBuilder.html
+    ///
+    /// Creates a [`RefundParamsBuilder`], which makes sure that
+    /// every field Swish requires is set.
+    pub fn builder() -> RefundParamsBuilder<'a, Missing, Missing> {
+        RefundParamsBuilder::new()
+    }
+
+    /// Validates the params against the constraints of the Swish API.
+    /// This is done before every refund is created, but can also
+    /// be done up front, e.g. to show all the problems of a form at once.
+    /// A missing callback URL isn't an error here, as the client may have
+    /// a default one, but creating the refund fails without either.
+    ///
+    /// # Returns
+    /// A Result that contains every `ValidationError` if the params are invalid.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        if self.original_payment_reference.is_empty() {
+            errors.push(ValidationError::Missing {
+                field: "originalPaymentReference",
+            });
+        }
+        if let Some(message) = self.message {
+            errors.extend(validation::validate_message(message).err());
+        }
+        errors.extend(validation::validate_amount(self.amount).err());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
 /// The currency the Swish API supports.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+///
+/// Other currencies returned by Swish are kept as `Other`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum Currency {
     /// SEK is currently the only currency supported at Swish.
+    #[default]
     SEK,
+    /// A currency that isn't supported by Swish, by its ISO 4217 code.
+    Other(String),
+}
+
+impl Currency {
+    /// Gets the ISO 4217 code of the currency, e.g. `SEK`.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Currency::SEK => "SEK",
+            Currency::Other(ref currency) => currency,
+        }
+    }
+
+    /// Parses a currency returned by Swish, keeping unsupported currencies as `Other`.
+    pub fn from_code(currency: &str) -> Currency {
+        match currency {
+            "SEK" => Currency::SEK,
+            _ => Currency::Other(currency.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl str::FromStr for Currency {
+    type Err = ValidationError;
+
+    /// Parses a currency supported by Swish by its ISO 4217 code, ignoring case.
+    fn from_str(currency: &str) -> Result<Self, Self::Err> {
+        match Currency::from_code(&currency.to_ascii_uppercase()) {
+            Currency::SEK => Ok(Currency::SEK),
+            _ => Err(ValidationError::InvalidFormat {
+                field: "currency",
+                expected: "SEK",
+            }),
+        }
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let currency = String::deserialize(deserializer)?;
+        Ok(Currency::from_code(&currency))
+    }
+}
+
+/// The environment of Swish that the client calls,
+/// deserialized from `"production"` or `"test"`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    /// The production environment, where payments move real money.
+    Production,
+    /// The test environment, i.e. the Merchant Swish Simulator (MSS).
+    #[default]
+    Test,
 }
 
-impl Default for Currency {
-    fn default() -> Self {
-        Currency::SEK
+impl Environment {
+    /// Gets the URL of the Swish API in the environment.
+    pub fn api_url(&self) -> &'static str {
+        match *self {
+            Environment::Production => SWISH_PRODUCTION_API_URL,
+            Environment::Test => SWISH_API_URL,
+        }
     }
 }
 
 /// This will be returned when a refund
 /// is successfully created.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CreatedRefund {
     pub id: String,
     pub location: String,
 }
 
+impl<'a> From<&'a CreatedPayment> for PaymentRef<'a> {
+    fn from(payment: &'a CreatedPayment) -> Self {
+        PaymentRef::from(payment.id.as_str())
+    }
+}
+
+impl<'a> From<&'a CreatedMCommercePayment> for PaymentRef<'a> {
+    fn from(payment: &'a CreatedMCommercePayment) -> Self {
+        PaymentRef::from(payment.id.as_str())
+    }
+}
+
+impl<'a> From<&'a Payment> for PaymentRef<'a> {
+    fn from(payment: &'a Payment) -> Self {
+        PaymentRef::from(payment.id.as_str())
+    }
+}
+
+impl<'a> From<&'a CreatedRefund> for RefundRef<'a> {
+    fn from(refund: &'a CreatedRefund) -> Self {
+        RefundRef::from(refund.id.as_str())
+    }
+}
+
+impl<'a> From<&'a Refund> for RefundRef<'a> {
+    fn from(refund: &'a Refund) -> Self {
+        RefundRef::from(refund.id.as_str())
+    }
+}
+
 /// This is all the data that's returned
 /// from the Swish API when fetching a refund.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Refund {
     pub id: String,
-    pub amount: f64,
+    pub amount: Amount,
     #[serde(rename = "payerPaymentReference")]
     pub payer_payment_reference: Option<String>,
-    #[serde(rename = "originalpaymentReference")]
+    /// Swish spells the field `originalpaymentReference`,
+    /// but `originalPaymentReference` is read as well.
+    #[serde(
+        rename = "originalpaymentReference",
+        alias = "originalPaymentReference"
+    )]
     pub original_payment_reference: Option<String>,
+    #[serde(rename = "paymentReference")]
+    pub payment_reference: Option<String>,
+    #[serde(rename = "callbackUrl")]
+    pub callback_url: Option<String>,
     #[serde(rename = "payerAlias")]
     pub payer_alias: Option<String>,
     #[serde(rename = "payeeAlias")]
@@ -169,33 +598,223 @@ pub struct Refund {
 
     // Errors can occur
     #[serde(rename = "errorCode")]
-    pub error_code: Option<String>,
+    pub error_code: Option<ErrorCode>,
     #[serde(rename = "errorMessage")]
     pub error_message: Option<String>,
     #[serde(rename = "additionalInformation")]
     pub additional_information: Option<String>,
+
+    /// Fields returned by Swish that aren't known by this crate yet.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "chrono")]
+impl Payment {
+    /// Gets when the payment was created.
+    ///
+    /// # Returns
+    /// The `date_created` timestamp, in the timezone Swish returned it in.
+    pub fn created_at(&self) -> Result<DateTime<FixedOffset>, chrono::ParseError> {
+        DateTime::parse_from_rfc3339(&self.date_created)
+    }
+
+    /// Gets when the payment was paid.
+    ///
+    /// # Returns
+    /// The `date_paid` timestamp, in the timezone Swish returned it in,
+    /// or `None` if the payment hasn't been paid.
+    pub fn paid_at(&self) -> Result<Option<DateTime<FixedOffset>>, chrono::ParseError> {
+        parse_optional_timestamp(self.date_paid.as_deref())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Refund {
+    /// Gets when the refund was created.
+    ///
+    /// # Returns
+    /// The `date_created` timestamp, in the timezone Swish returned it in.
+    pub fn created_at(&self) -> Result<DateTime<FixedOffset>, chrono::ParseError> {
+        DateTime::parse_from_rfc3339(&self.date_created)
+    }
+
+    /// Gets when the refund was paid.
+    ///
+    /// # Returns
+    /// The `date_paid` timestamp, in the timezone Swish returned it in,
+    /// or `None` if the refund hasn't been paid.
+    pub fn paid_at(&self) -> Result<Option<DateTime<FixedOffset>>, chrono::ParseError> {
+        parse_optional_timestamp(self.date_paid.as_deref())
+    }
+}
+
+/// Parses a timestamp returned by Swish, if there is one.
+#[cfg(feature = "chrono")]
+fn parse_optional_timestamp(
+    timestamp: Option<&str>,
+) -> Result<Option<DateTime<FixedOffset>>, chrono::ParseError> {
+    timestamp.map(DateTime::parse_from_rfc3339).transpose()
+}
+
+/// A body returned by the Swish API when fetching a payment or a refund.
+pub(crate) trait SwishResponse: DeserializeOwned + fmt::Debug {
+    /// The kind of resource that's returned.
+    const RESOURCE: Resource;
+    /// The path the resource is fetched from, without the id.
+    const PATH: &'static str;
+
+    /// The fields of the body that aren't known by this crate.
+    fn extra(&self) -> &serde_json::Map<String, serde_json::Value>;
+}
+
+impl SwishResponse for Payment {
+    const RESOURCE: Resource = Resource::Payment;
+    const PATH: &'static str = "paymentrequests";
+
+    fn extra(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+impl SwishResponse for Refund {
+    const RESOURCE: Resource = Resource::Refund;
+    const PATH: &'static str = "refunds";
+
+    fn extra(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+/// Options that only apply to a single call to the Swish API.
+///
+/// # Example
+///
+/// ```
+/// extern crate hyper;
+/// extern crate swish_api;
+///
+/// use hyper::header::{HeaderName, HeaderValue};
+/// use swish_api::client::RequestOptions;
+///
+/// let options = RequestOptions::new().header(
+///     HeaderName::from_static("x-internal-trace"),
+///     HeaderValue::from_static("abc123"),
+/// );
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct RequestOptions {
+    headers: HeaderMap,
+    correlation_id: Option<String>,
+    language: Option<Language>,
+}
+
+impl RequestOptions {
+    /// [`RequestOptions`]: struct.RequestOptions.html
+    ///
+    /// Creates new empty [`RequestOptions`].
+    pub fn new() -> Self {
+        RequestOptions::default()
+    }
+
+    /// Adds a header to the request. It takes precedence
+    /// over a default header with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header.
+    /// * `value` - The value of the header.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets the correlation id of the request. A random one
+    /// is generated for every request when it's not set.
+    ///
+    /// # Arguments
+    ///
+    /// * `correlation_id` - An id that identifies the request across services.
+    pub fn correlation_id(mut self, correlation_id: &str) -> Self {
+        self.correlation_id = Some(correlation_id.to_owned());
+        self
+    }
+
+    /// Sets the language of the messages that the errors of the request
+    /// have for customers, instead of the language of the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The [`Language`](../error/enum.Language.html) of the messages.
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+        self
+    }
 }
 
 /// Custom Header returned by the Swish API.
 const PAYMENT_REQUEST_TOKEN: &str = "paymentrequesttoken";
 
+const CREATE_PAYMENT: Endpoint = Endpoint {
+    operation: "create_payment",
+    path: "paymentrequests",
+};
+const GET_PAYMENT: Endpoint = Endpoint {
+    operation: "get_payment",
+    path: "paymentrequests",
+};
+const CANCEL_PAYMENT: Endpoint = Endpoint {
+    operation: "cancel_payment",
+    path: "paymentrequests",
+};
+const CREATE_REFUND: Endpoint = Endpoint {
+    operation: "create_refund",
+    path: "refunds",
+};
+const GET_REFUND: Endpoint = Endpoint {
+    operation: "get_refund",
+    path: "refunds",
+};
+
+/// The JSON patch that cancels a payment request.
+const CANCEL_PATCH: &str = r#"[{"op":"replace","path":"/status","value":"cancelled"}]"#;
+
+/// The URL of the Swish API the client calls by default, which is the one of the test environment.
+const SWISH_API_URL: &str = "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/";
+
+/// The URL of the Swish API in production.
+const SWISH_PRODUCTION_API_URL: &str = "https://cpc.getswish.net/swish-cpcapi/api/v1/";
+
+/// The default header used to send the correlation id of a request.
+const CORRELATION_ID: &str = "x-correlation-id";
+
+/// The User-Agent sent with every request, unless it's extended by the user.
+const CRATE_USER_AGENT: &str = concat!("swish-api/", env!("CARGO_PKG_VERSION"));
+
 /// Type alias for Future used within the SwishClient
-type SwishBoxFuture<'a, T> = Box<Future<Item = T, Error = SwishClientError> + 'a>;
+pub(crate) type SwishBoxFuture<'a, T> = Box<dyn Future<Item = T, Error = SwishClientError> + 'a>;
 
 impl SwishClient {
     /// [`SwishClient`]: struct.SwishClient.html
     ///
     /// Creates a new SwishClient
     ///
+    /// Use [`builder`](#method.builder) to configure anything else, e.g. the
+    /// environment, timeouts or retries.
+    ///
     /// # Arguments
     ///
     /// * `merchant_swish_number` - The merchants swish number which will receive the payments.
+    ///   Validating it up front makes a misconfigured number fail at startup.
     /// * `cert_path` - The path to the certificate.
     /// * `passphrase` - The passphrase to the certificate.
     /// * `handle` - A tokio reactor handle.
     ///
     /// # Returns
-    /// A configured [`SwishClient`].
+    /// A configured [`SwishClient`]. Use [`SwishClientBuilder`] for
+    /// more fine-grained configuration.
+    ///
+    /// [`SwishClientBuilder`]: struct.SwishClientBuilder.html
     ///
     /// # Example
     ///
@@ -203,10 +822,12 @@ impl SwishClient {
     /// extern crate tokio_core;
     /// extern crate swish_api;
     ///
+    /// use swish_api::alias::MerchantAlias;
     /// use swish_api::client::SwishClient;
     /// use tokio_core::reactor::Core;
     /// use std::env;
     ///
+    /// let merchant_alias = MerchantAlias::new("1231181189").unwrap();
     /// let core = Core::new().unwrap();
     /// let handle = core.handle();
     /// let current_dir = env::current_dir().unwrap();
@@ -215,24 +836,126 @@ impl SwishClient {
     ///    .into_os_string()
     ///    .to_str()
     ///    .map(|cert_path_string| {
-    ///        SwishClient::new("1231181189", cert_path_string, "swish", handle)
+    ///        SwishClient::new(merchant_alias, cert_path_string, "swish", handle)
     ///    }).unwrap();
     /// ```
     pub fn new(
-        merchant_swish_number: &str,
+        merchant_swish_number: MerchantAlias,
         cert_path: &str,
         passphrase: &str,
         handle: Handle,
     ) -> Self {
-        SwishClient {
-            merchant_swish_number: merchant_swish_number.to_owned(),
-            swish_api_url: "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/".to_owned(),
-            passphrase: passphrase.to_owned(),
-            cert_path: cert_path.to_owned(),
+        SwishClientBuilder::new(merchant_swish_number, cert_path, passphrase, handle).build()
+    }
+
+    /// [`SwishClient`]: struct.SwishClient.html
+    /// [`ConfigError`]: ../error/enum.ConfigError.html
+    ///
+    /// Creates a new [`SwishClient`], loading the certificate right away
+    /// instead of on the first request.
+    ///
+    /// # Returns
+    /// The [`SwishClient`], or a [`ConfigError`] if the certificate
+    /// couldn't be read or the passphrase is wrong.
+    ///
+    /// # Arguments
+    ///
+    /// * `merchant_swish_number` - The merchants swish number which will receive the payments.
+    /// * `cert_path` - The path to the certificate.
+    /// * `passphrase` - The passphrase to the certificate.
+    /// * `handle` - A tokio reactor handle.
+    pub fn try_new(
+        merchant_swish_number: MerchantAlias,
+        cert_path: &str,
+        passphrase: &str,
+        handle: Handle,
+    ) -> Result<Self, ConfigError> {
+        SwishClientBuilder::new(merchant_swish_number, cert_path, passphrase, handle).try_build()
+    }
+
+    /// [`SwishClientBuilder`]: struct.SwishClientBuilder.html
+    ///
+    /// Creates a [`SwishClientBuilder`] with nothing but the merchant configured,
+    /// calling the test environment. The certificate is set with
+    /// [`certificate_file`](struct.SwishClientBuilder.html#method.certificate_file)
+    /// or [`secret_provider`](struct.SwishClientBuilder.html#method.secret_provider),
+    /// or left out when a connector of its own is given.
+    ///
+    /// # Arguments
+    ///
+    /// * `merchant_swish_number` - The merchants swish number which will receive the payments.
+    /// * `handle` - A tokio reactor handle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate tokio_core;
+    /// extern crate swish_api;
+    ///
+    /// use std::time::Duration;
+    /// use swish_api::alias::MerchantAlias;
+    /// use swish_api::client::{Environment, SwishClient};
+    /// use swish_api::retry::RetryPolicy;
+    /// use tokio_core::reactor::Core;
+    ///
+    /// let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+    /// let core = Core::new().unwrap();
+    /// let swish_client = SwishClient::builder(merchant_alias, core.handle())
+    ///     .environment(Environment::Test)
+    ///     .certificate_file("./tests/test_cert.p12", "swish")
+    ///     .timeout(Duration::from_secs(10))
+    ///     .retry_policy(RetryPolicy::new().max_retries(2))
+    ///     .build();
+    /// ```
+    pub fn builder(merchant_swish_number: MerchantAlias, handle: Handle) -> SwishClientBuilder {
+        SwishClientBuilder {
+            merchant_swish_number,
+            secret_provider: Box::new(MissingSecretProvider),
+            min_tls_version: Some(Protocol::Tlsv12),
+            max_tls_version: None,
+            connector: None,
+            resolve_overrides: HashMap::new(),
+            dns_resolver: None,
+            dns_threads: 4,
+            executor: None,
+            default_headers: default_headers(),
+            metrics_sink: None,
+            correlation_id_header: HeaderName::from_static(CORRELATION_ID),
+            debug_capture: None,
+            error_reporter: None,
+            strict: false,
+            language: Language::default(),
+            default_callback_url: None,
+            payee_payment_reference_prefix: None,
+            swish_api_url: SWISH_API_URL.to_owned(),
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            clock: Arc::new(SystemClock),
             handle,
         }
     }
 
+    /// [`SwishConfig`]: ../config/struct.SwishConfig.html
+    /// [`SwishClient`]: struct.SwishClient.html
+    ///
+    /// Creates a new [`SwishClient`] with the settings of a [`SwishConfig`],
+    /// e.g. one deserialized from the config file of a service.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The [`SwishConfig`].
+    /// * `handle` - A tokio reactor handle.
+    ///
+    /// # Returns
+    /// The [`SwishClient`], or a `ConfigError` if the certificate couldn't be
+    /// read or loaded, e.g. because of a wrong path or passphrase. The certificate
+    /// is loaded right away, so that a wrong one fails at startup.
+    pub fn from_config(config: &SwishConfig, handle: Handle) -> Result<Self, ConfigError> {
+        config
+            .client_builder(handle)
+            .and_then(SwishClientBuilder::try_build)
+    }
+
     /// [`PaymentParams`]: struct.PaymentParams.html
     /// [`CreatedPayment`]: struct.CreatedPayment.html
     ///
@@ -253,8 +976,12 @@ impl SwishClient {
     ///
     /// use tokio_core::reactor::Core;
     /// use std::env;
+    /// use swish_api::alias::MerchantAlias;
+    /// use swish_api::amount::Amount;
+    /// use swish_api::callback::CallbackUrl;
     /// use swish_api::client::{PaymentParams, SwishClient};
     ///
+    /// let merchant_alias = MerchantAlias::new("1231181189").unwrap();
     /// let core = Core::new().unwrap();
     /// let handle = core.handle();
     /// let current_dir = env::current_dir().unwrap();
@@ -264,15 +991,18 @@ impl SwishClient {
     ///    .into_os_string()
     ///    .to_str()
     ///    .map(|cert_path_string| {
-    ///        SwishClient::new("1231181189", cert_path_string, "swish", handle)
+    ///        SwishClient::new(merchant_alias, cert_path_string, "swish", handle)
     ///    }).unwrap();
     ///
-    /// let mut payment_params = PaymentParams::default();
-    /// payment_params.amount = 100.00;
-    /// payment_params.payee_alias = "1231181189";
-    /// payment_params.payee_payment_reference = Some("0123456789");
-    /// payment_params.callback_url = "https://example.com/api/swishcb/paymentrequests";
-    /// payment_params.message = Some("Kingston USB Flash Drive 8 GB");
+    /// let callback_url = CallbackUrl::new("https://example.com/api/swishcb/paymentrequests");
+    /// let payment_params = PaymentParams::builder()
+    ///     .amount(Amount::from_kronor(100))
+    ///     .callback_url(callback_url.unwrap())
+    ///     .mcommerce()
+    ///     .payee_payment_reference("0123456789")
+    ///     .message("Kingston USB Flash Drive 8 GB")
+    ///     .build()
+    ///     .unwrap();
     ///
     /// let payment = swish_client.create_payment(payment_params);
     ///
@@ -281,56 +1011,164 @@ impl SwishClient {
         &'a self,
         params: PaymentParams,
     ) -> SwishBoxFuture<'a, CreatedPayment> {
+        self.create_payment_with_options(params, &RequestOptions::default())
+    }
+
+    /// [`RequestOptions`]: struct.RequestOptions.html
+    ///
+    /// Same as [`create_payment`](#method.create_payment) but with [`RequestOptions`]
+    /// that only apply to this call.
+    ///
+    /// # Returns
+    /// A Future with a [`CreatedPayment`](struct.CreatedPayment.html).
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - [`PaymentParams`](struct.PaymentParams.html).
+    /// * `options` - [`RequestOptions`].
+    pub fn create_payment_with_options<'a>(
+        &'a self,
+        params: PaymentParams,
+        options: &RequestOptions,
+    ) -> SwishBoxFuture<'a, CreatedPayment> {
+        let payee_payment_reference = params.payee_payment_reference.map(|reference| {
+            match self.payee_payment_reference_prefix {
+                Some(ref prefix) => format!("{}{}", prefix, reference),
+                None => reference.to_owned(),
+            }
+        });
         let payment_params = PaymentParams {
-            payee_alias: self.merchant_swish_number.as_str(),
+            payee_payment_reference: payee_payment_reference.as_deref(),
+            payee_alias: params
+                .payee_alias
+                .or_else(|| Some(self.merchant_swish_number.clone())),
+            callback_url: params
+                .callback_url
+                .or_else(|| self.default_callback_url.clone()),
             ..params
         };
+        let validated = require_callback_url(&payment_params.callback_url, payment_params.validate());
+        if let Err(errors) = validated {
+            return Box::new(future::err(SwishClientError::Validation(errors)));
+        }
+        let params_summary = self.params_summary(&payment_params);
 
-        let response: SwishBoxFuture<'a, (String, header::HeaderMap)> =
-            self.post::<CreatedPayment, PaymentParams>("paymentrequests", payment_params);
-
-        let payment_future = response.and_then(move |(_, headers)| {
-            let location = get_header_as_string(&headers, LOCATION);
-            let request_token = get_header_as_string(
-                &headers,
-                header::HeaderName::from_static(PAYMENT_REQUEST_TOKEN),
-            );
+        let payment_future = self.post(
+            "paymentrequests",
+            payment_params,
+            options,
+            move |_, headers| {
+                let location = get_header_as_string(&headers, LOCATION);
+                let request_token = get_header_as_string(
+                    &headers,
+                    header::HeaderName::from_static(PAYMENT_REQUEST_TOKEN),
+                );
 
-            let payment = location.and_then(|location| {
-                self.get_payment_id_from_location(&location)
-                    .map(|payment_id| CreatedPayment {
-                        id: payment_id,
-                        request_token,
-                        location,
+                location
+                    .and_then(|location| {
+                        self.get_payment_id_from_location(&location)
+                            .map(|payment_id| CreatedPayment {
+                                id: payment_id,
+                                request_token,
+                                location,
+                                created_at: self.clock.now(),
+                            })
+                    }).ok_or_else(|| {
+                        SwishClientError::parse(
+                            "Swish didn't return the location of the created payment",
+                        )
                     })
-            });
-
-            future::result(serde_json::from_value(json!(payment)).map_err(SwishClientError::from))
-        });
-        Box::new(payment_future)
+            },
+        );
+        self.instrument(CREATE_PAYMENT, params_summary, payment_future)
     }
 
-    /// [`Payment`]: struct.Payment.html
+    /// [`CreatedPayment`]: struct.CreatedPayment.html
+    /// [`PaymentParams`]: struct.PaymentParams.html
     ///
-    /// Gets a payment for a given `payment_id`.
+    /// Creates an e-commerce payment, which Swish sends
+    /// to the Swish app of the payer with the given alias.
     ///
     /// # Returns
-    /// A Future with a [`Payment`].
+    /// A Future with a [`CreatedPayment`], which has no request token.
     ///
     /// # Arguments
     ///
-    /// * `payment_id` - A string id for a payment
-    ///
-    /// # Example
-    ///
-    /// ```
+    /// * `payer_alias` - The mobile number the payer entered.
+    /// * `params` - [`PaymentParams`], whose `payer_alias` is replaced.
+    pub fn create_ecommerce_payment<'a>(
+        &'a self,
+        payer_alias: PhoneAlias,
+        params: PaymentParams,
+    ) -> SwishBoxFuture<'a, CreatedPayment> {
+        let params = PaymentParams {
+            payer_alias: Some(payer_alias),
+            ..params
+        };
+        self.create_payment(params)
+    }
+
+    /// [`CreatedMCommercePayment`]: struct.CreatedMCommercePayment.html
+    /// [`PaymentParams`]: struct.PaymentParams.html
+    ///
+    /// Creates an m-commerce payment, which the payer opens in
+    /// the Swish app on the same device with the request token.
+    ///
+    /// # Returns
+    /// A Future with a [`CreatedMCommercePayment`].
+    /// It fails if Swish didn't return a request token.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - [`PaymentParams`], whose `payer_alias` is removed.
+    pub fn create_mcommerce_payment<'a>(
+        &'a self,
+        params: PaymentParams,
+    ) -> SwishBoxFuture<'a, CreatedMCommercePayment> {
+        let params = PaymentParams {
+            payer_alias: None,
+            ..params
+        };
+        Box::new(self.create_payment(params).and_then(|payment| {
+            match payment.request_token {
+                Some(request_token) => Ok(CreatedMCommercePayment {
+                    id: payment.id,
+                    location: payment.location,
+                    request_token: PaymentRequestToken::new(request_token),
+                    created_at: payment.created_at,
+                }),
+                None => Err(SwishClientError::parse(format!(
+                    "Swish didn't return a payment request token for the m-commerce payment {}",
+                    payment.id
+                ))),
+            }
+        }))
+    }
+
+    /// [`Payment`]: struct.Payment.html
+    ///
+    /// Gets a payment for a given `payment`.
+    ///
+    /// # Returns
+    /// A Future with a [`Payment`].
+    ///
+    /// # Arguments
+    ///
+    /// * `payment` - The id of a payment, e.g. a `&str` or a `&CreatedPayment`.
+    ///   See [`PaymentRef`](../ids/struct.PaymentRef.html).
+    ///
+    /// # Example
+    ///
+    /// ```
     /// extern crate tokio_core;
     /// extern crate swish_api;
     ///
     /// use tokio_core::reactor::Core;
     /// use std::env;
+    /// use swish_api::alias::MerchantAlias;
     /// use swish_api::client::SwishClient;
     ///
+    /// let merchant_alias = MerchantAlias::new("1231181189").unwrap();
     /// let core = Core::new().unwrap();
     /// let handle = core.handle();
     /// let current_dir = env::current_dir().unwrap();
@@ -340,14 +1178,116 @@ impl SwishClient {
     ///    .into_os_string()
     ///    .to_str()
     ///    .map(|cert_path_string| {
-    ///        SwishClient::new("1231181189", cert_path_string, "swish", handle)
+    ///        SwishClient::new(merchant_alias, cert_path_string, "swish", handle)
     ///    }).unwrap();
     ///
     /// let payment_id = "111";
     /// let payment = swish_client.get_payment(payment_id);
     /// ```
-    pub fn get_payment<'a>(&'a self, payment_id: &str) -> SwishBoxFuture<'a, Payment> {
-        self.get(format!("paymentrequests/{}", payment_id).as_str())
+    pub fn get_payment<'a, 'b, P>(&'a self, payment: P) -> SwishBoxFuture<'a, Payment>
+    where
+        P: Into<PaymentRef<'b>>,
+    {
+        self.get_payment_with_options(payment, &RequestOptions::default())
+    }
+
+    /// [`RequestOptions`]: struct.RequestOptions.html
+    ///
+    /// Same as [`get_payment`](#method.get_payment) but with [`RequestOptions`]
+    /// that only apply to this call.
+    ///
+    /// # Returns
+    /// A Future with a [`Payment`](struct.Payment.html).
+    ///
+    /// # Arguments
+    ///
+    /// * `payment` - The id of a payment, e.g. a `&str` or a `&CreatedPayment`.
+    ///   See [`PaymentRef`](../ids/struct.PaymentRef.html).
+    /// * `options` - [`RequestOptions`].
+    pub fn get_payment_with_options<'a, 'b, P>(
+        &'a self,
+        payment: P,
+        options: &RequestOptions,
+    ) -> SwishBoxFuture<'a, Payment>
+    where
+        P: Into<PaymentRef<'b>>,
+    {
+        let payment_id = payment.into().as_str();
+        let payment = self.get(payment_id, options);
+        let params_summary = self.params_summary(&json!({ "id": payment_id }));
+        self.instrument(GET_PAYMENT, params_summary, payment)
+    }
+
+    /// [`Payment`]: struct.Payment.html
+    ///
+    /// Gets a payment for a given `payment` together with the
+    /// untyped json response, e.g. to persist it for audit purposes
+    /// or to access fields that [`Payment`] doesn't cover yet.
+    ///
+    /// # Returns
+    /// A Future with a Tuple that contains the [`Payment`] and the json response.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment` - The id of a payment, e.g. a `&str` or a `&CreatedPayment`.
+    ///   See [`PaymentRef`](../ids/struct.PaymentRef.html).
+    pub fn get_payment_with_raw<'a, 'b, P>(
+        &'a self,
+        payment: P,
+    ) -> SwishBoxFuture<'a, (Payment, serde_json::Value)>
+    where
+        P: Into<PaymentRef<'b>>,
+    {
+        let payment_id = payment.into().as_str();
+        let payment = self.get_with_raw(payment_id, &RequestOptions::default());
+        let params_summary = self.params_summary(&json!({ "id": payment_id }));
+        self.instrument(GET_PAYMENT, params_summary, payment)
+    }
+
+    /// [`Payment`]: struct.Payment.html
+    ///
+    /// Cancels a payment request that the payer hasn't answered yet,
+    /// e.g. because the payer abandoned the checkout.
+    ///
+    /// # Returns
+    /// A Future with the cancelled [`Payment`].
+    ///
+    /// # Arguments
+    ///
+    /// * `payment` - The id of a payment, e.g. a `&str` or a `&CreatedPayment`.
+    ///   See [`PaymentRef`](../ids/struct.PaymentRef.html).
+    pub fn cancel_payment<'a, 'b, P>(&'a self, payment: P) -> SwishBoxFuture<'a, Payment>
+    where
+        P: Into<PaymentRef<'b>>,
+    {
+        let payment_id = payment.into().as_str();
+        let request: Result<_, SwishClientError> = self
+            .get_uri(&format!("{}/{}", Payment::PATH, payment_id))
+            .and_then(|uri| {
+                let mut request = Request::patch(uri).body(CANCEL_PATCH.to_owned())?;
+                request.headers_mut().insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/json-patch+json"),
+                );
+                Ok(request)
+            });
+        let params_summary = self.params_summary(&json!({ "id": payment_id }));
+        let payment: SwishBoxFuture<'a, Payment> = match request {
+            Ok(request) => {
+                let id = payment_id.to_owned();
+                Box::new(
+                    self.perform_swish_api_request(
+                        request,
+                        &RequestOptions::default(),
+                        move |body, _| parse_body::<Payment>(&body, self.strict),
+                    )
+                    .map(|(payment, _)| payment)
+                    .map_err(move |err| not_found::<Payment>(err, id)),
+                )
+            }
+            Err(err) => Box::new(future::err(err)),
+        };
+        self.instrument(CANCEL_PAYMENT, params_summary, payment)
     }
 
     /// [`RefundParams`]: struct.RefundParams.html
@@ -370,8 +1310,12 @@ impl SwishClient {
     ///
     /// use tokio_core::reactor::Core;
     /// use std::env;
+    /// use swish_api::alias::MerchantAlias;
+    /// use swish_api::amount::Amount;
+    /// use swish_api::callback::CallbackUrl;
     /// use swish_api::client::{RefundParams, SwishClient};
     ///
+    /// let merchant_alias = MerchantAlias::new("1231181189").unwrap();
     /// let core = Core::new().unwrap();
     /// let handle = core.handle();
     /// let current_dir = env::current_dir().unwrap();
@@ -381,51 +1325,85 @@ impl SwishClient {
     ///    .into_os_string()
     ///    .to_str()
     ///    .map(|cert_path_string| {
-    ///        SwishClient::new("1231181189", cert_path_string, "swish", handle)
+    ///        SwishClient::new(merchant_alias, cert_path_string, "swish", handle)
     ///    }).unwrap();
     ///
-    /// let mut refund_params = RefundParams::default();
-    /// refund_params.amount = 100.00;
-    /// refund_params.callback_url = "https://example.com/api/swishcb/refunds";
-    /// refund_params.payer_payment_reference = Some("0123456789");
-    /// refund_params.message = Some("Refund for Kingston USB Flash Drive 8 GB");
+    /// let callback_url = CallbackUrl::new("https://example.com/api/swishcb/refunds");
+    /// let refund_params = RefundParams::builder()
+    ///     .original_payment_reference("6D6CD7406ECE4542A80152D909EF9F6B")
+    ///     .amount(Amount::from_kronor(100))
+    ///     .callback_url(callback_url.unwrap())
+    ///     .payer_payment_reference("0123456789")
+    ///     .message("Refund for Kingston USB Flash Drive 8 GB")
+    ///     .build()
+    ///     .unwrap();
     ///
     /// let refund = swish_client.create_refund(refund_params);
     /// ```
     pub fn create_refund<'a>(&'a self, params: RefundParams) -> SwishBoxFuture<'a, CreatedRefund> {
+        self.create_refund_with_options(params, &RequestOptions::default())
+    }
+
+    /// [`RequestOptions`]: struct.RequestOptions.html
+    ///
+    /// Same as [`create_refund`](#method.create_refund) but with [`RequestOptions`]
+    /// that only apply to this call.
+    ///
+    /// # Returns
+    /// A Future with a [`CreatedRefund`](struct.CreatedRefund.html).
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - [`RefundParams`](struct.RefundParams.html).
+    /// * `options` - [`RequestOptions`].
+    pub fn create_refund_with_options<'a>(
+        &'a self,
+        params: RefundParams,
+        options: &RequestOptions,
+    ) -> SwishBoxFuture<'a, CreatedRefund> {
         let refund_params = RefundParams {
-            payer_alias: self.merchant_swish_number.as_str(),
+            payer_alias: params
+                .payer_alias
+                .or_else(|| Some(self.merchant_swish_number.clone())),
+            callback_url: params
+                .callback_url
+                .or_else(|| self.default_callback_url.clone()),
             ..params
         };
+        let validated = require_callback_url(&refund_params.callback_url, refund_params.validate());
+        if let Err(errors) = validated {
+            return Box::new(future::err(SwishClientError::Validation(errors)));
+        }
+        let params_summary = self.params_summary(&refund_params);
 
-        let response = self.post::<CreatedRefund, RefundParams>("refunds", refund_params);
-
-        let refund_future = response.and_then(move |(_, headers)| {
+        let refund_future = self.post("refunds", refund_params, options, move |_, headers| {
             let location = get_header_as_string(&headers, LOCATION);
 
-            let refund = location.and_then(|location| {
-                self.get_payment_id_from_location(&location)
-                    .map(|refund_id| CreatedRefund {
-                        id: refund_id,
-                        location,
-                    })
-            });
-
-            future::result(serde_json::from_value(json!(refund)).map_err(SwishClientError::from))
+            location
+                .and_then(|location| {
+                    self.get_payment_id_from_location(&location)
+                        .map(|refund_id| CreatedRefund {
+                            id: refund_id,
+                            location,
+                        })
+                }).ok_or_else(|| {
+                    SwishClientError::parse("Swish didn't return the location of the created refund")
+                })
         });
-        Box::new(refund_future)
+        self.instrument(CREATE_REFUND, params_summary, refund_future)
     }
 
     /// [`Refund`]: struct.Refund.html
     ///
-    /// Gets a refund for a given `refund_id`.
+    /// Gets a refund for a given `refund`.
     ///
     /// # Returns
     /// A Future with a [`Refund`].
     ///
     /// # Arguments
     ///
-    /// * `refund_id` - A string id for a refund
+    /// * `refund` - The id of a refund, e.g. a `&str` or a `&CreatedRefund`.
+    ///   See [`RefundRef`](../ids/struct.RefundRef.html).
     ///
     /// # Example
     ///
@@ -435,8 +1413,10 @@ impl SwishClient {
     ///
     /// use tokio_core::reactor::Core;
     /// use std::env;
+    /// use swish_api::alias::MerchantAlias;
     /// use swish_api::client::SwishClient;
     ///
+    /// let merchant_alias = MerchantAlias::new("1231181189").unwrap();
     /// let core = Core::new().unwrap();
     /// let handle = core.handle();
     /// let current_dir = env::current_dir().unwrap();
@@ -446,50 +1426,181 @@ impl SwishClient {
     ///    .into_os_string()
     ///    .to_str()
     ///    .map(|cert_path_string| {
-    ///        SwishClient::new("1231181189", cert_path_string, "swish", handle)
+    ///        SwishClient::new(merchant_alias, cert_path_string, "swish", handle)
     ///    }).unwrap();
     ///
     /// let refund_id = "111";
     /// let refund = swish_client.get_refund(refund_id);
     /// ```
-    pub fn get_refund<'a>(&'a self, refund_id: &str) -> SwishBoxFuture<'a, Refund> {
-        self.get(format!("refunds/{}", refund_id).as_str())
+    pub fn get_refund<'a, 'b, R>(&'a self, refund: R) -> SwishBoxFuture<'a, Refund>
+    where
+        R: Into<RefundRef<'b>>,
+    {
+        self.get_refund_with_options(refund, &RequestOptions::default())
     }
 
-    /// Reads a given cert into a Vec.
-    /// Returns a Result that contains the Vec if it succeeded.
+    /// [`RequestOptions`]: struct.RequestOptions.html
+    ///
+    /// Same as [`get_refund`](#method.get_refund) but with [`RequestOptions`]
+    /// that only apply to this call.
+    ///
+    /// # Returns
+    /// A Future with a [`Refund`](struct.Refund.html).
     ///
     /// # Arguments
     ///
-    /// * `cert_path` - A string path to the place where the cert is
-    fn read_cert(&self, cert_path: &str) -> Result<Vec<u8>, io::Error> {
-        let cert_path = Path::new(&cert_path);
-        let mut buf = vec![];
-        let _result = File::open(cert_path).and_then(|mut f| f.read_to_end(&mut buf));
-        Ok(buf)
+    /// * `refund` - The id of a refund, e.g. a `&str` or a `&CreatedRefund`.
+    ///   See [`RefundRef`](../ids/struct.RefundRef.html).
+    /// * `options` - [`RequestOptions`].
+    pub fn get_refund_with_options<'a, 'b, R>(
+        &'a self,
+        refund: R,
+        options: &RequestOptions,
+    ) -> SwishBoxFuture<'a, Refund>
+    where
+        R: Into<RefundRef<'b>>,
+    {
+        let refund_id = refund.into().as_str();
+        let refund = self.get(refund_id, options);
+        let params_summary = self.params_summary(&json!({ "id": refund_id }));
+        self.instrument(GET_REFUND, params_summary, refund)
+    }
+
+    /// [`Refund`]: struct.Refund.html
+    ///
+    /// Gets a refund for a given `refund` together with the
+    /// untyped json response, e.g. to persist it for audit purposes
+    /// or to access fields that [`Refund`] doesn't cover yet.
+    ///
+    /// # Returns
+    /// A Future with a Tuple that contains the [`Refund`] and the json response.
+    ///
+    /// # Arguments
+    ///
+    /// * `refund` - The id of a refund, e.g. a `&str` or a `&CreatedRefund`.
+    ///   See [`RefundRef`](../ids/struct.RefundRef.html).
+    pub fn get_refund_with_raw<'a, 'b, R>(
+        &'a self,
+        refund: R,
+    ) -> SwishBoxFuture<'a, (Refund, serde_json::Value)>
+    where
+        R: Into<RefundRef<'b>>,
+    {
+        let refund_id = refund.into().as_str();
+        let refund = self.get_with_raw(refund_id, &RequestOptions::default());
+        let params_summary = self.params_summary(&json!({ "id": refund_id }));
+        self.instrument(GET_REFUND, params_summary, refund)
+    }
+
+    /// [`CapturedExchange`]: ../capture/struct.CapturedExchange.html
+    ///
+    /// Gets the last captured exchanges with the Swish API, oldest first.
+    ///
+    /// # Returns
+    /// The [`CapturedExchange`]s, empty unless `debug_capture`
+    /// was enabled on the [`SwishClientBuilder`](struct.SwishClientBuilder.html).
+    pub fn captured_exchanges(&self) -> Vec<CapturedExchange> {
+        self.capture
+            .as_ref()
+            .map(|capture| capture.exchanges())
+            .unwrap_or_default()
+    }
+
+    /// [`ClientStats`]: ../stats/struct.ClientStats.html
+    ///
+    /// Gets what the client is doing right now, e.g. how many
+    /// connections it has open to Swish and the calls that are in flight.
+    ///
+    /// # Returns
+    /// A snapshot of the [`ClientStats`].
+    pub fn stats(&self) -> ClientStats {
+        self.stats.snapshot()
+    }
+
+    /// Gets the reactor handle the client was created with.
+    pub(crate) fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Gets the clock the client was created with.
+    pub(crate) fn clock(&self) -> &dyn Clock {
+        &*self.clock
+    }
+
+    /// Gets the HTTPS client shared by all requests, building it on first use
+    /// so that its connection pool is reused between calls.
+    fn http_client(&self) -> Result<HttpClient<SwishConnector, Body>, SwishClientError> {
+        let mut http_client = self
+            .http_client
+            .lock()
+            .map_err(|_| SwishClientError::parse("the HTTPS client was poisoned"))?;
+        if let Some(ref client) = *http_client {
+            return Ok(client.clone());
+        }
+        let client = self.build_client()?;
+        *http_client = Some(client.clone());
+        Ok(client)
     }
 
     /// Build a HTTPS client with the root_cert and the client_cert.
     /// # Returns
     /// A Result that contains the client if it succeeded.
-    fn build_client(
-        &self,
-    ) -> Result<HttpClient<HttpsConnector<HttpConnector>, Body>, Box<error::Error>> {
-        let pkcs12_cert = &self.read_cert(&self.cert_path)?;
-        let client_cert = Identity::from_pkcs12(&pkcs12_cert, &self.passphrase)?;
-
-        let tls_connector = TlsConnector::builder().identity(client_cert).build()?;
+    /// If a connector has been supplied through the [`SwishClientBuilder`]
+    /// it's used as is and no certificate is read.
+    ///
+    /// [`SwishClientBuilder`]: struct.SwishClientBuilder.html
+    fn build_client(&self) -> Result<HttpClient<SwishConnector, Body>, ConfigError> {
+        let https_connector = match self.connector {
+            Some(Connector::Https(ref https_connector)) => HttpsKind::Custom(https_connector.clone()),
+            Some(Connector::Tls(ref tls_connector)) => HttpsKind::Resolving {
+                resolver: self.build_resolver(),
+                tls: tls_connector.clone(),
+            },
+            None => {
+                let pkcs12_cert = self
+                    .secret_provider
+                    .certificate()
+                    .map_err(ConfigError::Secret)?;
+                let passphrase = self
+                    .secret_provider
+                    .passphrase()
+                    .map_err(ConfigError::Secret)?;
+                let client_cert = Identity::from_pkcs12(&pkcs12_cert, passphrase.expose_secret())
+                    .map_err(ConfigError::Identity)?;
 
-        let mut http_connector = HttpConnector::new(4);
-        http_connector.enforce_http(false);
+                let tls_connector = TlsConnector::builder()
+                    .identity(client_cert)
+                    .min_protocol_version(self.min_tls_version)
+                    .max_protocol_version(self.max_tls_version)
+                    .build()
+                    .map_err(ConfigError::Tls)?;
 
-        let https_connector = HttpsConnector::from((http_connector, tls_connector));
+                HttpsKind::Resolving {
+                    resolver: self.build_resolver(),
+                    tls: tls_connector,
+                }
+            }
+        };
+        let https_connector = SwishConnector::new(https_connector, self.stats.open_connections());
 
-        let client = hyper::client::Client::builder().build(https_connector);
+        let mut client_builder = hyper::client::Client::builder();
+        if let Some(ref executor) = self.executor {
+            client_builder.executor(executor.clone());
+        }
+        let client = client_builder.build(https_connector);
 
         Ok(client)
     }
 
+    /// Builds the resolver that connections to the Swish API are made with.
+    fn build_resolver(&self) -> SwishResolver {
+        SwishResolver::new(
+            self.resolve_overrides.clone(),
+            self.dns_resolver.clone(),
+            self.dns_threads,
+        )
+    }
+
     /// Performs a http POST request to the Swish API.
     ///
     /// # Returns
@@ -500,30 +1611,29 @@ impl SwishClient {
     ///
     /// * `path` - A string path
     /// * `params` - Params that implements Serialize which are json sent as the body
-    fn post<'a, T: 'a, P>(
+    /// * `options` - Options for this request
+    /// * `parse` - Parses the body and the headers of a successful response
+    fn post<'a, P, T, F>(
         &'a self,
         path: &str,
         params: P,
-    ) -> SwishBoxFuture<'a, (String, hyper::header::HeaderMap)>
+        options: &RequestOptions,
+        parse: F,
+    ) -> SwishBoxFuture<'a, T>
     where
-        T: DeserializeOwned + fmt::Debug,
         P: Serialize,
+        T: 'a,
+        F: FnOnce(String, hyper::HeaderMap) -> Result<T, SwishClientError> + 'a,
     {
-        let future_result: Result<_, SwishClientError> = self
-            .get_uri(path)
-            .and_then(|uri| {
-                serde_json::to_string(&params)
-                    .and_then(|json_params| {
-                        let mut request = Request::post(uri.to_owned())
-                            .body(Body::from(json_params))
-                            .unwrap();
-                        request
-                            .headers_mut()
-                            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-
-                        Ok(self.perform_swish_api_request(request))
-                    }).map_err(SwishClientError::from)
-            }).and_then(Ok);
+        let future_result: Result<_, SwishClientError> = self.get_uri(path).and_then(|uri| {
+            let json_params = serde_json::to_string(&params)?;
+            let mut request = Request::post(uri).body(json_params)?;
+            request
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+            Ok(self.perform_swish_api_request(request, options, parse))
+        });
         Box::new(future::result(future_result).flatten())
     }
 
@@ -535,30 +1645,54 @@ impl SwishClient {
     ///
     /// # Arguments
     ///
-    /// * `path` - A string path
-    fn get<'a, T: 'a>(&'a self, path: &str) -> SwishBoxFuture<'a, T>
+    /// * `id` - The id of the payment or refund
+    /// * `options` - Options for this request
+    fn get<'a, T>(&'a self, id: &str, options: &RequestOptions) -> SwishBoxFuture<'a, T>
     where
-        T: DeserializeOwned + fmt::Debug,
+        T: SwishResponse + 'a,
     {
-        let uri = self.get_uri(path).unwrap();
-        let request = Request::get(uri).body(Body::empty()).unwrap();
-
-        let future = self
-            .perform_swish_api_request(request)
-            .and_then(move |(body, _)| future::result(self.parse_body::<T>(&body)));
-        Box::new(future)
+        Box::new(self.get_with_raw(id, options).map(|(parsed, _)| parsed))
     }
 
-    /// Parse body as json.
+    /// Performs a http GET request to the Swish API.
+    ///
+    /// # Returns
+    /// A Future with a Tuple that contains the parsed body
+    /// and the body as untyped json.
     ///
     /// # Arguments
     ///
-    /// * `body` - A string body
-    fn parse_body<T>(&self, body: &str) -> Result<T, SwishClientError>
+    /// * `id` - The id of the payment or refund
+    /// * `options` - Options for this request
+    fn get_with_raw<'a, T>(
+        &'a self,
+        id: &str,
+        options: &RequestOptions,
+    ) -> SwishBoxFuture<'a, (T, serde_json::Value)>
     where
-        T: DeserializeOwned + fmt::Debug,
+        T: SwishResponse + 'a,
     {
-        serde_json::from_str(&body).map_err(SwishClientError::from)
+        let uri = match self.get_uri(&format!("{}/{}", T::PATH, id)) {
+            Ok(uri) => uri,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        let options = options.clone();
+        let id = id.to_owned();
+
+        let future = retry::retry(
+            self.retry_policy,
+            self.clock(),
+            &self.handle,
+            &self.stats,
+            move || match Request::get(uri.clone()).body(String::new()) {
+                Ok(request) => self.perform_swish_api_request(request, &options, move |body, _| {
+                    parse_body::<T>(&body, self.strict)
+                }),
+                Err(err) => Box::new(future::err(SwishClientError::from(err))),
+            },
+        )
+        .map_err(move |err| not_found::<T>(err, id));
+        Box::new(future)
     }
 
     /// Parse a given string path into an Uri.
@@ -583,81 +1717,870 @@ impl SwishClient {
     }
 
     /// Performs the actual request to the Swish API.
+    /// The errors of the request, including the ones of `parse`,
+    /// get the [`RequestContext`](../error/struct.RequestContext.html) of the request.
+    ///
     /// # Returns
-    /// A Future with a Tuple that contains the body as a String
-    /// and the Response headers.
-    fn perform_swish_api_request<'a>(
+    /// A Future with what `parse` made of the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to send
+    /// * `options` - Options for this request
+    /// * `parse` - Parses the body and the headers of a successful response
+    fn perform_swish_api_request<'a, T, F>(
         &'a self,
-        request: Request<hyper::Body>,
-    ) -> SwishBoxFuture<'a, (String, hyper::HeaderMap)> {
-        let client = self
-            .build_client()
-            .expect("The HttpsClient couldn't be built, the certificate is probably wrong");
-
-        let future = client
-            .request(request)
-            .map_err(SwishClientError::from)
-            .and_then(move |response| {
+        mut request: Request<String>,
+        options: &RequestOptions,
+        parse: F,
+    ) -> SwishBoxFuture<'a, T>
+    where
+        T: 'a,
+        F: FnOnce(String, hyper::HeaderMap) -> Result<T, SwishClientError> + 'a,
+    {
+        let correlation_id = options
+            .correlation_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        match HeaderValue::from_str(&correlation_id) {
+            Ok(value) => {
+                request
+                    .headers_mut()
+                    .insert(self.correlation_id_header.clone(), value);
+            }
+            Err(_) => {
+                return Box::new(future::err(SwishClientError::parse(format!(
+                    "The correlation id {} isn't a valid header value",
+                    correlation_id
+                ))))
+            }
+        }
+        for (name, value) in &options.headers {
+            request.headers_mut().insert(name, value.clone());
+        }
+        for (name, value) in &self.default_headers {
+            if !request.headers().contains_key(name) {
+                request.headers_mut().insert(name, value.clone());
+            }
+        }
+
+        let context = Arc::new(RequestContext {
+            method: request.method().clone(),
+            path: request.uri().path().to_owned(),
+            merchant: self.merchant_swish_number.as_str().to_owned(),
+            correlation_id: correlation_id.clone(),
+            language: options.language.unwrap_or(self.language),
+        });
+
+        let client = match self.http_client() {
+            Ok(client) => client,
+            Err(err) => return Box::new(future::err(err)),
+        };
+
+        log_debug!(
+            "Swish API request {}: {} {} [{}] {}",
+            correlation_id,
+            request.method(),
+            request.uri(),
+            redact::redact_headers(request.headers()),
+            redact::redact_body(request.body())
+        );
+
+        let exchange = self.capture.as_ref().map(|capture| {
+            let exchange = CapturedExchange {
+                correlation_id: correlation_id.clone(),
+                method: request.method().clone(),
+                uri: request.uri().clone(),
+                request_headers: request.headers().clone(),
+                request_body: request.body().clone(),
+                response: None,
+                timings: None,
+            };
+            (capture.clone(), exchange)
+        });
+        let failed_exchange = exchange.clone();
+
+        #[cfg(feature = "otel")]
+        let otel_span = RequestSpan::start(&mut request, &correlation_id);
+        #[cfg(feature = "otel")]
+        let (response_otel_span, ended_otel_span) = (otel_span.clone(), otel_span);
+
+        let request = request.map(Body::from);
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "swish_request",
+            method = %request.method(),
+            path = request.uri().path(),
+            correlation_id = correlation_id.as_str(),
+            status = tracing::field::Empty,
+            dns_ms = tracing::field::Empty,
+            tcp_connect_ms = tracing::field::Empty,
+            tls_handshake_ms = tracing::field::Empty,
+            time_to_first_byte_ms = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let response_span = span.clone();
+
+        #[cfg(feature = "log")]
+        let response_correlation_id = correlation_id.clone();
+
+        let future = future::lazy(move || {
+            let sent = Instant::now();
+            client
+                .request(request)
+                .map(move |response| (sent.elapsed(), response))
+        }).map_err(move |err| {
+                if let Some((capture, exchange)) = failed_exchange {
+                    capture.record(exchange);
+                }
+                SwishClientError::from(err)
+            }).and_then(move |(elapsed, response)| {
                 let status = response.status();
                 let headers = response.headers().to_owned();
+                let timings =
+                    RequestTimings::new(elapsed, response.extensions().get::<ConnectTimings>());
+
+                #[cfg(feature = "tracing")]
+                response_span.record("status", status.as_u16());
+                #[cfg(feature = "tracing")]
+                timings.record(&response_span);
+                #[cfg(feature = "otel")]
+                response_otel_span.record_status(status);
 
                 response
                     .into_body()
                     .concat2()
                     .map_err(SwishClientError::from)
                     .and_then(move |body| {
-                        let body = str::from_utf8(&body).unwrap();
-
-                        if status == StatusCode::NOT_FOUND {
-                            let error = RequestError {
-                                http_status: StatusCode::NOT_FOUND,
-                                code: None,
-                                additional_information: None,
-                                message: body.to_owned(),
-                            };
-                            return future::err(SwishClientError::from(error));
+                        let body = match str::from_utf8(&body) {
+                            Ok(body) => body,
+                            Err(err) => return future::err(SwishClientError::from(err)),
+                        };
+                        if let Some((capture, exchange)) = exchange {
+                            capture.record(CapturedExchange {
+                                response: Some(CapturedResponse {
+                                    status,
+                                    headers: headers.clone(),
+                                    body: body.to_owned(),
+                                }),
+                                timings: Some(timings),
+                                ..exchange
+                            });
                         }
+                        log_debug!(
+                            "Swish API response {}: {} [{}] {}",
+                            response_correlation_id,
+                            status,
+                            redact::redact_headers(&headers),
+                            redact::redact_body(body)
+                        );
 
-                        if !status.is_success() {
-                            // Swish can sometimes return an array of errors.
-                            // TODO: http_status is wrong, set it to the actually status.
-                            let errors: SwishClientError =
-                                match serde_json::from_str::<serde_json::Value>(body) {
-                                    Ok(json) => {
-                                        let errors: Vec<_> = json
-                                            .as_array()
-                                            .into_iter()
-                                            .flat_map(|e| {
-                                                e.iter()
-                                                    .flat_map(|err| {
-                                                        serde_json::from_value::<RequestError>(
-                                                            err.clone(),
-                                                        )
-                                                    }).map(|request_error| RequestError {
-                                                        http_status: status,
-                                                        ..request_error
-                                                    }).map(SwishClientError::from)
-                                                    .collect::<Vec<SwishClientError>>()
-                                            }).collect();
-                                        SwishClientError::from(errors)
-                                    }
-                                    Err(err) => {
-                                        let error = RequestError {
-                                            additional_information: None,
-                                            code: None,
-                                            http_status: status,
-                                            message: err.to_string(),
-                                        };
-                                        SwishClientError::from(error)
-                                    }
-                                };
-                            return future::err(errors);
-                        }
-                        future::result(Ok((body.to_owned(), headers)))
+                        future::result(
+                            check_status(status, headers, body)
+                                .and_then(|headers| parse(body.to_owned(), headers)),
+                        )
                     })
             });
+        let future: SwishBoxFuture<'a, T> = match self.timeout {
+            Some(timeout) => Box::new(
+                future
+                    .select2(future::result(Timeout::new(timeout, &self.handle)).flatten())
+                    .then(move |result| match result {
+                        Ok(Either::A((parsed, _))) => Ok(parsed),
+                        Err(Either::A((err, _))) => Err(err),
+                        Ok(Either::B(((), _))) => Err(SwishClientError::from(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("Swish didn't respond within {:?}", timeout),
+                        ))),
+                        Err(Either::B((err, _))) => Err(SwishClientError::from(err)),
+                    }),
+            ),
+            None => Box::new(future),
+        };
+        let future = future.map_err(move |err| err.with_context(&context));
+
+        #[cfg(feature = "otel")]
+        let future = future.then(move |result| {
+            ended_otel_span.end(result.as_ref().err());
+            result
+        });
+
+        #[cfg(feature = "tracing")]
+        let future = tracing_futures::Instrument::instrument(future, span);
+
         Box::new(future)
     }
+
+    /// Summarizes the params of a call for the
+    /// [`ErrorReporter`](../reporter/trait.ErrorReporter.html), with personal data masked.
+    /// Nothing is summarized when there's no reporter.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The params of the call
+    fn params_summary<P: Serialize>(&self, params: &P) -> Option<serde_json::Value> {
+        self.error_reporter.as_ref().and_then(|_| {
+            serde_json::to_value(params).ok().map(|mut summary| {
+                redact::redact_json(&mut summary);
+                summary
+            })
+        })
+    }
+
+    /// Wraps the future of a public API call so that it reports to the
+    /// [`MetricsSink`](../metrics/trait.MetricsSink.html) and
+    /// [`ErrorReporter`](../reporter/trait.ErrorReporter.html) and, with the `tracing`
+    /// feature, runs in a span carrying the operation, endpoint and merchant number.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The Swish API endpoint that's called
+    /// * `params_summary` - The redacted params of the call
+    /// * `future` - The future of the call
+    fn instrument<'a, T: 'a>(
+        &self,
+        endpoint: Endpoint,
+        params_summary: Option<serde_json::Value>,
+        future: SwishBoxFuture<'a, T>,
+    ) -> SwishBoxFuture<'a, T> {
+        let in_flight = StatsRecorder::start_call(&self.stats, endpoint);
+        let future: SwishBoxFuture<'a, T> = Box::new(future.then(move |result| {
+            drop(in_flight);
+            result
+        }));
+
+        let future: SwishBoxFuture<'a, T> = match self.error_reporter {
+            Some(ref error_reporter) => {
+                let error_reporter = error_reporter.clone();
+                Box::new(future.map_err(move |err| {
+                    error_reporter.report(&ErrorReport::new(
+                        endpoint,
+                        params_summary.as_ref(),
+                        &err,
+                    ));
+                    err
+                }))
+            }
+            None => future,
+        };
+
+        let future: SwishBoxFuture<'a, T> = match self.metrics_sink {
+            Some(ref metrics_sink) => {
+                let metrics_sink = metrics_sink.clone();
+                Box::new(
+                    future::lazy({
+                        let metrics_sink = metrics_sink.clone();
+                        move || {
+                            metrics_sink.on_request_start(endpoint);
+                            Ok(Instant::now())
+                        }
+                    }).and_then(move |start| {
+                        future.then(move |result| {
+                            let outcome = match result {
+                                Ok(_) => Outcome::Success,
+                                Err(ref err) => Outcome::from_error(err),
+                            };
+                            metrics_sink.on_request_end(endpoint, start.elapsed(), &outcome);
+                            result
+                        })
+                    }),
+                )
+            }
+            None => future,
+        };
+
+        #[cfg(feature = "tracing")]
+        let future: SwishBoxFuture<'a, T> = {
+            let span = tracing::info_span!(
+                "swish_api_call",
+                operation = endpoint.operation,
+                endpoint = endpoint.path,
+                merchant = self.merchant_swish_number.as_str(),
+            );
+            Box::new(tracing_futures::Instrument::instrument(future, span))
+        };
+
+        future
+    }
+}
+
+impl SwishClientBuilder {
+    /// [`SwishClientBuilder`]: struct.SwishClientBuilder.html
+    ///
+    /// Creates a new SwishClientBuilder
+    ///
+    /// # Arguments
+    ///
+    /// * `merchant_swish_number` - The merchants swish number which will receive the payments.
+    ///   Validating it up front makes a misconfigured number fail at startup.
+    /// * `cert_path` - The path to the certificate.
+    /// * `passphrase` - The passphrase to the certificate.
+    /// * `handle` - A tokio reactor handle.
+    ///
+    /// # Returns
+    /// A [`SwishClientBuilder`] that only accepts TLS 1.2 or newer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate tokio_core;
+    /// extern crate swish_api;
+    ///
+    /// use swish_api::alias::MerchantAlias;
+    /// use swish_api::client::{Protocol, SwishClientBuilder};
+    /// use tokio_core::reactor::Core;
+    ///
+    /// let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+    /// let core = Core::new().unwrap();
+    /// let handle = core.handle();
+    /// let swish_client =
+    ///     SwishClientBuilder::new(merchant_alias, "./tests/test_cert.p12", "swish", handle)
+    ///         .max_tls_version(Some(Protocol::Tlsv12))
+    ///         .build();
+    /// ```
+    pub fn new(
+        merchant_swish_number: MerchantAlias,
+        cert_path: &str,
+        passphrase: &str,
+        handle: Handle,
+    ) -> Self {
+        SwishClient::builder(merchant_swish_number, handle).certificate_file(cert_path, passphrase)
+    }
+
+    /// Sets the minimum TLS version the client will negotiate.
+    /// Defaults to TLS 1.2, which is the lowest version Swish accepts.
+    /// `None` falls back to the platform default.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The minimum [`Protocol`](enum.Protocol.html) version.
+    pub fn min_tls_version(mut self, version: Option<Protocol>) -> Self {
+        self.min_tls_version = version;
+        self
+    }
+
+    /// Sets the maximum TLS version the client will negotiate.
+    /// Defaults to `None`, meaning the newest version the platform supports.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The maximum [`Protocol`](enum.Protocol.html) version.
+    pub fn max_tls_version(mut self, version: Option<Protocol>) -> Self {
+        self.max_tls_version = version;
+        self
+    }
+
+    /// [`SecretProvider`]: ../secret/trait.SecretProvider.html
+    ///
+    /// Uses a [`SecretProvider`] to obtain the certificate and passphrase
+    /// instead of reading them from `cert_path` and `passphrase`.
+    /// The provider is read once, when the HTTPS client is built on first use.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_provider` - A [`SecretProvider`], e.g. backed by an OS keychain.
+    pub fn secret_provider<P>(mut self, secret_provider: P) -> Self
+    where
+        P: SecretProvider + 'static,
+    {
+        self.secret_provider = Box::new(secret_provider);
+        self
+    }
+
+    /// Uses a pre-built `TlsConnector` instead of building one from the
+    /// certificate and passphrase, e.g. when the client identity lives in an HSM.
+    /// The TLS version settings of the builder are not applied to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `tls_connector` - A configured `native_tls::TlsConnector`.
+    pub fn tls_connector(mut self, tls_connector: TlsConnector) -> Self {
+        self.connector = Some(Connector::Tls(tls_connector));
+        self
+    }
+
+    /// Pins `host` to the given IP addresses, bypassing DNS for it entirely.
+    /// The TLS certificate is still verified against `host`.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The hostname to pin, e.g. `cpc.getswish.net`.
+    /// * `addrs` - The IP addresses to connect to instead.
+    pub fn resolve(mut self, host: &str, addrs: Vec<IpAddr>) -> Self {
+        self.resolve_overrides.insert(host.to_owned(), addrs);
+        self
+    }
+
+    /// [`DnsResolver`]: ../dns/trait.DnsResolver.html
+    ///
+    /// Resolves hostnames through a custom [`DnsResolver`].
+    /// Hosts pinned with `resolve` are never passed to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `dns_resolver` - A [`DnsResolver`].
+    pub fn dns_resolver<R>(mut self, dns_resolver: R) -> Self
+    where
+        R: DnsResolver + 'static,
+    {
+        self.dns_resolver = Some(Arc::new(dns_resolver));
+        self
+    }
+
+    /// Sets the number of threads used for blocking DNS lookups.
+    /// Defaults to 4.
+    ///
+    /// # Arguments
+    ///
+    /// * `threads` - The number of DNS worker threads.
+    pub fn dns_threads(mut self, threads: usize) -> Self {
+        self.dns_threads = threads;
+        self
+    }
+
+    /// Sets the executor used to run the background connection tasks.
+    /// Defaults to the executor of the current tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - A futures `Executor` for boxed connection tasks.
+    pub fn executor<E>(mut self, executor: E) -> Self
+    where
+        E: Executor<ConnectionTask> + Send + Sync + 'static,
+    {
+        self.executor = Some(SharedExecutor::new(executor));
+        self
+    }
+
+    /// Sets the User-Agent sent with every request.
+    /// The crate name and version is always appended,
+    /// e.g. `my-shop/1.0 swish-api/0.1.0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - The User-Agent of the application.
+    pub fn user_agent(mut self, user_agent: HeaderValue) -> Self {
+        let mut value = user_agent.as_bytes().to_vec();
+        value.push(b' ');
+        value.extend_from_slice(CRATE_USER_AGENT.as_bytes());
+        let value = HeaderValue::from_bytes(&value).unwrap_or(user_agent);
+        self.default_headers.insert(USER_AGENT, value);
+        self
+    }
+
+    /// Adds a header that's sent with every request.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header.
+    /// * `value` - The value of the header.
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// [`MetricsSink`]: ../metrics/trait.MetricsSink.html
+    ///
+    /// Reports the latency and outcome of every call to a [`MetricsSink`].
+    ///
+    /// # Arguments
+    ///
+    /// * `metrics_sink` - A [`MetricsSink`].
+    pub fn metrics_sink<M>(mut self, metrics_sink: M) -> Self
+    where
+        M: MetricsSink + 'static,
+    {
+        self.metrics_sink = Some(Arc::new(metrics_sink));
+        self
+    }
+
+    /// Sets the header used to send the correlation id of every request.
+    /// Defaults to `X-Correlation-ID`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header.
+    pub fn correlation_id_header(mut self, name: HeaderName) -> Self {
+        self.correlation_id_header = name;
+        self
+    }
+
+    /// [`ErrorReporter`]: ../reporter/trait.ErrorReporter.html
+    ///
+    /// Reports every failed call to an [`ErrorReporter`].
+    ///
+    /// # Arguments
+    ///
+    /// * `error_reporter` - An [`ErrorReporter`].
+    pub fn error_reporter<R>(mut self, error_reporter: R) -> Self
+    where
+        R: ErrorReporter + 'static,
+    {
+        self.error_reporter = Some(Arc::new(error_reporter));
+        self
+    }
+
+    /// Records the raw requests and responses of the last `capacity` calls,
+    /// retrievable through `SwishClient::captured_exchanges`.
+    /// The captured data contains personal data and is not redacted.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of calls to keep.
+    pub fn debug_capture(mut self, capacity: usize) -> Self {
+        self.debug_capture = Some(capacity);
+        self
+    }
+
+    /// Fails calls whose response contains fields that aren't known by this crate,
+    /// e.g. to notice changes to the Swish API in a staging environment.
+    /// By default, unknown fields are kept in the `extra` field of the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - Whether unknown fields are an error.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the language of the messages that errors have for customers,
+    /// unless a call sets its own. Defaults to English.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The [`Language`](../error/enum.Language.html) of the messages.
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Sets the callback URL of the payments and refunds
+    /// whose params don't have a `callback_url` of their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback_url` - The [`CallbackUrl`](../callback/struct.CallbackUrl.html) to use by default.
+    pub fn default_callback_url(mut self, callback_url: CallbackUrl) -> Self {
+        self.default_callback_url = Some(callback_url);
+        self
+    }
+
+    /// Prepends a prefix to the `payee_payment_reference` of every payment,
+    /// e.g. to tell apart the payments of several shops using the same Swish number.
+    /// The prefixed reference is validated like any other reference.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The prefix of the references.
+    pub fn payee_payment_reference_prefix(mut self, prefix: &str) -> Self {
+        self.payee_payment_reference_prefix = Some(prefix.to_owned());
+        self
+    }
+
+    /// Sets the URL of the Swish API the client calls, e.g. the one of a
+    /// [`SwishSimulator`](../simulator/struct.SwishSimulator.html).
+    /// Defaults to the Swish test environment.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL the paths of the Swish API are appended to,
+    ///   e.g. `https://mss.cpc.getswish.net/swish-cpcapi/api/v1/`.
+    pub fn api_url(mut self, url: &str) -> Self {
+        self.swish_api_url = url.to_owned();
+        if !self.swish_api_url.ends_with('/') {
+            self.swish_api_url.push('/');
+        }
+        self
+    }
+
+    /// [`Clock`]: ../clock/trait.Clock.html
+    ///
+    /// Sets the [`Clock`] that the polling of payments, the backoff of retries
+    /// and the creation time of payments are timed by, e.g. a `ManualClock` in tests.
+    /// The [`timeout`](#method.timeout) of requests is always timed by the reactor.
+    /// Defaults to the clock of the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The [`Clock`].
+    pub fn clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Sets the environment of Swish to call. Defaults to the test environment.
+    /// Use [`api_url`](#method.api_url) for a Swish API at another URL, e.g. a simulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The [`Environment`](enum.Environment.html).
+    pub fn environment(self, environment: Environment) -> Self {
+        self.api_url(environment.api_url())
+    }
+
+    /// Reads the certificate from a PKCS#12 file, once the client first uses it.
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_path` - The path to the certificate.
+    /// * `passphrase` - The passphrase to the certificate.
+    pub fn certificate_file(mut self, cert_path: &str, passphrase: &str) -> Self {
+        self.secret_provider = Box::new(FileSecretProvider::new(cert_path, passphrase));
+        self
+    }
+
+    /// Sets how long to wait for Swish to respond to a request, after which it fails
+    /// with an `io::Error` of the kind `TimedOut`, which is retryable. Defaults to
+    /// waiting as long as the connection stays open.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The time from when the request is sent, including connecting.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// [`RetryPolicy`]: ../retry/struct.RetryPolicy.html
+    ///
+    /// Sets the [`RetryPolicy`] that fetching payments and refunds is retried with.
+    /// Defaults to not retrying.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_policy` - The [`RetryPolicy`].
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Uses a pre-built hyper connector for all requests. Neither the
+    /// certificate, the TLS version settings nor the DNS settings
+    /// of the builder are used.
+    ///
+    /// # Arguments
+    ///
+    /// * `https_connector` - A configured `hyper_tls::HttpsConnector`.
+    pub fn https_connector(mut self, https_connector: HttpsConnector<HttpConnector>) -> Self {
+        self.connector = Some(Connector::Https(https_connector));
+        self
+    }
+
+    /// [`SwishClient`]: struct.SwishClient.html
+    ///
+    /// Builds the configured [`SwishClient`].
+    pub fn build(self) -> SwishClient {
+        SwishClient {
+            merchant_swish_number: self.merchant_swish_number,
+            swish_api_url: self.swish_api_url,
+            secret_provider: self.secret_provider,
+            min_tls_version: self.min_tls_version,
+            max_tls_version: self.max_tls_version,
+            connector: self.connector,
+            resolve_overrides: Arc::new(self.resolve_overrides),
+            dns_resolver: self.dns_resolver,
+            dns_threads: self.dns_threads,
+            executor: self.executor,
+            default_headers: self.default_headers,
+            metrics_sink: self.metrics_sink,
+            correlation_id_header: self.correlation_id_header,
+            capture: self
+                .debug_capture
+                .map(|capacity| Arc::new(CaptureBuffer::new(capacity))),
+            error_reporter: self.error_reporter,
+            strict: self.strict,
+            language: self.language,
+            default_callback_url: self.default_callback_url,
+            payee_payment_reference_prefix: self.payee_payment_reference_prefix,
+            stats: Arc::new(StatsRecorder::default()),
+            timeout: self.timeout,
+            retry_policy: self.retry_policy,
+            clock: self.clock,
+            http_client: Mutex::new(None),
+            handle: self.handle,
+        }
+    }
+
+    /// [`SwishClient`]: struct.SwishClient.html
+    /// [`ConfigError`]: ../error/enum.ConfigError.html
+    ///
+    /// Builds the configured [`SwishClient`], loading the certificate
+    /// and building the TLS connector right away.
+    ///
+    /// # Returns
+    /// The [`SwishClient`], or a [`ConfigError`] if the certificate
+    /// couldn't be read or the passphrase is wrong.
+    pub fn try_build(self) -> Result<SwishClient, ConfigError> {
+        let mut client = self.build();
+        let http_client = client.build_client()?;
+        *client
+            .http_client
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = Some(http_client);
+        Ok(client)
+    }
+}
+
+/// The secrets of a client built without a certificate, which fail to be read.
+#[derive(Debug)]
+struct MissingSecretProvider;
+
+impl SecretProvider for MissingSecretProvider {
+    fn passphrase(&self) -> io::Result<SecretString> {
+        Err(missing_certificate())
+    }
+
+    fn certificate(&self) -> io::Result<Vec<u8>> {
+        Err(missing_certificate())
+    }
+}
+
+fn missing_certificate() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        "no certificate is configured, set one with certificate_file or secret_provider",
+    )
+}
+
+/// The headers sent with every request by default.
+fn default_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static(CRATE_USER_AGENT));
+    headers
+}
+
+/// Turns a response with an error status into the error Swish
+/// responded with. The headers are handed back when the status is a success.
+///
+/// # Arguments
+///
+/// * `status` - The status of the response
+/// * `headers` - The headers of the response
+/// * `body` - The body of the response
+pub(crate) fn check_status(
+    status: StatusCode,
+    headers: HeaderMap,
+    body: &str,
+) -> Result<HeaderMap, SwishClientError> {
+    if status == StatusCode::NOT_FOUND {
+        let error = RequestError {
+            http_status: StatusCode::NOT_FOUND,
+            code: None,
+            additional_information: None,
+            message: body.to_owned(),
+            response: Some(Arc::new(ErrorResponse {
+                headers,
+                body: body.to_owned(),
+            })),
+            correlation_id: None,
+            context: None,
+        };
+        return Err(SwishClientError::from(error));
+    }
+    if !status.is_success() {
+        return Err(parse_error_body(status, headers, body));
+    }
+    Ok(headers)
+}
+
+/// Turns a 404 response of Swish into a
+/// [`SwishClientError::NotFound`](../error/enum.SwishClientError.html#variant.NotFound).
+fn not_found<T: SwishResponse>(err: SwishClientError, id: String) -> SwishClientError {
+    match err {
+        SwishClientError::Swish(ref request_error)
+            if request_error.http_status == StatusCode::NOT_FOUND =>
+        {
+            SwishClientError::NotFound {
+                resource: T::RESOURCE,
+                id,
+                correlation_id: request_error.correlation_id.clone(),
+            }
+        }
+        err => err,
+    }
+}
+
+/// Parses the body of a fetched payment or refund into `T` and untyped json.
+/// In strict mode, fields that aren't known by `T` are an error.
+///
+/// # Arguments
+///
+/// * `body` - The body of the response
+/// * `strict` - Whether unknown fields are an error
+pub(crate) fn parse_body<T>(
+    body: &str,
+    strict: bool,
+) -> Result<(T, serde_json::Value), SwishClientError>
+where
+    T: SwishResponse,
+{
+    if body.trim().is_empty() {
+        return Err(SwishClientError::parse("Swish responded without a body"));
+    }
+    let raw = serde_json::from_str::<serde_json::Value>(body)?;
+    let parsed = T::deserialize(&raw)?;
+    if strict && !parsed.extra().is_empty() {
+        let fields: Vec<_> = parsed.extra().keys().map(String::as_str).collect();
+        return Err(SwishClientError::parse(format!(
+            "The response from Swish contains unknown fields: {}",
+            fields.join(", ")
+        )));
+    }
+    Ok((parsed, raw))
+}
+
+/// Parses the body of an unsuccessful response into the errors Swish returned.
+/// Swish returns an array of errors, but a single error is handled as well.
+/// Every error gets the status, the headers and the body of the response.
+///
+/// # Arguments
+///
+/// * `status` - The status of the response
+/// * `headers` - The headers of the response
+/// * `body` - The body of the response
+fn parse_error_body(status: StatusCode, headers: HeaderMap, body: &str) -> SwishClientError {
+    let response = Arc::new(ErrorResponse {
+        headers,
+        body: body.to_owned(),
+    });
+    let request_error = |message: String| RequestError {
+        http_status: status,
+        code: None,
+        message,
+        additional_information: None,
+        response: Some(response.clone()),
+        correlation_id: None,
+        context: None,
+    };
+    let parse_error = |error: serde_json::Value| {
+        serde_json::from_value::<RequestError>(error.clone())
+            .map(|parsed_error| RequestError {
+                http_status: status,
+                response: Some(response.clone()),
+                ..parsed_error
+            })
+            .unwrap_or_else(|_| request_error(error.to_string()))
+    };
+
+    if body.trim().is_empty() {
+        return SwishClientError::from(request_error(String::new()));
+    }
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Array(errors)) => SwishClientError::from(
+            errors
+                .into_iter()
+                .map(|error| SwishClientError::from(parse_error(error)))
+                .collect::<ErrorCollection>(),
+        ),
+        Ok(error @ serde_json::Value::Object(_)) => SwishClientError::from(parse_error(error)),
+        Ok(_) => SwishClientError::from(request_error(body.to_owned())),
+        Err(err) => SwishClientError::from(request_error(err.to_string())),
+    }
 }
 
 /// Gets a hyper::Header and turns it into a String.
@@ -673,3 +2596,30 @@ fn get_header_as_string(
         .get(header)
         .and_then(|h| h.to_str().ok().map(|h| h.to_string()))
 }
+
+/// Adds a missing callback URL to the errors of params, once the
+/// default callback URL of the client has been filled in, as Swish requires one.
+///
+/// # Arguments
+///
+/// * `callback_url` - The callback URL of the params.
+/// * `validated` - The result of validating the params.
+fn require_callback_url(
+    callback_url: &Option<CallbackUrl>,
+    validated: Result<(), Vec<ValidationError>>,
+) -> Result<(), Vec<ValidationError>> {
+    let mut errors = validated.err().unwrap_or_default();
+    if callback_url.is_none() {
+        errors.insert(
+            0,
+            ValidationError::Missing {
+                field: "callbackUrl",
+            },
+        );
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,187 @@
+//! # The clock module
+//!
+//! Contains the clock that the polling of payments and its backoff,
+//! and the expiry of payment requests are timed by. Tests can replace the
+//! [`SystemClock`](struct.SystemClock.html) with a
+//! [`ManualClock`](struct.ManualClock.html) to move time on instantly.
+//!
+//! # Example
+//!
+//! ```
+//! extern crate futures;
+//! extern crate swish_api;
+//! extern crate tokio_core;
+//!
+//! use futures::Future;
+//! use std::thread;
+//! use std::time::Duration;
+//! use swish_api::clock::{Clock, ManualClock};
+//! use tokio_core::reactor::Core;
+//!
+//! let mut core = Core::new().unwrap();
+//! let clock = ManualClock::new();
+//! let sleep = clock.sleep(&core.handle(), Duration::from_secs(60));
+//!
+//! let advancing = clock.clone();
+//! thread::spawn(move || advancing.advance(Duration::from_secs(60)));
+//!
+//! core.run(sleep).unwrap();
+//! assert_eq!(clock.elapsed(), Duration::from_secs(60));
+//! ```
+//!
+use futures::task::{self, Task};
+use futures::{future, Async, Future, Poll};
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio_core::reactor::{Handle, Timeout};
+
+/// The future returned by [`Clock::sleep`](trait.Clock.html#tymethod.sleep).
+pub type Sleep = Box<dyn Future<Item = (), Error = io::Error>>;
+
+/// Tells the time, and waits for it to pass.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Gets the current time, e.g. to tell when a payment was created.
+    fn now(&self) -> SystemTime;
+
+    /// Gets the current instant, e.g. to schedule the next poll of a payment.
+    fn instant(&self) -> Instant;
+
+    /// Completes once the duration has passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The tokio-core handle to time the sleep on.
+    /// * `duration` - The time to sleep.
+    fn sleep(&self, handle: &Handle, duration: Duration) -> Sleep;
+}
+
+/// The clock of the system, which is the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, handle: &Handle, duration: Duration) -> Sleep {
+        Box::new(future::result(Timeout::new(duration, handle)).flatten())
+    }
+}
+
+/// A clock that only moves when it's advanced, for tests.
+///
+/// Clones share the time, so a clone can be advanced from another thread
+/// while the futures sleeping on the clock are run.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    state: Arc<Mutex<ManualState>>,
+}
+
+#[derive(Debug)]
+struct ManualState {
+    started: SystemTime,
+    started_instant: Instant,
+    elapsed: Duration,
+    sleepers: Vec<Task>,
+}
+
+impl ManualClock {
+    /// [`ManualClock`]: struct.ManualClock.html
+    ///
+    /// Creates a new [`ManualClock`] starting at the current time.
+    pub fn new() -> Self {
+        ManualClock::starting_at(SystemTime::now())
+    }
+
+    /// [`ManualClock`]: struct.ManualClock.html
+    ///
+    /// Creates a new [`ManualClock`] starting at the given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `started` - The time the clock starts at.
+    pub fn starting_at(started: SystemTime) -> Self {
+        ManualClock {
+            state: Arc::new(Mutex::new(ManualState {
+                started,
+                started_instant: Instant::now(),
+                elapsed: Duration::default(),
+                sleepers: Vec::new(),
+            })),
+        }
+    }
+
+    /// Moves the time on, completing the sleeps that are over.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - The time to move on.
+    pub fn advance(&self, duration: Duration) {
+        let sleepers = {
+            let mut state = self.state.lock().unwrap();
+            state.elapsed += duration;
+            std::mem::take(&mut state.sleepers)
+        };
+        for sleeper in sleepers {
+            sleeper.notify();
+        }
+    }
+
+    /// Gets the time the clock has been advanced.
+    pub fn elapsed(&self) -> Duration {
+        self.state.lock().unwrap().elapsed
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        let state = self.state.lock().unwrap();
+        state.started + state.elapsed
+    }
+
+    fn instant(&self) -> Instant {
+        let state = self.state.lock().unwrap();
+        state.started_instant + state.elapsed
+    }
+
+    fn sleep(&self, _: &Handle, duration: Duration) -> Sleep {
+        Box::new(ManualSleep {
+            state: self.state.clone(),
+            until: self.elapsed() + duration,
+        })
+    }
+}
+
+/// A sleep on a [`ManualClock`](struct.ManualClock.html).
+struct ManualSleep {
+    state: Arc<Mutex<ManualState>>,
+    until: Duration,
+}
+
+impl Future for ManualSleep {
+    type Item = ();
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(), io::Error> {
+        let mut state = self.state.lock().unwrap();
+        if state.elapsed >= self.until {
+            Ok(Async::Ready(()))
+        } else {
+            state.sleepers.push(task::current());
+            Ok(Async::NotReady)
+        }
+    }
+}
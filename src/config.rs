@@ -0,0 +1,158 @@
+//! # The config module
+//!
+//! Contains the settings of a client in a form that can be deserialized with serde,
+//! e.g. from the TOML or YAML config file of a service, so that the environment,
+//! the certificate, the timeout and the retries can be changed without changing code.
+//!
+//! # Example
+//!
+//! ```toml
+//! [swish]
+//! merchant_swish_number = "1231181189"
+//! environment = "production"
+//! cert_path = "/etc/swish/cert.p12"
+//! passphrase = "swish"
+//! timeout_ms = 10000
+//!
+//! [swish.retry]
+//! max_retries = 2
+//! ```
+//!
+//! ```no_run
+//! extern crate serde_json;
+//! extern crate swish_api;
+//! extern crate tokio_core;
+//!
+//! use swish_api::client::SwishClient;
+//! use swish_api::config::SwishConfig;
+//! use tokio_core::reactor::Core;
+//!
+//! let config: SwishConfig = serde_json::from_str(
+//!     r#"{
+//!         "merchant_swish_number": "1231181189",
+//!         "cert_path": "./tests/test_cert.p12",
+//!         "passphrase": "swish",
+//!         "retry": { "max_retries": 2 }
+//!     }"#,
+//! ).unwrap();
+//! let core = Core::new().unwrap();
+//! let swish_client = SwishClient::from_config(&config, core.handle()).unwrap();
+//! ```
+//!
+use alias::MerchantAlias;
+use client::{Environment, SwishClient, SwishClientBuilder};
+use error::ConfigError;
+use native_tls::TlsConnector;
+use retry::RetryPolicy;
+use secret::{FileSecretProvider, SecretString};
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio_core::reactor::Handle;
+
+/// The settings of a [`SwishClient`](../client/struct.SwishClient.html).
+///
+/// Unknown fields are rejected, so that a misspelled setting fails at startup
+/// instead of being ignored.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwishConfig {
+    /// The Swish number of the merchant which will receive the payments.
+    pub merchant_swish_number: MerchantAlias,
+    /// The environment of Swish to call. Defaults to the test environment.
+    #[serde(default)]
+    pub environment: Environment,
+    /// The URL of a Swish API to call instead of the one of the environment,
+    /// e.g. the one of a simulator.
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// The path to the PKCS#12 certificate. It can only be left out together
+    /// with an `api_url`, e.g. the one of a simulator, which the client then
+    /// connects to without a client certificate.
+    #[serde(default)]
+    pub cert_path: Option<PathBuf>,
+    /// The passphrase to the certificate.
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// How many milliseconds to wait for Swish to respond to a request.
+    /// Defaults to waiting as long as the connection stays open.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// How fetching payments and refunds is retried.
+    #[serde(default)]
+    pub retry: RetryConfig,
+}
+
+/// The settings of a [`RetryPolicy`](../retry/struct.RetryPolicy.html),
+/// which default to the ones of `RetryPolicy::new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// How many times a request is retried after it first failed.
+    pub max_retries: u32,
+    /// How many milliseconds to wait before the first retry.
+    pub backoff_ms: u64,
+    /// The most milliseconds to wait between retries.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        let policy = RetryPolicy::default();
+        RetryConfig {
+            max_retries: policy.max_retries,
+            backoff_ms: policy.backoff.as_millis() as u64,
+            max_backoff_ms: policy.max_backoff.as_millis() as u64,
+        }
+    }
+}
+
+impl From<RetryConfig> for RetryPolicy {
+    fn from(config: RetryConfig) -> Self {
+        RetryPolicy::new()
+            .max_retries(config.max_retries)
+            .backoff(Duration::from_millis(config.backoff_ms))
+            .max_backoff(Duration::from_millis(config.max_backoff_ms))
+    }
+}
+
+impl SwishConfig {
+    /// [`SwishClientBuilder`]: ../client/struct.SwishClientBuilder.html
+    ///
+    /// Creates a [`SwishClientBuilder`] with the settings, e.g. to set
+    /// the hooks of the client before it's built.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - A tokio reactor handle.
+    ///
+    /// # Returns
+    /// The [`SwishClientBuilder`], or a `ConfigError` if there's no certificate
+    /// and no `api_url`, or a TLS connector without a certificate couldn't be built.
+    pub fn client_builder(&self, handle: Handle) -> Result<SwishClientBuilder, ConfigError> {
+        let mut builder = SwishClient::builder(self.merchant_swish_number.clone(), handle)
+            .environment(self.environment)
+            .retry_policy(self.retry.into());
+        if let Some(ref api_url) = self.api_url {
+            builder = builder.api_url(api_url);
+        }
+        builder = if let Some(ref cert_path) = self.cert_path {
+            builder.secret_provider(FileSecretProvider::new(cert_path, self.passphrase.clone()))
+        } else if self.api_url.is_some() {
+            builder.tls_connector(TlsConnector::new().map_err(ConfigError::Tls)?)
+        } else {
+            return Err(ConfigError::Secret(missing_cert_path()));
+        };
+        if let Some(timeout_ms) = self.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout_ms));
+        }
+        Ok(builder)
+    }
+}
+
+fn missing_cert_path() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        "no cert_path is configured, which is only allowed with an api_url",
+    )
+}
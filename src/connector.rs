@@ -0,0 +1,203 @@
+//! The connector used by the client to establish HTTPS connections.
+//!
+use dns::SwishResolver;
+use futures::future::{ExecuteError, Executor};
+use futures::{Future, Poll};
+use hyper::client::connect::dns::{Name, Resolve};
+use hyper::client::connect::{Connect, Connected, Destination};
+use hyper::client::HttpConnector;
+use hyper_tls::{HttpsConnector, MaybeHttpsStream};
+use native_tls::TlsConnector;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use timing::ConnectPhases;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+type TcpTransport = <HttpConnector as Connect>::Transport;
+
+/// Either a connector supplied by the user or
+/// one built by the client using its own resolver.
+#[derive(Debug, Clone)]
+pub(crate) enum HttpsKind {
+    Custom(HttpsConnector<HttpConnector>),
+    Resolving {
+        resolver: SwishResolver,
+        tls: TlsConnector,
+    },
+}
+
+/// The connector used by the client, which keeps
+/// count of the connections it has open.
+#[derive(Debug, Clone)]
+pub(crate) struct SwishConnector {
+    kind: HttpsKind,
+    open_connections: Arc<AtomicUsize>,
+}
+
+impl SwishConnector {
+    pub(crate) fn new(kind: HttpsKind, open_connections: Arc<AtomicUsize>) -> Self {
+        SwishConnector {
+            kind,
+            open_connections,
+        }
+    }
+}
+
+impl Connect for SwishConnector {
+    type Transport = CountedStream<MaybeHttpsStream<TcpTransport>>;
+    type Error = io::Error;
+    type Future = Box<dyn Future<Item = (Self::Transport, Connected), Error = io::Error> + Send>;
+
+    fn connect(&self, dst: Destination) -> Self::Future {
+        let open_connections = self.open_connections.clone();
+        match self.kind {
+            HttpsKind::Custom(ref connector) => {
+                Box::new(connector.connect(dst).map(move |(stream, connected)| {
+                    (CountedStream::new(stream, open_connections), connected)
+                }))
+            }
+            HttpsKind::Resolving {
+                ref resolver,
+                ref tls,
+            } => {
+                let phases = ConnectPhases::start();
+                let mut http_connector = HttpConnector::new_with_resolver(TimedResolver {
+                    inner: resolver.clone(),
+                    phases: phases.clone(),
+                });
+                http_connector.enforce_http(false);
+                let http_connector = TimedConnector {
+                    inner: http_connector,
+                    phases: phases.clone(),
+                };
+
+                Box::new(
+                    HttpsConnector::from((http_connector, tls.clone()))
+                        .connect(dst)
+                        .map(move |(stream, connected)| {
+                            let connected = connected.extra(phases.finish());
+                            (CountedStream::new(stream, open_connections), connected)
+                        }),
+                )
+            }
+        }
+    }
+}
+
+/// Notes when the address of a connection has been resolved.
+#[derive(Debug, Clone)]
+struct TimedResolver {
+    inner: SwishResolver,
+    phases: Arc<ConnectPhases>,
+}
+
+impl Resolve for TimedResolver {
+    type Addrs = <SwishResolver as Resolve>::Addrs;
+    type Future = Box<dyn Future<Item = Self::Addrs, Error = io::Error> + Send>;
+
+    fn resolve(&self, name: Name) -> Self::Future {
+        let phases = self.phases.clone();
+        Box::new(self.inner.resolve(name).map(move |addrs| {
+            phases.resolved();
+            addrs
+        }))
+    }
+}
+
+/// Notes when the TCP connection has been established, before the TLS handshake.
+#[derive(Debug, Clone)]
+struct TimedConnector {
+    inner: HttpConnector<TimedResolver>,
+    phases: Arc<ConnectPhases>,
+}
+
+impl Connect for TimedConnector {
+    type Transport = TcpTransport;
+    type Error = io::Error;
+    type Future = Box<dyn Future<Item = (Self::Transport, Connected), Error = io::Error> + Send>;
+
+    fn connect(&self, dst: Destination) -> Self::Future {
+        let phases = self.phases.clone();
+        Box::new(self.inner.connect(dst).map(move |connection| {
+            phases.connected();
+            connection
+        }))
+    }
+}
+
+/// A stream that's counted as an open connection until it's dropped.
+#[derive(Debug)]
+pub(crate) struct CountedStream<T> {
+    inner: T,
+    open_connections: Arc<AtomicUsize>,
+}
+
+impl<T> CountedStream<T> {
+    fn new(inner: T, open_connections: Arc<AtomicUsize>) -> Self {
+        open_connections.fetch_add(1, Ordering::SeqCst);
+        CountedStream {
+            inner,
+            open_connections,
+        }
+    }
+}
+
+impl<T> Drop for CountedStream<T> {
+    fn drop(&mut self) {
+        self.open_connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<T: Read> Read for CountedStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<T: Write> Write for CountedStream<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for CountedStream<T> {}
+
+impl<T: AsyncWrite> AsyncWrite for CountedStream<T> {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
+
+/// A background connection task that hyper hands to the executor.
+pub type ConnectionTask = Box<dyn Future<Item = (), Error = ()> + Send>;
+
+/// An executor that can be cloned into every hyper client the client builds.
+#[derive(Clone)]
+pub(crate) struct SharedExecutor(Arc<dyn Executor<ConnectionTask> + Send + Sync>);
+
+impl SharedExecutor {
+    pub(crate) fn new<E>(executor: E) -> Self
+    where
+        E: Executor<ConnectionTask> + Send + Sync + 'static,
+    {
+        SharedExecutor(Arc::new(executor))
+    }
+}
+
+impl Executor<ConnectionTask> for SharedExecutor {
+    fn execute(&self, future: ConnectionTask) -> Result<(), ExecuteError<ConnectionTask>> {
+        self.0.execute(future)
+    }
+}
+
+impl fmt::Debug for SharedExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SharedExecutor").finish()
+    }
+}
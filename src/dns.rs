@@ -0,0 +1,77 @@
+//! # The dns module
+//!
+//! Contains the hooks used to override how the Swish hostnames are resolved,
+//! e.g. to pin the API to a set of allowlisted IP addresses.
+//!
+use futures::{future, Future};
+use hyper::client::connect::dns::{GaiResolver, Name, Resolve};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::vec;
+
+/// A custom DNS resolver.
+///
+/// Implement this to resolve the Swish hostnames in environments
+/// with split-horizon DNS or static egress allowlists.
+pub trait DnsResolver: fmt::Debug + Send + Sync {
+    /// Resolves `host` into a set of IP addresses.
+    ///
+    /// Returning `Ok(None)` falls back to the system resolver.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The hostname that's about to be connected to.
+    fn resolve(&self, host: &str) -> io::Result<Option<Vec<IpAddr>>>;
+}
+
+/// The resolver used by the client. Pinned addresses take
+/// precedence over the custom resolver, which in turn takes
+/// precedence over the system resolver.
+#[derive(Debug, Clone)]
+pub(crate) struct SwishResolver {
+    overrides: Arc<HashMap<String, Vec<IpAddr>>>,
+    resolver: Option<Arc<dyn DnsResolver>>,
+    gai: GaiResolver,
+}
+
+impl SwishResolver {
+    pub(crate) fn new(
+        overrides: Arc<HashMap<String, Vec<IpAddr>>>,
+        resolver: Option<Arc<dyn DnsResolver>>,
+        threads: usize,
+    ) -> Self {
+        SwishResolver {
+            overrides,
+            resolver,
+            gai: GaiResolver::new(threads),
+        }
+    }
+}
+
+impl Resolve for SwishResolver {
+    type Addrs = vec::IntoIter<IpAddr>;
+    type Future = Box<dyn Future<Item = Self::Addrs, Error = io::Error> + Send>;
+
+    fn resolve(&self, name: Name) -> Self::Future {
+        if let Some(addrs) = self.overrides.get(name.as_str()) {
+            return Box::new(future::ok(addrs.clone().into_iter()));
+        }
+
+        if let Some(ref resolver) = self.resolver {
+            match resolver.resolve(name.as_str()) {
+                Ok(Some(addrs)) => return Box::new(future::ok(addrs.into_iter())),
+                Err(err) => return Box::new(future::err(err)),
+                Ok(None) => {}
+            }
+        }
+
+        Box::new(
+            self.gai
+                .resolve(name)
+                .map(|addrs| addrs.collect::<Vec<_>>().into_iter()),
+        )
+    }
+}
@@ -0,0 +1,176 @@
+//! # The eligibility module
+//!
+//! Contains the checks of whether a payment can be refunded, which are
+//! done by Swish as well, so that a refund that would be rejected can be
+//! explained to the user before it's sent.
+//!
+use amount::Amount;
+use chrono::{DateTime, FixedOffset, Months, Utc};
+use client::{Payment, Status};
+use error::ErrorCode;
+use std::cmp;
+use std::fmt;
+use std::time::SystemTime;
+use validation::{self, ValidationError};
+
+/// The number of months after it was paid that a payment can be refunded.
+pub const REFUND_WINDOW_MONTHS: u32 = 13;
+
+/// A reason Swish would reject a refund of a payment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RefundIneligibility {
+    /// The payment isn't paid.
+    NotPaid { status: Option<Status> },
+    /// The payment doesn't say when it was paid, so its age can't be checked.
+    PaidDateUnknown,
+    /// The payment was paid more than 13 months ago.
+    TooOld {
+        /// The last moment the payment could be refunded.
+        refundable_until: DateTime<FixedOffset>,
+    },
+    /// The amount isn't one Swish accepts for a refund.
+    InvalidAmount(ValidationError),
+    /// The amount is more than what's left of the payment after previous refunds.
+    ExceedsRemaining {
+        requested: Amount,
+        remaining: Amount,
+    },
+}
+
+impl RefundIneligibility {
+    /// Gets the error code Swish would reject the refund with, if there's one.
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match *self {
+            RefundIneligibility::TooOld { .. } => Some(ErrorCode::RF02),
+            RefundIneligibility::InvalidAmount(_) => Some(ErrorCode::PA02),
+            RefundIneligibility::ExceedsRemaining { .. } => Some(ErrorCode::RF08),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RefundIneligibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RefundIneligibility::NotPaid {
+                status: Some(ref status),
+            } => write!(f, "the payment is {}, not PAID", status.as_str()),
+            RefundIneligibility::NotPaid { status: None } => {
+                write!(f, "the payment has no status")
+            }
+            RefundIneligibility::PaidDateUnknown => {
+                write!(f, "it's not known when the payment was paid")
+            }
+            RefundIneligibility::TooOld { refundable_until } => write!(
+                f,
+                "the payment could only be refunded until {}",
+                refundable_until.to_rfc3339()
+            ),
+            RefundIneligibility::InvalidAmount(ref error) => error.fmt(f),
+            RefundIneligibility::ExceedsRemaining {
+                requested,
+                remaining,
+            } => write!(
+                f,
+                "{} SEK is more than the {} SEK left to refund",
+                requested, remaining
+            ),
+        }
+    }
+}
+
+/// Checks whether Swish would accept a refund of a payment.
+///
+/// # Arguments
+///
+/// * `payment` - The payment, as fetched from Swish.
+/// * `amount` - The amount of the refund.
+/// * `refunded` - The amount refunded of the payment so far, e.g. from
+///   [`SwishStore::refunded`](../store/trait.SwishStore.html#method.refunded).
+///
+/// # Returns
+/// A Result that contains every `RefundIneligibility` if the refund would be rejected.
+///
+/// # Example
+///
+/// ```
+/// extern crate serde_json;
+/// extern crate swish_api;
+///
+/// use swish_api::amount::Amount;
+/// use swish_api::client::Payment;
+/// use swish_api::eligibility::{self, RefundIneligibility};
+///
+/// let payment: Payment = serde_json::from_str(r#"{
+///     "id": "AB23D7406ECE4542A80152D909EF9F6B",
+///     "amount": 100.00,
+///     "currency": "SEK",
+///     "status": "DECLINED",
+///     "dateCreated": "2015-02-19T22:01:53+01:00"
+/// }"#).unwrap();
+///
+/// let reasons = eligibility::check_refund(&payment, Amount::from_kronor(100), Amount::from_ore(0))
+///     .unwrap_err();
+/// assert!(matches!(reasons[0], RefundIneligibility::NotPaid { .. }));
+/// ```
+pub fn check_refund(
+    payment: &Payment,
+    amount: Amount,
+    refunded: Amount,
+) -> Result<(), Vec<RefundIneligibility>> {
+    check_refund_at(payment, amount, refunded, SystemTime::now().into())
+}
+
+/// Same as [`check_refund`](fn.check_refund.html), but at the given time
+/// instead of now.
+///
+/// # Arguments
+///
+/// * `payment` - The payment, as fetched from Swish.
+/// * `amount` - The amount of the refund.
+/// * `refunded` - The amount refunded of the payment so far.
+/// * `now` - The time the refund would be created.
+pub fn check_refund_at(
+    payment: &Payment,
+    amount: Amount,
+    refunded: Amount,
+    now: DateTime<Utc>,
+) -> Result<(), Vec<RefundIneligibility>> {
+    let mut reasons = Vec::new();
+
+    if payment.status != Some(Status::Paid) {
+        reasons.push(RefundIneligibility::NotPaid {
+            status: payment.status.clone(),
+        });
+    } else {
+        match payment.paid_at() {
+            Ok(Some(paid_at)) => {
+                let refundable_until = paid_at
+                    .checked_add_months(Months::new(REFUND_WINDOW_MONTHS))
+                    .unwrap_or(paid_at);
+                if now > refundable_until {
+                    reasons.push(RefundIneligibility::TooOld { refundable_until });
+                }
+            }
+            _ => reasons.push(RefundIneligibility::PaidDateUnknown),
+        }
+    }
+
+    if let Err(error) = validation::validate_amount(amount) {
+        reasons.push(RefundIneligibility::InvalidAmount(error));
+    }
+    let remaining = payment.amount - refunded;
+    if amount > remaining {
+        reasons.push(RefundIneligibility::ExceedsRemaining {
+            requested: amount,
+            remaining: cmp::max(remaining, Amount::from_ore(0)),
+        });
+    }
+
+    if reasons.is_empty() {
+        Ok(())
+    } else {
+        Err(reasons)
+    }
+}
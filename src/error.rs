@@ -6,37 +6,377 @@ extern crate hyper;
 extern crate serde_json;
 
 use hyper::http::uri;
+use native_tls;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::error;
 use std::fmt;
 use std::io;
+use std::str;
+use std::sync::Arc;
+use store::StoreError;
+use validation::ValidationError;
 
 pub type ErrorCollection = Vec<SwishClientError>;
 
+/// An error that occurred while calling the Swish API.
+///
+/// New variants may be added, so matching on it needs a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum SwishClientError {
+    /// Swish responded with an error.
     Swish(RequestError),
-    Parse(String),
+    /// Swish responded that the payment or refund doesn't exist,
+    /// e.g. because it's fetched before Swish has created it.
+    NotFound {
+        /// The kind of resource that was fetched.
+        resource: Resource,
+        /// The id it was fetched by.
+        id: String,
+        /// The correlation id that was sent with the request.
+        correlation_id: Option<String>,
+    },
+    /// The request couldn't be made or the response couldn't be understood.
+    #[non_exhaustive]
+    Parse {
+        /// What went wrong.
+        message: String,
+    },
+    /// The connection to Swish failed.
     Http(hyper::Error),
+    /// The URL of a request was invalid.
     Uri(uri::InvalidUri),
+    /// Reading or writing failed.
     Io(io::Error),
+    /// The JSON of a request or a response was invalid.
     Json(serde_json::Error),
+    /// The request couldn't be built, e.g. because of an invalid header.
+    Request(hyper::http::Error),
+    /// The body of a response isn't valid UTF-8.
+    Utf8(str::Utf8Error),
+    /// The client couldn't be set up, e.g. because the certificate is wrong.
+    /// Every call of the client fails the same way until it's fixed.
+    Configuration(ConfigError),
+    /// Swish responded with several errors.
     ErrorCollection(ErrorCollection),
+    /// The params were rejected before being sent to Swish.
+    Validation(Vec<ValidationError>),
+    /// The connection to Swish failed or the response couldn't be
+    /// understood, in the request described by the context.
+    InRequest {
+        /// The request that failed.
+        context: Box<RequestContext>,
+        /// What went wrong.
+        source: Box<SwishClientError>,
+    },
+    /// The store of the payments and refunds refused or failed.
+    Store(StoreError),
+}
+
+impl SwishClientError {
+    /// Creates a [`SwishClientError::Parse`](#variant.Parse) with the given message.
+    pub(crate) fn parse<M: Into<String>>(message: M) -> Self {
+        SwishClientError::Parse {
+            message: message.into(),
+        }
+    }
 }
 
 impl fmt::Display for SwishClientError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            SwishClientError::Swish(ref err) => write!(f, ": {}", err),
-            SwishClientError::Http(ref err) => write!(f, ": {}", err),
-            SwishClientError::Io(ref err) => write!(f, ": {}", err),
-            SwishClientError::Json(ref err) => write!(f, ": {}", err),
-            SwishClientError::Parse(ref err) => write!(f, ": {}", err),
-            SwishClientError::Uri(ref err) => write!(f, ": {}", err),
-            SwishClientError::ErrorCollection(ref err) => write!(
+            SwishClientError::Swish(ref err) => match err.context {
+                Some(ref context) => write!(f, "Swish responded with {} in {}", err, context),
+                None => write!(f, "Swish responded with {}", err),
+            },
+            SwishClientError::NotFound {
+                resource, ref id, ..
+            } => write!(f, "the {} {} wasn't found at Swish", resource, id),
+            SwishClientError::Http(ref err) => write!(f, "the connection to Swish failed: {}", err),
+            SwishClientError::Io(ref err) => write!(f, "an I/O error occurred: {}", err),
+            SwishClientError::Json(ref err) => write!(f, "invalid JSON: {}", err),
+            SwishClientError::Request(ref err) => {
+                write!(f, "the request couldn't be built: {}", err)
+            }
+            SwishClientError::Utf8(ref err) => {
+                write!(f, "the response isn't valid UTF-8: {}", err)
+            }
+            SwishClientError::Configuration(ref err) => {
+                write!(f, "the client couldn't be set up: {}", err)
+            }
+            SwishClientError::Parse { ref message } => f.write_str(message),
+            SwishClientError::Uri(ref err) => write!(f, "invalid URL: {}", err),
+            SwishClientError::Validation(ref errors) => write!(
                 f,
-                ": {}",
-                err.iter()
-                    .fold(String::new(), |acc, curr| acc + &curr.to_string() + ", ")
+                "invalid params: {}",
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
             ),
+            SwishClientError::ErrorCollection(ref errors) => write!(
+                f,
+                "Swish responded with several errors: {}",
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SwishClientError::InRequest {
+                ref context,
+                ref source,
+            } => write!(f, "{} in {}", source, context),
+            SwishClientError::Store(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for SwishClientError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            SwishClientError::Swish(ref err) => Some(err),
+            SwishClientError::Http(ref err) => Some(err),
+            SwishClientError::Uri(ref err) => Some(err),
+            SwishClientError::Io(ref err) => Some(err),
+            SwishClientError::Json(ref err) => Some(err),
+            SwishClientError::Request(ref err) => Some(err),
+            SwishClientError::Utf8(ref err) => Some(err),
+            SwishClientError::Configuration(ref err) => Some(err),
+            SwishClientError::Validation(ref errors) if errors.len() == 1 => Some(&errors[0]),
+            SwishClientError::ErrorCollection(ref errors) if errors.len() == 1 => Some(&errors[0]),
+            SwishClientError::InRequest { ref source, .. } => Some(&**source),
+            SwishClientError::Store(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl SwishClientError {
+    /// Gets the request the error happened in, if it happened while calling Swish.
+    pub fn context(&self) -> Option<&RequestContext> {
+        match *self {
+            SwishClientError::Swish(ref err) => err.context.as_deref(),
+            SwishClientError::ErrorCollection(ref errors) => {
+                errors.iter().filter_map(|err| err.context()).next()
+            }
+            SwishClientError::InRequest { ref context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Gets a message about the error that can be shown to a customer, in the
+    /// language of the client or the call. The message of the first error code
+    /// is used when Swish responded with several, and `None` is
+    /// returned when Swish didn't respond with an error code.
+    pub fn localized_message(&self) -> Option<&'static str> {
+        let language = self
+            .context()
+            .map(|context| context.language)
+            .unwrap_or_default();
+        self.swish_error_codes()
+            .first()
+            .map(|code| code.localized_message(language))
+    }
+
+    /// Gets the correlation id of the request that failed, if it reached Swish.
+    pub fn correlation_id(&self) -> Option<&str> {
+        match *self {
+            SwishClientError::Swish(ref err) => err.correlation_id.as_deref(),
+            SwishClientError::InRequest { ref context, .. } => Some(&context.correlation_id),
+            SwishClientError::NotFound {
+                ref correlation_id, ..
+            } => correlation_id.as_deref(),
+            SwishClientError::ErrorCollection(ref errors) => {
+                errors.iter().filter_map(|err| err.correlation_id()).next()
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the HTTP status Swish responded with, if the request reached Swish.
+    pub fn http_status(&self) -> Option<hyper::StatusCode> {
+        match *self {
+            SwishClientError::Swish(ref err) => Some(err.http_status),
+            SwishClientError::NotFound { .. } => Some(hyper::StatusCode::NOT_FOUND),
+            SwishClientError::ErrorCollection(ref errors) => {
+                errors.iter().filter_map(|err| err.http_status()).next()
+            }
+            SwishClientError::InRequest { ref source, .. } => source.http_status(),
+            _ => None,
+        }
+    }
+
+    /// Gets the body Swish responded with, exactly as it was sent,
+    /// e.g. to log it when the error couldn't be fully parsed.
+    pub fn response_body(&self) -> Option<&str> {
+        match *self {
+            SwishClientError::Swish(ref err) => {
+                err.response.as_ref().map(|response| response.body.as_str())
+            }
+            SwishClientError::ErrorCollection(ref errors) => {
+                errors.iter().filter_map(|err| err.response_body()).next()
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the headers Swish responded with.
+    pub fn response_headers(&self) -> Option<&hyper::HeaderMap> {
+        match *self {
+            SwishClientError::Swish(ref err) => {
+                err.response.as_ref().map(|response| &response.headers)
+            }
+            SwishClientError::ErrorCollection(ref errors) => {
+                errors.iter().filter_map(|err| err.response_headers()).next()
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the error codes Swish responded with, in the order they were returned.
+    pub fn swish_error_codes(&self) -> Vec<&ErrorCode> {
+        match *self {
+            SwishClientError::Swish(ref err) => err.code.iter().collect(),
+            SwishClientError::ErrorCollection(ref errors) => errors
+                .iter()
+                .flat_map(|err| err.swish_error_codes())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Gets the [`ErrorCategory`](enum.ErrorCategory.html) of the error.
+    /// The category of the first error code is used when Swish responded with several.
+    /// Errors without a code are categorized by how the request failed, and
+    /// `None` is returned when that doesn't tell anything.
+    pub fn category(&self) -> Option<ErrorCategory> {
+        if let Some(code) = self.swish_error_codes().first() {
+            return Some(code.category());
+        }
+        match *self {
+            SwishClientError::Validation(_) => Some(ErrorCategory::Validation),
+            _ if self.is_retryable() => Some(ErrorCategory::System),
+            _ => None,
+        }
+    }
+
+    /// Whether the request was rejected because of what was sent, either
+    /// by the validation of this crate or by Swish with a 4xx status.
+    /// Sending the same request again will fail the same way.
+    pub fn is_client_error(&self) -> bool {
+        match *self {
+            SwishClientError::Validation(_) => true,
+            _ => self
+                .http_status()
+                .is_some_and(|status| status.is_client_error()),
+        }
+    }
+
+    /// Whether Swish failed to handle the request, i.e. responded with a 5xx status.
+    pub fn is_server_error(&self) -> bool {
+        self.http_status()
+            .is_some_and(|status| status.is_server_error())
+    }
+
+    /// Whether the request may succeed if it's sent again, i.e. if it
+    /// failed on the way to Swish, Swish failed to handle it or asked
+    /// for it to be sent later.
+    ///
+    /// Creating a payment or a refund again is only safe when Swish
+    /// can't have created it the first time, e.g. when the instruction
+    /// id is reused.
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            SwishClientError::Http(_) | SwishClientError::Io(_) => true,
+            SwishClientError::InRequest { ref source, .. } => source.is_retryable(),
+            _ => self.http_status().is_some_and(|status| {
+                status.is_server_error() || status == hyper::StatusCode::TOO_MANY_REQUESTS
+            }),
+        }
+    }
+
+    /// Attaches the request the error happened in. The Swish errors within this
+    /// error get the context and correlation id set, the errors that didn't
+    /// come from Swish are wrapped in a
+    /// [`SwishClientError::InRequest`](#variant.InRequest).
+    pub(crate) fn with_context(self, context: &Arc<RequestContext>) -> Self {
+        match self {
+            SwishClientError::Swish(err) => SwishClientError::Swish(RequestError {
+                correlation_id: Some(context.correlation_id.clone()),
+                context: Some(context.clone()),
+                ..err
+            }),
+            SwishClientError::NotFound { resource, id, .. } => SwishClientError::NotFound {
+                resource,
+                id,
+                correlation_id: Some(context.correlation_id.clone()),
+            },
+            SwishClientError::ErrorCollection(errors) => SwishClientError::ErrorCollection(
+                errors
+                    .into_iter()
+                    .map(|err| err.with_context(context))
+                    .collect(),
+            ),
+            err @ SwishClientError::Validation(_) | err @ SwishClientError::InRequest { .. } => err,
+            err => SwishClientError::InRequest {
+                context: Box::new((**context).clone()),
+                source: Box::new(err),
+            },
+        }
+    }
+}
+
+/// A kind of resource at Swish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Resource {
+    /// A payment request.
+    Payment,
+    /// A refund.
+    Refund,
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Resource::Payment => f.write_str("payment"),
+            Resource::Refund => f.write_str("refund"),
+        }
+    }
+}
+
+/// The configuration of a client couldn't be used to connect to Swish.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The certificate or the passphrase couldn't be read.
+    Secret(io::Error),
+    /// The certificate couldn't be loaded, e.g. because the passphrase is wrong.
+    Identity(native_tls::Error),
+    /// The TLS connector couldn't be built with the configured settings.
+    Tls(native_tls::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Secret(ref err) => write!(f, "couldn't read the secrets: {}", err),
+            ConfigError::Identity(ref err) => {
+                write!(f, "couldn't load the certificate: {}", err)
+            }
+            ConfigError::Tls(ref err) => write!(f, "couldn't build the TLS connector: {}", err),
+        }
+    }
+}
+
+impl error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ConfigError::Secret(ref err) => Some(err),
+            ConfigError::Identity(ref err) | ConfigError::Tls(ref err) => Some(err),
         }
     }
 }
@@ -53,6 +393,12 @@ impl From<ErrorCollection> for SwishClientError {
     }
 }
 
+impl From<ValidationError> for SwishClientError {
+    fn from(err: ValidationError) -> SwishClientError {
+        SwishClientError::Validation(vec![err])
+    }
+}
+
 impl From<hyper::Error> for SwishClientError {
     fn from(err: hyper::Error) -> SwishClientError {
         SwishClientError::Http(err)
@@ -71,13 +417,36 @@ impl From<io::Error> for SwishClientError {
     }
 }
 
+impl From<ConfigError> for SwishClientError {
+    fn from(err: ConfigError) -> SwishClientError {
+        SwishClientError::Configuration(err)
+    }
+}
+
+impl From<hyper::http::Error> for SwishClientError {
+    fn from(err: hyper::http::Error) -> SwishClientError {
+        SwishClientError::Request(err)
+    }
+}
+
+impl From<str::Utf8Error> for SwishClientError {
+    fn from(err: str::Utf8Error) -> SwishClientError {
+        SwishClientError::Utf8(err)
+    }
+}
+
 impl From<serde_json::Error> for SwishClientError {
     fn from(err: serde_json::Error) -> SwishClientError {
         SwishClientError::Json(err)
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// An error code returned by Swish.
+///
+/// Codes that aren't known by this crate yet are kept as `Unknown`,
+/// so that a new code doesn't make the whole response fail to parse.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum ErrorCode {
     /// PayeePaymentReference is invalid.
     FF08,
@@ -109,9 +478,353 @@ pub enum ErrorCode {
     PA01,
     /// Original Payment not found or original payment is more than than 13 months old.
     RF02,
+    /// Bank system processing error.
+    FF10,
+    /// No payment request found related to a token.
+    RP04,
+    /// The payment request has been cancelled.
+    RP08,
+    /// The given instructionUUID is not available.
+    RP09,
+    /// Payer alias in the refund does not match the payee alias in the original payment.
+    RF03,
+    /// Payer organization number does not match the payee organization number in the original payment.
+    RF04,
+    /// The payer SSN in the original payment is not the same as the SSN of the current payee.
+    RF06,
+    /// Transaction declined.
+    RF07,
+    /// Amount value is too large, or the amount exceeds the original payment minus previous refunds.
+    RF08,
+    /// Refund already in progress.
+    RF09,
+    /// Swish timed out before the payment was started.
+    TM01,
+    /// Swish timed out waiting for an answer from the banks after the payment was started.
+    DS24,
+    /// The payer's age isn't allowed for this payment.
+    VR01,
+    /// The payer alias isn't enrolled with the social security number in the payment.
+    VR02,
+    /// The payer cancelled the BankID signing.
+    BANKIDCL,
+    /// BankID is already in use.
+    BANKIDONGOING,
+    /// BankID failed with an unknown error.
+    BANKIDUNKN,
+    /// A code that isn't known by this crate yet.
+    Unknown(String),
+}
+
+impl ErrorCode {
+    /// Gets the code as it's returned by Swish, e.g. `ACMT03`.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ErrorCode::FF08 => "FF08",
+            ErrorCode::RP03 => "RP03",
+            ErrorCode::BE18 => "BE18",
+            ErrorCode::RP01 => "RP01",
+            ErrorCode::PA02 => "PA02",
+            ErrorCode::AM06 => "AM06",
+            ErrorCode::AM02 => "AM02",
+            ErrorCode::AM03 => "AM03",
+            ErrorCode::RP02 => "RP02",
+            ErrorCode::RP06 => "RP06",
+            ErrorCode::ACMT03 => "ACMT03",
+            ErrorCode::ACMT01 => "ACMT01",
+            ErrorCode::ACMT07 => "ACMT07",
+            ErrorCode::PA01 => "PA01",
+            ErrorCode::RF02 => "RF02",
+            ErrorCode::FF10 => "FF10",
+            ErrorCode::RP04 => "RP04",
+            ErrorCode::RP08 => "RP08",
+            ErrorCode::RP09 => "RP09",
+            ErrorCode::RF03 => "RF03",
+            ErrorCode::RF04 => "RF04",
+            ErrorCode::RF06 => "RF06",
+            ErrorCode::RF07 => "RF07",
+            ErrorCode::RF08 => "RF08",
+            ErrorCode::RF09 => "RF09",
+            ErrorCode::TM01 => "TM01",
+            ErrorCode::DS24 => "DS24",
+            ErrorCode::VR01 => "VR01",
+            ErrorCode::VR02 => "VR02",
+            ErrorCode::BANKIDCL => "BANKIDCL",
+            ErrorCode::BANKIDONGOING => "BANKIDONGOING",
+            ErrorCode::BANKIDUNKN => "BANKIDUNKN",
+            ErrorCode::Unknown(ref code) => code,
+        }
+    }
+
+    /// Gets the meaning of the code as documented by Swish,
+    /// e.g. "The payer isn't enrolled in Swish" for `ACMT03`.
+    pub fn description(&self) -> &'static str {
+        match *self {
+            ErrorCode::FF08 => "The payee payment reference is invalid",
+            ErrorCode::RP03 => "The callback URL is missing or doesn't use HTTPS",
+            ErrorCode::BE18 => "The payer alias is invalid",
+            ErrorCode::RP01 => "The payee alias is missing or empty",
+            ErrorCode::PA02 => "The amount is missing or not a valid number",
+            ErrorCode::AM06 => "The amount is too low",
+            ErrorCode::AM02 => "The amount is too large",
+            ErrorCode::AM03 => "The currency is invalid or missing",
+            ErrorCode::RP02 => "The message is wrongly formatted",
+            ErrorCode::RP06 => "Another active payment request already exists for this payer",
+            ErrorCode::ACMT03 => "The payer isn't enrolled in Swish",
+            ErrorCode::ACMT01 => "The counterpart isn't activated",
+            ErrorCode::ACMT07 => "The payee isn't enrolled in Swish",
+            ErrorCode::PA01 => "A parameter isn't correct",
+            ErrorCode::RF02 => "The original payment wasn't found or is more than 13 months old",
+            ErrorCode::FF10 => "The bank system failed to process the request",
+            ErrorCode::RP04 => "No payment request was found for the token",
+            ErrorCode::RP08 => "The payment request has been cancelled",
+            ErrorCode::RP09 => "The instruction UUID isn't available",
+            ErrorCode::RF03 => "The payer alias of the refund doesn't match the payee alias of the original payment",
+            ErrorCode::RF04 => "The payer organization number doesn't match the payee of the original payment",
+            ErrorCode::RF06 => "The payer SSN of the original payment doesn't match the SSN of the payee",
+            ErrorCode::RF07 => "The transaction was declined",
+            ErrorCode::RF08 => "The amount is larger than what's left of the original payment",
+            ErrorCode::RF09 => "A refund is already in progress",
+            ErrorCode::TM01 => "Swish timed out before the payment was started",
+            ErrorCode::DS24 => "Swish timed out waiting for the banks after the payment was started",
+            ErrorCode::VR01 => "The payer's age isn't allowed for this payment",
+            ErrorCode::VR02 => "The payer alias isn't enrolled with the SSN of the payment",
+            ErrorCode::BANKIDCL => "The payer cancelled the BankID signing",
+            ErrorCode::BANKIDONGOING => "BankID is already in use",
+            ErrorCode::BANKIDUNKN => "BankID failed with an unknown error",
+            ErrorCode::Unknown(_) => "An error code that isn't known",
+        }
+    }
+
+    /// Gets a message about the code that can be shown to a customer,
+    /// in the given language. The codes caused by the request
+    /// rather than the customer share a general message.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The [`Language`](enum.Language.html) of the message.
+    pub fn localized_message(&self, language: Language) -> &'static str {
+        let (english, swedish) = match *self {
+            ErrorCode::BE18 => (
+                "The mobile number is invalid.",
+                "Mobilnumret är ogiltigt.",
+            ),
+            ErrorCode::AM06 => (
+                "The amount is too low for Swish.",
+                "Beloppet är för lågt för Swish.",
+            ),
+            ErrorCode::AM02 => (
+                "The amount is larger than Swish allows.",
+                "Beloppet är högre än vad Swish tillåter.",
+            ),
+            ErrorCode::RP06 => (
+                "There's already a payment waiting in your Swish app.",
+                "Det finns redan en betalning som väntar i din Swish-app.",
+            ),
+            ErrorCode::ACMT03 => (
+                "The mobile number isn't connected to Swish.",
+                "Mobilnumret är inte anslutet till Swish.",
+            ),
+            ErrorCode::ACMT01 => (
+                "Swish isn't activated for the mobile number.",
+                "Swish är inte aktiverat för mobilnumret.",
+            ),
+            ErrorCode::ACMT07 => (
+                "The shop can't receive Swish payments right now.",
+                "Butiken kan inte ta emot Swish-betalningar just nu.",
+            ),
+            ErrorCode::RF02 => (
+                "The payment couldn't be found or is too old to be refunded.",
+                "Betalningen hittades inte eller är för gammal för att återbetalas.",
+            ),
+            ErrorCode::FF10 => (
+                "Your bank couldn't handle the payment. Please try again later.",
+                "Din bank kunde inte hantera betalningen. Försök igen senare.",
+            ),
+            ErrorCode::RP08 => ("The payment was cancelled.", "Betalningen avbröts."),
+            ErrorCode::RF07 => ("The payment was declined.", "Betalningen nekades."),
+            ErrorCode::RF08 => (
+                "The refund is larger than what's left of the payment.",
+                "Återbetalningen är större än det som finns kvar av betalningen.",
+            ),
+            ErrorCode::RF09 => (
+                "The payment is already being refunded.",
+                "Betalningen håller redan på att återbetalas.",
+            ),
+            ErrorCode::TM01 => (
+                "The payment wasn't started in time. Please try again.",
+                "Betalningen startades inte i tid. Försök igen.",
+            ),
+            ErrorCode::DS24 => (
+                "Swish didn't get an answer from your bank in time. Check the Swish app before trying again.",
+                "Swish fick inget svar från din bank i tid. Kontrollera Swish-appen innan du försöker igen.",
+            ),
+            ErrorCode::VR01 => (
+                "Your age doesn't allow this payment.",
+                "Din ålder tillåter inte den här betalningen.",
+            ),
+            ErrorCode::VR02 => (
+                "The mobile number isn't connected to the right personal identity number.",
+                "Mobilnumret är inte kopplat till rätt personnummer.",
+            ),
+            ErrorCode::BANKIDCL => (
+                "The BankID signing was cancelled.",
+                "Signeringen med BankID avbröts.",
+            ),
+            ErrorCode::BANKIDONGOING => (
+                "BankID is already in use. Finish or cancel it and try again.",
+                "BankID används redan. Slutför eller avbryt och försök igen.",
+            ),
+            ErrorCode::BANKIDUNKN => (
+                "BankID couldn't sign the payment. Please try again.",
+                "BankID kunde inte signera betalningen. Försök igen.",
+            ),
+            _ => (
+                "Something went wrong with the payment. Please try again later.",
+                "Något gick fel med betalningen. Försök igen senare.",
+            ),
+        };
+        match language {
+            Language::English => english,
+            Language::Swedish => swedish,
+        }
+    }
+
+    /// Gets the [`ErrorCategory`](enum.ErrorCategory.html) of the code.
+    pub fn category(&self) -> ErrorCategory {
+        match *self {
+            ErrorCode::FF08
+            | ErrorCode::RP03
+            | ErrorCode::BE18
+            | ErrorCode::RP01
+            | ErrorCode::PA02
+            | ErrorCode::AM03
+            | ErrorCode::RP02
+            | ErrorCode::PA01
+            | ErrorCode::RP04
+            | ErrorCode::RF02
+            | ErrorCode::RF03
+            | ErrorCode::RF04
+            | ErrorCode::RF06 => ErrorCategory::Validation,
+            ErrorCode::ACMT03
+            | ErrorCode::ACMT01
+            | ErrorCode::ACMT07
+            | ErrorCode::VR01
+            | ErrorCode::VR02 => ErrorCategory::Enrollment,
+            ErrorCode::AM06
+            | ErrorCode::AM02
+            | ErrorCode::RF08 => ErrorCategory::AmountLimits,
+            ErrorCode::RP06
+            | ErrorCode::RP09
+            | ErrorCode::RF09
+            | ErrorCode::BANKIDONGOING => ErrorCategory::Duplicate,
+            ErrorCode::RP08
+            | ErrorCode::RF07
+            | ErrorCode::BANKIDCL => ErrorCategory::Declined,
+            ErrorCode::FF10
+            | ErrorCode::TM01
+            | ErrorCode::DS24
+            | ErrorCode::BANKIDUNKN => ErrorCategory::System,
+            ErrorCode::Unknown(_) => ErrorCategory::Other,
+        }
+    }
+
+    /// Parses a code returned by Swish, keeping unknown codes as `Unknown`.
+    pub fn from_code(code: &str) -> ErrorCode {
+        match code {
+            "FF08" => ErrorCode::FF08,
+            "RP03" => ErrorCode::RP03,
+            "BE18" => ErrorCode::BE18,
+            "RP01" => ErrorCode::RP01,
+            "PA02" => ErrorCode::PA02,
+            "AM06" => ErrorCode::AM06,
+            "AM02" => ErrorCode::AM02,
+            "AM03" => ErrorCode::AM03,
+            "RP02" => ErrorCode::RP02,
+            "RP06" => ErrorCode::RP06,
+            "ACMT03" => ErrorCode::ACMT03,
+            "ACMT01" => ErrorCode::ACMT01,
+            "ACMT07" => ErrorCode::ACMT07,
+            "PA01" => ErrorCode::PA01,
+            "RF02" => ErrorCode::RF02,
+            "FF10" => ErrorCode::FF10,
+            "RP04" => ErrorCode::RP04,
+            "RP08" => ErrorCode::RP08,
+            "RP09" => ErrorCode::RP09,
+            "RF03" => ErrorCode::RF03,
+            "RF04" => ErrorCode::RF04,
+            "RF06" => ErrorCode::RF06,
+            "RF07" => ErrorCode::RF07,
+            "RF08" => ErrorCode::RF08,
+            "RF09" => ErrorCode::RF09,
+            "TM01" => ErrorCode::TM01,
+            "DS24" => ErrorCode::DS24,
+            "VR01" => ErrorCode::VR01,
+            "VR02" => ErrorCode::VR02,
+            "BANKIDCL" => ErrorCode::BANKIDCL,
+            "BANKIDONGOING" => ErrorCode::BANKIDONGOING,
+            "BANKIDUNKN" => ErrorCode::BANKIDUNKN,
+            _ => ErrorCode::Unknown(code.to_owned()),
+        }
+    }
+}
+
+/// The language of the messages that are shown to customers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Language {
+    /// English, which is the default.
+    #[default]
+    English,
+    /// Swedish.
+    Swedish,
+}
+
+/// What kind of problem an error is, e.g. to choose what to tell a customer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// The request was invalid, e.g. a malformed alias or callback URL.
+    Validation,
+    /// The payer or the payee isn't enrolled in, or can't use, Swish.
+    Enrollment,
+    /// The amount is too low, too large or exceeds a limit.
+    AmountLimits,
+    /// The same request, or one for the same payer, is already in progress.
+    Duplicate,
+    /// The payment or refund was declined or cancelled.
+    Declined,
+    /// Swish or the banks failed, so the request may succeed later.
+    System,
+    /// An error code that isn't known by this crate yet.
+    Other,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.description(), self.as_str())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
 }
 
-#[derive(Debug, Default, Deserialize, Clone)]
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Ok(ErrorCode::from_code(&code))
+    }
+}
+
+/// An error Swish responded with.
+///
+/// More fields may be added, so outside of this crate it's
+/// made from `RequestError::default()` rather than a struct expression.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct RequestError {
     #[serde(skip_deserializing)]
     pub http_status: hyper::StatusCode,
@@ -124,10 +837,288 @@ pub struct RequestError {
 
     #[serde(rename = "additionalInformation")]
     pub additional_information: Option<String>,
+
+    /// The response the error was parsed from,
+    /// shared by all the errors parsed from the same response.
+    #[serde(skip_deserializing)]
+    pub response: Option<Arc<ErrorResponse>>,
+
+    /// The correlation id that was sent with the request.
+    #[serde(skip_deserializing)]
+    pub correlation_id: Option<String>,
+
+    /// The request the error was responded to.
+    #[serde(skip_deserializing)]
+    pub context: Option<Arc<RequestContext>>,
+}
+
+/// A request to the Swish API, as described in errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    /// The HTTP method of the request.
+    pub method: hyper::Method,
+    /// The path of the request, e.g. `/swish-cpcapi/api/v1/paymentrequests`.
+    pub path: String,
+    /// The Swish number of the merchant the request was made for.
+    pub merchant: String,
+    /// The correlation id that was sent with the request.
+    pub correlation_id: String,
+    /// The language the messages of the errors are shown to customers in.
+    pub language: Language,
+}
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} for merchant {} (correlation id {})",
+            self.method, self.path, self.merchant, self.correlation_id
+        )
+    }
+}
+
+/// The response Swish sent with an error, exactly as it was received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorResponse {
+    /// The headers of the response.
+    pub headers: hyper::HeaderMap,
+    /// The body of the response.
+    pub body: String,
 }
 
 impl fmt::Display for RequestError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.http_status)
+        match self.code {
+            Some(ref code) => write!(f, "{}: {}", self.http_status, code),
+            None if !self.message.is_empty() => write!(f, "{}: {}", self.http_status, self.message),
+            None => write!(f, "{}", self.http_status),
+        }
+    }
+}
+
+impl error::Error for RequestError {}
+
+impl SwishClientError {
+    /// Gets the only error Swish responded with, if it responded with exactly one.
+    fn into_request_error(self) -> Result<RequestError, SwishClientError> {
+        match self {
+            SwishClientError::Swish(err) => Ok(err),
+            SwishClientError::ErrorCollection(mut errors) => {
+                if errors.len() == 1 {
+                    errors.remove(0).into_request_error()
+                } else {
+                    Err(SwishClientError::ErrorCollection(errors))
+                }
+            }
+            err => Err(err),
+        }
+    }
+}
+
+/// Defines the errors of an operation, with a variant for every
+/// error code Swish documents for it and `Other` for everything else.
+macro_rules! operation_error {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident => $code:ident,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant(RequestError),)*
+            /// Any other failure, e.g. a network error or an error
+            /// code that isn't documented for the operation.
+            Other(SwishClientError),
+        }
+
+        impl $name {
+            /// Gets the error code Swish responded with, if it's documented for the operation.
+            pub fn code(&self) -> Option<ErrorCode> {
+                match *self {
+                    $($name::$variant(_) => Some(ErrorCode::$code),)*
+                    $name::Other(_) => None,
+                }
+            }
+        }
+
+        impl From<SwishClientError> for $name {
+            fn from(error: SwishClientError) -> Self {
+                let request_error = match error.into_request_error() {
+                    Ok(request_error) => request_error,
+                    Err(error) => return $name::Other(error),
+                };
+                match request_error.code {
+                    $(Some(ErrorCode::$code) => $name::$variant(request_error),)*
+                    _ => $name::Other(SwishClientError::Swish(request_error)),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match *self {
+                    $($name::$variant(_) => write!(f, "{}", ErrorCode::$code),)*
+                    $name::Other(ref err) => write!(f, "{}", err),
+                }
+            }
+        }
+
+        impl error::Error for $name {
+            fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+                match *self {
+                    $($name::$variant(ref err) => Some(err),)*
+                    $name::Other(ref err) => Some(err),
+                }
+            }
+        }
+    };
+}
+
+operation_error! {
+    /// The errors Swish can respond with when creating a payment.
+    ///
+    /// It's created from the [`SwishClientError`](enum.SwishClientError.html) of the call:
+    /// `client.create_payment(params).map_err(CreatePaymentError::from)`.
+    pub enum CreatePaymentError {
+        /// The payee payment reference is invalid (FF08).
+        InvalidPayeePaymentReference => FF08,
+        /// The callback URL is missing or doesn't use HTTPS (RP03).
+        InvalidCallbackUrl => RP03,
+        /// The payer alias is invalid (BE18).
+        InvalidPayerAlias => BE18,
+        /// The payee alias is missing or empty (RP01).
+        MissingPayeeAlias => RP01,
+        /// The amount is missing or not a valid number (PA02).
+        InvalidAmount => PA02,
+        /// The amount is too low (AM06).
+        AmountTooLow => AM06,
+        /// The amount is too large (AM02).
+        AmountTooLarge => AM02,
+        /// The currency is invalid or missing (AM03).
+        InvalidCurrency => AM03,
+        /// The message is wrongly formatted (RP02).
+        InvalidMessage => RP02,
+        /// Another active payment request already exists for the payer (RP06).
+        PaymentRequestExists => RP06,
+        /// The instruction UUID isn't available (RP09).
+        InstructionUuidUnavailable => RP09,
+        /// The payer isn't enrolled in Swish (ACMT03).
+        PayerNotEnrolled => ACMT03,
+        /// The counterpart isn't activated (ACMT01).
+        CounterpartNotActivated => ACMT01,
+        /// The payee isn't enrolled in Swish (ACMT07).
+        PayeeNotEnrolled => ACMT07,
+        /// A parameter isn't correct (PA01).
+        InvalidParameter => PA01,
+    }
+}
+
+operation_error! {
+    /// The errors Swish can respond with when creating a refund.
+    ///
+    /// It's created from the [`SwishClientError`](enum.SwishClientError.html) of the call:
+    /// `client.create_refund(params).map_err(CreateRefundError::from)`.
+    pub enum CreateRefundError {
+        /// The payer payment reference is invalid (FF08).
+        InvalidPayerPaymentReference => FF08,
+        /// The callback URL is missing or doesn't use HTTPS (RP03).
+        InvalidCallbackUrl => RP03,
+        /// The payer alias is missing or empty (RP01).
+        MissingPayerAlias => RP01,
+        /// The amount is missing or not a valid number (PA02).
+        InvalidAmount => PA02,
+        /// The amount is too low (AM06).
+        AmountTooLow => AM06,
+        /// The amount is too large (AM02).
+        AmountTooLarge => AM02,
+        /// The currency is invalid or missing (AM03).
+        InvalidCurrency => AM03,
+        /// The message is wrongly formatted (RP02).
+        InvalidMessage => RP02,
+        /// The instruction UUID isn't available (RP09).
+        InstructionUuidUnavailable => RP09,
+        /// A parameter isn't correct (PA01).
+        InvalidParameter => PA01,
+        /// The original payment wasn't found or is more than 13 months old (RF02).
+        OriginalPaymentNotFound => RF02,
+        /// The payer alias doesn't match the payee alias of the original payment (RF03).
+        PayerAliasMismatch => RF03,
+        /// The payer organization number doesn't match the original payment (RF04).
+        OrganizationNumberMismatch => RF04,
+        /// The payer SSN of the original payment doesn't match the payee (RF06).
+        SsnMismatch => RF06,
+        /// The transaction was declined (RF07).
+        Declined => RF07,
+        /// The amount is larger than what's left of the original payment (RF08).
+        AmountExceedsOriginalPayment => RF08,
+        /// A refund is already in progress (RF09).
+        RefundInProgress => RF09,
+        /// The bank system failed to process the refund (FF10).
+        BankSystemError => FF10,
     }
 }
+
+/// Defines the errors of fetching a resource, which
+/// Swish only responds to with a status and no error code.
+macro_rules! get_error {
+    ($(#[$meta:meta])* pub enum $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub enum $name {
+            /// Swish responded with 404, e.g. because it's polled before it's created.
+            NotFound {
+                /// The id it was fetched by.
+                id: String,
+            },
+            /// Any other failure.
+            Other(SwishClientError),
+        }
+
+        impl From<SwishClientError> for $name {
+            fn from(error: SwishClientError) -> Self {
+                match error {
+                    SwishClientError::NotFound { id, .. } => $name::NotFound { id },
+                    error => $name::Other(error),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match *self {
+                    $name::NotFound { ref id } => write!(f, "{} wasn't found at Swish", id),
+                    $name::Other(ref err) => write!(f, "{}", err),
+                }
+            }
+        }
+
+        impl error::Error for $name {
+            fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+                match *self {
+                    $name::NotFound { .. } => None,
+                    $name::Other(ref err) => Some(err),
+                }
+            }
+        }
+    };
+}
+
+get_error! {
+    /// The errors Swish can respond with when fetching a payment.
+    ///
+    /// It's created from the [`SwishClientError`](enum.SwishClientError.html) of the call:
+    /// `client.get_payment(id).map_err(GetPaymentError::from)`.
+    pub enum GetPaymentError
+}
+
+get_error! {
+    /// The errors Swish can respond with when fetching a refund.
+    ///
+    /// It's created from the [`SwishClientError`](enum.SwishClientError.html) of the call:
+    /// `client.get_refund(id).map_err(GetRefundError::from)`.
+    pub enum GetRefundError
+}
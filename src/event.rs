@@ -0,0 +1,253 @@
+//! # The event module
+//!
+//! Contains the events of payments and refunds, which are the same
+//! whether they're received as callbacks or polled from Swish.
+//!
+use amount::Amount;
+use callback::{PaymentCallback, RefundCallback};
+use client::{Payment, Refund, Status};
+use error::ErrorCode;
+use ids::{PaymentId, RefundId};
+
+/// Something that happened to a payment or a refund.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::callback::PaymentCallback;
+/// use swish_api::event::PaymentEvent;
+///
+/// let body = br#"{
+///     "id": "AB23D7406ECE4542A80152D909EF9F6B",
+///     "amount": 100.00,
+///     "currency": "SEK",
+///     "status": "CANCELLED",
+///     "dateCreated": "2015-02-19T22:01:53+01:00"
+/// }"#;
+///
+/// let callback = PaymentCallback::from_slice(body).unwrap();
+///
+/// match PaymentEvent::from(&callback) {
+///     PaymentEvent::Cancelled { payment_id } => assert_eq!(payment_id.as_str(), callback.id),
+///     event => panic!("Unexpected event {:?}", event),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PaymentEvent {
+    /// The payment request was created and is waiting for the payer.
+    Created { payment_id: PaymentId },
+    /// The payer paid.
+    Paid {
+        payment_id: PaymentId,
+        /// The reference of the payment at the bank of the payer.
+        payment_reference: Option<String>,
+        amount: Amount,
+        date_paid: Option<String>,
+    },
+    /// The payer declined the payment.
+    Declined { payment_id: PaymentId },
+    /// The payment request was cancelled.
+    Cancelled { payment_id: PaymentId },
+    /// The payment failed, e.g. because it timed out.
+    Errored {
+        payment_id: PaymentId,
+        error_code: Option<ErrorCode>,
+        error_message: Option<String>,
+    },
+    /// The payment request expired without any final status being seen,
+    /// which is only known by this crate and not sent by Swish.
+    Expired { payment_id: PaymentId },
+    /// The refund was created and is being handled by Swish.
+    RefundInitiated {
+        refund_id: RefundId,
+        original_payment_reference: Option<String>,
+    },
+    /// The refund was debited from the merchant, but not yet paid out.
+    RefundDebited {
+        refund_id: RefundId,
+        original_payment_reference: Option<String>,
+    },
+    /// The refund was paid out to the payer of the original payment.
+    RefundPaid {
+        refund_id: RefundId,
+        original_payment_reference: Option<String>,
+        amount: Amount,
+        date_paid: Option<String>,
+    },
+    /// The refund failed.
+    RefundErrored {
+        refund_id: RefundId,
+        original_payment_reference: Option<String>,
+        error_code: Option<ErrorCode>,
+        error_message: Option<String>,
+    },
+    /// The payment or refund got a status that isn't known by this crate yet.
+    Unknown { id: String, status: Status },
+}
+
+impl PaymentEvent {
+    /// Whether nothing more will happen to the payment or refund after this event.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            *self,
+            PaymentEvent::Paid { .. }
+                | PaymentEvent::Declined { .. }
+                | PaymentEvent::Cancelled { .. }
+                | PaymentEvent::Errored { .. }
+                | PaymentEvent::Expired { .. }
+                | PaymentEvent::RefundPaid { .. }
+                | PaymentEvent::RefundErrored { .. }
+        )
+    }
+
+    /// Gets the id of the payment or refund the event happened to.
+    pub fn id(&self) -> &str {
+        match *self {
+            PaymentEvent::Created { ref payment_id }
+            | PaymentEvent::Paid { ref payment_id, .. }
+            | PaymentEvent::Declined { ref payment_id }
+            | PaymentEvent::Cancelled { ref payment_id }
+            | PaymentEvent::Errored { ref payment_id, .. }
+            | PaymentEvent::Expired { ref payment_id } => payment_id.as_str(),
+            PaymentEvent::RefundInitiated { ref refund_id, .. }
+            | PaymentEvent::RefundDebited { ref refund_id, .. }
+            | PaymentEvent::RefundPaid { ref refund_id, .. }
+            | PaymentEvent::RefundErrored { ref refund_id, .. } => refund_id.as_str(),
+            PaymentEvent::Unknown { ref id, .. } => id,
+        }
+    }
+
+    /// The event of a payment with the given status.
+    fn from_payment(
+        id: &str,
+        status: &Status,
+        payment_reference: &Option<String>,
+        amount: Amount,
+        date_paid: &Option<String>,
+        error_code: Option<ErrorCode>,
+        error_message: &Option<String>,
+    ) -> Self {
+        let payment_id = PaymentId::from(id);
+        match *status {
+            Status::Created => PaymentEvent::Created { payment_id },
+            Status::Paid => PaymentEvent::Paid {
+                payment_id,
+                payment_reference: payment_reference.clone(),
+                amount,
+                date_paid: date_paid.clone(),
+            },
+            Status::Declined => PaymentEvent::Declined { payment_id },
+            Status::Cancelled => PaymentEvent::Cancelled { payment_id },
+            Status::Error => PaymentEvent::Errored {
+                payment_id,
+                error_code,
+                error_message: error_message.clone(),
+            },
+            ref status => PaymentEvent::Unknown {
+                id: id.to_owned(),
+                status: status.clone(),
+            },
+        }
+    }
+
+    /// The event of a refund with the given status.
+    fn from_refund(
+        id: &str,
+        status: &Status,
+        original_payment_reference: &Option<String>,
+        amount: Amount,
+        date_paid: &Option<String>,
+        error_code: Option<ErrorCode>,
+        error_message: &Option<String>,
+    ) -> Self {
+        let refund_id = RefundId::from(id);
+        let original_payment_reference = original_payment_reference.clone();
+        match *status {
+            Status::Created | Status::Validated | Status::Initiated => {
+                PaymentEvent::RefundInitiated {
+                    refund_id,
+                    original_payment_reference,
+                }
+            }
+            Status::Debited => PaymentEvent::RefundDebited {
+                refund_id,
+                original_payment_reference,
+            },
+            Status::Paid => PaymentEvent::RefundPaid {
+                refund_id,
+                original_payment_reference,
+                amount,
+                date_paid: date_paid.clone(),
+            },
+            Status::Error | Status::Declined | Status::Cancelled => PaymentEvent::RefundErrored {
+                refund_id,
+                original_payment_reference,
+                error_code,
+                error_message: error_message.clone(),
+            },
+            ref status => PaymentEvent::Unknown {
+                id: id.to_owned(),
+                status: status.clone(),
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a PaymentCallback> for PaymentEvent {
+    fn from(callback: &'a PaymentCallback) -> Self {
+        PaymentEvent::from_payment(
+            &callback.id,
+            &callback.status,
+            &callback.payment_reference,
+            callback.amount,
+            &callback.date_paid,
+            callback.error_code.clone(),
+            &callback.error_message,
+        )
+    }
+}
+
+/// A fetched payment without a status is seen as created.
+impl<'a> From<&'a Payment> for PaymentEvent {
+    fn from(payment: &'a Payment) -> Self {
+        PaymentEvent::from_payment(
+            &payment.id,
+            payment.status.as_ref().unwrap_or(&Status::Created),
+            &payment.payment_reference,
+            payment.amount,
+            &payment.date_paid,
+            payment.error_code.clone(),
+            &payment.error_message,
+        )
+    }
+}
+
+impl<'a> From<&'a RefundCallback> for PaymentEvent {
+    fn from(callback: &'a RefundCallback) -> Self {
+        PaymentEvent::from_refund(
+            &callback.id,
+            &callback.status,
+            &callback.original_payment_reference,
+            callback.amount,
+            &callback.date_paid,
+            callback.error_code.clone(),
+            &callback.error_message,
+        )
+    }
+}
+
+/// A fetched refund without a status is seen as initiated.
+impl<'a> From<&'a Refund> for PaymentEvent {
+    fn from(refund: &'a Refund) -> Self {
+        PaymentEvent::from_refund(
+            &refund.id,
+            refund.status.as_ref().unwrap_or(&Status::Initiated),
+            &refund.original_payment_reference,
+            refund.amount,
+            &refund.date_paid,
+            refund.error_code.clone(),
+            &refund.error_message,
+        )
+    }
+}
@@ -0,0 +1,251 @@
+//! # The fake module
+//!
+//! Contains generators of realistic, randomized payments and refunds, e.g. to seed
+//! demo environments or to build the datasets of load tests. Unlike the fixtures of
+//! the [`test_utils`](../test_utils/index.html) module, every payment differs, and
+//! unlike the `Arbitrary` implementations of the `proptest` feature, the data looks
+//! like what real customers send. It's only built with the `test-utils` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use swish_api::client::Status;
+//! use swish_api::fake::FakeData;
+//!
+//! let mut fake = FakeData::new(42);
+//! let payment = fake.payment(Status::Paid).payment();
+//! let params = fake.payment_params();
+//!
+//! assert_eq!(payment.status, Some(Status::Paid));
+//! assert!(params.as_params().validate().is_ok());
+//! ```
+//!
+use alias::{MerchantAlias, PhoneAlias};
+use amount::Amount;
+use callback::CallbackUrl;
+use client::Status;
+use params::{OwnedPaymentParams, OwnedRefundParams};
+use test_utils::{PaymentFixture, RefundFixture, PAYMENT_STATUSES, REFUND_STATUSES};
+use uuid::Uuid;
+
+/// The Swedish mobile prefixes that phone aliases are made up from.
+const MOBILE_PREFIXES: [&str; 5] = ["4670", "4672", "4673", "4676", "4679"];
+
+/// The products that payments are made for.
+const PRODUCTS: [&str; 12] = [
+    "Kingston USB Flash Drive 8 GB",
+    "Coffee and cinnamon bun",
+    "Concert ticket",
+    "Parking 2 hours",
+    "Monthly membership",
+    "Lunch buffet",
+    "Second hand bicycle",
+    "Flowers for Mother's Day",
+    "Football club fee",
+    "Bus ticket",
+    "Haircut",
+    "Book: The Long Ships",
+];
+
+/// The prices in kronor that payments are made for, of which some get öre.
+const PRICES: [i64; 12] = [10, 25, 39, 49, 79, 99, 120, 149, 199, 349, 599, 1_495];
+
+/// A generator of fake payments, refunds and params.
+///
+/// Generators created with the same seed generate the same data,
+/// so that a dataset can be rebuilt.
+#[derive(Debug, Clone)]
+pub struct FakeData {
+    state: u64,
+}
+
+impl FakeData {
+    /// [`FakeData`]: struct.FakeData.html
+    ///
+    /// Creates a new [`FakeData`] generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed of the generator.
+    pub fn new(seed: u64) -> Self {
+        FakeData { state: seed }
+    }
+
+    /// [`FakeData`]: struct.FakeData.html
+    ///
+    /// Creates a new [`FakeData`] generator with a random seed.
+    pub fn random() -> Self {
+        FakeData::new(Uuid::new_v4().as_u64_pair().0)
+    }
+
+    /// Generates the id of a payment or a refund, which is 32 uppercase hexadecimal digits.
+    pub fn id(&mut self) -> String {
+        format!("{:016X}{:016X}", self.next(), self.next())
+    }
+
+    /// Generates the alias of a payer, which is a Swedish mobile number.
+    pub fn phone_alias(&mut self) -> PhoneAlias {
+        let prefix = self.pick(&MOBILE_PREFIXES);
+        let number = format!("{}{:07}", prefix, self.below(10_000_000));
+        PhoneAlias::new(&number).expect("a Swedish mobile number is a phone alias")
+    }
+
+    /// Generates the Swish number of a merchant.
+    pub fn merchant_alias(&mut self) -> MerchantAlias {
+        let number = format!("123{:07}", self.below(10_000_000));
+        MerchantAlias::new(&number).expect("123 and 7 digits is a Swish number")
+    }
+
+    /// Generates a price, which every fourth time has öre.
+    pub fn amount(&mut self) -> Amount {
+        let kronor = Amount::from_kronor(*self.pick(&PRICES));
+        if self.below(4) == 0 {
+            kronor + Amount::from_ore(50)
+        } else {
+            kronor
+        }
+    }
+
+    /// Generates the reference a merchant gives a payment, which is a 10 digit order number.
+    pub fn payee_payment_reference(&mut self) -> String {
+        format!("{:010}", self.below(10_000_000_000))
+    }
+
+    /// Generates the message of a payment, which is the product that's paid for.
+    pub fn message(&mut self) -> String {
+        self.pick(&PRODUCTS).to_string()
+    }
+
+    /// Generates a payment with the status, e.g. for the responses of a fake Swish API.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status of the payment.
+    pub fn payment(&mut self, status: Status) -> PaymentFixture {
+        let fixture = PaymentFixture::new(status)
+            .id(&self.id())
+            .amount(self.amount())
+            .payee_payment_reference(&self.payee_payment_reference())
+            .payer_alias(self.phone_alias().as_str())
+            .message(&self.message());
+        if fixture.payment().payment_reference.is_some() {
+            fixture.payment_reference(&self.id())
+        } else {
+            fixture
+        }
+    }
+
+    /// Generates a payment with one of the statuses Swish gives payments.
+    pub fn any_payment(&mut self) -> PaymentFixture {
+        let status = self.pick(&PAYMENT_STATUSES).clone();
+        self.payment(status)
+    }
+
+    /// Generates a refund of a payment, with the status.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment` - The payment that's refunded, whose payment reference,
+    ///   payer alias, amount and message the refund gets.
+    /// * `status` - The status of the refund.
+    pub fn refund(&mut self, payment: &PaymentFixture, status: Status) -> RefundFixture {
+        let payment = payment.payment();
+        let mut fixture = RefundFixture::new(status)
+            .id(&self.id())
+            .amount(payment.amount)
+            .payer_payment_reference(&self.payee_payment_reference());
+        if let Some(ref reference) = payment.payment_reference {
+            fixture = fixture.original_payment_reference(reference);
+        }
+        if let Some(ref alias) = payment.payer_alias {
+            fixture = fixture.payee_alias(alias);
+        }
+        if let Some(ref message) = payment.message {
+            fixture = fixture.message(&format!("Refund for {}", message));
+        }
+        fixture
+    }
+
+    /// Generates a paid payment and a refund of it, with
+    /// one of the statuses Swish gives refunds.
+    pub fn any_refund(&mut self) -> (PaymentFixture, RefundFixture) {
+        let payment = self.payment(Status::Paid);
+        let status = self.pick(&REFUND_STATUSES).clone();
+        let refund = self.refund(&payment, status);
+        (payment, refund)
+    }
+
+    /// Generates the params of a payment that Swish accepts, e.g. to seed the
+    /// Swish test environment. Every other payment is an e-commerce payment.
+    pub fn payment_params(&mut self) -> OwnedPaymentParams {
+        let payee_payment_reference = Some(self.payee_payment_reference());
+        let payer_alias = if self.below(2) == 0 {
+            Some(self.phone_alias())
+        } else {
+            None
+        };
+        OwnedPaymentParams {
+            payee_payment_reference,
+            payer_alias,
+            payee_alias: None,
+            amount: self.amount(),
+            message: Some(self.message()),
+            callback_url: Some(callback_url("paymentrequests")),
+        }
+    }
+
+    /// Generates the params of a refund that Swish accepts.
+    ///
+    /// # Arguments
+    ///
+    /// * `original_payment_reference` - The payment reference of the payment that's refunded.
+    /// * `payee_alias` - The alias of the payer of the payment that's refunded.
+    /// * `amount` - The amount of the payment that's refunded, of which the refund is up to all.
+    pub fn refund_params(
+        &mut self,
+        original_payment_reference: &str,
+        payee_alias: &PhoneAlias,
+        amount: Amount,
+    ) -> OwnedRefundParams {
+        let payer_payment_reference = Some(self.payee_payment_reference());
+        let amount = if self.below(2) == 0 {
+            amount
+        } else {
+            Amount::from_ore(amount.as_ore() / 2).max(Amount::from_kronor(1))
+        };
+        OwnedRefundParams {
+            payer_payment_reference,
+            original_payment_reference: original_payment_reference.to_owned(),
+            payment_reference: None,
+            payer_alias: None,
+            payee_alias: payee_alias.as_str().to_owned(),
+            amount,
+            message: Some("Refund".to_owned()),
+            callback_url: Some(callback_url("refunds")),
+        }
+    }
+
+    /// Gets the next number of the generator, using SplitMix64.
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Gets a number below the bound.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    /// Picks one of the items.
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+fn callback_url(path: &str) -> CallbackUrl {
+    CallbackUrl::new(&format!("https://example.com/api/swishcb/{}", path))
+        .expect("the callback URL is an https URL")
+}
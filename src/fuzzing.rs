@@ -0,0 +1,40 @@
+//! # The fuzzing module
+//!
+//! Contains the entry points of the fuzz targets in `fuzz/`, which run
+//! the handling of responses on arbitrary bytes. It's only built with
+//! the `fuzzing` feature and isn't part of the public API.
+//!
+use client::{self, Payment, Refund, SwishResponse};
+use error::SwishClientError;
+use hyper::{HeaderMap, StatusCode};
+use std::str;
+
+/// Handles a response to fetching a payment the way the client does.
+///
+/// # Arguments
+///
+/// * `status` - The status of the response
+/// * `body` - The body of the response
+/// * `strict` - Whether unknown fields are an error
+pub fn fetch_payment(status: u16, body: &[u8], strict: bool) -> Result<Payment, SwishClientError> {
+    fetch(status, body, strict)
+}
+
+/// Handles a response to fetching a refund the way the client does.
+///
+/// # Arguments
+///
+/// * `status` - The status of the response
+/// * `body` - The body of the response
+/// * `strict` - Whether unknown fields are an error
+pub fn fetch_refund(status: u16, body: &[u8], strict: bool) -> Result<Refund, SwishClientError> {
+    fetch(status, body, strict)
+}
+
+fn fetch<T: SwishResponse>(status: u16, body: &[u8], strict: bool) -> Result<T, SwishClientError> {
+    let status = StatusCode::from_u16(status)
+        .map_err(|_| SwishClientError::parse(format!("invalid status {}", status)))?;
+    let body = str::from_utf8(body)?;
+    client::check_status(status, HeaderMap::new(), body)?;
+    client::parse_body::<T>(body, strict).map(|(parsed, _)| parsed)
+}
@@ -0,0 +1,40 @@
+//! # The golden module
+//!
+//! Contains the canonical JSON of payments, refunds, callbacks and errors
+//! as the Swish API sends them, which the models of this crate are tested against.
+//! They're published so that applications can test their own handling of Swish
+//! against the same JSON, and notice when it drifts from what Swish sends.
+//!
+//! The JSON is kept in the `golden/` directory of the crate.
+//!
+//! # Example
+//!
+//! ```
+//! use swish_api::callback::Callback;
+//! use swish_api::golden;
+//!
+//! let callback = Callback::from_slice(golden::PAYMENT_CALLBACK.as_bytes()).unwrap();
+//!
+//! assert_eq!(callback.id(), "AB23D7406ECE4542A80152D909EF9F6B");
+//! ```
+//!
+
+/// A paid payment, as it's fetched from the Swish API.
+pub const PAYMENT: &str = include_str!("../golden/payment.json");
+
+/// A payment that failed with the error code `TM01`, as it's fetched from the Swish API.
+pub const PAYMENT_ERROR: &str = include_str!("../golden/payment_error.json");
+
+/// A paid refund, as it's fetched from the Swish API,
+/// which spells the original payment reference `originalpaymentReference`.
+pub const REFUND: &str = include_str!("../golden/refund.json");
+
+/// The callback Swish posts when a payment is paid.
+pub const PAYMENT_CALLBACK: &str = include_str!("../golden/payment_callback.json");
+
+/// The callback Swish posts when a refund is debited from the merchant.
+pub const REFUND_CALLBACK: &str = include_str!("../golden/refund_callback.json");
+
+/// The body Swish responds with when it rejects a request,
+/// here because of a missing callback URL and an invalid amount.
+pub const ERRORS: &str = include_str!("../golden/errors.json");
@@ -0,0 +1,240 @@
+//! # The ids module
+//!
+//! Contains the identifiers used by the Swish API.
+//!
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+use validation::ValidationError;
+
+/// The identifier of an instruction, e.g. a payment or a payout,
+/// chosen by the merchant to make its creation idempotent.
+///
+/// It's 32 uppercase hexadecimal characters without dashes.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::ids::InstructionUuid;
+///
+/// let generated = InstructionUuid::new();
+/// let parsed: InstructionUuid = "11A86BE70EA346E4B1C39C874173F088".parse().unwrap();
+///
+/// assert_eq!(generated.as_str().len(), 32);
+/// assert!("11a86be7-0ea3-46e4-b1c3-9c874173f088"
+///     .parse::<InstructionUuid>()
+///     .is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InstructionUuid(String);
+
+impl InstructionUuid {
+    /// [`InstructionUuid`]: struct.InstructionUuid.html
+    ///
+    /// Generates a new random [`InstructionUuid`].
+    pub fn new() -> Self {
+        let mut buffer = Uuid::encode_buffer();
+        InstructionUuid(Uuid::new_v4().simple().encode_upper(&mut buffer).to_owned())
+    }
+
+    /// [`InstructionUuid`]: struct.InstructionUuid.html
+    ///
+    /// Validates an identifier supplied by the caller.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - 32 uppercase hexadecimal characters.
+    ///
+    /// # Returns
+    /// The [`InstructionUuid`], or a `ValidationError` if it isn't of the right format.
+    pub fn parse(id: &str) -> Result<Self, ValidationError> {
+        let valid = id.len() == 32
+            && id
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('A'..='F').contains(&c));
+        if !valid {
+            return Err(ValidationError::InvalidFormat {
+                field: "instructionUUID",
+                expected: "32 uppercase hexadecimal characters",
+            });
+        }
+        Ok(InstructionUuid(id.to_owned()))
+    }
+
+    /// Gets the identifier as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for InstructionUuid {
+    fn default() -> Self {
+        InstructionUuid::new()
+    }
+}
+
+impl FromStr for InstructionUuid {
+    type Err = ValidationError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        InstructionUuid::parse(id)
+    }
+}
+
+impl AsRef<str> for InstructionUuid {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for InstructionUuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for InstructionUuid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for InstructionUuid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        InstructionUuid::parse(&id).map_err(de::Error::custom)
+    }
+}
+
+/// The token Swish returns for an m-commerce payment, which
+/// the merchant app uses to open the payment in the Swish app.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PaymentRequestToken(String);
+
+impl PaymentRequestToken {
+    /// [`PaymentRequestToken`]: struct.PaymentRequestToken.html
+    ///
+    /// Creates a new [`PaymentRequestToken`] from the token returned by Swish.
+    pub fn new(token: String) -> Self {
+        PaymentRequestToken(token)
+    }
+
+    /// Gets the token as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for PaymentRequestToken {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for PaymentRequestToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The identifier Swish gave a payment.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PaymentId(String);
+
+/// The identifier Swish gave a refund.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RefundId(String);
+
+/// A reference to a payment, which is what a payment is fetched by.
+///
+/// It's created from anything that identifies a payment, e.g. a `&str`,
+/// a [`PaymentId`] or a `&CreatedPayment`.
+///
+/// [`PaymentId`]: struct.PaymentId.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PaymentRef<'a>(&'a str);
+
+/// A reference to a refund, which is what a refund is fetched by.
+///
+/// It's created from anything that identifies a refund, e.g. a `&str`,
+/// a [`RefundId`] or a `&CreatedRefund`.
+///
+/// [`RefundId`]: struct.RefundId.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RefundRef<'a>(&'a str);
+
+macro_rules! swish_id {
+    ($id:ident, $reference:ident) => {
+        impl $id {
+            /// Creates the identifier from the id returned by Swish.
+            pub fn new(id: String) -> Self {
+                $id(id)
+            }
+
+            /// Gets the identifier as a string slice.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<String> for $id {
+            fn from(id: String) -> Self {
+                $id(id)
+            }
+        }
+
+        impl<'a> From<&'a str> for $id {
+            fn from(id: &'a str) -> Self {
+                $id(id.to_owned())
+            }
+        }
+
+        impl AsRef<str> for $id {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl fmt::Display for $id {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl<'a> $reference<'a> {
+            /// Gets the identifier that's referred to.
+            pub fn as_str(&self) -> &'a str {
+                self.0
+            }
+        }
+
+        impl<'a> From<&'a str> for $reference<'a> {
+            fn from(id: &'a str) -> Self {
+                $reference(id)
+            }
+        }
+
+        impl<'a> From<&'a String> for $reference<'a> {
+            fn from(id: &'a String) -> Self {
+                $reference(id)
+            }
+        }
+
+        impl<'a> From<&'a $id> for $reference<'a> {
+            fn from(id: &'a $id) -> Self {
+                $reference(&id.0)
+            }
+        }
+
+        impl<'a> fmt::Display for $reference<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+    };
+}
+
+swish_id!(PaymentId, PaymentRef);
+swish_id!(RefundId, RefundRef);
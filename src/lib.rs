@@ -3,17 +3,162 @@
 //! Rust API bindings for the [Swish API](https://developer.getswish.se/merchants/).
 //! Built using [hyper](https://docs.rs/hyper/0.12.16/hyper/) and [tokio](https://docs.rs/tokio-core/0.1.17/tokio_core/).
 //!
+//! ## Features
+//!
+//! * `axum` - Adds [axum](https://docs.rs/axum) extractors of the callbacks of Swish.
+//! * `callback-cert` - Adds the verification of the client certificates
+//!   Swish presents with its callbacks, using OpenSSL.
+//! * `callback-server` - Adds a server that receives the callbacks of Swish
+//!   and hands them on to a handler or a channel.
+//! * `chaos` - Adds a proxy that injects latency, resets, error statuses and malformed
+//!   bodies into the calls of a client, for testing how it behaves under failure.
+//! * `cli` - Builds `swish-cli`, a command line client of the Swish API.
+//! * `chrono` - Adds accessors that parse the timestamps of payments and refunds
+//!   into [chrono](https://docs.rs/chrono) datetimes, and the checks of whether
+//!   a payment can still be refunded.
+//! * `fuzzing` - Exposes the handling of responses to the fuzz targets in `fuzz/`.
+//!   It isn't part of the public API.
+//! * `load-test` - Adds a harness that creates payments at shaped rates and reports
+//!   the latency percentiles and errors of the calls. It enables `test-utils`.
+//! * `log` - Logs requests and responses at debug level, with personal data masked.
+//! * `otel` - Emits [OpenTelemetry](https://docs.rs/opentelemetry) client spans for every request,
+//!   using the global tracer provider and propagator.
+//! * `proptest` - Implements [proptest](https://docs.rs/proptest)'s `Arbitrary` for params,
+//!   payments, refunds and callbacks, for property tests of applications using this crate.
+//! * `rocket` - Adds [Rocket](https://rocket.rs) data guards of the callbacks of Swish.
+//! * `simulator` - Adds a server that acts like the Swish test environment,
+//!   for integration tests that run offline.
+//! * `test-utils` - Adds builders of payments, refunds and callbacks in every status,
+//!   for the tests of applications using this crate, generators of fake ones,
+//!   and a harness posting callbacks to the callback handler of an application.
+//! * `tracing` - Emits [tracing](https://docs.rs/tracing) spans for every call to the Swish API.
+//! * `warp` - Adds [warp](https://docs.rs/warp) filters that extract the callbacks of Swish.
+//!
+//! ## Usage
+//!
+//! The types needed for most calls are re-exported from the crate root,
+//! and from the [`prelude`](prelude/index.html) module.
+//!
+//! ```
+//! use swish_api::prelude::*;
+//! ```
+//!
+#[cfg(feature = "axum")]
+extern crate axum;
+#[cfg(feature = "chrono")]
+extern crate chrono;
 extern crate futures;
+#[cfg(any(feature = "axum", feature = "rocket"))]
+extern crate futures_util;
 extern crate hyper;
 extern crate hyper_tls;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
 extern crate native_tls;
+#[cfg(feature = "callback-cert")]
+extern crate openssl;
+#[cfg(feature = "otel")]
+extern crate opentelemetry;
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(feature = "rocket")]
+extern crate rocket;
 extern crate serde;
 extern crate tokio_core;
+extern crate tokio_io;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "tracing")]
+extern crate tracing_futures;
+extern crate uuid;
+#[cfg(feature = "warp")]
+extern crate warp;
+extern crate zeroize;
 
 #[macro_use]
 extern crate serde_json;
 #[macro_use]
 extern crate serde_derive;
 
+/// Logs at debug level when the `log` feature is enabled.
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { debug!($($arg)*) };
+}
+
+/// Logs at debug level when the `log` feature is enabled.
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)*) => {};
+}
+
+pub mod active;
+pub mod alias;
+pub mod amount;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod callback;
+#[cfg(feature = "axum")]
+pub mod callback_axum;
+#[cfg(feature = "callback-cert")]
+pub mod callback_cert;
+pub mod callback_dedup;
+pub mod callback_ip;
+#[cfg(feature = "rocket")]
+pub mod callback_rocket;
+#[cfg(feature = "callback-server")]
+pub mod callback_server;
+#[cfg(feature = "warp")]
+pub mod callback_warp;
+pub mod capture;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod client;
+pub mod clock;
+pub mod config;
+mod connector;
+pub mod dns;
+#[cfg(feature = "chrono")]
+pub mod eligibility;
 pub mod error;
+pub mod event;
+#[cfg(feature = "test-utils")]
+pub mod fake;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing;
+pub mod golden;
+pub mod ids;
+#[cfg(feature = "load-test")]
+pub mod load;
+pub mod metrics;
+pub mod mss;
+pub mod params;
+pub mod polling;
+#[cfg(feature = "otel")]
+mod otel;
+pub mod prelude;
+pub mod push;
+pub mod redact;
+pub mod reporter;
+pub mod retry;
+pub mod secret;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+pub mod state;
+pub mod store;
+pub mod stats;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod timing;
+pub mod validation;
+pub mod watcher;
+#[cfg(feature = "test-utils")]
+pub mod webhook;
+
+pub use client::{
+    CreatedMCommercePayment, CreatedPayment, CreatedRefund, Currency, Payment, PaymentParams,
+    Refund, RefundParams, Status, SwishClient, SwishClientBuilder,
+};
+pub use error::SwishClientError;
@@ -0,0 +1,369 @@
+//! # The load module
+//!
+//! Contains a harness that creates payments at a sustained rate, against the
+//! Swish test environment or a [`SwishSimulator`](../simulator/struct.SwishSimulator.html),
+//! and reports the latencies and errors of the calls, so that the capacity of a
+//! setup can be checked before a peak. It's only built with the `load-test` feature.
+//!
+//! The rate is shaped by stages, e.g. a ramp up to a peak that's held for a while.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # extern crate swish_api;
+//! # extern crate tokio_core;
+//! # use swish_api::prelude::*;
+//! use std::time::Duration;
+//! use swish_api::load::LoadTest;
+//! # use tokio_core::reactor::Core;
+//! # let mut core = Core::new().unwrap();
+//! # let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+//! # let client = SwishClient::new(merchant_alias, "./tests/test_cert.p12", "swish", core.handle());
+//!
+//! let load_test = LoadTest::new()
+//!     .stage(Duration::from_secs(60), 5)
+//!     .stage(Duration::from_secs(600), 20);
+//! let report = core.run(load_test.run(&client)).unwrap();
+//!
+//! println!("{}", report);
+//! ```
+//!
+use client::SwishClient;
+use error::SwishClientError;
+use fake::FakeData;
+use futures::stream::FuturesUnordered;
+use futures::{Async, Future, Poll, Stream};
+use params::OwnedPaymentParams;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio_core::reactor::Timeout;
+
+/// A period during which calls are made at a steady rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stage {
+    duration: Duration,
+    requests_per_second: u32,
+}
+
+/// Creates payments at the rates of its stages, one stage after another.
+pub struct LoadTest {
+    stages: Vec<Stage>,
+    max_in_flight: usize,
+    params: Box<dyn FnMut(u64) -> OwnedPaymentParams>,
+}
+
+impl fmt::Debug for LoadTest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoadTest")
+            .field("stages", &self.stages)
+            .field("max_in_flight", &self.max_in_flight)
+            .finish()
+    }
+}
+
+impl Default for LoadTest {
+    fn default() -> Self {
+        LoadTest::new()
+    }
+}
+
+impl LoadTest {
+    /// [`LoadTest`]: struct.LoadTest.html
+    ///
+    /// Creates a new [`LoadTest`] without stages, with at most 100 calls in flight,
+    /// creating payments with the params of a [`FakeData`](../fake/struct.FakeData.html).
+    pub fn new() -> Self {
+        let mut fake = FakeData::random();
+        LoadTest {
+            stages: Vec::new(),
+            max_in_flight: 100,
+            params: Box::new(move |_| fake.payment_params()),
+        }
+    }
+
+    /// Adds a stage, which starts once the previous one is over.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long the stage lasts.
+    /// * `requests_per_second` - The number of payments created a second.
+    pub fn stage(mut self, duration: Duration, requests_per_second: u32) -> Self {
+        self.stages.push(Stage {
+            duration,
+            requests_per_second,
+        });
+        self
+    }
+
+    /// Sets the most calls in flight at once. Calls that would be made
+    /// while there are as many in flight are skipped, and counted as such.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_in_flight` - The number of calls.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Sets the params of the payments that are created.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Gets the params of the payment with the given number, counting from 0.
+    pub fn params<F>(mut self, params: F) -> Self
+    where
+        F: FnMut(u64) -> OwnedPaymentParams + 'static,
+    {
+        self.params = Box::new(params);
+        self
+    }
+
+    /// Runs the stages, and waits for the calls in flight to finish.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The [`SwishClient`](../client/struct.SwishClient.html) creating the payments.
+    ///
+    /// # Returns
+    /// A Future with the [`LoadReport`](struct.LoadReport.html), or an
+    /// `io::Error` if the timer of the reactor fails.
+    pub fn run<'a>(self, client: &'a SwishClient) -> LoadFuture<'a> {
+        let started = Instant::now();
+        LoadFuture {
+            client,
+            test: self,
+            started,
+            next_send: started,
+            stage: 0,
+            stage_ends: None,
+            sent: 0,
+            timer: None,
+            in_flight: FuturesUnordered::new(),
+            report: LoadReport::default(),
+        }
+    }
+}
+
+type Call<'a> = Box<dyn Future<Item = Result<Duration, (Duration, String)>, Error = ()> + 'a>;
+
+/// The future returned by [`LoadTest::run`](struct.LoadTest.html#method.run).
+pub struct LoadFuture<'a> {
+    client: &'a SwishClient,
+    test: LoadTest,
+    started: Instant,
+    next_send: Instant,
+    stage: usize,
+    stage_ends: Option<Instant>,
+    sent: u64,
+    timer: Option<Timeout>,
+    in_flight: FuturesUnordered<Call<'a>>,
+    report: LoadReport,
+}
+
+impl<'a> fmt::Debug for LoadFuture<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoadFuture")
+            .field("test", &self.test)
+            .field("stage", &self.stage)
+            .field("sent", &self.sent)
+            .field("in_flight", &self.in_flight.len())
+            .finish()
+    }
+}
+
+impl<'a> LoadFuture<'a> {
+    /// Makes the calls that are due, and sets the timer to the next one.
+    /// Returns whether all the stages are over.
+    fn send_due(&mut self) -> Result<bool, io::Error> {
+        loop {
+            let stage = match self.test.stages.get(self.stage) {
+                Some(stage) => *stage,
+                None => return Ok(true),
+            };
+            let stage_ends = *self
+                .stage_ends
+                .get_or_insert(self.next_send + stage.duration);
+            if self.next_send >= stage_ends || stage.requests_per_second == 0 {
+                self.stage += 1;
+                self.next_send = stage_ends;
+                self.stage_ends = None;
+                continue;
+            }
+
+            let now = Instant::now();
+            if self.next_send > now {
+                let mut timer = Timeout::new_at(self.next_send, self.client.handle())?;
+                if let Async::Ready(()) = timer.poll()? {
+                    continue;
+                }
+                self.timer = Some(timer);
+                return Ok(false);
+            }
+
+            self.send();
+            self.next_send += Duration::from_secs(1) / stage.requests_per_second;
+        }
+    }
+
+    /// Creates a payment, unless too many calls are in flight.
+    fn send(&mut self) {
+        if self.in_flight.len() >= self.test.max_in_flight {
+            self.report.skipped += 1;
+            return;
+        }
+        let params = (self.test.params)(self.sent);
+        self.sent += 1;
+        self.report.sent += 1;
+
+        let sent = Instant::now();
+        let call = self
+            .client
+            .create_payment(params.as_params())
+            .then(move |result| {
+                let latency = sent.elapsed();
+                Ok(result
+                    .map(|_| latency)
+                    .map_err(|error| (latency, error_kind(&error))))
+            });
+        self.in_flight.push(Box::new(call));
+    }
+}
+
+impl<'a> Future for LoadFuture<'a> {
+    type Item = LoadReport;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<LoadReport, io::Error> {
+        let timer_fired = match self.timer {
+            Some(ref mut timer) => timer.poll()?.is_ready(),
+            None => true,
+        };
+        let stages_over = if timer_fired {
+            self.timer = None;
+            self.send_due()?
+        } else {
+            false
+        };
+
+        while let Ok(Async::Ready(Some(result))) = self.in_flight.poll() {
+            match result {
+                Ok(latency) => self.report.latencies.push(latency),
+                Err((latency, kind)) => {
+                    self.report.latencies.push(latency);
+                    *self.report.errors.entry(kind).or_insert(0) += 1;
+                }
+            }
+        }
+
+        if stages_over && self.in_flight.is_empty() {
+            let mut report = std::mem::take(&mut self.report);
+            report.elapsed = self.started.elapsed();
+            report.latencies.sort();
+            Ok(Async::Ready(report))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+/// The outcome of a [`LoadTest`](struct.LoadTest.html).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// The number of payments that were created or failed.
+    pub sent: u64,
+    /// The number of payments that weren't created, because too many calls were in flight.
+    pub skipped: u64,
+    /// The number of failed calls by what they failed with, i.e. the error codes of
+    /// Swish, the HTTP status or e.g. `connection` if Swish couldn't be reached.
+    pub errors: BTreeMap<String, u64>,
+    /// The time the test took, including waiting for the last calls.
+    pub elapsed: Duration,
+    latencies: Vec<Duration>,
+}
+
+impl LoadReport {
+    /// Gets the number of calls that succeeded.
+    pub fn succeeded(&self) -> u64 {
+        self.sent - self.failed()
+    }
+
+    /// Gets the number of calls that failed.
+    pub fn failed(&self) -> u64 {
+        self.errors.values().sum()
+    }
+
+    /// Gets the number of calls made a second, over the whole test.
+    pub fn throughput(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.sent as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Gets the latency that the given share of the calls were at most as slow as,
+    /// both successful and failed ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `percentile` - The percentile, e.g. `99.0` for the 99th percentile.
+    ///
+    /// # Returns
+    /// The latency, or `None` if no calls were made.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.latencies.len() as f64).ceil();
+        let index = (rank as usize).clamp(1, self.latencies.len()) - 1;
+        Some(self.latencies[index])
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} calls in {:.1}s ({:.1}/s), {} succeeded, {} failed, {} skipped",
+            self.sent,
+            self.elapsed.as_secs_f64(),
+            self.throughput(),
+            self.succeeded(),
+            self.failed(),
+            self.skipped
+        )?;
+        let percentiles = [50.0, 90.0, 99.0, 100.0];
+        for &percentile in &percentiles {
+            if let Some(latency) = self.percentile(percentile) {
+                writeln!(f, "p{}: {:?}", percentile, latency)?;
+            }
+        }
+        for (kind, count) in &self.errors {
+            writeln!(f, "{}: {}", kind, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Gets what a call failed with, to count the failures by.
+fn error_kind(error: &SwishClientError) -> String {
+    let codes = error.swish_error_codes();
+    if !codes.is_empty() {
+        let codes: Vec<_> = codes.iter().map(|code| code.as_str()).collect();
+        return codes.join(",");
+    }
+    if let Some(status) = error.http_status() {
+        return status.to_string();
+    }
+    match *error {
+        SwishClientError::InRequest { ref source, .. } => error_kind(source),
+        SwishClientError::Http(_) | SwishClientError::Io(_) => "connection".to_owned(),
+        SwishClientError::Validation(_) => "validation".to_owned(),
+        SwishClientError::Configuration(_) => "configuration".to_owned(),
+        _ => "parse".to_owned(),
+    }
+}
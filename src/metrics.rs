@@ -0,0 +1,95 @@
+//! # The metrics module
+//!
+//! Contains the hook used to feed request metrics,
+//! such as latency and error rates, into e.g. statsd or Prometheus.
+//!
+use error::{ErrorCode, SwishClientError};
+use hyper::StatusCode;
+use std::fmt;
+use std::time::Duration;
+
+/// A call to the Swish API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    /// The name of the client method, e.g. `create_payment`.
+    pub operation: &'static str,
+    /// The Swish API resource, e.g. `paymentrequests`.
+    pub path: &'static str,
+}
+
+/// How a call to the Swish API ended.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// The call succeeded.
+    Success,
+    /// Swish responded with an error.
+    Rejected {
+        http_status: StatusCode,
+        error_codes: Vec<ErrorCode>,
+    },
+    /// The call failed without an error response from Swish,
+    /// e.g. because of a connection or parse error.
+    Failed,
+}
+
+impl Outcome {
+    /// [`Outcome`]: enum.Outcome.html
+    ///
+    /// Gets the [`Outcome`] of a failed call.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error the call failed with
+    pub fn from_error(error: &SwishClientError) -> Self {
+        match *error {
+            SwishClientError::Swish(ref err) => Outcome::Rejected {
+                http_status: err.http_status,
+                error_codes: err.code.iter().cloned().collect(),
+            },
+            SwishClientError::NotFound { .. } => Outcome::Rejected {
+                http_status: StatusCode::NOT_FOUND,
+                error_codes: Vec::new(),
+            },
+            SwishClientError::ErrorCollection(ref errors) => {
+                let requests_errors: Vec<_> = errors
+                    .iter()
+                    .filter_map(|error| match *error {
+                        SwishClientError::Swish(ref err) => Some(err),
+                        _ => None,
+                    }).collect();
+                match requests_errors.first() {
+                    Some(first) => Outcome::Rejected {
+                        http_status: first.http_status,
+                        error_codes: requests_errors
+                            .iter()
+                            .filter_map(|err| err.code.clone())
+                            .collect(),
+                    },
+                    None => Outcome::Failed,
+                }
+            }
+            _ => Outcome::Failed,
+        }
+    }
+}
+
+/// Receives metrics for every call to the Swish API.
+///
+/// Implement this to feed e.g. statsd or Prometheus.
+pub trait MetricsSink: fmt::Debug + Send + Sync {
+    /// Called when a call to the Swish API starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The [`Endpoint`](struct.Endpoint.html) that's called
+    fn on_request_start(&self, endpoint: Endpoint);
+
+    /// Called when a call to the Swish API has ended.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The [`Endpoint`](struct.Endpoint.html) that was called
+    /// * `duration` - How long the call took
+    /// * `outcome` - The [`Outcome`](enum.Outcome.html) of the call
+    fn on_request_end(&self, endpoint: Endpoint, duration: Duration, outcome: &Outcome);
+}
@@ -0,0 +1,120 @@
+//! # The mss module
+//!
+//! Contains the outcomes that the Swish test environment (MSS) can be made
+//! to simulate. MSS pays every payment request and refund, unless its message
+//! is the name of another outcome, e.g. `DECLINED` or an error code like `TM01`.
+//!
+use client::{PaymentParams, RefundParams, Status};
+use error::ErrorCode;
+
+/// An outcome of a payment or a refund to simulate in MSS.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::amount::Amount;
+/// use swish_api::client::{PaymentParams, Status};
+/// use swish_api::error::ErrorCode;
+/// use swish_api::mss::Simulation;
+///
+/// let mut params = PaymentParams::default();
+/// params.amount = Amount::from_kronor(100);
+///
+/// let simulation = Simulation::Error(ErrorCode::TM01);
+/// let params = simulation.payment_params(params);
+///
+/// assert_eq!(params.message, Some("TM01"));
+/// assert_eq!(simulation.final_status(), Some(Status::Error));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Simulation {
+    /// The payment or refund is paid.
+    Paid,
+    /// The payer declines the payment.
+    Declined,
+    /// The payment or refund fails with the error code, either right away
+    /// or once the payer has answered, like it does in production.
+    Error(ErrorCode),
+}
+
+impl Simulation {
+    /// Parses the message of a payment or a refund into the outcome it simulates.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message of the payment or refund.
+    ///
+    /// # Returns
+    /// The simulated outcome, which is `Paid` unless the message names another one.
+    pub fn from_message(message: Option<&str>) -> Self {
+        match message {
+            Some("DECLINED") => Simulation::Declined,
+            Some(message) => match ErrorCode::from_code(message) {
+                ErrorCode::Unknown(_) => Simulation::Paid,
+                code => Simulation::Error(code),
+            },
+            None => Simulation::Paid,
+        }
+    }
+
+    /// Gets the message that makes MSS simulate the outcome,
+    /// or `None` for a payment or refund that's paid.
+    pub fn message(&self) -> Option<&str> {
+        match *self {
+            Simulation::Paid => None,
+            Simulation::Declined => Some("DECLINED"),
+            Simulation::Error(ref code) => Some(code.as_str()),
+        }
+    }
+
+    /// Whether MSS rejects the payment or refund when it's created,
+    /// instead of failing it once the payer has answered.
+    pub fn is_rejected(&self) -> bool {
+        match *self {
+            Simulation::Error(ref code) => !matches!(
+                *code,
+                ErrorCode::TM01
+                    | ErrorCode::DS24
+                    | ErrorCode::FF10
+                    | ErrorCode::RF07
+                    | ErrorCode::BANKIDCL
+                    | ErrorCode::BANKIDONGOING
+                    | ErrorCode::BANKIDUNKN
+            ),
+            _ => false,
+        }
+    }
+
+    /// Gets the status the payment or refund ends up with,
+    /// or `None` when it's rejected when it's created.
+    pub fn final_status(&self) -> Option<Status> {
+        match *self {
+            _ if self.is_rejected() => None,
+            Simulation::Paid => Some(Status::Paid),
+            Simulation::Declined => Some(Status::Declined),
+            Simulation::Error(_) => Some(Status::Error),
+        }
+    }
+
+    /// Configures the params of a payment to simulate the outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The [`PaymentParams`](../client/struct.PaymentParams.html),
+    ///   whose message is replaced.
+    pub fn payment_params<'a>(&'a self, mut params: PaymentParams<'a>) -> PaymentParams<'a> {
+        params.message = self.message();
+        params
+    }
+
+    /// Configures the params of a refund to simulate the outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The [`RefundParams`](../client/struct.RefundParams.html),
+    ///   whose message is replaced.
+    pub fn refund_params<'a>(&'a self, mut params: RefundParams<'a>) -> RefundParams<'a> {
+        params.message = self.message();
+        params
+    }
+}
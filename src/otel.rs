@@ -0,0 +1,112 @@
+//! OpenTelemetry spans for the requests sent to the Swish API,
+//! following the HTTP client semantic conventions.
+//!
+use error::SwishClientError;
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+use hyper::{Request, StatusCode};
+use opentelemetry::propagation::Injector;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+
+/// The name of the tracer the spans are created with.
+const TRACER_NAME: &str = "swish-api";
+
+/// A client span covering a single request to the Swish API.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestSpan(Context);
+
+impl RequestSpan {
+    /// Starts a span for `request` and injects the trace context
+    /// into its headers using the global propagator.
+    pub(crate) fn start(request: &mut Request<String>, correlation_id: &str) -> Self {
+        let tracer = global::tracer(TRACER_NAME);
+        let mut attributes = vec![
+            KeyValue::new("http.request.method", request.method().as_str().to_owned()),
+            KeyValue::new("url.full", request.uri().to_string()),
+            KeyValue::new(
+                "server.port",
+                i64::from(
+                    request
+                        .uri()
+                        .port_part()
+                        .map(|port| port.as_u16())
+                        .unwrap_or(443),
+                ),
+            ),
+            KeyValue::new("swish.correlation_id", correlation_id.to_owned()),
+        ];
+        if let Some(host) = request.uri().host() {
+            attributes.push(KeyValue::new("server.address", host.to_owned()));
+        }
+
+        let span = tracer
+            .span_builder(request.method().as_str().to_owned())
+            .with_kind(SpanKind::Client)
+            .with_attributes(attributes)
+            .start(&tracer);
+        let context = Context::current_with_span(span);
+
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut HeaderInjector(request.headers_mut()))
+        });
+
+        RequestSpan(context)
+    }
+
+    /// Records the status of the response.
+    pub(crate) fn record_status(&self, status: StatusCode) {
+        let span = self.0.span();
+        span.set_attribute(KeyValue::new(
+            "http.response.status_code",
+            i64::from(status.as_u16()),
+        ));
+    }
+
+    /// Ends the span, marking it as failed when the request failed.
+    pub(crate) fn end(&self, error: Option<&SwishClientError>) {
+        let span = self.0.span();
+        if let Some(error) = error {
+            span.set_attribute(KeyValue::new("error.type", error_type(error)));
+            span.set_status(Status::error(error.to_string()));
+        }
+        span.end();
+    }
+}
+
+/// Gets the `error.type` attribute for an error. Error responses
+/// are identified by their status code, as the conventions recommend.
+fn error_type(error: &SwishClientError) -> String {
+    match *error {
+        SwishClientError::Swish(ref err) => err.http_status.as_str().to_owned(),
+        SwishClientError::NotFound { .. } => StatusCode::NOT_FOUND.as_str().to_owned(),
+        SwishClientError::ErrorCollection(ref errors) => errors
+            .first()
+            .map(error_type)
+            .unwrap_or_else(|| "_OTHER".to_owned()),
+        SwishClientError::Http(_) => "hyper::Error".to_owned(),
+        SwishClientError::Io(_) => "std::io::Error".to_owned(),
+        SwishClientError::Json(_) => "serde_json::Error".to_owned(),
+        SwishClientError::Uri(_) => "http::uri::InvalidUri".to_owned(),
+        SwishClientError::Request(_) => "http::Error".to_owned(),
+        SwishClientError::Utf8(_) => "std::str::Utf8Error".to_owned(),
+        SwishClientError::Configuration(_) => "swish_api::error::ConfigError".to_owned(),
+        SwishClientError::Parse { .. } => "_OTHER".to_owned(),
+        SwishClientError::Validation(_) => "swish_api::validation::ValidationError".to_owned(),
+        SwishClientError::InRequest { ref source, .. } => error_type(source),
+        SwishClientError::Store(_) => "swish_api::store::StoreError".to_owned(),
+    }
+}
+
+/// Injects the trace context as request headers.
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl<'a> Injector for HeaderInjector<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
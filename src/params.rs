@@ -0,0 +1,367 @@
+//! # The params module
+//!
+//! Contains the builders of the params used to create payments and refunds,
+//! which only build once every field Swish requires has been set,
+//! and owned variants of the params that can be stored and sent across threads.
+//!
+use alias::{MerchantAlias, PhoneAlias};
+use amount::Amount;
+use callback::CallbackUrl;
+use client::{PaymentParams, RefundParams};
+use std::marker::PhantomData;
+use validation::ValidationError;
+
+/// Marks a required field that hasn't been set yet.
+#[derive(Debug)]
+pub struct Missing;
+
+/// Marks a required field that has been set.
+#[derive(Debug)]
+pub struct Set;
+
+/// Marks an e-commerce payment, where the payer
+/// is identified by the mobile number they entered.
+#[derive(Debug)]
+pub struct ECommerce;
+
+/// Marks an m-commerce payment, where the Swish app is
+/// opened on the device of the payer with the request token.
+#[derive(Debug)]
+pub struct MCommerce;
+
+/// The flow of a payment, either [`ECommerce`] or [`MCommerce`].
+///
+/// [`ECommerce`]: struct.ECommerce.html
+/// [`MCommerce`]: struct.MCommerce.html
+pub trait Flow: private::Sealed {}
+
+impl Flow for ECommerce {}
+impl Flow for MCommerce {}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::ECommerce {}
+    impl Sealed for super::MCommerce {}
+}
+
+/// [`PaymentParams`]: ../client/struct.PaymentParams.html
+///
+/// Builder of [`PaymentParams`] that requires the amount and the flow
+/// of the payment to be set before the params can be built. The callback URL
+/// may be left out if the client has a default one.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::alias::PhoneAlias;
+/// use swish_api::amount::Amount;
+/// use swish_api::callback::CallbackUrl;
+/// use swish_api::client::PaymentParams;
+///
+/// let payment_params = PaymentParams::builder()
+///     .amount(Amount::from_kronor(100))
+///     .callback_url(CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").unwrap())
+///     .ecommerce(PhoneAlias::new("46712345678").unwrap())
+///     .message("Kingston USB Flash Drive 8 GB")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PaymentParamsBuilder<'a, A, F> {
+    params: PaymentParams<'a>,
+    state: PhantomData<(A, F)>,
+}
+
+impl<'a> PaymentParamsBuilder<'a, Missing, Missing> {
+    pub(crate) fn new() -> Self {
+        PaymentParamsBuilder {
+            params: PaymentParams::default(),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'a, A, F> PaymentParamsBuilder<'a, A, F> {
+    /// Changes the type state, keeping the params set so far.
+    fn into_state<A2, F2>(self) -> PaymentParamsBuilder<'a, A2, F2> {
+        PaymentParamsBuilder {
+            params: self.params,
+            state: PhantomData,
+        }
+    }
+
+    /// Sets the amount to pay.
+    pub fn amount(mut self, amount: Amount) -> PaymentParamsBuilder<'a, Set, F> {
+        self.params.amount = amount;
+        self.into_state()
+    }
+
+    /// Sets the URL Swish posts the outcome of the payment to,
+    /// instead of the default callback URL of the client.
+    pub fn callback_url(mut self, callback_url: CallbackUrl) -> Self {
+        self.params.callback_url = Some(callback_url);
+        self
+    }
+
+    /// Sets the reference of the payment chosen by the merchant, e.g. an order id.
+    pub fn payee_payment_reference(mut self, reference: &'a str) -> Self {
+        self.params.payee_payment_reference = Some(reference);
+        self
+    }
+
+    /// Sets the message shown to the payer.
+    pub fn message(mut self, message: &'a str) -> Self {
+        self.params.message = Some(message);
+        self
+    }
+
+    /// Sets the merchant that receives the payment,
+    /// instead of the merchant alias of the client.
+    pub fn payee_alias(mut self, payee_alias: MerchantAlias) -> Self {
+        self.params.payee_alias = Some(payee_alias);
+        self
+    }
+}
+
+impl<'a, A> PaymentParamsBuilder<'a, A, Missing> {
+    /// Makes it an e-commerce payment, which Swish sends
+    /// to the Swish app of the given payer.
+    ///
+    /// # Arguments
+    ///
+    /// * `payer_alias` - The mobile number the payer entered.
+    pub fn ecommerce(mut self, payer_alias: PhoneAlias) -> PaymentParamsBuilder<'a, A, ECommerce> {
+        self.params.payer_alias = Some(payer_alias);
+        self.into_state()
+    }
+
+    /// Makes it an m-commerce payment, which the payer opens
+    /// in the Swish app on the same device with the request token.
+    pub fn mcommerce(self) -> PaymentParamsBuilder<'a, A, MCommerce> {
+        self.into_state()
+    }
+}
+
+impl<'a, F: Flow> PaymentParamsBuilder<'a, Set, F> {
+    /// [`PaymentParams`]: ../client/struct.PaymentParams.html
+    ///
+    /// Builds the [`PaymentParams`].
+    ///
+    /// # Returns
+    /// A Result that contains the [`PaymentParams`],
+    /// or every `ValidationError` if they're invalid.
+    pub fn build(self) -> Result<PaymentParams<'a>, Vec<ValidationError>> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+/// [`RefundParams`]: ../client/struct.RefundParams.html
+///
+/// Builder of [`RefundParams`] that requires the reference of the original payment
+/// and the amount to be set before the params can be built. The callback URL
+/// may be left out if the client has a default one.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::amount::Amount;
+/// use swish_api::callback::CallbackUrl;
+/// use swish_api::client::RefundParams;
+///
+/// let refund_params = RefundParams::builder()
+///     .original_payment_reference("6D6CD7406ECE4542A80152D909EF9F6B")
+///     .amount(Amount::from_kronor(100))
+///     .callback_url(CallbackUrl::new("https://example.com/api/swishcb/refunds").unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RefundParamsBuilder<'a, R, A> {
+    params: RefundParams<'a>,
+    state: PhantomData<(R, A)>,
+}
+
+impl<'a> RefundParamsBuilder<'a, Missing, Missing> {
+    pub(crate) fn new() -> Self {
+        RefundParamsBuilder {
+            params: RefundParams::default(),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'a, R, A> RefundParamsBuilder<'a, R, A> {
+    /// Changes the type state, keeping the params set so far.
+    fn into_state<R2, A2>(self) -> RefundParamsBuilder<'a, R2, A2> {
+        RefundParamsBuilder {
+            params: self.params,
+            state: PhantomData,
+        }
+    }
+
+    /// Sets the payment reference of the payment to refund.
+    pub fn original_payment_reference(
+        mut self,
+        reference: &'a str,
+    ) -> RefundParamsBuilder<'a, Set, A> {
+        self.params.original_payment_reference = reference;
+        self.into_state()
+    }
+
+    /// Sets the amount to refund.
+    pub fn amount(mut self, amount: Amount) -> RefundParamsBuilder<'a, R, Set> {
+        self.params.amount = amount;
+        self.into_state()
+    }
+
+    /// Sets the URL Swish posts the outcome of the refund to,
+    /// instead of the default callback URL of the client.
+    pub fn callback_url(mut self, callback_url: CallbackUrl) -> Self {
+        self.params.callback_url = Some(callback_url);
+        self
+    }
+
+    /// Sets the reference of the refund chosen by the merchant.
+    pub fn payer_payment_reference(mut self, reference: &'a str) -> Self {
+        self.params.payer_payment_reference = Some(reference);
+        self
+    }
+
+    /// Sets the message shown to the receiver of the refund.
+    pub fn message(mut self, message: &'a str) -> Self {
+        self.params.message = Some(message);
+        self
+    }
+
+    /// Sets the merchant that pays the refund,
+    /// instead of the merchant alias of the client.
+    pub fn payer_alias(mut self, payer_alias: MerchantAlias) -> Self {
+        self.params.payer_alias = Some(payer_alias);
+        self
+    }
+}
+
+impl<'a> RefundParamsBuilder<'a, Set, Set> {
+    /// [`RefundParams`]: ../client/struct.RefundParams.html
+    ///
+    /// Builds the [`RefundParams`].
+    ///
+    /// # Returns
+    /// A Result that contains the [`RefundParams`],
+    /// or every `ValidationError` if they're invalid.
+    pub fn build(self) -> Result<RefundParams<'a>, Vec<ValidationError>> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+/// [`PaymentParams`]: ../client/struct.PaymentParams.html
+///
+/// The [`PaymentParams`] without borrowed fields, e.g. for params
+/// built from a request handler or moved into another task.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::amount::Amount;
+/// use swish_api::callback::CallbackUrl;
+/// use swish_api::params::OwnedPaymentParams;
+///
+/// let order_id = format!("{}", 1234);
+/// let mut owned_params = OwnedPaymentParams::default();
+/// owned_params.amount = Amount::from_kronor(100);
+/// owned_params.callback_url = CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").ok();
+/// owned_params.payee_payment_reference = Some(order_id);
+///
+/// let payment_params = owned_params.as_params();
+/// assert_eq!(payment_params.payee_payment_reference, Some("1234"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OwnedPaymentParams {
+    pub payee_payment_reference: Option<String>,
+    pub payer_alias: Option<PhoneAlias>,
+    pub payee_alias: Option<MerchantAlias>,
+    pub amount: Amount,
+    pub message: Option<String>,
+    pub callback_url: Option<CallbackUrl>,
+}
+
+impl OwnedPaymentParams {
+    /// [`PaymentParams`]: ../client/struct.PaymentParams.html
+    ///
+    /// Borrows the params as [`PaymentParams`], which the client is called with.
+    pub fn as_params(&self) -> PaymentParams<'_> {
+        let mut params = PaymentParams::default();
+        params.payee_payment_reference = self.payee_payment_reference.as_deref();
+        params.payer_alias = self.payer_alias.clone();
+        params.payee_alias = self.payee_alias.clone();
+        params.amount = self.amount;
+        params.message = self.message.as_deref();
+        params.callback_url = self.callback_url.clone();
+        params
+    }
+}
+
+impl<'a> From<PaymentParams<'a>> for OwnedPaymentParams {
+    fn from(params: PaymentParams<'a>) -> Self {
+        OwnedPaymentParams {
+            payee_payment_reference: params.payee_payment_reference.map(str::to_owned),
+            payer_alias: params.payer_alias,
+            payee_alias: params.payee_alias,
+            amount: params.amount,
+            message: params.message.map(str::to_owned),
+            callback_url: params.callback_url,
+        }
+    }
+}
+
+/// [`RefundParams`]: ../client/struct.RefundParams.html
+///
+/// The [`RefundParams`] without borrowed fields, e.g. for params
+/// built from a request handler or moved into another task.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OwnedRefundParams {
+    pub payer_payment_reference: Option<String>,
+    pub original_payment_reference: String,
+    pub payment_reference: Option<String>,
+    pub payer_alias: Option<MerchantAlias>,
+    pub payee_alias: String,
+    pub amount: Amount,
+    pub message: Option<String>,
+    pub callback_url: Option<CallbackUrl>,
+}
+
+impl OwnedRefundParams {
+    /// [`RefundParams`]: ../client/struct.RefundParams.html
+    ///
+    /// Borrows the params as [`RefundParams`], which the client is called with.
+    pub fn as_params(&self) -> RefundParams<'_> {
+        let mut params = RefundParams::default();
+        params.payer_payment_reference = self.payer_payment_reference.as_deref();
+        params.original_payment_reference = &self.original_payment_reference;
+        params.payment_reference = self.payment_reference.as_deref();
+        params.payer_alias = self.payer_alias.clone();
+        params.payee_alias = &self.payee_alias;
+        params.amount = self.amount;
+        params.message = self.message.as_deref();
+        params.callback_url = self.callback_url.clone();
+        params
+    }
+}
+
+impl<'a> From<RefundParams<'a>> for OwnedRefundParams {
+    fn from(params: RefundParams<'a>) -> Self {
+        OwnedRefundParams {
+            payer_payment_reference: params.payer_payment_reference.map(str::to_owned),
+            original_payment_reference: params.original_payment_reference.to_owned(),
+            payment_reference: params.payment_reference.map(str::to_owned),
+            payer_alias: params.payer_alias,
+            payee_alias: params.payee_alias.to_owned(),
+            amount: params.amount,
+            message: params.message.map(str::to_owned),
+            callback_url: params.callback_url,
+        }
+    }
+}
@@ -0,0 +1,499 @@
+//! # The polling module
+//!
+//! Contains the awaiting of payments, which races the callback of a payment
+//! against polling Swish for it, so that a lost callback doesn't leave
+//! the payment unfinished.
+//!
+use client::{PaymentParams, SwishBoxFuture, SwishClient, PAYMENT_REQUEST_TTL};
+use clock::{Clock, SystemClock};
+use error::SwishClientError;
+use event::PaymentEvent;
+use futures::future::{self, Loop};
+use futures::{Future, Stream};
+use ids::PaymentId;
+use std::cmp;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use store::{StoreError, SwishStore};
+use tokio_core::reactor::Handle;
+use watcher::PaymentWatcher;
+
+/// How a payment is polled while it's awaited.
+///
+/// A payment is polled until its payment request expires, which Swish does
+/// when it isn't answered within
+/// [`PAYMENT_REQUEST_TTL`](../client/constant.PAYMENT_REQUEST_TTL.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollPolicy {
+    pub(crate) interval: Duration,
+    pub(crate) max_backoff: Duration,
+}
+
+impl Default for PollPolicy {
+    fn default() -> Self {
+        PollPolicy {
+            interval: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl PollPolicy {
+    /// [`PollPolicy`]: struct.PollPolicy.html
+    ///
+    /// Creates a new [`PollPolicy`] polling every other second.
+    pub fn new() -> Self {
+        PollPolicy::default()
+    }
+
+    /// Sets how long to wait between polls.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The time between polls.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the longest time to wait between polls of a
+    /// [`PaymentPoller`](struct.PaymentPoller.html), which doubles the time
+    /// between polls of a payment for each poll in a row that fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_backoff` - The longest time between polls.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+}
+
+impl SwishClient {
+    /// Creates a payment and waits for it to finish, i.e. to be paid, declined,
+    /// cancelled or to fail. See [`await_payment`](#method.await_payment).
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - [`PaymentParams`](../client/struct.PaymentParams.html).
+    /// * `callbacks` - The events of the callbacks received, e.g. from a
+    ///   [`CallbackServer`](../callback_server/struct.CallbackServer.html).
+    /// * `policy` - The [`PollPolicy`](struct.PollPolicy.html).
+    ///
+    /// # Returns
+    /// A Future with the final [`PaymentEvent`](../event/enum.PaymentEvent.html),
+    /// or `PaymentEvent::Expired` if the payment request expired first.
+    pub fn create_payment_and_await<'a, S>(
+        &'a self,
+        params: PaymentParams,
+        callbacks: S,
+        policy: PollPolicy,
+    ) -> SwishBoxFuture<'a, PaymentEvent>
+    where
+        S: Stream<Item = PaymentEvent> + 'a,
+    {
+        Box::new(self.create_payment(params).and_then(move |created| {
+            let expires_at = created.expires_at();
+            self.await_payment_until(PaymentId::from(created.id), expires_at, callbacks, policy)
+        }))
+    }
+
+    /// Waits for a payment that was just created to finish, i.e. to be paid,
+    /// declined, cancelled or to fail. See [`await_payment_until`](#method.await_payment_until),
+    /// with the payment request expiring
+    /// [`PAYMENT_REQUEST_TTL`](../client/constant.PAYMENT_REQUEST_TTL.html) from now.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment_id` - The id of the payment.
+    /// * `callbacks` - The events of the callbacks received, which may be
+    ///   for other payments as well, e.g. from
+    ///   [`PaymentWatcher::watch`](../watcher/struct.PaymentWatcher.html#method.watch).
+    /// * `policy` - The [`PollPolicy`](struct.PollPolicy.html).
+    ///
+    /// # Returns
+    /// A Future with the final [`PaymentEvent`](../event/enum.PaymentEvent.html),
+    /// or `PaymentEvent::Expired` if the payment request expired first.
+    pub fn await_payment<'a, S>(
+        &'a self,
+        payment_id: PaymentId,
+        callbacks: S,
+        policy: PollPolicy,
+    ) -> SwishBoxFuture<'a, PaymentEvent>
+    where
+        S: Stream<Item = PaymentEvent> + 'a,
+    {
+        let expires_at = self.clock().now() + PAYMENT_REQUEST_TTL;
+        self.await_payment_until(payment_id, expires_at, callbacks, policy)
+    }
+
+    /// Waits for a payment to finish, i.e. to be paid, declined, cancelled or to fail.
+    ///
+    /// Whichever comes first is used: a final event for the payment on the callback
+    /// stream, or a final status when polling Swish. Polls failing because Swish
+    /// couldn't be reached or hasn't created the payment yet are retried.
+    /// Once the payment request has expired, it's given up as expired.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment_id` - The id of the payment.
+    /// * `expires_at` - When the payment request expires, e.g. the
+    ///   [`expires_at`](../client/struct.CreatedPayment.html#method.expires_at)
+    ///   of a `CreatedPayment`.
+    /// * `callbacks` - The events of the callbacks received, which may be
+    ///   for other payments as well, e.g. from
+    ///   [`PaymentWatcher::watch`](../watcher/struct.PaymentWatcher.html#method.watch).
+    /// * `policy` - The [`PollPolicy`](struct.PollPolicy.html).
+    ///
+    /// # Returns
+    /// A Future with the final [`PaymentEvent`](../event/enum.PaymentEvent.html),
+    /// or `PaymentEvent::Expired` if the payment request expired first.
+    pub fn await_payment_until<'a, S>(
+        &'a self,
+        payment_id: PaymentId,
+        expires_at: SystemTime,
+        callbacks: S,
+        policy: PollPolicy,
+    ) -> SwishBoxFuture<'a, PaymentEvent>
+    where
+        S: Stream<Item = PaymentEvent> + 'a,
+    {
+        let id = payment_id.clone();
+        let callback: SwishBoxFuture<'a, PaymentEvent> = Box::new(
+            callbacks
+                .filter(move |event| event.id() == id.as_str() && event.is_final())
+                .into_future()
+                .then(|result| -> SwishBoxFuture<'a, PaymentEvent> {
+                    match result {
+                        Ok((Some(event), _)) => Box::new(future::ok(event)),
+                        // Without callbacks it's up to the polling.
+                        _ => Box::new(future::empty()),
+                    }
+                }),
+        );
+
+        let expired_payment_id = payment_id.clone();
+        let time_left = expires_at
+            .duration_since(self.clock().now())
+            .unwrap_or_default();
+        let expired: SwishBoxFuture<'a, PaymentEvent> = Box::new(
+            sleep(self.clock(), self.handle(), time_left).map(move |()| PaymentEvent::Expired {
+                payment_id: expired_payment_id,
+            }),
+        );
+
+        let polled = self.poll_payment(payment_id, policy.interval);
+        first(first(callback, polled), expired)
+    }
+
+    /// Polls a payment until it has a final status.
+    fn poll_payment<'a>(
+        &'a self,
+        payment_id: PaymentId,
+        interval: Duration,
+    ) -> SwishBoxFuture<'a, PaymentEvent> {
+        Box::new(future::loop_fn(payment_id, move |payment_id| {
+            sleep(self.clock(), self.handle(), interval).and_then(move |()| {
+                self.get_payment(&payment_id)
+                    .then(move |result| match result {
+                        Ok(payment) => {
+                            let event = PaymentEvent::from(&payment);
+                            if event.is_final() {
+                                Ok(Loop::Break(event))
+                            } else {
+                                Ok(Loop::Continue(payment_id))
+                            }
+                        }
+                        Err(ref error) if is_transient(error) => Ok(Loop::Continue(payment_id)),
+                        Err(error) => Err(error),
+                    })
+            })
+        }))
+    }
+}
+
+/// Polls many pending payments in the background, sharing a limit on
+/// the number of requests sent to Swish, and hands their events to a
+/// [`PaymentWatcher`](../watcher/struct.PaymentWatcher.html).
+///
+/// Payments are polled until they finish, or until their payment request
+/// has expired, when they're given up with a `PaymentEvent::Expired`. Payments
+/// the watcher already knows are finished, e.g. from their callback,
+/// are no longer polled.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate swish_api;
+/// # extern crate tokio_core;
+/// # use swish_api::prelude::*;
+/// # use swish_api::polling::PaymentPoller;
+/// # use swish_api::watcher::PaymentWatcher;
+/// # use tokio_core::reactor::Core;
+/// # let mut core = Core::new().unwrap();
+/// # let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+/// # let client = SwishClient::new(merchant_alias, "./tests/test_cert.p12", "swish", core.handle());
+/// let watcher = PaymentWatcher::new();
+/// let poller = PaymentPoller::new(watcher.clone());
+///
+/// poller.track(PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"));
+/// let events = watcher.watch("AB23D7406ECE4542A80152D909EF9F6B");
+///
+/// core.run(poller.run(&client)).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct PaymentPoller {
+    watcher: PaymentWatcher,
+    policy: PollPolicy,
+    tick: Duration,
+    clock: Arc<dyn Clock>,
+    pending: Arc<Mutex<HashMap<PaymentId, Pending>>>,
+}
+
+/// A payment tracked by a [`PaymentPoller`](struct.PaymentPoller.html).
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    next_poll: Instant,
+    failures: u32,
+    expires_at: SystemTime,
+}
+
+impl PaymentPoller {
+    /// [`PaymentPoller`]: struct.PaymentPoller.html
+    ///
+    /// Creates a new [`PaymentPoller`] sending at most ten requests a second.
+    ///
+    /// # Arguments
+    ///
+    /// * `watcher` - The [`PaymentWatcher`](../watcher/struct.PaymentWatcher.html)
+    ///   the events of the payments are handed to.
+    pub fn new(watcher: PaymentWatcher) -> Self {
+        PaymentPoller {
+            watcher,
+            policy: PollPolicy::default(),
+            tick: Duration::from_millis(100),
+            clock: Arc::new(SystemClock),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets how the payments are polled.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The [`PollPolicy`](struct.PollPolicy.html).
+    pub fn policy(mut self, policy: PollPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the number of requests sent to Swish a second, shared by all payments.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests_per_second` - The number of requests.
+    pub fn rate_limit(mut self, requests_per_second: u32) -> Self {
+        self.tick = Duration::from_secs(1) / requests_per_second.max(1);
+        self
+    }
+
+    /// [`Clock`]: ../clock/trait.Clock.html
+    ///
+    /// Sets the [`Clock`] the polls, backoff and expiry are timed by.
+    /// Defaults to the clock of the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The [`Clock`].
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Starts polling a payment that was just created, unless it's already polled.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment_id` - The id of the payment.
+    pub fn track(&self, payment_id: PaymentId) {
+        self.track_since(payment_id, self.clock.now())
+    }
+
+    /// Starts polling a payment, unless it's already polled, until its
+    /// payment request expires
+    /// [`PAYMENT_REQUEST_TTL`](../client/constant.PAYMENT_REQUEST_TTL.html)
+    /// after it was created.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment_id` - The id of the payment.
+    /// * `created_at` - When the payment was created, e.g. the `created_at`
+    ///   of a [`CreatedPayment`](../client/struct.CreatedPayment.html).
+    pub fn track_since(&self, payment_id: PaymentId, created_at: SystemTime) {
+        let next_poll = self.clock.instant() + self.policy.interval;
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.entry(payment_id).or_insert(Pending {
+                next_poll,
+                failures: 0,
+                expires_at: created_at + PAYMENT_REQUEST_TTL,
+            });
+        }
+    }
+
+    /// Starts polling the payments of a store that aren't final yet,
+    /// e.g. the ones that were awaited when the application was restarted.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The [`SwishStore`](../store/trait.SwishStore.html) of the payments.
+    ///
+    /// # Returns
+    /// The number of payments, or the `StoreError` of the store.
+    pub fn resume<S: SwishStore + ?Sized>(&self, store: &S) -> Result<usize, StoreError> {
+        let payments = store.pending_payments()?;
+        let count = payments.len();
+        for payment in payments {
+            self.track_since(payment.id, payment.created_at);
+        }
+        Ok(count)
+    }
+
+    /// Stops polling a payment.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment_id` - The id of the payment.
+    pub fn untrack(&self, payment_id: &PaymentId) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(payment_id);
+        }
+    }
+
+    /// Gets the number of payments that are polled.
+    pub fn pending(&self) -> usize {
+        self.pending
+            .lock()
+            .map(|pending| pending.len())
+            .unwrap_or_default()
+    }
+
+    /// Polls the tracked payments, one at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The [`SwishClient`](../client/struct.SwishClient.html) polling Swish.
+    ///
+    /// # Returns
+    /// A Future that never completes, unless the timer of the reactor fails.
+    /// Failed polls are retried, and don't stop the poller.
+    pub fn run<'a>(&self, client: &'a SwishClient) -> SwishBoxFuture<'a, ()> {
+        let poller = self.clone();
+        Box::new(future::loop_fn((), move |()| {
+            let poller = poller.clone();
+            sleep(&*poller.clock, client.handle(), poller.tick)
+                .and_then(move |()| poller.poll_next(client))
+                .map(Loop::<(), ()>::Continue)
+        }))
+    }
+
+    /// Polls the payment that has waited the longest for its poll, if any.
+    /// Payments whose payment request has expired are given up, as expired.
+    fn poll_next<'a>(&self, client: &'a SwishClient) -> SwishBoxFuture<'a, ()> {
+        let now = self.clock.instant();
+        let time = self.clock.now();
+        let mut expired = Vec::new();
+        let due = match self.pending.lock() {
+            Ok(mut pending) => {
+                let watcher = &self.watcher;
+                pending.retain(|payment_id, payment| {
+                    let finished = watcher
+                        .latest(payment_id)
+                        .is_some_and(|event| event.is_final());
+                    let is_expired = time >= payment.expires_at;
+                    if !finished && is_expired {
+                        expired.push(payment_id.clone());
+                    }
+                    !finished && !is_expired
+                });
+                pending
+                    .iter()
+                    .filter(|&(_, payment)| payment.next_poll <= now)
+                    .min_by_key(|&(_, payment)| payment.next_poll)
+                    .map(|(payment_id, _)| payment_id.clone())
+            }
+            Err(_) => None,
+        };
+        for payment_id in expired {
+            self.watcher.notify(PaymentEvent::Expired { payment_id });
+        }
+
+        match due {
+            Some(payment_id) => {
+                let poller = self.clone();
+                Box::new(client.get_payment(&payment_id).then(move |result| {
+                    poller.record(
+                        &payment_id,
+                        result.map(|payment| PaymentEvent::from(&payment)),
+                    );
+                    Ok(())
+                }))
+            }
+            None => Box::new(future::ok(())),
+        }
+    }
+
+    /// Records the outcome of a poll, and schedules the next one.
+    fn record(&self, payment_id: &PaymentId, result: Result<PaymentEvent, SwishClientError>) {
+        let finished = result.as_ref().is_ok_and(PaymentEvent::is_final);
+        if let Ok(event) = result.as_ref() {
+            self.watcher.notify(event.clone());
+        }
+
+        let mut pending = match self.pending.lock() {
+            Ok(pending) => pending,
+            Err(_) => return,
+        };
+        if finished {
+            pending.remove(payment_id);
+        } else if let Some(payment) = pending.get_mut(payment_id) {
+            payment.failures = if result.is_ok() {
+                0
+            } else {
+                payment.failures.saturating_add(1)
+            };
+            let backoff = self
+                .policy
+                .interval
+                .checked_mul(1 << cmp::min(payment.failures, 16))
+                .unwrap_or(self.policy.max_backoff);
+            payment.next_poll = self.clock.instant() + cmp::min(backoff, self.policy.max_backoff);
+        }
+    }
+}
+
+/// Whether a poll may succeed later.
+fn is_transient(error: &SwishClientError) -> bool {
+    match *error {
+        SwishClientError::NotFound { .. } => true,
+        _ => error.is_retryable(),
+    }
+}
+
+/// Completes with whichever of the futures completes first.
+fn first<'a, T: 'a>(a: SwishBoxFuture<'a, T>, b: SwishBoxFuture<'a, T>) -> SwishBoxFuture<'a, T> {
+    Box::new(
+        a.select(b)
+            .map(|(item, _)| item)
+            .map_err(|(error, _)| error),
+    )
+}
+
+/// Completes after the given time has passed on the clock.
+fn sleep(clock: &dyn Clock, handle: &Handle, duration: Duration) -> SwishBoxFuture<'static, ()> {
+    Box::new(
+        clock
+            .sleep(handle, duration)
+            .map_err(SwishClientError::from),
+    )
+}
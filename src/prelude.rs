@@ -0,0 +1,23 @@
+//! # The prelude module
+//!
+//! Re-exports the types that are needed for most calls to the Swish API,
+//! so that they can be imported with a single `use swish_api::prelude::*;`.
+//!
+pub use alias::{MerchantAlias, PhoneAlias};
+pub use amount::Amount;
+pub use callback::{Callback, CallbackUrl, PaymentCallback, RefundCallback};
+pub use client::{
+    CreatedMCommercePayment, CreatedPayment, CreatedRefund, Currency, Environment, Payment,
+    PaymentParams, Refund, RefundParams, RequestOptions, Status, SwishClient, SwishClientBuilder,
+};
+pub use config::SwishConfig;
+pub use error::{
+    ConfigError, ErrorCategory, ErrorCode, Language, RequestContext, RequestError,
+    SwishClientError,
+};
+pub use event::PaymentEvent;
+pub use ids::{PaymentId, PaymentRef, PaymentRequestToken, RefundId, RefundRef};
+pub use polling::PollPolicy;
+pub use retry::RetryPolicy;
+pub use state::{PaymentState, RefundState};
+pub use validation::ValidationError;
@@ -0,0 +1,200 @@
+//! # The push module
+//!
+//! Contains the frames that push the events of payments to web frontends,
+//! over Server-Sent Events or WebSockets, so that a checkout can show
+//! the status of a payment as soon as it's known.
+//!
+use amount::Amount;
+use error::ErrorCode;
+use event::PaymentEvent;
+use futures::sync::mpsc::UnboundedReceiver;
+use futures::{Poll, Stream};
+use serde_json;
+use watcher::PaymentWatcher;
+
+/// The status of a payment or refund, as pushed to a frontend.
+///
+/// It's serialized as e.g. `{"kind":"payment","id":"AB23...","status":"PAID","final":true}`.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::event::PaymentEvent;
+/// use swish_api::ids::PaymentId;
+/// use swish_api::push::StatusFrame;
+///
+/// let frame = StatusFrame::from(&PaymentEvent::Cancelled {
+///     payment_id: PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"),
+/// });
+///
+/// assert_eq!(
+///     frame.to_sse(),
+///     "event: payment\ndata: {\"kind\":\"payment\",\"id\":\"AB23D7406ECE4542A80152D909EF9F6B\",\
+///      \"status\":\"CANCELLED\",\"final\":true}\n\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusFrame {
+    /// Either `payment` or `refund`.
+    pub kind: &'static str,
+    /// The id of the payment or refund.
+    pub id: String,
+    /// The status, as it's named by the Swish API, or `EXPIRED`.
+    pub status: String,
+    /// Whether nothing more will happen to the payment or refund.
+    #[serde(rename = "final")]
+    pub is_final: bool,
+    /// The amount of a paid payment or refund.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Amount>,
+    /// The error code of a failed payment or refund.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// The error message of a failed payment or refund.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+}
+
+impl StatusFrame {
+    /// Gets the frame as JSON, e.g. for a WebSocket text message.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a status frame is valid JSON")
+    }
+
+    /// Gets the frame as a Server-Sent Event, named by its kind.
+    pub fn to_sse(&self) -> String {
+        format!("event: {}\ndata: {}\n\n", self.kind, self.to_json())
+    }
+}
+
+impl<'a> From<&'a PaymentEvent> for StatusFrame {
+    fn from(event: &'a PaymentEvent) -> Self {
+        let (kind, status) = match *event {
+            PaymentEvent::Created { .. } => ("payment", "CREATED"),
+            PaymentEvent::Paid { .. } => ("payment", "PAID"),
+            PaymentEvent::Declined { .. } => ("payment", "DECLINED"),
+            PaymentEvent::Cancelled { .. } => ("payment", "CANCELLED"),
+            PaymentEvent::Errored { .. } => ("payment", "ERROR"),
+            PaymentEvent::Expired { .. } => ("payment", "EXPIRED"),
+            PaymentEvent::RefundInitiated { .. } => ("refund", "INITIATED"),
+            PaymentEvent::RefundDebited { .. } => ("refund", "DEBITED"),
+            PaymentEvent::RefundPaid { .. } => ("refund", "PAID"),
+            PaymentEvent::RefundErrored { .. } => ("refund", "ERROR"),
+            PaymentEvent::Unknown { ref status, .. } => ("payment", status.as_str()),
+        };
+        let (amount, error_code, error_message) = match *event {
+            PaymentEvent::Paid { amount, .. } | PaymentEvent::RefundPaid { amount, .. } => {
+                (Some(amount), None, None)
+            }
+            PaymentEvent::Errored {
+                ref error_code,
+                ref error_message,
+                ..
+            }
+            | PaymentEvent::RefundErrored {
+                ref error_code,
+                ref error_message,
+                ..
+            } => (None, error_code.clone(), error_message.clone()),
+            _ => (None, None, None),
+        };
+        StatusFrame {
+            kind,
+            id: event.id().to_owned(),
+            status: status.to_owned(),
+            is_final: event.is_final(),
+            amount,
+            error_code,
+            error_message,
+        }
+    }
+}
+
+/// Pushes the events handed to a [`PaymentWatcher`](../watcher/struct.PaymentWatcher.html)
+/// to the frontends watching the payments.
+///
+/// # Example
+///
+/// ```
+/// extern crate futures;
+/// extern crate swish_api;
+///
+/// use futures::Stream;
+/// use swish_api::event::PaymentEvent;
+/// use swish_api::ids::PaymentId;
+/// use swish_api::push::StatusBridge;
+/// use swish_api::watcher::PaymentWatcher;
+///
+/// let watcher = PaymentWatcher::new();
+/// let bridge = StatusBridge::new(watcher.clone());
+/// // The body of the response to e.g. `GET /payments/AB23.../events`.
+/// let body = bridge.sse("AB23D7406ECE4542A80152D909EF9F6B");
+///
+/// watcher.notify(PaymentEvent::Declined {
+///     payment_id: PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"),
+/// });
+///
+/// let events: Vec<_> = body.wait().collect::<Result<_, _>>().unwrap();
+/// assert!(events[0].contains("\"status\":\"DECLINED\""));
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatusBridge {
+    watcher: PaymentWatcher,
+}
+
+impl StatusBridge {
+    /// [`StatusBridge`]: struct.StatusBridge.html
+    ///
+    /// Creates a new [`StatusBridge`].
+    ///
+    /// # Arguments
+    ///
+    /// * `watcher` - The [`PaymentWatcher`](../watcher/struct.PaymentWatcher.html)
+    ///   the events are handed to, e.g. by callbacks or a poller.
+    pub fn new(watcher: PaymentWatcher) -> Self {
+        StatusBridge { watcher }
+    }
+
+    /// Watches a payment or a refund.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the payment or refund.
+    ///
+    /// # Returns
+    /// A stream of the [`StatusFrame`](struct.StatusFrame.html)s of the payment, starting
+    /// with the latest one if it's known. It ends after the final frame.
+    pub fn frames<I: AsRef<str>>(&self, id: I) -> Frames {
+        Frames {
+            events: self.watcher.watch(id),
+        }
+    }
+
+    /// Same as [`frames`](#method.frames), but as Server-Sent Events.
+    pub fn sse<I: AsRef<str>>(&self, id: I) -> impl Stream<Item = String, Error = ()> {
+        self.frames(id).map(|frame| frame.to_sse())
+    }
+
+    /// Same as [`frames`](#method.frames), but as JSON for WebSocket text messages.
+    pub fn json<I: AsRef<str>>(&self, id: I) -> impl Stream<Item = String, Error = ()> {
+        self.frames(id).map(|frame| frame.to_json())
+    }
+}
+
+/// The stream of the [`StatusFrame`](struct.StatusFrame.html)s of a payment or refund.
+#[derive(Debug)]
+pub struct Frames {
+    events: UnboundedReceiver<PaymentEvent>,
+}
+
+impl Stream for Frames {
+    type Item = StatusFrame;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<StatusFrame>, ()> {
+        self.events
+            .poll()
+            .map(|event| event.map(|event| event.map(|event| StatusFrame::from(&event))))
+    }
+}
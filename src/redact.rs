@@ -0,0 +1,148 @@
+//! # The redact module
+//!
+//! Masks personal data, such as payer aliases and payment request tokens,
+//! in request and response data so that it can be safely logged.
+//!
+use hyper::header::HeaderMap;
+use serde_json::{self, Value};
+
+/// JSON fields that contain personal data or secrets.
+const SENSITIVE_FIELDS: &[&str] = &[
+    "payeralias",
+    "payerssn",
+    "ssn",
+    "personalnumber",
+    "paymentrequesttoken",
+    "token",
+];
+
+/// JSON fields with free text that may contain personal data.
+const FREE_TEXT_FIELDS: &[&str] = &["message", "errormessage", "additionalinformation"];
+
+/// Headers that contain secrets.
+const SENSITIVE_HEADERS: &[&str] = &["paymentrequesttoken", "authorization", "cookie"];
+
+/// Free text digit sequences at least this long are treated as phone or personal numbers.
+const MIN_SENSITIVE_DIGITS: usize = 8;
+
+/// Masks all but the last two characters of `value`.
+///
+/// # Arguments
+///
+/// * `value` - The value to mask
+///
+/// # Example
+///
+/// ```
+/// use swish_api::redact::mask;
+///
+/// assert_eq!(mask("46712345678"), "*********78");
+/// ```
+pub fn mask(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+    let visible = chars.len() - 2;
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, c)| if i < visible { '*' } else { *c })
+        .collect()
+}
+
+/// Redacts a request or response body.
+///
+/// JSON bodies have their sensitive fields masked. Anything else
+/// has long digit sequences, such as phone numbers, masked.
+///
+/// # Arguments
+///
+/// * `body` - The body to redact
+pub fn redact_body(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut json) => {
+            redact_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => redact_text(body),
+    }
+}
+
+/// Masks the sensitive fields of a JSON value in place.
+///
+/// # Arguments
+///
+/// * `json` - The JSON value to redact
+pub fn redact_json(json: &mut Value) {
+    match *json {
+        Value::Object(ref mut map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SENSITIVE_FIELDS.contains(&key.as_str()) {
+                    *value = match *value {
+                        Value::String(ref s) => Value::String(mask(s)),
+                        Value::Null => Value::Null,
+                        ref other => Value::String(mask(&other.to_string())),
+                    };
+                } else if FREE_TEXT_FIELDS.contains(&key.as_str()) {
+                    if let Value::String(ref mut s) = *value {
+                        *s = redact_text(s);
+                    }
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(ref mut values) => values.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// Masks digit sequences in free text that look like phone or personal numbers.
+///
+/// # Arguments
+///
+/// * `text` - The text to redact
+pub fn redact_text(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut digits = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        push_digits(&mut redacted, &mut digits);
+        redacted.push(c);
+    }
+    push_digits(&mut redacted, &mut digits);
+    redacted
+}
+
+/// Formats headers for logging with the sensitive ones masked.
+///
+/// # Arguments
+///
+/// * `headers` - The headers to redact
+pub fn redact_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = value.to_str().unwrap_or("<binary>");
+            if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                format!("{}: {}", name, mask(value))
+            } else {
+                format!("{}: {}", name, value)
+            }
+        }).collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn push_digits(redacted: &mut String, digits: &mut String) {
+    if digits.len() >= MIN_SENSITIVE_DIGITS {
+        redacted.push_str(&mask(digits));
+    } else {
+        redacted.push_str(digits);
+    }
+    digits.clear();
+}
@@ -0,0 +1,54 @@
+//! # The reporter module
+//!
+//! Contains the hook used to forward failed calls to e.g. Sentry or alerting,
+//! with personal data masked.
+//!
+use error::{ErrorCode, SwishClientError};
+use metrics::{Endpoint, Outcome};
+use serde_json::Value;
+use std::fmt;
+
+/// Everything that's known about a failed call to the Swish API.
+#[derive(Debug)]
+pub struct ErrorReport<'a> {
+    /// The [`Endpoint`](../metrics/struct.Endpoint.html) that was called.
+    pub endpoint: Endpoint,
+    /// The params of the call as json, with personal data masked.
+    pub params: Option<&'a Value>,
+    /// The error codes Swish responded with, if any.
+    pub error_codes: Vec<ErrorCode>,
+    /// The error the call failed with.
+    pub error: &'a SwishClientError,
+}
+
+impl<'a> ErrorReport<'a> {
+    pub(crate) fn new(
+        endpoint: Endpoint,
+        params: Option<&'a Value>,
+        error: &'a SwishClientError,
+    ) -> Self {
+        let error_codes = match Outcome::from_error(error) {
+            Outcome::Rejected { error_codes, .. } => error_codes,
+            _ => vec![],
+        };
+        ErrorReport {
+            endpoint,
+            params,
+            error_codes,
+            error,
+        }
+    }
+
+    /// Gets the correlation id of the failed request, if known.
+    pub fn correlation_id(&self) -> Option<&str> {
+        self.error.correlation_id()
+    }
+}
+
+/// Receives a report whenever a call to the Swish API ultimately fails.
+///
+/// Implement this to forward payment failures to e.g. Sentry or alerting.
+pub trait ErrorReporter: fmt::Debug + Send + Sync {
+    /// Called with the [`ErrorReport`](struct.ErrorReport.html) of a failed call.
+    fn report(&self, report: &ErrorReport);
+}
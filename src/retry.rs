@@ -0,0 +1,128 @@
+//! # The retry module
+//!
+//! Contains the policy the client retries failed requests with, when they
+//! failed on the way to Swish, Swish failed to handle them or asked for them to
+//! be sent later. Only the fetching of payments and refunds is retried, as
+//! Swish may have created a payment or a refund even if the request failed.
+//!
+use client::SwishBoxFuture;
+use clock::Clock;
+use error::SwishClientError;
+use futures::future::{self, Loop};
+use futures::Future;
+use stats::StatsRecorder;
+use std::cmp;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_core::reactor::Handle;
+
+/// How failed requests are retried.
+///
+/// The time between retries doubles for each retry, up to the max backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) backoff: Duration,
+    pub(crate) max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// [`RetryPolicy`]: struct.RetryPolicy.html
+    ///
+    /// Creates a new [`RetryPolicy`] that doesn't retry.
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Sets how many times a request is retried after it first failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_retries` - The number of retries.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets how long to wait before the first retry. Defaults to 200 milliseconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `backoff` - The time before the first retry.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the longest time to wait between retries. Defaults to 5 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_backoff` - The longest time between retries.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Gets the time to wait before the given retry, counting from 0.
+    fn backoff_before(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry);
+        cmp::min(
+            self.backoff.checked_mul(factor).unwrap_or(self.max_backoff),
+            self.max_backoff,
+        )
+    }
+}
+
+/// Sends a request, and sends it again while it fails in a way that's retryable
+/// and the policy allows more retries.
+///
+/// # Arguments
+///
+/// * `policy` - The [`RetryPolicy`](struct.RetryPolicy.html).
+/// * `clock` - The clock the backoff is timed by.
+/// * `handle` - The tokio-core handle the backoff is timed on.
+/// * `stats` - The stats the retries waiting out their backoff are counted in.
+/// * `send` - Sends the request.
+pub(crate) fn retry<'a, T, F>(
+    policy: RetryPolicy,
+    clock: &'a dyn Clock,
+    handle: &'a Handle,
+    stats: &'a Arc<StatsRecorder>,
+    send: F,
+) -> SwishBoxFuture<'a, T>
+where
+    T: 'a,
+    F: Fn() -> SwishBoxFuture<'a, T> + 'a,
+{
+    Box::new(future::loop_fn(0, move |retry| {
+        send().then(move |result| -> SwishBoxFuture<'a, Loop<T, u32>> {
+            match result {
+                Err(ref error) if retry < policy.max_retries && error.is_retryable() => {
+                    log_debug!("Retrying a request to Swish that failed: {}", error);
+                    let queued = StatsRecorder::queue_retry(stats);
+                    Box::new(
+                        clock
+                            .sleep(handle, policy.backoff_before(retry))
+                            .map_err(SwishClientError::from)
+                            .map(move |()| {
+                                drop(queued);
+                                Loop::Continue(retry + 1)
+                            }),
+                    )
+                }
+                result => Box::new(future::result(result.map(Loop::Break))),
+            }
+        })
+    }))
+}
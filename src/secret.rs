@@ -0,0 +1,133 @@
+//! # The secret module
+//!
+//! Contains types used to keep sensitive values,
+//! such as the certificate passphrase, out of logs and memory.
+//!
+use serde::de::{Deserialize, Deserializer};
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+/// A String that's zeroed out in memory when it's dropped
+/// and that's redacted from its `Debug` output.
+#[derive(Clone, Default)]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// [`SecretString`]: struct.SecretString.html
+    ///
+    /// Creates a new [`SecretString`].
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret value.
+    pub fn new(secret: String) -> Self {
+        SecretString(secret)
+    }
+
+    /// Exposes the secret value.
+    /// Make sure that it isn't copied or logged.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a str> for SecretString {
+    fn from(secret: &'a str) -> Self {
+        SecretString(secret.to_owned())
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        SecretString(secret)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(SecretString)
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretString([REDACTED])")
+    }
+}
+
+/// Provides the client with the certificate and its passphrase.
+///
+/// Implement this to integrate with OS keychains or secret managers.
+/// The client reads it once, when its HTTPS client is built on first use,
+/// so a rotated certificate is picked up by a new client.
+pub trait SecretProvider: fmt::Debug {
+    /// Returns the passphrase to the certificate.
+    fn passphrase(&self) -> io::Result<SecretString>;
+
+    /// Returns the PKCS#12 encoded certificate.
+    fn certificate(&self) -> io::Result<Vec<u8>>;
+}
+
+/// A [`SecretProvider`] that reads the certificate from a file
+/// and holds the passphrase in memory.
+///
+/// [`SecretProvider`]: trait.SecretProvider.html
+#[derive(Debug, Clone)]
+pub struct FileSecretProvider {
+    cert_path: PathBuf,
+    passphrase: SecretString,
+}
+
+impl FileSecretProvider {
+    /// [`FileSecretProvider`]: struct.FileSecretProvider.html
+    ///
+    /// Creates a new [`FileSecretProvider`].
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_path` - The path to the certificate.
+    /// * `passphrase` - The passphrase to the certificate.
+    pub fn new<P, S>(cert_path: P, passphrase: S) -> Self
+    where
+        P: AsRef<Path>,
+        S: Into<SecretString>,
+    {
+        FileSecretProvider {
+            cert_path: cert_path.as_ref().to_path_buf(),
+            passphrase: passphrase.into(),
+        }
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn passphrase(&self) -> io::Result<SecretString> {
+        Ok(self.passphrase.clone())
+    }
+
+    fn certificate(&self) -> io::Result<Vec<u8>> {
+        let mut buf = vec![];
+        File::open(&self.cert_path)
+            .and_then(|mut f| f.read_to_end(&mut buf))
+            .map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!(
+                        "couldn't read the certificate at {}: {}",
+                        self.cert_path.display(),
+                        err
+                    ),
+                )
+            })?;
+        Ok(buf)
+    }
+}
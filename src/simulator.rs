@@ -0,0 +1,645 @@
+//! # The simulator module
+//!
+//! Contains a server that acts like the Swish test environment (MSS), so that
+//! integration tests run offline and deterministically. Payment requests are
+//! paid, and refunds are paid out, once they have settled, after which their
+//! callbacks are delivered like Swish delivers them. Other outcomes are simulated
+//! by the messages MSS knows, see [`Simulation`](../mss/enum.Simulation.html).
+//!
+//! The simulator serves plain http, so give the client a `TlsConnector` of its own
+//! to keep it from loading a certificate it doesn't need.
+//!
+//! # Example
+//!
+//! ```no_run
+//! extern crate futures;
+//! extern crate native_tls;
+//! extern crate swish_api;
+//! extern crate tokio_core;
+//!
+//! use futures::Future;
+//! use native_tls::TlsConnector;
+//! use swish_api::alias::MerchantAlias;
+//! use swish_api::client::SwishClientBuilder;
+//! use swish_api::simulator::SwishSimulator;
+//! use tokio_core::reactor::Core;
+//!
+//! let core = Core::new().unwrap();
+//! let simulator =
+//!     SwishSimulator::bind(&"127.0.0.1:0".parse().unwrap(), &core.handle()).unwrap();
+//! let api_url = simulator.api_url().unwrap();
+//! core.handle()
+//!     .spawn(simulator.serve().map_err(|error| eprintln!("{}", error)));
+//!
+//! let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+//! let client = SwishClientBuilder::new(merchant_alias, "", "", core.handle())
+//!     .tls_connector(TlsConnector::new().unwrap())
+//!     .api_url(&api_url)
+//!     .build();
+//! ```
+//!
+use alias::PhoneAlias;
+use amount::Amount;
+use client::{Currency, Payment, Refund, Status};
+use error::ErrorCode;
+use futures::sync::mpsc::{self, UnboundedSender};
+use futures::{future, Future, Stream};
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderValue, CONTENT_TYPE, LOCATION};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Client, Method, Request, Response, StatusCode, Uri};
+use hyper_tls::HttpsConnector;
+use mss::Simulation;
+use serde_json::{self, Value};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio_core::net::TcpListener;
+use tokio_core::reactor::{Handle, Timeout};
+use uuid::Uuid;
+use validation::{self, AMOUNT_MAX, AMOUNT_MIN};
+
+/// The path the Swish API is served at.
+const API_PATH: &str = "/swish-cpcapi/api/v1/";
+
+/// The future returned by [`SwishSimulator::serve`](struct.SwishSimulator.html#method.serve),
+/// which runs until the listener fails.
+pub type SimulatorFuture = Box<dyn Future<Item = (), Error = io::Error>>;
+
+/// A server that acts like the Swish test environment.
+pub struct SwishSimulator {
+    listener: TcpListener,
+    handle: Handle,
+    settle_after: Duration,
+    callback_uri: Option<Uri>,
+}
+
+impl SwishSimulator {
+    /// [`SwishSimulator`]: struct.SwishSimulator.html
+    ///
+    /// Creates a new [`SwishSimulator`] listening on the given address.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on, e.g. `127.0.0.1:0` for any free port.
+    /// * `handle` - The tokio-core handle the connections are handled on.
+    ///
+    /// # Returns
+    /// The [`SwishSimulator`], or an `io::Error` if the address couldn't be bound.
+    pub fn bind(addr: &SocketAddr, handle: &Handle) -> io::Result<Self> {
+        Ok(SwishSimulator {
+            listener: TcpListener::bind(addr, handle)?,
+            handle: handle.clone(),
+            settle_after: Duration::from_secs(2),
+            callback_uri: None,
+        })
+    }
+
+    /// Sets how long payment requests stay created, and refunds stay
+    /// validated, before they're paid. Defaults to 2 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `settle_after` - The time until payments and refunds are paid.
+    pub fn settle_after(mut self, settle_after: Duration) -> Self {
+        self.settle_after = settle_after;
+        self
+    }
+
+    /// Delivers every callback to the given URL instead of the callback URL
+    /// of the payment or refund, e.g. to a local plain http server.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URL the callbacks are posted to.
+    pub fn deliver_callbacks_to(mut self, uri: Uri) -> Self {
+        self.callback_uri = Some(uri);
+        self
+    }
+
+    /// Gets the address the simulator is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Gets the URL of the API of the simulator, to give to
+    /// [`SwishClientBuilder::api_url`](../client/struct.SwishClientBuilder.html#method.api_url).
+    pub fn api_url(&self) -> io::Result<String> {
+        self.local_addr()
+            .map(|addr| format!("http://{}{}", addr, API_PATH))
+    }
+
+    /// Serves the Swish API until the listener fails.
+    ///
+    /// # Returns
+    /// A [`SimulatorFuture`](type.SimulatorFuture.html) that has to be run
+    /// for the simulator to accept connections.
+    pub fn serve(self) -> SimulatorFuture {
+        let SwishSimulator {
+            listener,
+            handle,
+            settle_after,
+            callback_uri,
+        } = self;
+        let api_url = match listener.local_addr() {
+            Ok(addr) => format!("http://{}{}", addr, API_PATH),
+            Err(error) => return Box::new(future::err(error)),
+        };
+        let https_connector = match HttpsConnector::new(1) {
+            Ok(https_connector) => https_connector,
+            Err(error) => return Box::new(future::err(io::Error::other(error))),
+        };
+        let callback_client = Client::builder().build::<_, Body>(https_connector);
+        let (settlements, settled) = mpsc::unbounded();
+        let mss = Arc::new(Mss {
+            ledger: Mutex::new(Ledger::default()),
+            api_url,
+            settlements,
+        });
+
+        let settle_handle = handle.clone();
+        let settling = {
+            let mss = mss.clone();
+            settled.for_each(move |settlement| {
+                let mss = mss.clone();
+                let callback_client = callback_client.clone();
+                let callback_uri = callback_uri.clone();
+                let timeout = match Timeout::new(settle_after, &settle_handle) {
+                    Ok(timeout) => timeout,
+                    Err(_) => return Ok(()),
+                };
+                settle_handle.spawn(timeout.map_err(|_| ()).and_then(move |_| {
+                    match mss.settle(&settlement) {
+                        Some((callback_url, body)) => {
+                            deliver(&callback_client, callback_uri, &callback_url, body)
+                        }
+                        None => Box::new(future::ok(())),
+                    }
+                }));
+                Ok(())
+            })
+        }
+        .map_err(|()| io::Error::other("the settlements of the simulator ended"));
+
+        let http = Http::new();
+        let accepting = listener.incoming().for_each(move |(stream, _)| {
+            let mss = mss.clone();
+            let service = service_fn(move |request| receive(request, mss.clone()));
+            handle.spawn(http.serve_connection(stream, service).map_err(|_| ()));
+            Ok(())
+        });
+
+        Box::new(
+            accepting
+                .select(settling)
+                .map(|_| ())
+                .map_err(|(error, _)| error),
+        )
+    }
+}
+
+/// A payment or a refund that's waiting to be paid.
+#[derive(Debug)]
+enum Settlement {
+    Payment(String),
+    Refund(String),
+}
+
+/// The payments and refunds of the simulator.
+#[derive(Debug, Default)]
+struct Ledger {
+    payments: HashMap<String, Payment>,
+    refunds: HashMap<String, Refund>,
+}
+
+/// What's shared by the requests made to the simulator.
+struct Mss {
+    ledger: Mutex<Ledger>,
+    api_url: String,
+    settlements: UnboundedSender<Settlement>,
+}
+
+impl Mss {
+    /// Creates a payment request, unless the params are rejected.
+    fn create_payment(&self, params: &Value) -> Result<Response<Body>, Vec<ErrorCode>> {
+        let mut errors = Vec::new();
+        let callback_url = check_callback_url(params, &mut errors);
+        let amount = check_amount(params, &mut errors);
+        check_currency(params, &mut errors);
+        check_message(params, &mut errors);
+        let payee_alias = string_field(params, "payeeAlias").filter(|alias| !alias.is_empty());
+        if payee_alias.is_none() {
+            errors.push(ErrorCode::RP01);
+        }
+        let payee_payment_reference = string_field(params, "payeePaymentReference");
+        if let Some(reference) = payee_payment_reference {
+            if validation::validate_payee_payment_reference(reference).is_err() {
+                errors.push(ErrorCode::FF08);
+            }
+        }
+        let payer_alias = string_field(params, "payerAlias");
+        if let Some(alias) = payer_alias {
+            if PhoneAlias::new(alias).is_err() {
+                errors.push(ErrorCode::BE18);
+            }
+        }
+
+        let mut ledger = self.ledger.lock().unwrap();
+        let is_active = |payment: &Payment| {
+            payment.status == Some(Status::Created) && payment.payer_alias.as_deref() == payer_alias
+        };
+        if payer_alias.is_some() && ledger.payments.values().any(is_active) {
+            errors.push(ErrorCode::RP06);
+        }
+        check_simulation(params, &mut errors);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let id = new_id();
+        ledger.payments.insert(
+            id.clone(),
+            Payment {
+                id: id.clone(),
+                amount: amount.unwrap_or_default(),
+                payee_payment_reference: payee_payment_reference.map(str::to_owned),
+                payment_reference: None,
+                callback_url: callback_url.map(str::to_owned),
+                payer_alias: payer_alias.map(str::to_owned),
+                payee_alias: payee_alias.map(str::to_owned),
+                message: string_field(params, "message").map(str::to_owned),
+                status: Some(Status::Created),
+                date_created: timestamp(SystemTime::now()),
+                currency: Currency::SEK,
+                date_paid: None,
+                error_code: None,
+                error_message: None,
+                extra: serde_json::Map::new(),
+            },
+        );
+        let _ = self
+            .settlements
+            .unbounded_send(Settlement::Payment(id.clone()));
+
+        let mut response = self.created("paymentrequests", &id);
+        if payer_alias.is_none() {
+            let token = Uuid::new_v4().simple().to_string();
+            if let Ok(token) = HeaderValue::from_str(&token) {
+                response.headers_mut().insert("paymentrequesttoken", token);
+            }
+        }
+        Ok(response)
+    }
+
+    /// Cancels a payment request the payer hasn't answered.
+    fn cancel_payment(&self, id: &str) -> Result<Response<Body>, Vec<ErrorCode>> {
+        let mut ledger = self.ledger.lock().unwrap();
+        match ledger.payments.get_mut(id) {
+            Some(ref mut payment) if payment.status == Some(Status::Created) => {
+                payment.status = Some(Status::Cancelled);
+                Ok(json_response(StatusCode::OK, &**payment))
+            }
+            Some(_) => Err(vec![ErrorCode::PA01]),
+            None => Ok(respond(StatusCode::NOT_FOUND)),
+        }
+    }
+
+    /// Creates a refund of a paid payment, unless the params are rejected.
+    fn create_refund(&self, params: &Value) -> Result<Response<Body>, Vec<ErrorCode>> {
+        let mut errors = Vec::new();
+        let callback_url = check_callback_url(params, &mut errors);
+        let amount = check_amount(params, &mut errors);
+        check_currency(params, &mut errors);
+        check_message(params, &mut errors);
+        let original_payment_reference = string_field(params, "originalPaymentReference");
+        let payer_alias = string_field(params, "payerAlias");
+
+        let mut ledger = self.ledger.lock().unwrap();
+        let original = original_payment_reference.and_then(|reference| {
+            ledger.payments.values().find(|payment| {
+                payment.status == Some(Status::Paid)
+                    && payment.payment_reference.as_deref() == Some(reference)
+            })
+        });
+        let original = match original {
+            Some(original) => original.clone(),
+            None => {
+                errors.push(ErrorCode::RF02);
+                return Err(errors);
+            }
+        };
+        if payer_alias != original.payee_alias.as_deref() {
+            errors.push(ErrorCode::RF03);
+        }
+        let refunded = ledger
+            .refunds
+            .values()
+            .filter(|refund| {
+                refund.original_payment_reference == original.payment_reference
+                    && refund.status != Some(Status::Error)
+            })
+            .fold(Amount::default(), |refunded, refund| {
+                refunded + refund.amount
+            });
+        if let Some(amount) = amount {
+            if refunded + amount > original.amount {
+                errors.push(ErrorCode::RF08);
+            }
+        }
+        check_simulation(params, &mut errors);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let id = new_id();
+        ledger.refunds.insert(
+            id.clone(),
+            Refund {
+                id: id.clone(),
+                amount: amount.unwrap_or_default(),
+                payer_payment_reference: string_field(params, "payerPaymentReference")
+                    .map(str::to_owned),
+                original_payment_reference: original.payment_reference.clone(),
+                payment_reference: None,
+                callback_url: callback_url.map(str::to_owned),
+                payer_alias: payer_alias.map(str::to_owned),
+                payee_alias: original.payer_alias.clone(),
+                message: string_field(params, "message").map(str::to_owned),
+                status: Some(Status::Validated),
+                date_created: timestamp(SystemTime::now()),
+                currency: Currency::SEK,
+                date_paid: None,
+                error_code: None,
+                error_message: None,
+                additional_information: None,
+                extra: serde_json::Map::new(),
+            },
+        );
+        let _ = self
+            .settlements
+            .unbounded_send(Settlement::Refund(id.clone()));
+        Ok(self.created("refunds", &id))
+    }
+
+    /// Pays a payment or a refund that's still waiting, unless its
+    /// message simulates another outcome.
+    ///
+    /// # Returns
+    /// The callback URL and the body of the callback, unless
+    /// the payment or refund has been cancelled.
+    fn settle(&self, settlement: &Settlement) -> Option<(String, Vec<u8>)> {
+        let mut ledger = self.ledger.lock().unwrap();
+        let (callback_url, body) = match *settlement {
+            Settlement::Payment(ref id) => {
+                let payment = ledger.payments.get_mut(id)?;
+                if payment.status != Some(Status::Created) {
+                    return None;
+                }
+                match Simulation::from_message(payment.message.as_deref()) {
+                    Simulation::Paid => {
+                        payment.status = Some(Status::Paid);
+                        payment.payment_reference = Some(new_id());
+                        payment.date_paid = Some(timestamp(SystemTime::now()));
+                    }
+                    Simulation::Declined => payment.status = Some(Status::Declined),
+                    Simulation::Error(code) => {
+                        payment.status = Some(Status::Error);
+                        payment.error_message = Some(code.description().to_owned());
+                        payment.error_code = Some(code.clone());
+                    }
+                }
+                (payment.callback_url.clone(), serde_json::to_vec(&*payment))
+            }
+            Settlement::Refund(ref id) => {
+                let refund = ledger.refunds.get_mut(id)?;
+                if refund.status != Some(Status::Validated) {
+                    return None;
+                }
+                match Simulation::from_message(refund.message.as_deref()) {
+                    Simulation::Paid => {
+                        refund.status = Some(Status::Paid);
+                        refund.payment_reference = Some(new_id());
+                        refund.date_paid = Some(timestamp(SystemTime::now()));
+                    }
+                    Simulation::Declined => {
+                        refund.status = Some(Status::Error);
+                        refund.error_message = Some(ErrorCode::RF07.description().to_owned());
+                        refund.error_code = Some(ErrorCode::RF07);
+                    }
+                    Simulation::Error(code) => {
+                        refund.status = Some(Status::Error);
+                        refund.error_message = Some(code.description().to_owned());
+                        refund.error_code = Some(code.clone());
+                    }
+                }
+                (refund.callback_url.clone(), serde_json::to_vec(&*refund))
+            }
+        };
+        Some((callback_url?, body.ok()?))
+    }
+
+    /// The response to a payment or refund that was created.
+    fn created(&self, path: &str, id: &str) -> Response<Body> {
+        let mut response = respond(StatusCode::CREATED);
+        if let Ok(location) = HeaderValue::from_str(&format!("{}{}/{}", self.api_url, path, id)) {
+            response.headers_mut().insert(LOCATION, location);
+        }
+        response
+    }
+}
+
+/// Answers a single request made to the simulator.
+fn receive(
+    request: Request<Body>,
+    mss: Arc<Mss>,
+) -> Box<dyn Future<Item = Response<Body>, Error = ::hyper::Error> + Send> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_owned();
+    Box::new(request.into_body().concat2().map(move |body| {
+        let params = serde_json::from_slice::<Value>(&body);
+        let route = path
+            .strip_prefix(API_PATH)
+            .map(|route| route.splitn(2, '/').collect::<Vec<_>>());
+        let response = match (method, route.as_deref()) {
+            (Method::POST, Some(["paymentrequests"])) => match params {
+                Ok(params) => mss.create_payment(&params),
+                Err(_) => Ok(respond(StatusCode::BAD_REQUEST)),
+            },
+            (Method::GET, Some(["paymentrequests", id])) => {
+                let ledger = mss.ledger.lock().unwrap();
+                Ok(found(ledger.payments.get(*id)))
+            }
+            (Method::PATCH, Some(["paymentrequests", id])) => mss.cancel_payment(id),
+            (Method::POST, Some(["refunds"])) => match params {
+                Ok(params) => mss.create_refund(&params),
+                Err(_) => Ok(respond(StatusCode::BAD_REQUEST)),
+            },
+            (Method::GET, Some(["refunds", id])) => {
+                let ledger = mss.ledger.lock().unwrap();
+                Ok(found(ledger.refunds.get(*id)))
+            }
+            (_, Some(_)) => Ok(respond(StatusCode::METHOD_NOT_ALLOWED)),
+            (_, None) => Ok(respond(StatusCode::NOT_FOUND)),
+        };
+        response.unwrap_or_else(rejected)
+    }))
+}
+
+/// Posts the callback of a payment or a refund, ignoring whether it's received.
+fn deliver(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    callback_uri: Option<Uri>,
+    callback_url: &str,
+    body: Vec<u8>,
+) -> Box<dyn Future<Item = (), Error = ()>> {
+    let uri = match callback_uri.map_or_else(|| callback_url.parse(), Ok) {
+        Ok(uri) => uri,
+        Err(_) => return Box::new(future::ok(())),
+    };
+    let mut request = Request::new(Body::from(body));
+    *request.method_mut() = Method::POST;
+    *request.uri_mut() = uri;
+    request
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Box::new(client.request(request).then(|result| {
+        if let Err(_error) = result {
+            log_debug!("The simulator couldn't deliver a callback: {}", _error);
+        }
+        Ok(())
+    }))
+}
+
+/// Checks the callback URL, which Swish requires to be https.
+fn check_callback_url<'a>(params: &'a Value, errors: &mut Vec<ErrorCode>) -> Option<&'a str> {
+    let callback_url = string_field(params, "callbackUrl");
+    if !callback_url.is_some_and(|url| url.starts_with("https://")) {
+        errors.push(ErrorCode::RP03);
+    }
+    callback_url
+}
+
+/// Checks the amount, which Swish requires to be within its limits.
+fn check_amount(params: &Value, errors: &mut Vec<ErrorCode>) -> Option<Amount> {
+    let amount = params
+        .get("amount")
+        .and_then(|amount| serde_json::from_value::<Amount>(amount.clone()).ok());
+    match amount {
+        Some(amount) if amount < AMOUNT_MIN => errors.push(ErrorCode::AM06),
+        Some(amount) if amount > AMOUNT_MAX => errors.push(ErrorCode::AM02),
+        Some(_) => (),
+        None => errors.push(ErrorCode::PA02),
+    }
+    amount
+}
+
+/// Checks the currency, which Swish requires to be SEK.
+fn check_currency(params: &Value, errors: &mut Vec<ErrorCode>) {
+    if string_field(params, "currency") != Some("SEK") {
+        errors.push(ErrorCode::AM03);
+    }
+}
+
+/// Checks the characters and length of the message, if there is one.
+fn check_message(params: &Value, errors: &mut Vec<ErrorCode>) {
+    if let Some(message) = string_field(params, "message") {
+        if validation::validate_message(message).is_err() {
+            errors.push(ErrorCode::RP02);
+        }
+    }
+}
+
+/// Checks whether the message simulates an error that's returned right away.
+fn check_simulation(params: &Value, errors: &mut Vec<ErrorCode>) {
+    let simulation = Simulation::from_message(string_field(params, "message"));
+    if let Simulation::Error(ref code) = simulation {
+        if simulation.is_rejected() {
+            errors.push(code.clone());
+        }
+    }
+}
+
+fn string_field<'a>(params: &'a Value, field: &str) -> Option<&'a str> {
+    params.get(field).and_then(Value::as_str)
+}
+
+/// The response to fetching a payment or refund.
+fn found<T: ::serde::Serialize>(resource: Option<&T>) -> Response<Body> {
+    match resource {
+        Some(resource) => json_response(StatusCode::OK, resource),
+        None => respond(StatusCode::NOT_FOUND),
+    }
+}
+
+/// The response to params that Swish rejects, with the errors as Swish returns them.
+fn rejected(errors: Vec<ErrorCode>) -> Response<Body> {
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|code| {
+            json!({
+                "errorCode": code.as_str(),
+                "errorMessage": code.description(),
+                "additionalInformation": null,
+            })
+        })
+        .collect();
+    json_response(StatusCode::UNPROCESSABLE_ENTITY, &errors)
+}
+
+fn json_response<T: ::serde::Serialize + ?Sized>(status: StatusCode, body: &T) -> Response<Body> {
+    let mut response = Response::new(Body::from(serde_json::to_vec(body).unwrap_or_default()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn respond(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// An id like the ones Swish gives payments, refunds and payment references.
+fn new_id() -> String {
+    Uuid::new_v4().simple().to_string().to_uppercase()
+}
+
+/// Formats a time as Swish does, e.g. `2015-02-19T21:01:53.000Z`.
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let days = (seconds / 86_400) as i64;
+    let seconds_of_day = seconds % 86_400;
+
+    // The civil date of a number of days since 1970-01-01, by Howard Hinnant.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
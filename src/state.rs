@@ -0,0 +1,300 @@
+//! # The state module
+//!
+//! Contains the states of payments and refunds and the transitions between
+//! them, so that callbacks and polls arriving in any order can't move
+//! an order back from e.g. paid to created.
+//!
+use error::Resource;
+use event::PaymentEvent;
+use std::error;
+use std::fmt;
+
+/// The state of a payment request.
+///
+/// A payment starts as `Created` and moves to one of the final states.
+/// A payment seen as `Expired` is final, but can still move to the final
+/// state Swish reports, as expiry is only known by this crate.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::state::PaymentState;
+///
+/// let state = PaymentState::Created.transition(PaymentState::Paid).unwrap();
+///
+/// assert_eq!(state, PaymentState::Paid);
+/// assert!(state.transition(PaymentState::Cancelled).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PaymentState {
+    /// The payment request was created and is waiting for the payer.
+    #[default]
+    Created,
+    /// The payer paid.
+    Paid,
+    /// The payer declined the payment.
+    Declined,
+    /// The payment request was cancelled.
+    Cancelled,
+    /// The payment failed.
+    Error,
+    /// The payment request expired without any final status being seen.
+    Expired,
+}
+
+impl PaymentState {
+    /// Gets the state as it's named by the Swish API, e.g. `PAID`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            PaymentState::Created => "CREATED",
+            PaymentState::Paid => "PAID",
+            PaymentState::Declined => "DECLINED",
+            PaymentState::Cancelled => "CANCELLED",
+            PaymentState::Error => "ERROR",
+            PaymentState::Expired => "EXPIRED",
+        }
+    }
+
+    /// Gets the state a payment is in after an event,
+    /// or `None` if it isn't an event of a payment.
+    pub fn from_event(event: &PaymentEvent) -> Option<Self> {
+        match *event {
+            PaymentEvent::Created { .. } => Some(PaymentState::Created),
+            PaymentEvent::Paid { .. } => Some(PaymentState::Paid),
+            PaymentEvent::Declined { .. } => Some(PaymentState::Declined),
+            PaymentEvent::Cancelled { .. } => Some(PaymentState::Cancelled),
+            PaymentEvent::Errored { .. } => Some(PaymentState::Error),
+            PaymentEvent::Expired { .. } => Some(PaymentState::Expired),
+            _ => None,
+        }
+    }
+
+    /// Whether nothing more will happen to the payment in this state.
+    pub fn is_final(&self) -> bool {
+        !matches!(*self, PaymentState::Created)
+    }
+
+    /// Whether the payment can move from this state to the given one.
+    /// Staying in the same state is allowed, as callbacks may be delivered twice,
+    /// and `Expired` may be followed by the final state Swish reports.
+    pub fn can_transition_to(&self, to: PaymentState) -> bool {
+        match (*self, to) {
+            (from, to) if from == to => true,
+            (PaymentState::Created, _) => true,
+            (PaymentState::Expired, to) => to != PaymentState::Created,
+            _ => false,
+        }
+    }
+
+    /// Moves the payment to the given state.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The state to move to.
+    ///
+    /// # Returns
+    /// The new state, or a `TransitionError` if the payment can't move there.
+    pub fn transition(self, to: PaymentState) -> Result<Self, TransitionError> {
+        if self.can_transition_to(to) {
+            Ok(to)
+        } else {
+            Err(TransitionError::new(
+                Resource::Payment,
+                self.as_str(),
+                to.as_str(),
+            ))
+        }
+    }
+
+    /// Moves the payment to the state it's in after an event.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event, e.g. from a callback or a poll.
+    ///
+    /// # Returns
+    /// The new state, or a `TransitionError` if the payment can't move there
+    /// or the event isn't one of a payment.
+    pub fn apply(self, event: &PaymentEvent) -> Result<Self, TransitionError> {
+        match PaymentState::from_event(event) {
+            Some(to) => self.transition(to),
+            None => Err(TransitionError::new(
+                Resource::Payment,
+                self.as_str(),
+                event_name(event),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PaymentState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The state of a refund.
+///
+/// A refund starts as `Initiated`, may be `Debited` from the merchant,
+/// and ends as `Paid` or `Error`.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::state::RefundState;
+///
+/// let state = RefundState::Initiated
+///     .transition(RefundState::Debited)
+///     .and_then(|state| state.transition(RefundState::Paid))
+///     .unwrap();
+///
+/// assert!(state.transition(RefundState::Debited).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RefundState {
+    /// The refund was created and is being handled by Swish.
+    #[default]
+    Initiated,
+    /// The refund was debited from the merchant, but not yet paid out.
+    Debited,
+    /// The refund was paid out to the payer of the original payment.
+    Paid,
+    /// The refund failed.
+    Error,
+}
+
+impl RefundState {
+    /// Gets the state as it's named by the Swish API, e.g. `DEBITED`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            RefundState::Initiated => "INITIATED",
+            RefundState::Debited => "DEBITED",
+            RefundState::Paid => "PAID",
+            RefundState::Error => "ERROR",
+        }
+    }
+
+    /// Gets the state a refund is in after an event,
+    /// or `None` if it isn't an event of a refund.
+    pub fn from_event(event: &PaymentEvent) -> Option<Self> {
+        match *event {
+            PaymentEvent::RefundInitiated { .. } => Some(RefundState::Initiated),
+            PaymentEvent::RefundDebited { .. } => Some(RefundState::Debited),
+            PaymentEvent::RefundPaid { .. } => Some(RefundState::Paid),
+            PaymentEvent::RefundErrored { .. } => Some(RefundState::Error),
+            _ => None,
+        }
+    }
+
+    /// Whether nothing more will happen to the refund in this state.
+    pub fn is_final(&self) -> bool {
+        matches!(*self, RefundState::Paid | RefundState::Error)
+    }
+
+    /// Whether the refund can move from this state to the given one.
+    /// Staying in the same state is allowed, as callbacks may be delivered twice,
+    /// and `Debited` may be skipped, as it isn't always reported.
+    pub fn can_transition_to(&self, to: RefundState) -> bool {
+        match (*self, to) {
+            (from, to) if from == to => true,
+            (RefundState::Initiated, _) => true,
+            (RefundState::Debited, to) => to.is_final(),
+            _ => false,
+        }
+    }
+
+    /// Moves the refund to the given state.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The state to move to.
+    ///
+    /// # Returns
+    /// The new state, or a `TransitionError` if the refund can't move there.
+    pub fn transition(self, to: RefundState) -> Result<Self, TransitionError> {
+        if self.can_transition_to(to) {
+            Ok(to)
+        } else {
+            Err(TransitionError::new(
+                Resource::Refund,
+                self.as_str(),
+                to.as_str(),
+            ))
+        }
+    }
+
+    /// Moves the refund to the state it's in after an event.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event, e.g. from a callback or a poll.
+    ///
+    /// # Returns
+    /// The new state, or a `TransitionError` if the refund can't move there
+    /// or the event isn't one of a refund.
+    pub fn apply(self, event: &PaymentEvent) -> Result<Self, TransitionError> {
+        match RefundState::from_event(event) {
+            Some(to) => self.transition(to),
+            None => Err(TransitionError::new(
+                Resource::Refund,
+                self.as_str(),
+                event_name(event),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RefundState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A payment or refund was asked to move to a state it can't move to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionError {
+    /// Whether it's a payment or a refund.
+    pub resource: Resource,
+    /// The state it's in.
+    pub from: &'static str,
+    /// The state it was asked to move to.
+    pub to: String,
+}
+
+impl TransitionError {
+    fn new<T: Into<String>>(resource: Resource, from: &'static str, to: T) -> Self {
+        TransitionError {
+            resource,
+            from,
+            to: to.into(),
+        }
+    }
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a {} can't move from {} to {}",
+            self.resource, self.from, self.to
+        )
+    }
+}
+
+impl error::Error for TransitionError {}
+
+/// Gets the name of the state an event moves its payment or refund to.
+fn event_name(event: &PaymentEvent) -> String {
+    if let Some(state) = PaymentState::from_event(event) {
+        return state.as_str().to_owned();
+    }
+    if let Some(state) = RefundState::from_event(event) {
+        return format!("REFUND {}", state.as_str());
+    }
+    match *event {
+        PaymentEvent::Unknown { ref status, .. } => status.as_str().to_owned(),
+        _ => "UNKNOWN".to_owned(),
+    }
+}
@@ -0,0 +1,120 @@
+//! # The stats module
+//!
+//! Contains the runtime introspection of a client, used to see
+//! whether the connections to Swish are saturated or if calls are piling up.
+//!
+use metrics::Endpoint;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A snapshot of what a client is doing right now.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientStats {
+    /// The connections to Swish that are open, both idle ones
+    /// in the connection pool and the ones being used by a request.
+    pub open_connections: usize,
+    /// The calls to the Swish API that have been made but
+    /// haven't finished or been dropped yet, per endpoint.
+    pub in_flight: HashMap<Endpoint, usize>,
+    /// The retries that are waiting out their backoff before being sent,
+    /// see [`RetryPolicy`](../retry/struct.RetryPolicy.html).
+    pub queued_retries: usize,
+}
+
+impl ClientStats {
+    /// Gets the number of calls that haven't finished yet, for all endpoints.
+    pub fn total_in_flight(&self) -> usize {
+        self.in_flight.values().sum()
+    }
+}
+
+/// Keeps the counters that a [`ClientStats`] is built from.
+///
+/// [`ClientStats`]: struct.ClientStats.html
+#[derive(Debug, Default)]
+pub(crate) struct StatsRecorder {
+    open_connections: Arc<AtomicUsize>,
+    in_flight: Mutex<HashMap<Endpoint, usize>>,
+    queued_retries: AtomicUsize,
+}
+
+impl StatsRecorder {
+    /// The counter of open connections, shared with the connector.
+    pub(crate) fn open_connections(&self) -> Arc<AtomicUsize> {
+        self.open_connections.clone()
+    }
+
+    /// Counts a call as in flight until the returned guard is dropped.
+    pub(crate) fn start_call(recorder: &Arc<StatsRecorder>, endpoint: Endpoint) -> InFlightGuard {
+        *recorder
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(endpoint)
+            .or_insert(0) += 1;
+        InFlightGuard {
+            recorder: recorder.clone(),
+            endpoint,
+        }
+    }
+
+    /// Counts a retry as queued until the returned guard is dropped,
+    /// i.e. until its backoff is over or the call is dropped.
+    pub(crate) fn queue_retry(recorder: &Arc<StatsRecorder>) -> QueuedRetryGuard {
+        recorder.queued_retries.fetch_add(1, Ordering::SeqCst);
+        QueuedRetryGuard {
+            recorder: recorder.clone(),
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ClientStats {
+        let in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(&endpoint, &count)| (endpoint, count))
+            .collect();
+
+        ClientStats {
+            open_connections: self.open_connections.load(Ordering::SeqCst),
+            in_flight,
+            queued_retries: self.queued_retries.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// A call that's in flight, which is counted as finished when dropped.
+#[derive(Debug)]
+pub(crate) struct InFlightGuard {
+    recorder: Arc<StatsRecorder>,
+    endpoint: Endpoint,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Some(count) = self
+            .recorder
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(&self.endpoint)
+        {
+            *count -= 1;
+        }
+    }
+}
+
+/// A retry waiting out its backoff, which is no longer counted when dropped.
+#[derive(Debug)]
+pub(crate) struct QueuedRetryGuard {
+    recorder: Arc<StatsRecorder>,
+}
+
+impl Drop for QueuedRetryGuard {
+    fn drop(&mut self) {
+        self.recorder.queued_retries.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,551 @@
+//! # The store module
+//!
+//! Contains the store keeping the payments and refunds created at Swish
+//! together with their states, which the [`PaymentWatcher`](../watcher/struct.PaymentWatcher.html)
+//! updates and the [`PaymentPoller`](../polling/struct.PaymentPoller.html) resumes from.
+//!
+use alias::PhoneAlias;
+use amount::Amount;
+use client::{
+    CreatedPayment, CreatedRefund, PaymentParams, RefundParams, SwishBoxFuture, SwishClient,
+    PAYMENT_REQUEST_TTL,
+};
+use error::SwishClientError;
+use event::PaymentEvent;
+use futures::{future, Future};
+use ids::{PaymentId, RefundId};
+use state::{PaymentState, RefundState, TransitionError};
+use std::cmp;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// A payment kept by a [`SwishStore`](trait.SwishStore.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredPayment {
+    pub id: PaymentId,
+    /// The reference of the payment given by the merchant, e.g. an order number.
+    pub payee_payment_reference: Option<String>,
+    /// The reference of the payment at the bank of the payer, which refunds
+    /// refer to. It's set by [`update_status`](trait.SwishStore.html#method.update_status)
+    /// once the payment is paid.
+    pub payment_reference: Option<String>,
+    /// The phone number of the payer, for e-commerce payments.
+    pub payer_alias: Option<PhoneAlias>,
+    pub amount: Amount,
+    pub state: PaymentState,
+    pub created_at: SystemTime,
+}
+
+impl StoredPayment {
+    /// [`StoredPayment`]: struct.StoredPayment.html
+    ///
+    /// Creates a new [`StoredPayment`] in the `Created` state.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment` - The payment returned by Swish.
+    /// * `params` - The params the payment was created with.
+    pub fn new(payment: &CreatedPayment, params: &PaymentParams) -> Self {
+        StoredPayment {
+            id: PaymentId::from(payment.id.as_str()),
+            payee_payment_reference: params.payee_payment_reference.map(str::to_owned),
+            payment_reference: None,
+            payer_alias: params.payer_alias.clone(),
+            amount: params.amount,
+            state: PaymentState::Created,
+            created_at: payment.created_at,
+        }
+    }
+}
+
+/// A refund kept by a [`SwishStore`](trait.SwishStore.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredRefund {
+    pub id: RefundId,
+    /// The payment reference of the payment that's refunded.
+    pub original_payment_reference: String,
+    pub amount: Amount,
+    pub state: RefundState,
+    pub created_at: SystemTime,
+}
+
+impl StoredRefund {
+    /// [`StoredRefund`]: struct.StoredRefund.html
+    ///
+    /// Creates a new [`StoredRefund`] in the `Initiated` state.
+    ///
+    /// # Arguments
+    ///
+    /// * `refund` - The refund returned by Swish.
+    /// * `params` - The params the refund was created with.
+    /// * `created_at` - When Swish responded that the refund was created,
+    ///   e.g. the time of a [`Clock`](../clock/trait.Clock.html).
+    pub fn new(refund: &CreatedRefund, params: &RefundParams, created_at: SystemTime) -> Self {
+        StoredRefund {
+            id: RefundId::from(refund.id.as_str()),
+            original_payment_reference: params.original_payment_reference.to_owned(),
+            amount: params.amount,
+            state: RefundState::Initiated,
+            created_at,
+        }
+    }
+}
+
+/// An error of a [`SwishStore`](trait.SwishStore.html).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StoreError {
+    /// There's no payment or refund with the id.
+    NotFound(String),
+    /// The event would move the payment or refund to a state it can't move to.
+    Transition(TransitionError),
+    /// The refund would refund more than what's left of the payment.
+    OverRefund {
+        /// The payment reference of the payment.
+        payment_reference: String,
+        /// The amount of the refund.
+        requested: Amount,
+        /// What's left to refund of the payment.
+        remaining: Amount,
+    },
+    /// The backend of the store failed, e.g. because the database is down.
+    Backend(Box<dyn error::Error + Send + Sync>),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StoreError::NotFound(ref id) => write!(f, "{} isn't stored", id),
+            StoreError::Transition(ref error) => error.fmt(f),
+            StoreError::OverRefund {
+                ref payment_reference,
+                requested,
+                remaining,
+            } => write!(
+                f,
+                "refunding {} SEK of {} exceeds the {} SEK left to refund",
+                requested, payment_reference, remaining
+            ),
+            StoreError::Backend(ref error) => write!(f, "the store failed: {}", error),
+        }
+    }
+}
+
+impl error::Error for StoreError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            StoreError::NotFound(_) | StoreError::OverRefund { .. } => None,
+            StoreError::Transition(ref error) => Some(error),
+            StoreError::Backend(ref error) => Some(&**error),
+        }
+    }
+}
+
+impl From<TransitionError> for StoreError {
+    fn from(error: TransitionError) -> Self {
+        StoreError::Transition(error)
+    }
+}
+
+/// Keeps the payments and refunds created at Swish.
+///
+/// Implement this with e.g. a database table, so that payments being
+/// awaited survive a restart and are shared by several instances.
+pub trait SwishStore: fmt::Debug + Send + Sync {
+    /// Saves a payment, replacing any payment with the same id.
+    fn save_payment(&self, payment: StoredPayment) -> Result<(), StoreError>;
+
+    /// Saves a refund, replacing any refund with the same id.
+    fn save_refund(&self, refund: StoredRefund) -> Result<(), StoreError>;
+
+    /// Moves the payment or refund of an event to the state it's in after the event.
+    ///
+    /// # Returns
+    /// The `StoreError::Transition` of the [`PaymentState`](../state/enum.PaymentState.html)
+    /// or [`RefundState`](../state/enum.RefundState.html) if it can't move there.
+    fn update_status(&self, event: &PaymentEvent) -> Result<(), StoreError>;
+
+    /// Gets a payment by the reference given by the merchant.
+    fn find_by_payee_reference(&self, reference: &str)
+        -> Result<Option<StoredPayment>, StoreError>;
+
+    /// Gets a paid payment by its payment reference.
+    fn find_by_payment_reference(
+        &self,
+        reference: &str,
+    ) -> Result<Option<StoredPayment>, StoreError>;
+
+    /// Gets the refunds of a payment.
+    ///
+    /// # Arguments
+    ///
+    /// * `original_payment_reference` - The payment reference of the payment.
+    fn refunds_of(&self, original_payment_reference: &str)
+        -> Result<Vec<StoredRefund>, StoreError>;
+
+    /// Gets the payments that aren't final yet, oldest first.
+    fn pending_payments(&self) -> Result<Vec<StoredPayment>, StoreError>;
+
+    /// Gets the refunds that aren't final yet, oldest first.
+    fn pending_refunds(&self) -> Result<Vec<StoredRefund>, StoreError>;
+
+    /// Gets the payment request of a payer that the payer may still answer,
+    /// which Swish doesn't allow another payment request for (RP06).
+    ///
+    /// # Arguments
+    ///
+    /// * `payer_alias` - The phone number of the payer.
+    /// * `now` - The time to check the expiry at, e.g. the time of a
+    ///   [`Clock`](../clock/trait.Clock.html).
+    ///
+    /// # Returns
+    /// The newest payment of the payer that's created and not yet expired, if any.
+    fn active_payment_for(
+        &self,
+        payer_alias: &PhoneAlias,
+        now: SystemTime,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        Ok(self.pending_payments()?.into_iter().rfind(|payment| {
+            payment.state == PaymentState::Created
+                && payment.payer_alias.as_ref() == Some(payer_alias)
+                && now < payment.created_at + PAYMENT_REQUEST_TTL
+        }))
+    }
+
+    /// Gets the amount refunded of a payment, counting the refunds
+    /// that are paid or on their way, but not the failed ones.
+    fn refunded(&self, payment: &StoredPayment) -> Result<Amount, StoreError> {
+        let reference = match payment.payment_reference {
+            Some(ref reference) => reference,
+            None => return Ok(Amount::from_ore(0)),
+        };
+        Ok(self
+            .refunds_of(reference)?
+            .iter()
+            .filter(|refund| refund.state != RefundState::Error)
+            .fold(Amount::from_ore(0), |refunded, refund| {
+                refunded + refund.amount
+            }))
+    }
+
+    /// Gets the amount that's left to refund of a payment.
+    fn remaining_refundable(&self, payment: &StoredPayment) -> Result<Amount, StoreError> {
+        let remaining = payment.amount - self.refunded(payment)?;
+        Ok(cmp::max(remaining, Amount::from_ore(0)))
+    }
+
+    /// Checks that a refund wouldn't refund more than what's left of a payment.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment` - The payment to refund.
+    /// * `amount` - The amount of the refund.
+    ///
+    /// # Returns
+    /// A `StoreError::OverRefund` if the amount is more than what's left.
+    fn check_refund(&self, payment: &StoredPayment, amount: Amount) -> Result<(), StoreError> {
+        let remaining = self.remaining_refundable(payment)?;
+        if amount > remaining {
+            return Err(StoreError::OverRefund {
+                payment_reference: payment.payment_reference.clone().unwrap_or_default(),
+                requested: amount,
+                remaining,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<S: SwishStore + ?Sized> SwishStore for Arc<S> {
+    fn save_payment(&self, payment: StoredPayment) -> Result<(), StoreError> {
+        (**self).save_payment(payment)
+    }
+
+    fn save_refund(&self, refund: StoredRefund) -> Result<(), StoreError> {
+        (**self).save_refund(refund)
+    }
+
+    fn update_status(&self, event: &PaymentEvent) -> Result<(), StoreError> {
+        (**self).update_status(event)
+    }
+
+    fn find_by_payee_reference(
+        &self,
+        reference: &str,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        (**self).find_by_payee_reference(reference)
+    }
+
+    fn find_by_payment_reference(
+        &self,
+        reference: &str,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        (**self).find_by_payment_reference(reference)
+    }
+
+    fn refunds_of(
+        &self,
+        original_payment_reference: &str,
+    ) -> Result<Vec<StoredRefund>, StoreError> {
+        (**self).refunds_of(original_payment_reference)
+    }
+
+    fn pending_payments(&self) -> Result<Vec<StoredPayment>, StoreError> {
+        (**self).pending_payments()
+    }
+
+    fn pending_refunds(&self) -> Result<Vec<StoredRefund>, StoreError> {
+        (**self).pending_refunds()
+    }
+
+    fn active_payment_for(
+        &self,
+        payer_alias: &PhoneAlias,
+        now: SystemTime,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        (**self).active_payment_for(payer_alias, now)
+    }
+
+    fn refunded(&self, payment: &StoredPayment) -> Result<Amount, StoreError> {
+        (**self).refunded(payment)
+    }
+
+    fn remaining_refundable(&self, payment: &StoredPayment) -> Result<Amount, StoreError> {
+        (**self).remaining_refundable(payment)
+    }
+
+    fn check_refund(&self, payment: &StoredPayment, amount: Amount) -> Result<(), StoreError> {
+        (**self).check_refund(payment, amount)
+    }
+}
+
+/// Keeps the payments and refunds in memory,
+/// which is enough for a single instance that's fine with losing them on restart.
+///
+/// # Example
+///
+/// ```
+/// use std::time::SystemTime;
+/// use swish_api::amount::Amount;
+/// use swish_api::event::PaymentEvent;
+/// use swish_api::ids::PaymentId;
+/// use swish_api::state::PaymentState;
+/// use swish_api::store::{InMemoryStore, StoredPayment, SwishStore};
+///
+/// let store = InMemoryStore::new();
+/// store
+///     .save_payment(StoredPayment {
+///         id: PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"),
+///         payee_payment_reference: Some("0123456789".to_owned()),
+///         payment_reference: None,
+///         payer_alias: None,
+///         amount: Amount::from_kronor(100),
+///         state: PaymentState::Created,
+///         created_at: SystemTime::now(),
+///     })
+///     .unwrap();
+///
+/// store
+///     .update_status(&PaymentEvent::Cancelled {
+///         payment_id: PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"),
+///     })
+///     .unwrap();
+///
+/// let payment = store.find_by_payee_reference("0123456789").unwrap().unwrap();
+/// assert_eq!(payment.state, PaymentState::Cancelled);
+/// assert!(store.pending_payments().unwrap().is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct InMemoryStore {
+    records: Mutex<Records>,
+}
+
+#[derive(Debug, Default)]
+struct Records {
+    payments: HashMap<PaymentId, StoredPayment>,
+    refunds: HashMap<RefundId, StoredRefund>,
+    payee_references: HashMap<String, PaymentId>,
+    payment_references: HashMap<String, PaymentId>,
+}
+
+impl InMemoryStore {
+    /// [`InMemoryStore`]: struct.InMemoryStore.html
+    ///
+    /// Creates a new, empty, [`InMemoryStore`].
+    pub fn new() -> Self {
+        InMemoryStore::default()
+    }
+
+    fn records(&self) -> Result<::std::sync::MutexGuard<'_, Records>, StoreError> {
+        self.records
+            .lock()
+            .map_err(|_| StoreError::Backend("the store was poisoned".into()))
+    }
+}
+
+impl SwishStore for InMemoryStore {
+    fn save_payment(&self, payment: StoredPayment) -> Result<(), StoreError> {
+        let mut records = self.records()?;
+        if let Some(ref reference) = payment.payee_payment_reference {
+            records
+                .payee_references
+                .insert(reference.clone(), payment.id.clone());
+        }
+        if let Some(ref reference) = payment.payment_reference {
+            records
+                .payment_references
+                .insert(reference.clone(), payment.id.clone());
+        }
+        records.payments.insert(payment.id.clone(), payment);
+        Ok(())
+    }
+
+    fn save_refund(&self, refund: StoredRefund) -> Result<(), StoreError> {
+        let mut records = self.records()?;
+        records.refunds.insert(refund.id.clone(), refund);
+        Ok(())
+    }
+
+    fn update_status(&self, event: &PaymentEvent) -> Result<(), StoreError> {
+        let mut records = self.records()?;
+        let not_found = || StoreError::NotFound(event.id().to_owned());
+        if RefundState::from_event(event).is_some() {
+            let refund = records
+                .refunds
+                .get_mut(&RefundId::from(event.id()))
+                .ok_or_else(not_found)?;
+            refund.state = refund.state.apply(event)?;
+        } else {
+            let payment_id = PaymentId::from(event.id());
+            let payment = records
+                .payments
+                .get_mut(&payment_id)
+                .ok_or_else(not_found)?;
+            payment.state = payment.state.apply(event)?;
+            if let PaymentEvent::Paid {
+                payment_reference: Some(ref reference),
+                ..
+            } = *event
+            {
+                payment.payment_reference = Some(reference.clone());
+                records
+                    .payment_references
+                    .insert(reference.clone(), payment_id);
+            }
+        }
+        Ok(())
+    }
+
+    fn find_by_payee_reference(
+        &self,
+        reference: &str,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        let records = self.records()?;
+        Ok(records
+            .payee_references
+            .get(reference)
+            .and_then(|id| records.payments.get(id))
+            .cloned())
+    }
+
+    fn find_by_payment_reference(
+        &self,
+        reference: &str,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        let records = self.records()?;
+        Ok(records
+            .payment_references
+            .get(reference)
+            .and_then(|id| records.payments.get(id))
+            .cloned())
+    }
+
+    fn refunds_of(
+        &self,
+        original_payment_reference: &str,
+    ) -> Result<Vec<StoredRefund>, StoreError> {
+        let records = self.records()?;
+        let mut refunds: Vec<_> = records
+            .refunds
+            .values()
+            .filter(|refund| refund.original_payment_reference == original_payment_reference)
+            .cloned()
+            .collect();
+        refunds.sort_by_key(|refund| refund.created_at);
+        Ok(refunds)
+    }
+
+    fn pending_payments(&self) -> Result<Vec<StoredPayment>, StoreError> {
+        let records = self.records()?;
+        let mut payments: Vec<_> = records
+            .payments
+            .values()
+            .filter(|payment| !payment.state.is_final())
+            .cloned()
+            .collect();
+        payments.sort_by_key(|payment| payment.created_at);
+        Ok(payments)
+    }
+
+    fn pending_refunds(&self) -> Result<Vec<StoredRefund>, StoreError> {
+        let records = self.records()?;
+        let mut refunds: Vec<_> = records
+            .refunds
+            .values()
+            .filter(|refund| !refund.state.is_final())
+            .cloned()
+            .collect();
+        refunds.sort_by_key(|refund| refund.created_at);
+        Ok(refunds)
+    }
+}
+
+impl SwishClient {
+    /// Creates a refund, unless it would refund more than what's left of the
+    /// payment, and saves it in the store so that it counts for the next refund.
+    ///
+    /// The check and the save aren't atomic, so refunds of the same payment
+    /// shouldn't be created concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - [`RefundParams`](../client/struct.RefundParams.html).
+    /// * `store` - The [`SwishStore`](trait.SwishStore.html) with the paid payment.
+    ///
+    /// # Returns
+    /// A Future with the [`CreatedRefund`](../client/struct.CreatedRefund.html), or a
+    /// `SwishClientError::Store` if the payment isn't stored or the refund is too large.
+    /// If the refund was created but couldn't be saved, the error of the store is returned.
+    pub fn create_refund_tracked<'a, S: SwishStore + ?Sized>(
+        &'a self,
+        params: RefundParams,
+        store: &'a S,
+    ) -> SwishBoxFuture<'a, CreatedRefund> {
+        let reference = params.original_payment_reference;
+        let checked = store
+            .find_by_payment_reference(reference)
+            .and_then(|payment| payment.ok_or_else(|| StoreError::NotFound(reference.to_owned())))
+            .and_then(|payment| store.check_refund(&payment, params.amount));
+        if let Err(error) = checked {
+            return Box::new(future::err(SwishClientError::Store(error)));
+        }
+
+        let original_payment_reference = reference.to_owned();
+        let amount = params.amount;
+        Box::new(self.create_refund(params).and_then(move |created| {
+            store
+                .save_refund(StoredRefund {
+                    id: RefundId::from(created.id.as_str()),
+                    original_payment_reference,
+                    amount,
+                    state: RefundState::Initiated,
+                    created_at: self.clock().now(),
+                })
+                .map(|()| created)
+                .map_err(SwishClientError::Store)
+        }))
+    }
+}
@@ -0,0 +1,476 @@
+//! # The test_utils module
+//!
+//! Contains builders of realistic payments, refunds and callbacks in every
+//! status, for the tests of applications using this crate. It's only built
+//! with the `test-utils` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use swish_api::client::Status;
+//! use swish_api::test_utils::PaymentFixture;
+//!
+//! let payment = PaymentFixture::new(Status::Paid).payment();
+//!
+//! assert_eq!(payment.status, Some(Status::Paid));
+//! assert!(payment.payment_reference.is_some());
+//! ```
+//!
+use amount::Amount;
+use callback::{PaymentCallback, RefundCallback};
+use client::{
+    CreatedMCommercePayment, CreatedPayment, Currency, Payment, Refund, Status, PAYMENT_REQUEST_TTL,
+};
+use error::ErrorCode;
+use ids::PaymentRequestToken;
+use serde_json;
+use std::time::{Duration, SystemTime};
+
+/// The statuses Swish gives payments.
+pub const PAYMENT_STATUSES: [Status; 5] = [
+    Status::Created,
+    Status::Paid,
+    Status::Declined,
+    Status::Cancelled,
+    Status::Error,
+];
+
+/// The statuses Swish gives refunds.
+pub const REFUND_STATUSES: [Status; 6] = [
+    Status::Created,
+    Status::Validated,
+    Status::Initiated,
+    Status::Debited,
+    Status::Paid,
+    Status::Error,
+];
+
+const PAYMENT_ID: &str = "AB23D7406ECE4542A80152D909EF9F6B";
+const REFUND_ID: &str = "ABC2D7406ECE4542A80152D909EF9F6B";
+const PAYMENT_REFERENCE: &str = "6D6CD7406ECE4542A80152D909EF9F6B";
+const REFUND_PAYMENT_REFERENCE: &str = "1E2FC19E5E5E4E18916609B7F8911C12";
+const MERCHANT_ALIAS: &str = "1231181189";
+const PAYER_ALIAS: &str = "46701234567";
+const DATE_CREATED: &str = "2015-02-19T22:01:53+01:00";
+const DATE_PAID: &str = "2015-02-19T22:03:53+01:00";
+const API_URL: &str = "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/";
+
+/// A payment as Swish returns it when it's fetched, or posts it to its callback URL.
+///
+/// The fixture is filled in the way Swish fills in a payment with the given status,
+/// e.g. a paid payment has a payment reference and a failed one has an error code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentFixture {
+    id: String,
+    payee_payment_reference: Option<String>,
+    payment_reference: Option<String>,
+    callback_url: Option<String>,
+    payer_alias: Option<String>,
+    payee_alias: Option<String>,
+    amount: Amount,
+    message: Option<String>,
+    status: Status,
+    date_created: String,
+    date_paid: Option<String>,
+    error_code: Option<ErrorCode>,
+    error_message: Option<String>,
+}
+
+impl PaymentFixture {
+    /// [`PaymentFixture`]: struct.PaymentFixture.html
+    ///
+    /// Creates a new [`PaymentFixture`] of an e-commerce payment of 100 SEK.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status of the payment.
+    pub fn new(status: Status) -> Self {
+        let is_paid = status == Status::Paid;
+        let (error_code, error_message) = match status {
+            Status::Error => (
+                Some(ErrorCode::TM01),
+                Some("Swish timed out before the payment was started".to_owned()),
+            ),
+            _ => (None, None),
+        };
+        PaymentFixture {
+            id: PAYMENT_ID.to_owned(),
+            payee_payment_reference: Some("0123456789".to_owned()),
+            payment_reference: if is_paid {
+                Some(PAYMENT_REFERENCE.to_owned())
+            } else {
+                None
+            },
+            callback_url: Some("https://example.com/api/swishcb/paymentrequests".to_owned()),
+            payer_alias: Some(PAYER_ALIAS.to_owned()),
+            payee_alias: Some(MERCHANT_ALIAS.to_owned()),
+            amount: Amount::from_kronor(100),
+            message: Some("Kingston USB Flash Drive 8 GB".to_owned()),
+            status,
+            date_created: DATE_CREATED.to_owned(),
+            date_paid: if is_paid {
+                Some(DATE_PAID.to_owned())
+            } else {
+                None
+            },
+            error_code,
+            error_message,
+        }
+    }
+
+    /// Sets the id of the payment.
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = id.to_owned();
+        self
+    }
+
+    /// Sets the amount of the payment.
+    pub fn amount(mut self, amount: Amount) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    /// Sets the reference the merchant gave the payment.
+    pub fn payee_payment_reference(mut self, reference: &str) -> Self {
+        self.payee_payment_reference = Some(reference.to_owned());
+        self
+    }
+
+    /// Sets the reference of the payment at the bank of the payer.
+    pub fn payment_reference(mut self, reference: &str) -> Self {
+        self.payment_reference = Some(reference.to_owned());
+        self
+    }
+
+    /// Sets the alias of the payer.
+    pub fn payer_alias(mut self, alias: &str) -> Self {
+        self.payer_alias = Some(alias.to_owned());
+        self
+    }
+
+    /// Makes the payment an m-commerce payment, which has no payer alias.
+    pub fn mcommerce(mut self) -> Self {
+        self.payer_alias = None;
+        self
+    }
+
+    /// Sets the alias of the merchant.
+    pub fn payee_alias(mut self, alias: &str) -> Self {
+        self.payee_alias = Some(alias.to_owned());
+        self
+    }
+
+    /// Sets the message of the payment.
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_owned());
+        self
+    }
+
+    /// Sets the error of the payment, e.g. for a status of `ERROR`.
+    pub fn error(mut self, error_code: ErrorCode, error_message: &str) -> Self {
+        self.error_code = Some(error_code);
+        self.error_message = Some(error_message.to_owned());
+        self
+    }
+
+    /// Gets the payment as it's fetched from Swish.
+    pub fn payment(&self) -> Payment {
+        Payment {
+            id: self.id.clone(),
+            amount: self.amount,
+            payee_payment_reference: self.payee_payment_reference.clone(),
+            payment_reference: self.payment_reference.clone(),
+            callback_url: self.callback_url.clone(),
+            payer_alias: self.payer_alias.clone(),
+            payee_alias: self.payee_alias.clone(),
+            message: self.message.clone(),
+            status: Some(self.status.clone()),
+            date_created: self.date_created.clone(),
+            currency: Currency::SEK,
+            date_paid: self.date_paid.clone(),
+            error_code: self.error_code.clone(),
+            error_message: self.error_message.clone(),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Gets the payment as Swish posts it to its callback URL.
+    pub fn callback(&self) -> PaymentCallback {
+        PaymentCallback {
+            id: self.id.clone(),
+            payee_payment_reference: self.payee_payment_reference.clone(),
+            payment_reference: self.payment_reference.clone(),
+            callback_url: self.callback_url.clone(),
+            payer_alias: self.payer_alias.clone(),
+            payee_alias: self.payee_alias.clone(),
+            amount: self.amount,
+            currency: Currency::SEK,
+            message: self.message.clone(),
+            status: self.status.clone(),
+            date_created: self.date_created.clone(),
+            date_paid: self.date_paid.clone(),
+            error_code: self.error_code.clone(),
+            error_message: self.error_message.clone(),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Gets the body of a response to fetching the payment.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.payment()).expect("a payment is valid JSON")
+    }
+
+    /// Gets the body of the callback of the payment.
+    pub fn callback_body(&self) -> Vec<u8> {
+        serde_json::to_vec(&self.callback()).expect("a payment callback is valid JSON")
+    }
+}
+
+/// A refund as Swish returns it when it's fetched, or posts it to its callback URL.
+///
+/// The fixture is filled in the way Swish fills in a refund with the given status,
+/// e.g. a paid refund has a payment reference and a failed one has an error code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefundFixture {
+    id: String,
+    payer_payment_reference: Option<String>,
+    original_payment_reference: Option<String>,
+    payment_reference: Option<String>,
+    callback_url: Option<String>,
+    payer_alias: Option<String>,
+    payee_alias: Option<String>,
+    amount: Amount,
+    message: Option<String>,
+    status: Status,
+    date_created: String,
+    date_paid: Option<String>,
+    error_code: Option<ErrorCode>,
+    error_message: Option<String>,
+    additional_information: Option<String>,
+}
+
+impl RefundFixture {
+    /// [`RefundFixture`]: struct.RefundFixture.html
+    ///
+    /// Creates a new [`RefundFixture`] of a refund of 100 SEK.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status of the refund.
+    pub fn new(status: Status) -> Self {
+        let is_paid = status == Status::Paid;
+        let (error_code, error_message) = match status {
+            Status::Error => (
+                Some(ErrorCode::RF07),
+                Some("Transaction declined".to_owned()),
+            ),
+            _ => (None, None),
+        };
+        RefundFixture {
+            id: REFUND_ID.to_owned(),
+            payer_payment_reference: Some("0123456789".to_owned()),
+            original_payment_reference: Some(PAYMENT_REFERENCE.to_owned()),
+            payment_reference: if is_paid {
+                Some(REFUND_PAYMENT_REFERENCE.to_owned())
+            } else {
+                None
+            },
+            callback_url: Some("https://example.com/api/swishcb/refunds".to_owned()),
+            payer_alias: Some(MERCHANT_ALIAS.to_owned()),
+            payee_alias: Some(PAYER_ALIAS.to_owned()),
+            amount: Amount::from_kronor(100),
+            message: Some("Refund for Kingston USB Flash Drive 8 GB".to_owned()),
+            status,
+            date_created: DATE_CREATED.to_owned(),
+            date_paid: if is_paid {
+                Some(DATE_PAID.to_owned())
+            } else {
+                None
+            },
+            error_code,
+            error_message,
+            additional_information: None,
+        }
+    }
+
+    /// Sets the id of the refund.
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = id.to_owned();
+        self
+    }
+
+    /// Sets the amount of the refund.
+    pub fn amount(mut self, amount: Amount) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    /// Sets the payment reference of the payment that's refunded.
+    pub fn original_payment_reference(mut self, reference: &str) -> Self {
+        self.original_payment_reference = Some(reference.to_owned());
+        self
+    }
+
+    /// Sets the reference the merchant gave the refund.
+    pub fn payer_payment_reference(mut self, reference: &str) -> Self {
+        self.payer_payment_reference = Some(reference.to_owned());
+        self
+    }
+
+    /// Sets the alias of the merchant.
+    pub fn payer_alias(mut self, alias: &str) -> Self {
+        self.payer_alias = Some(alias.to_owned());
+        self
+    }
+
+    /// Sets the alias of the payer of the original payment.
+    pub fn payee_alias(mut self, alias: &str) -> Self {
+        self.payee_alias = Some(alias.to_owned());
+        self
+    }
+
+    /// Sets the message of the refund.
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_owned());
+        self
+    }
+
+    /// Sets the error of the refund, e.g. for a status of `ERROR`.
+    pub fn error(mut self, error_code: ErrorCode, error_message: &str) -> Self {
+        self.error_code = Some(error_code);
+        self.error_message = Some(error_message.to_owned());
+        self
+    }
+
+    /// Gets the refund as it's fetched from Swish.
+    pub fn refund(&self) -> Refund {
+        Refund {
+            id: self.id.clone(),
+            amount: self.amount,
+            payer_payment_reference: self.payer_payment_reference.clone(),
+            original_payment_reference: self.original_payment_reference.clone(),
+            payment_reference: self.payment_reference.clone(),
+            callback_url: self.callback_url.clone(),
+            payer_alias: self.payer_alias.clone(),
+            payee_alias: self.payee_alias.clone(),
+            message: self.message.clone(),
+            status: Some(self.status.clone()),
+            date_created: self.date_created.clone(),
+            currency: Currency::SEK,
+            date_paid: self.date_paid.clone(),
+            error_code: self.error_code.clone(),
+            error_message: self.error_message.clone(),
+            additional_information: self.additional_information.clone(),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Gets the refund as Swish posts it to its callback URL.
+    pub fn callback(&self) -> RefundCallback {
+        RefundCallback {
+            id: self.id.clone(),
+            payment_reference: self.payment_reference.clone(),
+            payer_payment_reference: self.payer_payment_reference.clone(),
+            original_payment_reference: self.original_payment_reference.clone(),
+            callback_url: self.callback_url.clone(),
+            payer_alias: self.payer_alias.clone(),
+            payee_alias: self.payee_alias.clone(),
+            amount: self.amount,
+            currency: Currency::SEK,
+            message: self.message.clone(),
+            status: self.status.clone(),
+            date_created: self.date_created.clone(),
+            date_paid: self.date_paid.clone(),
+            error_code: self.error_code.clone(),
+            error_message: self.error_message.clone(),
+            additional_information: self.additional_information.clone(),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Gets the body of a response to fetching the refund.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.refund()).expect("a refund is valid JSON")
+    }
+
+    /// Gets the body of the callback of the refund.
+    pub fn callback_body(&self) -> Vec<u8> {
+        serde_json::to_vec(&self.callback()).expect("a refund callback is valid JSON")
+    }
+}
+
+/// A payment as it's returned when it's created at Swish.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedPaymentFixture {
+    id: String,
+    request_token: Option<String>,
+    created_at: SystemTime,
+}
+
+impl CreatedPaymentFixture {
+    /// [`CreatedPaymentFixture`]: struct.CreatedPaymentFixture.html
+    ///
+    /// Creates a new [`CreatedPaymentFixture`] of an e-commerce payment created just now.
+    pub fn new() -> Self {
+        CreatedPaymentFixture {
+            id: PAYMENT_ID.to_owned(),
+            request_token: None,
+            created_at: SystemTime::now(),
+        }
+    }
+
+    /// Sets the id of the payment.
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = id.to_owned();
+        self
+    }
+
+    /// Sets the request token of an m-commerce payment.
+    pub fn request_token(mut self, request_token: &str) -> Self {
+        self.request_token = Some(request_token.to_owned());
+        self
+    }
+
+    /// Sets when Swish responded that the payment was created.
+    pub fn created_at(mut self, created_at: SystemTime) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    /// Makes the payment request one that has expired.
+    pub fn expired(self) -> Self {
+        let created_at = SystemTime::now() - PAYMENT_REQUEST_TTL - Duration::from_secs(1);
+        self.created_at(created_at)
+    }
+
+    /// Gets the payment as it's returned by `SwishClient::create_payment`.
+    pub fn created_payment(&self) -> CreatedPayment {
+        CreatedPayment {
+            id: self.id.clone(),
+            location: format!("{}paymentrequests/{}", API_URL, self.id),
+            request_token: self.request_token.clone(),
+            created_at: self.created_at,
+        }
+    }
+
+    /// Gets the payment as it's returned by `SwishClient::create_mcommerce_payment`.
+    /// It gets a request token unless one has been set.
+    pub fn created_mcommerce_payment(&self) -> CreatedMCommercePayment {
+        let request_token = self
+            .request_token
+            .clone()
+            .unwrap_or_else(|| "f34DS34lfd0d03fdDselkfd3ffk21".to_owned());
+        CreatedMCommercePayment {
+            id: self.id.clone(),
+            location: format!("{}paymentrequests/{}", API_URL, self.id),
+            request_token: PaymentRequestToken::new(request_token),
+            created_at: self.created_at,
+        }
+    }
+}
+
+impl Default for CreatedPaymentFixture {
+    fn default() -> Self {
+        CreatedPaymentFixture::new()
+    }
+}
@@ -0,0 +1,134 @@
+//! # The timing module
+//!
+//! Contains the breakdown of where the time of a request was spent,
+//! which tells whether a slow call is caused by the network,
+//! the TLS layer or the Swish API itself.
+//!
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Where the time of a request to the Swish API was spent.
+///
+/// The connection phases are `None` when the request reused a pooled connection,
+/// or when the connector was supplied through `https_connector`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTimings {
+    /// The time it took to resolve the address of the Swish API.
+    pub dns: Option<Duration>,
+    /// The time it took to establish the TCP connection.
+    pub tcp_connect: Option<Duration>,
+    /// The time it took to complete the TLS handshake.
+    pub tls_handshake: Option<Duration>,
+    /// The time from the request being sent until the
+    /// response headers were received, excluding the connection phases.
+    pub time_to_first_byte: Duration,
+}
+
+impl RequestTimings {
+    /// Splits the time it took to receive the response headers into its phases.
+    pub(crate) fn new(elapsed: Duration, connection: Option<&ConnectTimings>) -> Self {
+        match connection.and_then(ConnectTimings::take) {
+            Some((dns, tcp_connect, tls_handshake)) => RequestTimings {
+                dns: Some(dns),
+                tcp_connect: Some(tcp_connect),
+                tls_handshake: Some(tls_handshake),
+                time_to_first_byte: elapsed
+                    .checked_sub(dns + tcp_connect + tls_handshake)
+                    .unwrap_or_default(),
+            },
+            None => RequestTimings {
+                dns: None,
+                tcp_connect: None,
+                tls_handshake: None,
+                time_to_first_byte: elapsed,
+            },
+        }
+    }
+
+    /// Records the timings on the `swish_request` span.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn record(&self, span: &tracing::Span) {
+        let phases = [
+            ("dns_ms", self.dns),
+            ("tcp_connect_ms", self.tcp_connect),
+            ("tls_handshake_ms", self.tls_handshake),
+            ("time_to_first_byte_ms", Some(self.time_to_first_byte)),
+        ];
+        for &(field, duration) in &phases {
+            if let Some(duration) = duration {
+                span.record(field, duration.as_millis() as u64);
+            }
+        }
+    }
+}
+
+/// The instants at which the phases of establishing a connection ended.
+#[derive(Debug)]
+pub(crate) struct ConnectPhases {
+    started: Instant,
+    resolved: Mutex<Option<Instant>>,
+    connected: Mutex<Option<Instant>>,
+}
+
+impl ConnectPhases {
+    pub(crate) fn start() -> Arc<Self> {
+        Arc::new(ConnectPhases {
+            started: Instant::now(),
+            resolved: Mutex::new(None),
+            connected: Mutex::new(None),
+        })
+    }
+
+    pub(crate) fn resolved(&self) {
+        *self.resolved.lock().unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
+    }
+
+    pub(crate) fn connected(&self) {
+        *self
+            .connected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
+    }
+
+    /// Ends the TLS handshake, which is the last phase.
+    pub(crate) fn finish(&self) -> ConnectTimings {
+        let finished = Instant::now();
+        let connected = self
+            .connected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .unwrap_or(finished);
+        let resolved = self
+            .resolved
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .unwrap_or(self.started);
+
+        ConnectTimings {
+            dns: resolved.duration_since(self.started),
+            tcp_connect: connected.duration_since(resolved),
+            tls_handshake: finished.duration_since(connected),
+            taken: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+/// The timings of a connection, which hyper hands to every response sent over it.
+/// Only the first response gets them, as later ones reused the connection.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectTimings {
+    dns: Duration,
+    tcp_connect: Duration,
+    tls_handshake: Duration,
+    taken: Arc<AtomicBool>,
+}
+
+impl ConnectTimings {
+    fn take(&self) -> Option<(Duration, Duration, Duration)> {
+        if self.taken.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        Some((self.dns, self.tcp_connect, self.tls_handshake))
+    }
+}
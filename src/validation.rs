@@ -0,0 +1,173 @@
+//! # The validation module
+//!
+//! Contains the validations of the values sent to the Swish API, and the
+//! errors returned when a value doesn't meet the constraints of the API.
+//!
+use amount::Amount;
+use std::error;
+use std::fmt;
+
+/// The smallest amount Swish accepts for a payment or a refund.
+pub const AMOUNT_MIN: Amount = Amount::from_kronor(1);
+
+/// The largest amount Swish accepts for a payment or a refund.
+pub const AMOUNT_MAX: Amount = Amount::from_ore(99_999_999_999_999);
+
+/// The maximum number of characters of a message.
+pub const MESSAGE_MAX_LENGTH: usize = 50;
+
+/// The maximum number of characters of a payee payment reference.
+pub const PAYEE_PAYMENT_REFERENCE_MAX_LENGTH: usize = 35;
+
+/// The characters, besides letters and digits, that Swish allows in a message.
+const MESSAGE_SPECIAL_CHARACTERS: &str = " :;.,?!()-\"'";
+
+/// A value that would be rejected by the Swish API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// The value isn't of the format expected by Swish.
+    InvalidFormat {
+        field: &'static str,
+        expected: &'static str,
+    },
+    /// The value is required by Swish but is missing.
+    Missing { field: &'static str },
+    /// The value is empty, which Swish doesn't allow.
+    Empty { field: &'static str },
+    /// The value is longer than Swish allows.
+    TooLong { field: &'static str, max: usize },
+    /// The amount is outside of the range Swish allows.
+    OutOfRange {
+        field: &'static str,
+        min: Amount,
+        max: Amount,
+    },
+    /// The value contains a character that Swish doesn't allow.
+    InvalidCharacter { field: &'static str, character: char },
+}
+
+impl ValidationError {
+    /// Gets the name of the field that's invalid.
+    pub fn field(&self) -> &'static str {
+        match *self {
+            ValidationError::InvalidFormat { field, .. }
+            | ValidationError::Missing { field }
+            | ValidationError::Empty { field }
+            | ValidationError::TooLong { field, .. }
+            | ValidationError::OutOfRange { field, .. }
+            | ValidationError::InvalidCharacter { field, .. } => field,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::InvalidFormat { field, expected } => {
+                write!(f, "{} must be {}", field, expected)
+            }
+            ValidationError::Missing { field } => write!(f, "{} is required", field),
+            ValidationError::Empty { field } => write!(f, "{} can't be empty", field),
+            ValidationError::TooLong { field, max } => {
+                write!(f, "{} must be at most {} characters", field, max)
+            }
+            ValidationError::OutOfRange { field, min, max } => {
+                write!(f, "{} must be between {} and {} SEK", field, min, max)
+            }
+            ValidationError::InvalidCharacter { field, character } => {
+                write!(f, "{} can't contain the character {:?}", field, character)
+            }
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+/// Validates the message of a payment or a refund, which is
+/// at most 50 characters of the letters a-ö, digits and `:;.,?!()-"'`.
+///
+/// # Arguments
+///
+/// * `message` - The message shown to the payer.
+///
+/// # Example
+///
+/// ```
+/// use swish_api::validation::{self, ValidationError};
+///
+/// assert!(validation::validate_message("Kingston USB Flash Drive 8 GB").is_ok());
+/// assert_eq!(
+///     validation::validate_message("100% off"),
+///     Err(ValidationError::InvalidCharacter {
+///         field: "message",
+///         character: '%',
+///     })
+/// );
+/// ```
+pub fn validate_message(message: &str) -> Result<(), ValidationError> {
+    if message.chars().count() > MESSAGE_MAX_LENGTH {
+        return Err(ValidationError::TooLong {
+            field: "message",
+            max: MESSAGE_MAX_LENGTH,
+        });
+    }
+    let invalid = message.chars().find(|&c| {
+        !(c.is_ascii_alphanumeric()
+            || "åäöÅÄÖ".contains(c)
+            || MESSAGE_SPECIAL_CHARACTERS.contains(c))
+    });
+    match invalid {
+        Some(character) => Err(ValidationError::InvalidCharacter {
+            field: "message",
+            character,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Validates the reference of a payment chosen by the merchant,
+/// e.g. an order id, which is 1 to 35 of the letters a-ö and digits.
+///
+/// # Arguments
+///
+/// * `reference` - The payee payment reference.
+pub fn validate_payee_payment_reference(reference: &str) -> Result<(), ValidationError> {
+    let field = "payeePaymentReference";
+    if reference.is_empty() {
+        return Err(ValidationError::Empty { field });
+    }
+    if reference.chars().count() > PAYEE_PAYMENT_REFERENCE_MAX_LENGTH {
+        return Err(ValidationError::TooLong {
+            field,
+            max: PAYEE_PAYMENT_REFERENCE_MAX_LENGTH,
+        });
+    }
+    match reference
+        .chars()
+        .find(|&c| !(c.is_ascii_alphanumeric() || "åäöÅÄÖ".contains(c)))
+    {
+        Some(character) => Err(ValidationError::InvalidCharacter { field, character }),
+        None => Ok(()),
+    }
+}
+
+/// Validates the amount of a payment or a refund, which
+/// is between 1 and 999 999 999 999.99 SEK. As an [`Amount`] is kept
+/// in öre, it never has more than the two decimals Swish allows.
+///
+/// [`Amount`]: ../amount/struct.Amount.html
+///
+/// # Arguments
+///
+/// * `amount` - The amount to pay or refund.
+pub fn validate_amount(amount: Amount) -> Result<(), ValidationError> {
+    if amount < AMOUNT_MIN || amount > AMOUNT_MAX {
+        return Err(ValidationError::OutOfRange {
+            field: "amount",
+            min: AMOUNT_MIN,
+            max: AMOUNT_MAX,
+        });
+    }
+    Ok(())
+}
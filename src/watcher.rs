@@ -0,0 +1,207 @@
+//! # The watcher module
+//!
+//! Contains the registry that hands the events of payments to whoever
+//! is waiting for them, e.g. the request handler of a checkout, no matter
+//! if the events come from callbacks or from polling Swish.
+//!
+use event::PaymentEvent;
+use futures::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use store::{StoreError, SwishStore};
+
+/// The number of payments whose latest event is remembered by
+/// [`PaymentWatcher::new`](struct.PaymentWatcher.html#method.new).
+pub const DEFAULT_WATCHER_CAPACITY: usize = 10_000;
+
+/// Hands the events of payments and refunds to the ones watching them.
+///
+/// The latest event of each payment is remembered, so that a payment
+/// watched after its callback arrived still gets it. Events that are
+/// the same as the latest one are only handed on once.
+///
+/// With a [`SwishStore`](../store/trait.SwishStore.html), the state of each
+/// stored payment is updated before its events are handed on, and events
+/// that would move it to a state it can't move to are dropped.
+///
+/// # Example
+///
+/// ```
+/// extern crate futures;
+/// extern crate swish_api;
+///
+/// use futures::Stream;
+/// use swish_api::event::PaymentEvent;
+/// use swish_api::ids::PaymentId;
+/// use swish_api::watcher::PaymentWatcher;
+///
+/// let watcher = PaymentWatcher::new();
+/// let events = watcher.watch("AB23D7406ECE4542A80152D909EF9F6B");
+///
+/// watcher.notify(PaymentEvent::Cancelled {
+///     payment_id: PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"),
+/// });
+///
+/// // The stream ends after the final event.
+/// let events: Vec<_> = events.wait().collect();
+/// assert_eq!(events.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PaymentWatcher {
+    capacity: usize,
+    watches: Arc<Mutex<Watches>>,
+    store: Option<Arc<dyn SwishStore>>,
+}
+
+#[derive(Debug, Default)]
+struct Watches {
+    senders: HashMap<String, Vec<UnboundedSender<PaymentEvent>>>,
+    latest: HashMap<String, PaymentEvent>,
+    order: VecDeque<String>,
+}
+
+impl Default for PaymentWatcher {
+    fn default() -> Self {
+        PaymentWatcher::with_capacity(DEFAULT_WATCHER_CAPACITY)
+    }
+}
+
+impl PaymentWatcher {
+    /// [`PaymentWatcher`]: struct.PaymentWatcher.html
+    ///
+    /// Creates a new [`PaymentWatcher`] remembering the latest event
+    /// of the last `DEFAULT_WATCHER_CAPACITY` payments.
+    pub fn new() -> Self {
+        PaymentWatcher::default()
+    }
+
+    /// [`PaymentWatcher`]: struct.PaymentWatcher.html
+    ///
+    /// Creates a new [`PaymentWatcher`].
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of payments whose latest event is remembered.
+    pub fn with_capacity(capacity: usize) -> Self {
+        PaymentWatcher {
+            capacity: capacity.max(1),
+            watches: Arc::new(Mutex::new(Watches::default())),
+            store: None,
+        }
+    }
+
+    /// Sets the store whose payments and refunds are updated by the events.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The [`SwishStore`](../store/trait.SwishStore.html) to update,
+    ///   e.g. an `Arc` of a store that's shared with the rest of the application.
+    pub fn store<S: SwishStore + 'static>(mut self, store: S) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Watches a payment or a refund.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the payment or refund.
+    ///
+    /// # Returns
+    /// A stream of the events of the payment, starting with the latest one if
+    /// it's known. It ends after the final event.
+    pub fn watch<I: AsRef<str>>(&self, id: I) -> UnboundedReceiver<PaymentEvent> {
+        let id = id.as_ref();
+        let (sender, receiver) = mpsc::unbounded();
+        if let Ok(mut watches) = self.watches.lock() {
+            if let Some(event) = watches.latest.get(id) {
+                let _ = sender.unbounded_send(event.clone());
+                if event.is_final() {
+                    return receiver;
+                }
+            }
+            watches
+                .senders
+                .entry(id.to_owned())
+                .or_default()
+                .push(sender);
+        }
+        receiver
+    }
+
+    /// Hands an event to the ones watching its payment or refund,
+    /// e.g. the [`event`](../callback/enum.Callback.html#method.event) of a callback
+    /// or the [`PaymentEvent`](../event/enum.PaymentEvent.html) of a fetched payment.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event.
+    pub fn notify(&self, event: PaymentEvent) {
+        if let Some(ref store) = self.store {
+            let rejected = match store.update_status(&event) {
+                Ok(()) => false,
+                Err(error) => {
+                    log_debug!("Couldn't store the event of {}: {}", event.id(), error);
+                    matches!(error, StoreError::Transition(_))
+                }
+            };
+            if rejected {
+                return;
+            }
+        }
+
+        let mut watches = match self.watches.lock() {
+            Ok(watches) => watches,
+            Err(_) => return,
+        };
+        let id = event.id().to_owned();
+        if watches.latest.get(&id) == Some(&event) {
+            return;
+        }
+
+        if watches.latest.insert(id.clone(), event.clone()).is_none() {
+            watches.order.push_back(id.clone());
+            while watches.order.len() > self.capacity {
+                if let Some(oldest) = watches.order.pop_front() {
+                    watches.latest.remove(&oldest);
+                }
+            }
+        }
+
+        if event.is_final() {
+            for sender in watches.senders.remove(&id).unwrap_or_default() {
+                let _ = sender.unbounded_send(event.clone());
+            }
+        } else if let Some(senders) = watches.senders.get_mut(&id) {
+            senders.retain(|sender| sender.unbounded_send(event.clone()).is_ok());
+        }
+    }
+
+    /// Gets the latest event of a payment or refund, if it's remembered.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the payment or refund.
+    pub fn latest<I: AsRef<str>>(&self, id: I) -> Option<PaymentEvent> {
+        self.watches
+            .lock()
+            .ok()
+            .and_then(|watches| watches.latest.get(id.as_ref()).cloned())
+    }
+
+    /// Gets the number of payments and refunds that are watched.
+    pub fn watched(&self) -> usize {
+        self.watches
+            .lock()
+            .map(|watches| watches.senders.len())
+            .unwrap_or_default()
+    }
+}
+
+/// Hands the event of each callback to the ones watching its payment or refund.
+#[cfg(feature = "callback-server")]
+impl ::callback_server::CallbackHandler for PaymentWatcher {
+    fn handle(&self, callback: ::callback::Callback) {
+        self.notify(callback.event())
+    }
+}
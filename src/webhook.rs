@@ -0,0 +1,195 @@
+//! # The webhook module
+//!
+//! Contains a harness that posts callbacks shaped like the ones of Swish to
+//! a callback handler, e.g. one running locally, so that the handler can be tested
+//! end to end before going live. Swish retries callbacks that fail, so the harness
+//! can post the same callback more than once, and after a delay.
+//! It's only built with the `test-utils` feature.
+//!
+//! Swish doesn't sign the body of a callback, but presents a client certificate
+//! when it posts it. Give the harness a TLS connector with a certificate to
+//! post callbacks the same way, e.g. to a handler checking them with the
+//! `callback-cert` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! extern crate swish_api;
+//! extern crate tokio_core;
+//!
+//! use std::time::Duration;
+//! use swish_api::client::Status;
+//! use swish_api::test_utils::PaymentFixture;
+//! use swish_api::webhook::WebhookHarness;
+//! use tokio_core::reactor::Core;
+//!
+//! let mut core = Core::new().unwrap();
+//! let harness = WebhookHarness::new("http://localhost:8080/swish/callbacks", &core.handle())
+//!     .unwrap()
+//!     .delay(Duration::from_millis(500))
+//!     .duplicates(2);
+//!
+//! let callback = PaymentFixture::new(Status::Paid).callback_body();
+//! let statuses = core.run(harness.deliver(callback)).unwrap();
+//! assert!(statuses.iter().all(|status| status.is_success()));
+//! ```
+//!
+use callback::Callback;
+use futures::future::{self, Loop};
+use futures::Future;
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::{Body, Client, Method, Request, StatusCode, Uri};
+use hyper_tls::HttpsConnector;
+use native_tls::TlsConnector;
+use serde_json;
+use std::io;
+use std::time::Duration;
+use tokio_core::reactor::{Handle, Timeout};
+
+/// The future returned by [`WebhookHarness::deliver`](struct.WebhookHarness.html#method.deliver),
+/// with the status the handler responded to every post with.
+pub type DeliveryFuture = Box<dyn Future<Item = Vec<StatusCode>, Error = io::Error>>;
+
+/// Posts callbacks to a callback handler, the way Swish does.
+#[derive(Debug, Clone)]
+pub struct WebhookHarness {
+    uri: Uri,
+    delay: Duration,
+    duplicates: usize,
+    tls_connector: Option<TlsConnector>,
+    handle: Handle,
+}
+
+impl WebhookHarness {
+    /// [`WebhookHarness`]: struct.WebhookHarness.html
+    ///
+    /// Creates a new [`WebhookHarness`] posting every callback once, right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the callback handler, which may be http.
+    /// * `handle` - The tokio-core handle the callbacks are posted on.
+    ///
+    /// # Returns
+    /// The [`WebhookHarness`], or an `io::Error` if the URL is invalid.
+    pub fn new(url: &str, handle: &Handle) -> io::Result<Self> {
+        let uri = url
+            .parse::<Uri>()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        Ok(WebhookHarness {
+            uri,
+            delay: Duration::default(),
+            duplicates: 0,
+            tls_connector: None,
+            handle: handle.clone(),
+        })
+    }
+
+    /// Sets how long to wait before every post of a callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - The time to wait.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Posts every callback again, like Swish does when it retries a callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `duplicates` - The number of times to post a callback after the first.
+    pub fn duplicates(mut self, duplicates: usize) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// Posts the callbacks with the given connector, e.g. one with
+    /// a client certificate, or one trusting the certificate of the handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `tls_connector` - A configured `native_tls::TlsConnector`.
+    pub fn tls_connector(mut self, tls_connector: TlsConnector) -> Self {
+        self.tls_connector = Some(tls_connector);
+        self
+    }
+
+    /// Posts a callback, e.g. a [`PaymentCallback`](../callback/struct.PaymentCallback.html)
+    /// of a [`PaymentFixture`](../test_utils/struct.PaymentFixture.html).
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The [`Callback`](../callback/enum.Callback.html) to post.
+    ///
+    /// # Returns
+    /// A [`DeliveryFuture`](type.DeliveryFuture.html).
+    pub fn deliver_callback(&self, callback: &Callback) -> DeliveryFuture {
+        let body = match *callback {
+            Callback::Payment(ref callback) => serde_json::to_vec(callback),
+            Callback::Refund(ref callback) => serde_json::to_vec(callback),
+        };
+        match body {
+            Ok(body) => self.deliver(body),
+            Err(error) => Box::new(future::err(io::Error::other(error))),
+        }
+    }
+
+    /// Posts the body of a callback, e.g. a malformed one.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The json body of the callback.
+    ///
+    /// # Returns
+    /// A [`DeliveryFuture`](type.DeliveryFuture.html) with the status of every post,
+    /// or an `io::Error` if the handler couldn't be reached.
+    pub fn deliver(&self, body: Vec<u8>) -> DeliveryFuture {
+        let mut http_connector = HttpConnector::new(1);
+        http_connector.enforce_http(false);
+        let tls_connector = match self
+            .tls_connector
+            .clone()
+            .map_or_else(TlsConnector::new, Ok)
+        {
+            Ok(tls_connector) => tls_connector,
+            Err(error) => return Box::new(future::err(io::Error::other(error))),
+        };
+        // Swish connects anew for every callback.
+        let client = Client::builder()
+            .keep_alive(false)
+            .build::<_, Body>(HttpsConnector::from((http_connector, tls_connector)));
+
+        let harness = self.clone();
+        Box::new(future::loop_fn(Vec::new(), move |mut statuses| {
+            let mut request = Request::new(Body::from(body.clone()));
+            *request.method_mut() = Method::POST;
+            *request.uri_mut() = harness.uri.clone();
+            request
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+            let duplicates = harness.duplicates;
+            let client = client.clone();
+            let posted = move |()| client.request(request).map_err(io::Error::other);
+            harness.sleep().and_then(posted).map(move |response| {
+                statuses.push(response.status());
+                if statuses.len() > duplicates {
+                    Loop::Break(statuses)
+                } else {
+                    Loop::Continue(statuses)
+                }
+            })
+        }))
+    }
+
+    /// Waits for the delay of the harness.
+    fn sleep(&self) -> Box<dyn Future<Item = (), Error = io::Error>> {
+        if self.delay == Duration::default() {
+            return Box::new(future::ok(()));
+        }
+        Box::new(future::result(Timeout::new(self.delay, &self.handle)).flatten())
+    }
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "proptest")]
+extern crate proptest;
+extern crate serde_json;
+extern crate swish_api;
+
+use proptest::prelude::*;
+use swish_api::callback::{Callback, PaymentCallback, RefundCallback};
+use swish_api::client::{Payment, Refund, Status};
+use swish_api::params::{OwnedPaymentParams, OwnedRefundParams};
+
+proptest! {
+    #[test]
+    fn test_params_are_accepted_by_swish(
+        payment in any::<OwnedPaymentParams>(),
+        refund in any::<OwnedRefundParams>(),
+    ) {
+        prop_assert_eq!(payment.as_params().validate(), Ok(()));
+        prop_assert_eq!(refund.as_params().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_payments_and_refunds_round_trip(payment in any::<Payment>(), refund in any::<Refund>()) {
+        let json = serde_json::to_string(&payment).unwrap();
+        prop_assert_eq!(serde_json::from_str::<Payment>(&json).unwrap(), payment);
+        let json = serde_json::to_string(&refund).unwrap();
+        prop_assert_eq!(serde_json::from_str::<Refund>(&json).unwrap(), refund);
+    }
+
+    #[test]
+    fn test_payments_are_consistent_with_their_status(payment in any::<Payment>()) {
+        let paid = payment.status == Some(Status::Paid);
+        prop_assert_eq!(payment.date_paid.is_some(), paid);
+        prop_assert_eq!(payment.payment_reference.is_some(), paid);
+        prop_assert_eq!(payment.error_code.is_some(), payment.status == Some(Status::Error));
+    }
+
+    #[test]
+    fn test_callbacks_are_told_apart(payment in any::<PaymentCallback>(), refund in any::<RefundCallback>()) {
+        let json = serde_json::to_vec(&payment).unwrap();
+        prop_assert_eq!(Callback::from_slice(&json).unwrap(), Callback::Payment(payment));
+        let json = serde_json::to_vec(&refund).unwrap();
+        prop_assert_eq!(Callback::from_slice(&json).unwrap(), Callback::Refund(refund));
+    }
+}
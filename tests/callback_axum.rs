@@ -0,0 +1,63 @@
+#![cfg(feature = "axum")]
+extern crate axum;
+extern crate futures_util;
+extern crate swish_api;
+
+use axum::body::Body;
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use futures_util::FutureExt;
+use swish_api::callback::{Callback, CALLBACK_MAX_BODY_LENGTH};
+use swish_api::callback_axum::{
+    CallbackRejection, SwishCallback, SwishPaymentCallback, SwishRefundCallback,
+};
+use swish_api::client::Status;
+use swish_api::golden;
+
+/// Runs an extractor on a request posting the given body.
+fn extract<T: FromRequest<()>>(body: &str) -> Result<T, T::Rejection> {
+    let request = Request::post("/swish/callbacks")
+        .body(Body::from(body.to_owned()))
+        .unwrap();
+    T::from_request(request, &())
+        .now_or_never()
+        .expect("the body is read right away")
+}
+
+#[test]
+fn test_callbacks_are_extracted() {
+    let SwishPaymentCallback(payment) = extract(golden::PAYMENT_CALLBACK).unwrap();
+    let SwishRefundCallback(refund) = extract(golden::REFUND_CALLBACK).unwrap();
+    let SwishCallback(callback) = extract(golden::REFUND_CALLBACK).unwrap();
+
+    assert_eq!(payment.status, Status::Paid);
+    assert_eq!(refund.id, callback.id());
+    assert_eq!(callback, Callback::Refund(refund));
+}
+
+#[test]
+fn test_invalid_callbacks_are_rejected_with_bad_request() {
+    let rejection = extract::<SwishPaymentCallback>(r#"{"id": 1}"#).unwrap_err();
+
+    match rejection {
+        CallbackRejection::Invalid(_) => {}
+        ref other => panic!("Expected Invalid, got {:?}", other),
+    }
+    assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_too_large_callbacks_are_rejected() {
+    let body = format!(
+        r#"{{"padding": "{}"}}"#,
+        " ".repeat(CALLBACK_MAX_BODY_LENGTH)
+    );
+
+    let rejection = extract::<SwishCallback>(&body).unwrap_err();
+
+    assert_eq!(
+        rejection.into_response().status(),
+        StatusCode::PAYLOAD_TOO_LARGE
+    );
+}
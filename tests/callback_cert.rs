@@ -0,0 +1,45 @@
+#![cfg(feature = "callback-cert")]
+extern crate swish_api;
+
+use swish_api::callback_cert::{CallbackCertVerifier, CertificateError};
+
+const SWISH_CA: &[u8] = include_bytes!("certs/swish_root_ca.pem");
+const SWISH_CHAIN: &[u8] = include_bytes!("certs/swish_callback_chain.pem");
+const OTHER: &[u8] = include_bytes!("certs/other_callback.pem");
+
+#[test]
+fn test_certificates_issued_by_swish_are_verified() {
+    let verifier = CallbackCertVerifier::from_pem(SWISH_CA)
+        .unwrap()
+        .common_name("swish.example.com");
+
+    assert!(verifier.verify_pem(SWISH_CHAIN).is_ok());
+}
+
+#[test]
+fn test_other_certificates_are_untrusted() {
+    let verifier = CallbackCertVerifier::from_pem(SWISH_CA).unwrap();
+
+    match verifier.verify_pem(OTHER) {
+        Err(CertificateError::Untrusted(_)) => (),
+        result => panic!("Unexpected result {:?}", result),
+    }
+    match verifier.verify_pem(b"") {
+        Err(CertificateError::Missing) => (),
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_certificates_issued_to_someone_else_are_rejected() {
+    let verifier = CallbackCertVerifier::from_pem(SWISH_CA)
+        .unwrap()
+        .common_name("callbacks.getswish.se");
+
+    match verifier.verify_pem(SWISH_CHAIN) {
+        Err(CertificateError::CommonName { actual, .. }) => {
+            assert_eq!(actual.as_deref(), Some("swish.example.com"))
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
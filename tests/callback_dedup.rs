@@ -0,0 +1,33 @@
+extern crate swish_api;
+
+use swish_api::callback_dedup::{CallbackDedupStore, CallbackKey, InMemoryDedupStore};
+use swish_api::client::Status;
+
+fn key(id: &str, status: Status) -> CallbackKey {
+    CallbackKey {
+        id: id.to_owned(),
+        status,
+    }
+}
+
+#[test]
+fn test_duplicates_are_detected_by_id_and_status() {
+    let store = InMemoryDedupStore::default();
+
+    assert!(store.insert(&key("A", Status::Debited)));
+    assert!(store.insert(&key("A", Status::Paid)));
+    assert!(store.insert(&key("B", Status::Paid)));
+    assert!(!store.insert(&key("A", Status::Paid)));
+}
+
+#[test]
+fn test_the_oldest_callbacks_are_forgotten() {
+    let store = InMemoryDedupStore::new(2);
+
+    assert!(store.insert(&key("A", Status::Paid)));
+    assert!(store.insert(&key("B", Status::Paid)));
+    assert!(store.insert(&key("C", Status::Paid)));
+
+    assert!(!store.insert(&key("C", Status::Paid)));
+    assert!(store.insert(&key("A", Status::Paid)));
+}
@@ -0,0 +1,34 @@
+extern crate swish_api;
+
+use swish_api::callback_ip::{is_swish_source, CallbackSources, IpRange};
+
+#[test]
+fn test_swish_sources_are_recognized() {
+    assert!(is_swish_source("213.132.115.94".parse().unwrap()));
+    assert!(is_swish_source("35.228.51.239".parse().unwrap()));
+    assert!(is_swish_source("::ffff:34.140.166.129".parse().unwrap()));
+    assert!(!is_swish_source("213.132.115.95".parse().unwrap()));
+    assert!(!is_swish_source("127.0.0.1".parse().unwrap()));
+}
+
+#[test]
+fn test_ranges_are_parsed() {
+    let range: IpRange = "2001:db8::/32".parse().unwrap();
+    assert!(range.contains("2001:db8::1".parse().unwrap()));
+    assert!(!range.contains("2001:db9::1".parse().unwrap()));
+    assert_eq!(range.to_string(), "2001:db8::/32");
+
+    assert_eq!("0.0.0.0/0".parse::<IpRange>().unwrap().prefix_len(), 0);
+    assert!("10.0.0.1/8".parse::<IpRange>().is_err());
+    assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+    assert!("10.0.0/8".parse::<IpRange>().is_err());
+}
+
+#[test]
+fn test_sources_can_be_overridden() {
+    let sources: CallbackSources = "192.0.2.1,".parse().unwrap();
+    assert_eq!(sources.ranges().len(), 1);
+    assert!(sources.contains("192.0.2.1".parse().unwrap()));
+    assert!(!sources.contains("213.132.115.94".parse().unwrap()));
+    assert!("192.0.2.1, nope".parse::<CallbackSources>().is_err());
+}
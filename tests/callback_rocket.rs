@@ -0,0 +1,99 @@
+#![cfg(feature = "rocket")]
+extern crate futures_util;
+extern crate rocket;
+extern crate swish_api;
+
+use futures_util::FutureExt;
+use rocket::data::{Data, FromData};
+use rocket::http::{Method, Status};
+use rocket::local::blocking::Client;
+use rocket::outcome::Outcome;
+use rocket::route::{self, BoxFuture, Route};
+use rocket::Request;
+use swish_api::callback::CALLBACK_MAX_BODY_LENGTH;
+use swish_api::callback_rocket::{SwishCallback, SwishPaymentCallback, SwishRefundCallback};
+use swish_api::golden;
+
+trait CallbackId {
+    fn callback_id(self) -> String;
+}
+
+impl CallbackId for SwishCallback {
+    fn callback_id(self) -> String {
+        self.0.id().to_owned()
+    }
+}
+
+impl CallbackId for SwishPaymentCallback {
+    fn callback_id(self) -> String {
+        self.0.id
+    }
+}
+
+impl CallbackId for SwishRefundCallback {
+    fn callback_id(self) -> String {
+        self.0.id
+    }
+}
+
+/// Answers with the id of the callback the guard passes on,
+/// as the `#[post]` attribute isn't available in the 2015 edition.
+fn handler<'r, T>(request: &'r Request, data: Data<'r>) -> BoxFuture<'r>
+where
+    T: for<'x> FromData<'x> + CallbackId + Send + 'static,
+{
+    Box::pin(T::from_data(request, data).map(move |outcome| match outcome {
+        Outcome::Success(callback) => route::Outcome::from(request, callback.callback_id()),
+        Outcome::Error((status, _)) => Outcome::Error(status),
+        Outcome::Forward(forward) => Outcome::Forward(forward),
+    }))
+}
+
+fn client() -> Client {
+    let routes = vec![
+        Route::new(Method::Post, "/", handler::<SwishCallback>),
+        Route::new(Method::Post, "/payments", handler::<SwishPaymentCallback>),
+        Route::new(Method::Post, "/refunds", handler::<SwishRefundCallback>),
+    ];
+    Client::untracked(rocket::build().mount("/", routes)).unwrap()
+}
+
+#[test]
+fn test_callbacks_are_guarded() {
+    let client = client();
+
+    let payment = client.post("/payments").body(golden::PAYMENT_CALLBACK).dispatch();
+    assert_eq!(payment.status(), Status::Ok);
+    let payment_id = payment.into_string().unwrap();
+
+    let refund = client.post("/refunds").body(golden::REFUND_CALLBACK).dispatch();
+    assert_eq!(refund.status(), Status::Ok);
+    let refund_id = refund.into_string().unwrap();
+
+    let callback = client.post("/").body(golden::REFUND_CALLBACK).dispatch();
+    assert_eq!(callback.status(), Status::Ok);
+    assert_eq!(callback.into_string().unwrap(), refund_id);
+    assert_ne!(payment_id, refund_id);
+}
+
+#[test]
+fn test_invalid_callbacks_are_answered_with_bad_request() {
+    let client = client();
+
+    let response = client.post("/payments").body(r#"{"id": 1}"#).dispatch();
+
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn test_too_large_callbacks_are_answered_with_payload_too_large() {
+    let client = client();
+    let body = format!(
+        r#"{{"padding": "{}"}}"#,
+        " ".repeat(CALLBACK_MAX_BODY_LENGTH)
+    );
+
+    let response = client.post("/").body(body).dispatch();
+
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "callback-server")]
+extern crate futures;
+extern crate swish_api;
+extern crate tokio_core;
+
+use futures::Future;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+use swish_api::alias::MerchantAlias;
+use swish_api::callback::Callback;
+use swish_api::callback_ip::CallbackSources;
+use swish_api::callback_server::CallbackServer;
+use swish_api::event::PaymentEvent;
+use tokio_core::reactor::Core;
+
+const PAYMENT: &str = r#"{
+    "id": "AB23D7406ECE4542A80152D909EF9F6B",
+    "payeePaymentReference": "0123456789",
+    "paymentReference": "6D6CD7406ECE4542A80152D909EF9F6B",
+    "payeeAlias": "1231181189",
+    "amount": 100.00,
+    "currency": "SEK",
+    "status": "PAID",
+    "dateCreated": "2015-02-19T22:01:53+01:00",
+    "datePaid": "2015-02-19T22:03:53+01:00"
+}"#;
+
+/// Starts a plain http server on a thread of its own,
+/// which sends the callbacks it receives to the returned receiver.
+fn start_server(
+    merchant: Option<&str>,
+    sources: Option<CallbackSources>,
+) -> (SocketAddr, Receiver<Callback>) {
+    let merchant = merchant.map(|merchant| MerchantAlias::new(merchant).unwrap());
+    let (addr_sender, addr_receiver) = mpsc::channel();
+    let (callback_sender, callback_receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut core = Core::new().unwrap();
+        let mut server =
+            CallbackServer::bind(&"127.0.0.1:0".parse().unwrap(), &core.handle()).unwrap();
+        if let Some(merchant) = merchant {
+            server = server.merchant(merchant);
+        }
+        if let Some(sources) = sources {
+            server = server.sources(sources);
+        }
+        addr_sender.send(server.local_addr().unwrap()).unwrap();
+
+        let server = server.serve(move |callback| {
+            let _ = callback_sender.send(callback);
+        });
+        core.run(server.map_err(|_| ())).unwrap();
+    });
+
+    (addr_receiver.recv().unwrap(), callback_receiver)
+}
+
+/// Sends a request to the server and gets the status code of the response.
+fn send(addr: SocketAddr, method: &str, body: &str) -> u16 {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{} /swish HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        body.len(),
+        body
+    )
+    .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response[9..12].parse().unwrap()
+}
+
+#[test]
+fn test_callbacks_are_handed_to_the_handler() {
+    let (addr, callbacks) = start_server(Some("1231181189"), None);
+
+    assert_eq!(send(addr, "POST", PAYMENT), 200);
+
+    let callback = callbacks.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(callback.id(), "AB23D7406ECE4542A80152D909EF9F6B");
+    match callback.event() {
+        PaymentEvent::Paid { payment_id, .. } => {
+            assert_eq!(payment_id.as_str(), "AB23D7406ECE4542A80152D909EF9F6B")
+        }
+        event => panic!("Unexpected event {:?}", event),
+    }
+}
+
+#[test]
+fn test_duplicate_callbacks_are_only_handed_on_once() {
+    let (addr, callbacks) = start_server(None, None);
+
+    assert_eq!(send(addr, "POST", PAYMENT), 200);
+    assert_eq!(send(addr, "POST", PAYMENT), 200);
+
+    assert!(callbacks.recv_timeout(Duration::from_secs(5)).is_ok());
+    assert!(callbacks.try_recv().is_err());
+}
+
+#[test]
+fn test_invalid_callbacks_are_rejected() {
+    let (addr, callbacks) = start_server(Some("1234679304"), None);
+
+    assert_eq!(send(addr, "GET", ""), 405);
+    assert_eq!(send(addr, "POST", "{\"id\":"), 400);
+    assert_eq!(send(addr, "POST", PAYMENT), 403);
+    assert!(callbacks.try_recv().is_err());
+}
+
+#[test]
+fn test_connections_from_other_sources_are_closed() {
+    let (addr, callbacks) = start_server(None, Some(CallbackSources::swish()));
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert_eq!(response, "");
+    assert!(callbacks.try_recv().is_err());
+
+    let (addr, _) = start_server(None, Some("127.0.0.0/8".parse().unwrap()));
+    assert_eq!(send(addr, "POST", PAYMENT), 200);
+}
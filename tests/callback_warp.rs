@@ -0,0 +1,76 @@
+#![cfg(feature = "warp")]
+extern crate swish_api;
+extern crate warp;
+
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
+use swish_api::callback::{Callback, CALLBACK_MAX_BODY_LENGTH};
+use swish_api::callback_warp;
+use swish_api::client::Status;
+use swish_api::golden;
+use warp::http::StatusCode;
+use warp::test::request;
+use warp::Filter;
+
+/// Gets the output of a future that's ready when it's first polled,
+/// as the filters read a body that's already in memory.
+fn now<F: Future>(future: F) -> F::Output {
+    match Box::pin(future)
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("the body is read right away"),
+    }
+}
+
+#[test]
+fn test_callbacks_are_extracted() {
+    let payment = now(request()
+        .method("POST")
+        .body(golden::PAYMENT_CALLBACK)
+        .filter(&callback_warp::payment_callback()))
+    .unwrap();
+    let refund = now(request()
+        .method("POST")
+        .body(golden::REFUND_CALLBACK)
+        .filter(&callback_warp::refund_callback()))
+    .unwrap();
+    let callback = now(request()
+        .method("POST")
+        .body(golden::REFUND_CALLBACK)
+        .filter(&callback_warp::callback()))
+    .unwrap();
+
+    assert_eq!(payment.status, Status::Paid);
+    assert_eq!(callback, Callback::Refund(refund));
+}
+
+#[test]
+fn test_invalid_callbacks_are_answered_with_bad_request() {
+    let filter = callback_warp::payment_callback()
+        .map(|_| StatusCode::OK)
+        .recover(callback_warp::recover_invalid_callback);
+
+    let response = now(request()
+        .method("POST")
+        .body(r#"{"id": 1}"#)
+        .reply(&filter));
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_too_large_callbacks_are_rejected() {
+    let filter = callback_warp::callback()
+        .map(|_| StatusCode::OK)
+        .recover(callback_warp::recover_invalid_callback);
+    let body = format!(
+        r#"{{"padding": "{}"}}"#,
+        " ".repeat(CALLBACK_MAX_BODY_LENGTH)
+    );
+
+    let response = now(request().method("POST").body(body).reply(&filter));
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
@@ -0,0 +1,132 @@
+#![cfg(all(feature = "chaos", feature = "simulator"))]
+extern crate futures;
+extern crate hyper;
+extern crate native_tls;
+extern crate swish_api;
+extern crate tokio_core;
+
+use futures::Future;
+use hyper::StatusCode;
+use native_tls::TlsConnector;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use swish_api::alias::MerchantAlias;
+use swish_api::amount::Amount;
+use swish_api::callback::CallbackUrl;
+use swish_api::chaos::{ChaosProxy, ChaosSchedule, Fault};
+use swish_api::client::{PaymentParams, SwishClientBuilder};
+use swish_api::error::SwishClientError;
+use swish_api::simulator::SwishSimulator;
+use tokio_core::reactor::Core;
+
+/// Starts a simulator behind a chaos proxy on a thread of their own.
+fn start_proxy(schedule: ChaosSchedule) -> String {
+    let (url_sender, url_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut core = Core::new().unwrap();
+        let addr = "127.0.0.1:0".parse().unwrap();
+        let simulator = SwishSimulator::bind(&addr, &core.handle()).unwrap();
+        let proxy = ChaosProxy::bind(
+            &addr,
+            &core.handle(),
+            &simulator.api_url().unwrap(),
+            schedule,
+        )
+        .unwrap();
+        url_sender.send(proxy.api_url().unwrap()).unwrap();
+        core.handle().spawn(proxy.serve().map_err(|_| ()));
+        core.run(simulator.serve()).unwrap();
+    });
+    url_receiver.recv().unwrap()
+}
+
+#[test]
+fn test_faults_are_injected_by_schedule() {
+    let api_url = start_proxy(
+        ChaosSchedule::new()
+            .then_pass()
+            .then(Fault::Status(StatusCode::SERVICE_UNAVAILABLE))
+            .then(Fault::MalformedBody)
+            .then(Fault::Reset)
+            .then(Fault::Latency(Duration::from_millis(300))),
+    );
+    let mut core = Core::new().unwrap();
+    let client = SwishClientBuilder::new(
+        MerchantAlias::new("1231181189").unwrap(),
+        "",
+        "",
+        core.handle(),
+    )
+    .tls_connector(TlsConnector::new().unwrap())
+    .api_url(&api_url)
+    .default_callback_url(
+        CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").unwrap(),
+    )
+    .build();
+
+    let mut params = PaymentParams::default();
+    params.amount = Amount::from_kronor(100);
+    let created = core.run(client.create_mcommerce_payment(params)).unwrap();
+
+    let unavailable = core.run(client.get_payment(&created)).unwrap_err();
+    assert_eq!(
+        unavailable.http_status(),
+        Some(StatusCode::SERVICE_UNAVAILABLE)
+    );
+    assert!(unavailable.is_retryable());
+
+    match core.run(client.get_payment(&created)) {
+        Err(SwishClientError::InRequest { source, .. }) => match *source {
+            SwishClientError::Json(_) => (),
+            other => panic!("Expected a JSON error, got {:?}", other),
+        },
+        other => panic!("Expected a malformed body, got {:?}", other),
+    }
+
+    let reset = core.run(client.get_payment(&created)).unwrap_err();
+    assert!(reset.is_retryable());
+
+    let started = Instant::now();
+    let payment = core.run(client.get_payment(&created)).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(300));
+    assert_eq!(payment.id, created.id);
+}
+
+#[test]
+fn test_repeating_schedules_start_over() {
+    let api_url = start_proxy(
+        ChaosSchedule::new()
+            .then(Fault::Status(StatusCode::BAD_GATEWAY))
+            .then_pass()
+            .repeat(),
+    );
+    let mut core = Core::new().unwrap();
+    let client = SwishClientBuilder::new(
+        MerchantAlias::new("1231181189").unwrap(),
+        "",
+        "",
+        core.handle(),
+    )
+    .tls_connector(TlsConnector::new().unwrap())
+    .api_url(&api_url)
+    .build();
+
+    let statuses: Vec<_> = (0..4)
+        .map(|_| {
+            core.run(client.get_payment("111"))
+                .unwrap_err()
+                .http_status()
+        })
+        .collect();
+
+    assert_eq!(
+        statuses,
+        vec![
+            Some(StatusCode::BAD_GATEWAY),
+            Some(StatusCode::NOT_FOUND),
+            Some(StatusCode::BAD_GATEWAY),
+            Some(StatusCode::NOT_FOUND),
+        ]
+    );
+}
@@ -0,0 +1,434 @@
+#![cfg(all(feature = "cli", feature = "simulator"))]
+extern crate native_tls;
+extern crate openssl;
+extern crate swish_api;
+extern crate tokio_core;
+
+use native_tls::TlsConnector;
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::hash::MessageDigest;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::x509::{X509Name, X509};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use swish_api::prelude::*;
+use swish_api::simulator::SwishSimulator;
+use swish_api::test_utils::PaymentFixture;
+use tokio_core::reactor::Core;
+
+/// Starts a simulator on a thread of its own, and gets the URL of its API.
+fn start_simulator() -> String {
+    let (url_sender, url_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut core = Core::new().unwrap();
+        let simulator = SwishSimulator::bind(&"127.0.0.1:0".parse().unwrap(), &core.handle())
+            .unwrap()
+            .settle_after(Duration::from_millis(500));
+        url_sender.send(simulator.api_url().unwrap()).unwrap();
+        core.run(simulator.serve()).unwrap();
+    });
+    url_receiver.recv().unwrap()
+}
+
+/// A client of the simulator.
+fn simulator_client(core: &Core, api_url: &str) -> SwishClient {
+    SwishClientBuilder::new(
+        MerchantAlias::new("1231181189").unwrap(),
+        "",
+        "",
+        core.handle(),
+    )
+    .tls_connector(TlsConnector::new().unwrap())
+    .api_url(api_url)
+    .build()
+}
+
+/// Creates a payment in the simulator, and gets its id.
+fn create_payment(api_url: &str, payer: &str, message: &str) -> String {
+    let mut core = Core::new().unwrap();
+    let client = simulator_client(&core, api_url);
+    let params = PaymentParams::builder()
+        .amount(Amount::from_kronor(100))
+        .callback_url(CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").unwrap())
+        .ecommerce(PhoneAlias::new(payer).unwrap())
+        .message(message)
+        .build()
+        .unwrap();
+    core.run(client.create_payment(params))
+        .unwrap()
+        .id
+        .to_string()
+}
+
+/// Cancels a payment in the simulator before it's answered.
+fn cancel_payment(api_url: &str, id: &str) {
+    let mut core = Core::new().unwrap();
+    let client = simulator_client(&core, api_url);
+    core.run(client.cancel_payment(id)).unwrap();
+}
+
+fn swish_cli(api_url: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_swish-cli"))
+        .args(args)
+        .env("SWISH_MERCHANT", "1231181189")
+        .env("SWISH_API_URL", api_url)
+        .env_remove("SWISH_CERT")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_wait_exits_with_the_status_of_the_payment() {
+    let api_url = start_simulator();
+    let paid = create_payment(&api_url, "46701234567", "Kingston USB Flash Drive 8 GB");
+    let declined = create_payment(&api_url, "46707654321", "DECLINED");
+    let failed = create_payment(&api_url, "46701111111", "TM01");
+    let cancelled = create_payment(&api_url, "46702222222", "Kingston USB Flash Drive 8 GB");
+    cancel_payment(&api_url, &cancelled);
+
+    let output = swish_cli(&api_url, &["payment", "wait", &paid, "--interval", "1"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with(&format!("PAID {}", paid)));
+
+    let output = swish_cli(&api_url, &["payment", "wait", &declined, "--interval", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = swish_cli(&api_url, &["payment", "wait", &failed, "--interval", "1"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = swish_cli(&api_url, &["payment", "wait", &cancelled, "--interval", "1"]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn test_wait_times_out() {
+    let api_url = start_simulator();
+    let output = swish_cli(
+        &api_url,
+        &[
+            "payment",
+            "wait",
+            "AB23D7406ECE4542A80152D909EF9F6B",
+            "--interval",
+            "1",
+            "--timeout",
+            "1",
+        ],
+    );
+
+    assert_eq!(output.status.code(), Some(5));
+}
+
+#[test]
+fn test_usage_errors_exit_with_64() {
+    let api_url = start_simulator();
+
+    assert_eq!(
+        swish_cli(&api_url, &["payment", "wait"]).status.code(),
+        Some(64)
+    );
+    assert_eq!(swish_cli(&api_url, &["unknown"]).status.code(), Some(64));
+}
+
+/// Waits for a payment in the simulator to be paid.
+fn wait_until_paid(api_url: &str, id: &str) {
+    let output = swish_cli(api_url, &["payment", "wait", id, "--interval", "1"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+fn refund_create(api_url: &str, id: &str, extra: &[&str], stdin: &str) -> Output {
+    let mut args = vec![
+        "refund",
+        "create",
+        id,
+        "--amount",
+        "40",
+        "--callback-url",
+        "https://example.com/api/swishcb/refunds",
+    ];
+    args.extend_from_slice(extra);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_swish-cli"))
+        .args(&args)
+        .env("SWISH_MERCHANT", "1231181189")
+        .env("SWISH_API_URL", api_url)
+        .env_remove("SWISH_CERT")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_refund_create_asks_before_refunding() {
+    let api_url = start_simulator();
+    let id = create_payment(&api_url, "46701111111", "Concert ticket");
+    wait_until_paid(&api_url, &id);
+
+    let output = refund_create(&api_url, &id, &["--refunded", "25"], "n\n");
+    let shown = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(shown.contains("100.00 SEK"));
+    assert!(shown.contains("46701111111"));
+    assert!(shown.contains("refundable: 75.00 SEK"));
+    assert!(output.stdout.is_empty());
+
+    let output = refund_create(&api_url, &id, &[], "y\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("CREATED "));
+}
+
+#[test]
+fn test_refund_create_with_yes_does_not_ask() {
+    let api_url = start_simulator();
+    let id = create_payment(&api_url, "46702222222", "Concert ticket");
+    wait_until_paid(&api_url, &id);
+
+    let output = refund_create(&api_url, &id, &["--yes"], "");
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = refund_create(&api_url, &id, &["--yes", "--refunded", "80"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_payment_create_renders_the_request_token_as_a_qr_code() {
+    let api_url = start_simulator();
+    let args = [
+        "payment",
+        "create",
+        "--amount",
+        "100",
+        "--callback-url",
+        "https://example.com/api/swishcb/paymentrequests",
+    ];
+
+    let output = swish_cli(&api_url, &args);
+    let printed = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(printed.lines().nth(1).unwrap().starts_with("TOKEN "));
+
+    let output = swish_cli(&api_url, &[&args[..], &["--qr"]].concat());
+    let printed = String::from_utf8_lossy(&output.stdout);
+    let qr_code: Vec<_> = printed.lines().skip(1).collect();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(qr_code.len() > 10);
+    assert!(qr_code
+        .iter()
+        .all(|line| line.chars().all(|module| " ▀▄█".contains(module))));
+
+    let output = swish_cli(
+        &api_url,
+        &[&args[..], &["--qr", "--payer", "46701234567"]].concat(),
+    );
+    assert_eq!(output.status.code(), Some(64));
+}
+
+/// Writes a certificate issued to the Swish number as a PKCS#12 bundle, and as a PEM one
+/// with an unencrypted key, and gets their paths.
+fn write_certificate(name: &str, number: &str, not_after: Asn1Time) -> (String, String) {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut subject = X509Name::builder().unwrap();
+    subject.append_entry_by_text("C", "SE").unwrap();
+    subject.append_entry_by_text("CN", number).unwrap();
+    let subject = subject.build();
+    let mut certificate = X509::builder().unwrap();
+    certificate.set_version(2).unwrap();
+    certificate
+        .set_serial_number(&BigNum::from_u32(0x1234).unwrap().to_asn1_integer().unwrap())
+        .unwrap();
+    certificate.set_subject_name(&subject).unwrap();
+    certificate.set_issuer_name(&subject).unwrap();
+    certificate.set_pubkey(&key).unwrap();
+    certificate
+        .set_not_before(&Asn1Time::from_unix(1_500_000_000).unwrap())
+        .unwrap();
+    certificate.set_not_after(&not_after).unwrap();
+    certificate.sign(&key, MessageDigest::sha256()).unwrap();
+    let certificate = certificate.build();
+
+    let dir = std::env::temp_dir().join(format!("swish-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let p12 = dir.join(format!("{}.p12", name));
+    let pkcs12 = Pkcs12::builder()
+        .name(number)
+        .pkey(&key)
+        .cert(&certificate)
+        .build2("swish")
+        .unwrap();
+    fs::write(&p12, pkcs12.to_der().unwrap()).unwrap();
+    let pem = dir.join(format!("{}.pem", name));
+    let mut bundle = certificate.to_pem().unwrap();
+    bundle.extend(key.private_key_to_pem_pkcs8().unwrap());
+    fs::write(&pem, bundle).unwrap();
+
+    (
+        p12.to_str().unwrap().to_owned(),
+        pem.to_str().unwrap().to_owned(),
+    )
+}
+
+#[test]
+fn test_cert_info_checks_the_certificate_of_the_merchant() {
+    let (p12, pem) =
+        write_certificate("valid", "1231181189", Asn1Time::days_from_now(365).unwrap());
+
+    let output = swish_cli("", &["cert", "info", &p12, "--passphrase", "swish"]);
+    let printed = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(printed.contains("subject:     C=SE, CN=1231181189"));
+    assert!(printed.contains("serial:      1234"));
+    assert!(printed.contains("valid from:  Jul 14 02:40:00 2017 GMT"));
+    assert!(printed.contains("merchant:    matches 1231181189"));
+
+    let output = swish_cli("", &["cert", "info", &pem]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = swish_cli("", &["cert", "info", &p12, "--passphrase", "wrong"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = swish_cli(
+        "",
+        &[
+            "cert",
+            "info",
+            &p12,
+            "--passphrase",
+            "swish",
+            "--merchant",
+            "1239999999",
+        ],
+    );
+    let problems = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(problems.contains("issued to 1231181189, not the merchant 1239999999"));
+}
+
+#[test]
+fn test_cert_info_fails_for_an_expired_certificate() {
+    let (_, pem) = write_certificate(
+        "expired",
+        "1231181189",
+        Asn1Time::from_unix(1_600_000_000).unwrap(),
+    );
+
+    let output = swish_cli("", &["cert", "info", &pem]);
+    let problems = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(problems.contains("the certificate has expired"));
+}
+
+#[test]
+fn test_listen_prints_and_forwards_callbacks() {
+    let forwarded = TcpListener::bind("127.0.0.1:0").unwrap();
+    let forward_url = format!("http://{}/callbacks", forwarded.local_addr().unwrap());
+    let (body_sender, body_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stream = forwarded.incoming().next().unwrap().unwrap();
+        let mut request = vec![0; 8192];
+        let read = stream.read(&mut request).unwrap();
+        let request = String::from_utf8_lossy(&request[..read]).into_owned();
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+        body_sender.send(request).unwrap();
+    });
+
+    let mut listener = Command::new(env!("CARGO_BIN_EXE_swish-cli"))
+        .args(["listen", "--port", "0", "--forward", &forward_url])
+        .env("SWISH_MERCHANT", "1231181189")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut listening = String::new();
+    BufReader::new(listener.stderr.take().unwrap())
+        .read_line(&mut listening)
+        .unwrap();
+    let addr = listening
+        .trim()
+        .rsplit("http://")
+        .next()
+        .unwrap()
+        .trim_end_matches('/');
+
+    let callback = PaymentFixture::new(Status::Paid).callback_body();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "POST /callbacks HTTP/1.1\r\nhost: {}\r\ncontent-type: application/json\r\n\
+         content-length: {}\r\nconnection: close\r\n\r\n",
+        addr,
+        callback.len()
+    )
+    .unwrap();
+    stream.write_all(&callback).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"));
+
+    let mut printed = BufReader::new(listener.stdout.take().unwrap()).lines();
+    let first = printed.next().unwrap().unwrap();
+    let forwarded = body_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    listener.kill().unwrap();
+    let _ = listener.wait();
+
+    assert_eq!(
+        first,
+        format!(
+            "PAYMENT PAID {}",
+            PaymentFixture::new(Status::Paid).payment().id
+        )
+    );
+    assert_eq!(printed.next().unwrap().unwrap(), "{");
+    assert!(forwarded.starts_with("POST /callbacks"));
+    assert!(forwarded.contains("\"status\":\"PAID\""));
+}
+
+#[test]
+fn test_doctor_checks_every_step() {
+    let api_url = start_simulator();
+
+    let output = swish_cli(&api_url, &["doctor", "--payer", "46703333333"]);
+    let printed = String::from_utf8_lossy(&output.stdout);
+    let steps: Vec<_> = printed.lines().collect();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(steps.len(), 5);
+    assert!(steps.iter().all(|step| step.starts_with("ok ")));
+    assert!(steps[2].contains("await payment") && steps[2].contains("PAID"));
+    assert!(steps[4].contains("await refund") && steps[4].contains("PAID"));
+}
+
+#[test]
+fn test_doctor_skips_the_steps_after_one_that_fails() {
+    let api_url = start_simulator();
+
+    let output = swish_cli(&api_url, &["doctor", "--merchant", "123"]);
+    let printed = String::from_utf8_lossy(&output.stdout);
+    let steps: Vec<_> = printed.lines().collect();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(steps[0].starts_with("FAILED  configuration"));
+    assert_eq!(
+        steps[1..],
+        [
+            "skipped create payment",
+            "skipped await payment",
+            "skipped create refund",
+            "skipped await refund"
+        ]
+    );
+}
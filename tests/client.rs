@@ -0,0 +1,766 @@
+extern crate futures;
+extern crate native_tls;
+extern crate swish_api;
+extern crate tokio_core;
+
+use futures::future::Either;
+use futures::{stream, Future};
+use native_tls::TlsConnector;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use swish_api::active::ActivePaymentAction;
+use swish_api::alias::{MerchantAlias, PhoneAlias};
+use swish_api::amount::Amount;
+use swish_api::callback::CallbackUrl;
+use swish_api::client::{
+    Environment, PaymentParams, RefundParams, RequestOptions, SwishClient, SwishClientBuilder,
+    PAYMENT_REQUEST_TTL,
+};
+use swish_api::clock::{Clock, ManualClock};
+use swish_api::error::{ConfigError, Language, SwishClientError};
+use swish_api::event::PaymentEvent;
+use swish_api::golden;
+use swish_api::ids::PaymentId;
+use swish_api::metrics::{Endpoint, MetricsSink, Outcome};
+use swish_api::polling::{PaymentPoller, PollPolicy};
+use swish_api::reporter::{ErrorReport, ErrorReporter};
+use swish_api::retry::RetryPolicy;
+use swish_api::state::PaymentState;
+use swish_api::store::{InMemoryStore, StoreError, StoredPayment, SwishStore};
+use swish_api::validation::ValidationError;
+use swish_api::watcher::PaymentWatcher;
+use tokio_core::reactor::{Core, Timeout};
+
+#[derive(Debug, Default, Clone)]
+struct RecordingSink {
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl MetricsSink for RecordingSink {
+    fn on_request_start(&self, endpoint: Endpoint) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("start {}", endpoint.operation));
+    }
+
+    fn on_request_end(&self, endpoint: Endpoint, _duration: Duration, outcome: &Outcome) {
+        let outcome = match *outcome {
+            Outcome::Success => "success",
+            Outcome::Rejected { .. } => "rejected",
+            Outcome::Failed => "failed",
+        };
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("end {} {}", endpoint.operation, outcome));
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct RecordingReporter {
+    reports: Arc<Mutex<Vec<String>>>,
+}
+
+impl ErrorReporter for RecordingReporter {
+    fn report(&self, report: &ErrorReport) {
+        let params = report.params.map(|params| params.to_string());
+        self.reports.lock().unwrap().push(format!(
+            "{} {}",
+            report.endpoint.operation,
+            params.unwrap_or_default()
+        ));
+    }
+}
+
+/// A client whose API host resolves to a local port where nothing listens.
+fn get_unreachable_client(core: &Core) -> SwishClientBuilder {
+    let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+    SwishClientBuilder::new(
+        merchant_alias,
+        "./tests/test_cert.p12",
+        "swish",
+        core.handle(),
+    )
+    .tls_connector(TlsConnector::new().unwrap())
+    .resolve(
+        "mss.cpc.getswish.net",
+        vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))],
+    )
+}
+
+#[test]
+fn test_metrics_sink_reports_failed_request() {
+    let mut core = Core::new().unwrap();
+    let sink = RecordingSink::default();
+    let client: SwishClient = get_unreachable_client(&core)
+        .metrics_sink(sink.clone())
+        .build();
+
+    let payment = core.run(client.get_payment("111"));
+
+    assert!(payment.is_err());
+    assert_eq!(
+        *sink.events.lock().unwrap(),
+        vec!["start get_payment", "end get_payment failed"]
+    );
+}
+
+#[test]
+fn test_invalid_correlation_id_is_rejected() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let options = RequestOptions::new().correlation_id("not\na header");
+
+    let payment = core.run(client.get_payment_with_options("111", &options));
+
+    match payment {
+        Err(SwishClientError::Parse { message, .. }) => assert!(message.contains("correlation id")),
+        other => panic!("Expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_debug_capture_records_failed_requests() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).debug_capture(1).build();
+    let options = RequestOptions::new().correlation_id("abc123");
+
+    let _ = core.run(client.get_payment("111"));
+    let _ = core.run(client.get_payment_with_options("222", &options));
+    let exchanges = client.captured_exchanges();
+
+    assert_eq!(exchanges.len(), 1);
+    assert_eq!(exchanges[0].correlation_id, "abc123");
+    assert_eq!(
+        exchanges[0].uri.path(),
+        "/swish-cpcapi/api/v1/paymentrequests/222"
+    );
+    assert!(exchanges[0].request_body.is_empty());
+    assert!(exchanges[0].response.is_none());
+    assert!(exchanges[0].timings.is_none());
+}
+
+#[test]
+fn test_error_reporter_receives_failed_request() {
+    let mut core = Core::new().unwrap();
+    let reporter = RecordingReporter::default();
+    let client = get_unreachable_client(&core)
+        .error_reporter(reporter.clone())
+        .build();
+
+    let _ = core.run(client.get_payment("111"));
+
+    assert_eq!(
+        *reporter.reports.lock().unwrap(),
+        vec![r#"get_payment {"id":"111"}"#]
+    );
+}
+
+#[test]
+fn test_stats_count_in_flight_calls() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let get_payment = Endpoint {
+        operation: "get_payment",
+        path: "paymentrequests",
+    };
+
+    let payment = client.get_payment("111");
+    let in_flight = client.stats();
+    let _ = core.run(payment);
+    let finished = client.stats();
+
+    assert_eq!(in_flight.in_flight.get(&get_payment), Some(&1));
+    assert_eq!(in_flight.total_in_flight(), 1);
+    assert_eq!(finished.total_in_flight(), 0);
+    assert_eq!(finished.open_connections, 0);
+    assert_eq!(finished.queued_retries, 0);
+}
+
+#[test]
+fn test_invalid_message_is_rejected_before_sending() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).debug_capture(1).build();
+    let mut payment_params = PaymentParams::default();
+    payment_params.callback_url =
+        CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").ok();
+    payment_params.message = Some("Order #1234");
+
+    let payment = core.run(client.create_payment(payment_params));
+
+    match payment {
+        Err(SwishClientError::Validation(errors)) => assert_eq!(
+            errors,
+            vec![
+                ValidationError::InvalidCharacter {
+                    field: "message",
+                    character: '#',
+                },
+                ValidationError::OutOfRange {
+                    field: "amount",
+                    min: Amount::from_kronor(1),
+                    max: Amount::from_ore(99_999_999_999_999),
+                },
+            ]
+        ),
+        other => panic!("Expected a validation error, got {:?}", other),
+    }
+    assert!(client.captured_exchanges().is_empty());
+}
+
+fn get_payment_params<'a>() -> PaymentParams<'a> {
+    let mut payment_params = PaymentParams::default();
+    payment_params.amount = Amount::from_kronor(100);
+    payment_params.callback_url =
+        CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").ok();
+    payment_params.payer_alias = PhoneAlias::new("46701234567").ok();
+    payment_params
+}
+
+#[test]
+fn test_payment_flow_decides_the_payer_alias() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).debug_capture(2).build();
+    let payer_alias = PhoneAlias::new("070-123 45 68").unwrap();
+
+    let _ = core.run(client.create_ecommerce_payment(payer_alias, get_payment_params()));
+    let _ = core.run(client.create_mcommerce_payment(get_payment_params()));
+    let exchanges = client.captured_exchanges();
+
+    assert_eq!(exchanges.len(), 2);
+    assert!(exchanges[0]
+        .request_body
+        .contains(r#""payerAlias":"46701234568""#));
+    assert!(!exchanges[1].request_body.contains("payerAlias"));
+}
+
+#[test]
+fn test_try_new_fails_without_a_certificate() {
+    let core = Core::new().unwrap();
+    let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+
+    let client = SwishClient::try_new(
+        merchant_alias,
+        "./tests/missing_cert.p12",
+        "swish",
+        core.handle(),
+    );
+
+    match client {
+        Err(ConfigError::Secret(_)) => (),
+        other => panic!("Expected a secret error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_builder_fails_without_a_certificate() {
+    let core = Core::new().unwrap();
+    let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+
+    let client = SwishClient::builder(merchant_alias, core.handle())
+        .environment(Environment::Production)
+        .try_build();
+
+    match client {
+        Err(ConfigError::Secret(ref err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+        other => panic!("Expected a secret error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_environments_have_their_api_url() {
+    assert_eq!(
+        Environment::Production.api_url(),
+        "https://cpc.getswish.net/swish-cpcapi/api/v1/"
+    );
+    assert_eq!(
+        Environment::default().api_url(),
+        "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/"
+    );
+}
+
+#[test]
+fn test_try_build_fails_with_a_wrong_passphrase() {
+    let core = Core::new().unwrap();
+    let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+
+    let client = SwishClientBuilder::new(
+        merchant_alias,
+        "./tests/test_cert.p12",
+        "not the passphrase",
+        core.handle(),
+    )
+    .try_build();
+
+    match client {
+        Err(ConfigError::Identity(_)) => (),
+        other => panic!("Expected an identity error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_try_build_uses_a_supplied_tls_connector() {
+    let core = Core::new().unwrap();
+
+    assert!(get_unreachable_client(&core).try_build().is_ok());
+}
+
+#[test]
+fn test_client_defaults_fill_in_the_params() {
+    let mut core = Core::new().unwrap();
+    let default_callback_url = CallbackUrl::new("https://example.com/api/swishcb/default").unwrap();
+    let client = get_unreachable_client(&core)
+        .default_callback_url(default_callback_url)
+        .payee_payment_reference_prefix("shop1")
+        .debug_capture(2)
+        .build();
+    let mut without_callback_url = get_payment_params();
+    without_callback_url.callback_url = None;
+    without_callback_url.payee_payment_reference = Some("0123456789");
+
+    let _ = core.run(client.create_payment(without_callback_url));
+    let _ = core.run(client.create_payment(get_payment_params()));
+    let exchanges = client.captured_exchanges();
+
+    assert_eq!(exchanges.len(), 2);
+    assert!(exchanges[0]
+        .request_body
+        .contains(r#""callbackUrl":"https://example.com/api/swishcb/default""#));
+    assert!(exchanges[0]
+        .request_body
+        .contains(r#""payeePaymentReference":"shop10123456789""#));
+    assert!(exchanges[1]
+        .request_body
+        .contains(r#""callbackUrl":"https://example.com/api/swishcb/paymentrequests""#));
+}
+
+#[test]
+fn test_params_without_a_callback_url_are_rejected_without_a_client_default() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).debug_capture(1).build();
+    let payment_params = PaymentParams::builder()
+        .amount(Amount::from_kronor(100))
+        .mcommerce()
+        .build()
+        .unwrap();
+
+    let payment = core.run(client.create_payment(payment_params));
+
+    match payment {
+        Err(SwishClientError::Validation(errors)) => assert_eq!(
+            errors,
+            vec![ValidationError::Missing {
+                field: "callbackUrl",
+            }]
+        ),
+        other => panic!("Expected a validation error, got {:?}", other),
+    }
+    assert!(client.captured_exchanges().is_empty());
+}
+
+#[test]
+fn test_failed_requests_have_their_context() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let options = RequestOptions::new().correlation_id("abc123");
+
+    let error = core
+        .run(client.get_payment_with_options("111", &options))
+        .unwrap_err();
+    let context = error.context().unwrap();
+
+    assert!(error.is_retryable());
+    assert_eq!(error.correlation_id(), Some("abc123"));
+    assert_eq!(context.method, "GET");
+    assert_eq!(context.path, "/swish-cpcapi/api/v1/paymentrequests/111");
+    assert_eq!(context.merchant, "1231181189");
+    assert!(error.to_string().ends_with(
+        " in GET /swish-cpcapi/api/v1/paymentrequests/111 for merchant 1231181189 (correlation id abc123)"
+    ));
+}
+
+#[test]
+fn test_the_language_of_a_call_overrides_the_client() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core)
+        .language(Language::Swedish)
+        .build();
+    let options = RequestOptions::new().language(Language::English);
+
+    let swedish_error = core.run(client.get_payment("111")).unwrap_err();
+    let english_error = core
+        .run(client.get_payment_with_options("111", &options))
+        .unwrap_err();
+
+    assert_eq!(swedish_error.context().unwrap().language, Language::Swedish);
+    assert_eq!(english_error.context().unwrap().language, Language::English);
+}
+
+#[test]
+fn test_an_invalid_id_is_an_error() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).debug_capture(1).build();
+
+    let payment = core.run(client.get_payment("not an id"));
+
+    match payment {
+        Err(SwishClientError::Uri(_)) => {}
+        other => panic!("Expected an invalid URL error, got {:?}", other),
+    }
+    assert!(client.captured_exchanges().is_empty());
+}
+
+#[test]
+fn test_calls_fail_without_a_certificate() {
+    let mut core = Core::new().unwrap();
+    let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+    let client = SwishClient::new(
+        merchant_alias,
+        "./tests/missing_cert.p12",
+        "swish",
+        core.handle(),
+    );
+
+    let payment = core.run(client.get_payment("111"));
+
+    match payment {
+        Err(SwishClientError::Configuration(ConfigError::Secret(_))) => (),
+        other => panic!("Expected a configuration error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_awaited_payments_finish_on_their_callback() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let policy = PollPolicy::new().interval(Duration::from_millis(10));
+    let callbacks = stream::iter_ok::<_, ()>(vec![
+        PaymentEvent::Cancelled {
+            payment_id: PaymentId::from("222"),
+        },
+        PaymentEvent::Created {
+            payment_id: PaymentId::from("111"),
+        },
+        PaymentEvent::Declined {
+            payment_id: PaymentId::from("111"),
+        },
+    ]);
+
+    let event = core.run(client.await_payment(PaymentId::from("111"), callbacks, policy));
+
+    assert_eq!(
+        event.unwrap(),
+        PaymentEvent::Declined {
+            payment_id: PaymentId::from("111")
+        }
+    );
+}
+
+#[test]
+fn test_awaited_payments_expire_with_their_payment_request() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let policy = PollPolicy::new().interval(Duration::from_millis(10));
+
+    let event = core.run(client.await_payment_until(
+        PaymentId::from("111"),
+        SystemTime::now() + Duration::from_millis(100),
+        stream::empty::<PaymentEvent, ()>(),
+        policy,
+    ));
+
+    assert_eq!(
+        event.unwrap(),
+        PaymentEvent::Expired {
+            payment_id: PaymentId::from("111")
+        }
+    );
+}
+
+/// Runs the poller for the given time.
+fn run_poller(core: &mut Core, client: &SwishClient, poller: &PaymentPoller, duration: Duration) {
+    let timeout = Timeout::new(duration, &core.handle())
+        .unwrap()
+        .map_err(SwishClientError::from);
+    core.run(poller.run(client).select(timeout).map(|_| ()))
+        .map_err(|(error, _)| error)
+        .unwrap();
+}
+
+#[test]
+fn test_poller_stops_polling_finished_payments() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let watcher = PaymentWatcher::new();
+    let poller = PaymentPoller::new(watcher.clone())
+        .policy(PollPolicy::new().interval(Duration::from_millis(10)))
+        .rate_limit(100);
+
+    poller.track(PaymentId::from("111"));
+    poller.track(PaymentId::from("222"));
+    poller.track(PaymentId::from("222"));
+    assert_eq!(poller.pending(), 2);
+
+    watcher.notify(PaymentEvent::Cancelled {
+        payment_id: PaymentId::from("222"),
+    });
+    run_poller(&mut core, &client, &poller, Duration::from_millis(100));
+
+    assert_eq!(poller.pending(), 1);
+    poller.untrack(&PaymentId::from("111"));
+    assert_eq!(poller.pending(), 0);
+}
+
+#[test]
+fn test_poller_gives_up_expired_payment_requests() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let watcher = PaymentWatcher::new();
+    let poller = PaymentPoller::new(watcher.clone())
+        .policy(PollPolicy::new().interval(Duration::from_millis(10)))
+        .rate_limit(100);
+    let expiring = SystemTime::now() - PAYMENT_REQUEST_TTL;
+
+    poller.track_since(PaymentId::from("111"), expiring + Duration::from_millis(50));
+    poller.track_since(PaymentId::from("222"), expiring);
+    poller.track(PaymentId::from("333"));
+    run_poller(&mut core, &client, &poller, Duration::from_millis(200));
+
+    assert_eq!(poller.pending(), 1);
+    for id in &["111", "222"] {
+        assert_eq!(
+            watcher.latest(id),
+            Some(PaymentEvent::Expired {
+                payment_id: PaymentId::from(*id)
+            })
+        );
+    }
+    assert_eq!(watcher.latest("333"), None);
+}
+
+#[test]
+fn test_refunds_are_limited_to_what_is_left() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let store = InMemoryStore::new();
+    store
+        .save_payment(StoredPayment {
+            id: PaymentId::from("111"),
+            payee_payment_reference: None,
+            payment_reference: Some("6D6CD7406ECE4542A80152D909EF9F6B".to_owned()),
+            payer_alias: None,
+            amount: Amount::from_kronor(100),
+            state: PaymentState::Paid,
+            created_at: SystemTime::now(),
+        })
+        .unwrap();
+    let params = |reference, kronor| {
+        RefundParams::builder()
+            .original_payment_reference(reference)
+            .amount(Amount::from_kronor(kronor))
+            .callback_url(CallbackUrl::new("https://example.com/api/swishcb/refunds").unwrap())
+            .build()
+            .unwrap()
+    };
+
+    let result = core.run(client.create_refund_tracked(
+        params("6D6CD7406ECE4542A80152D909EF9F6B", 101),
+        &store,
+    ));
+    match result {
+        Err(SwishClientError::Store(StoreError::OverRefund { remaining, .. })) => {
+            assert_eq!(remaining, Amount::from_kronor(100))
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+
+    let result = core.run(client.create_refund_tracked(params("unknown", 1), &store));
+    match result {
+        Err(SwishClientError::Store(StoreError::NotFound(reference))) => {
+            assert_eq!(reference, "unknown")
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_payers_without_active_payments_need_no_resolving() {
+    let mut core = Core::new().unwrap();
+    let client = get_unreachable_client(&core).build();
+    let store = InMemoryStore::new();
+    let payer = PhoneAlias::new("46701234567").unwrap();
+
+    let event = core.run(client.resolve_active_payment(
+        &payer,
+        &store,
+        ActivePaymentAction::Cancel,
+    ));
+    assert_eq!(event.unwrap(), None);
+}
+
+/// Serves the responses in order on a local port, one for every connection,
+/// and gets the URL of the API and the number of requests received.
+fn serve_responses(responses: Vec<String>) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}/", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let received = requests.clone();
+    thread::spawn(move || {
+        for (stream, response) in listener.incoming().zip(responses) {
+            let mut stream = stream.unwrap();
+            let mut request = vec![0; 8192];
+            let _ = stream.read(&mut request).unwrap();
+            received.fetch_add(1, Ordering::SeqCst);
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (api_url, requests)
+}
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[test]
+fn test_fetching_is_retried_by_the_retry_policy() {
+    let mut core = Core::new().unwrap();
+    let unavailable = response("503 Service Unavailable", "");
+    let (api_url, requests) = serve_responses(vec![
+        unavailable.clone(),
+        unavailable,
+        response("200 OK", golden::PAYMENT),
+    ]);
+    let client = SwishClient::builder(MerchantAlias::new("1231181189").unwrap(), core.handle())
+        .tls_connector(TlsConnector::new().unwrap())
+        .api_url(&api_url)
+        .retry_policy(
+            RetryPolicy::new()
+                .max_retries(2)
+                .backoff(Duration::from_millis(10)),
+        )
+        .build();
+
+    let payment = core.run(client.get_payment("AB23D7406ECE4542A80152D909EF9F6B"));
+
+    assert!(payment.is_ok());
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_fetching_fails_once_the_retries_are_used_up() {
+    let mut core = Core::new().unwrap();
+    let unavailable = response("503 Service Unavailable", "");
+    let (api_url, requests) =
+        serve_responses(vec![unavailable.clone(), unavailable.clone(), unavailable]);
+    let client = SwishClient::builder(MerchantAlias::new("1231181189").unwrap(), core.handle())
+        .tls_connector(TlsConnector::new().unwrap())
+        .api_url(&api_url)
+        .retry_policy(
+            RetryPolicy::new()
+                .max_retries(1)
+                .backoff(Duration::from_millis(10)),
+        )
+        .build();
+
+    let payment = core.run(client.get_payment("AB23D7406ECE4542A80152D909EF9F6B"));
+
+    assert!(payment.unwrap_err().is_retryable());
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_requests_time_out() {
+    let mut core = Core::new().unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}/", listener.local_addr().unwrap());
+    let client = SwishClient::builder(MerchantAlias::new("1231181189").unwrap(), core.handle())
+        .tls_connector(TlsConnector::new().unwrap())
+        .api_url(&api_url)
+        .clock(ManualClock::new())
+        .timeout(Duration::from_millis(100))
+        .build();
+
+    let payment = core.run(client.get_payment("AB23D7406ECE4542A80152D909EF9F6B"));
+
+    match payment {
+        Err(SwishClientError::InRequest { ref source, .. }) => match **source {
+            SwishClientError::Io(ref err) => assert_eq!(err.kind(), io::ErrorKind::TimedOut),
+            ref other => panic!("Expected a timeout, got {:?}", other),
+        },
+        other => panic!("Expected a timeout, got {:?}", other),
+    }
+    drop(listener);
+}
+
+#[test]
+fn test_tracked_refunds_are_created_at_the_time_of_the_clock() {
+    let mut core = Core::new().unwrap();
+    let created = "HTTP/1.1 201 Created\r\nlocation: https://mss.cpc.getswish.net/swish-cpcapi/api/v1/refunds/ABC2D7406ECE4542A80152D909EF9F6B\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+    let (api_url, _) = serve_responses(vec![created.to_owned()]);
+    let clock = ManualClock::starting_at(SystemTime::now() - Duration::from_secs(3600));
+    let client = SwishClient::builder(MerchantAlias::new("1231181189").unwrap(), core.handle())
+        .tls_connector(TlsConnector::new().unwrap())
+        .api_url(&api_url)
+        .clock(clock.clone())
+        .build();
+    let store = InMemoryStore::new();
+    store
+        .save_payment(StoredPayment {
+            id: PaymentId::from("111"),
+            payee_payment_reference: None,
+            payment_reference: Some("6D6CD7406ECE4542A80152D909EF9F6B".to_owned()),
+            payer_alias: None,
+            amount: Amount::from_kronor(100),
+            state: PaymentState::Paid,
+            created_at: clock.now(),
+        })
+        .unwrap();
+    let params = RefundParams::builder()
+        .original_payment_reference("6D6CD7406ECE4542A80152D909EF9F6B")
+        .amount(Amount::from_kronor(10))
+        .callback_url(CallbackUrl::new("https://example.com/api/swishcb/refunds").unwrap())
+        .build()
+        .unwrap();
+
+    core.run(client.create_refund_tracked(params, &store)).unwrap();
+
+    let refunds = store.refunds_of("6D6CD7406ECE4542A80152D909EF9F6B").unwrap();
+    assert_eq!(refunds.len(), 1);
+    assert_eq!(refunds[0].created_at, clock.now());
+}
+
+#[test]
+fn test_stats_count_the_retries_waiting_out_their_backoff() {
+    let mut core = Core::new().unwrap();
+    let (api_url, requests) = serve_responses(vec![response("503 Service Unavailable", "")]);
+    let client = SwishClient::builder(MerchantAlias::new("1231181189").unwrap(), core.handle())
+        .tls_connector(TlsConnector::new().unwrap())
+        .api_url(&api_url)
+        .retry_policy(
+            RetryPolicy::new()
+                .max_retries(1)
+                .backoff(Duration::from_secs(60)),
+        )
+        .build();
+    let waited = Timeout::new(Duration::from_millis(500), &core.handle()).unwrap();
+
+    let payment = match core.run(client.get_payment("111").select2(waited)) {
+        Ok(Either::B((_, payment))) => payment,
+        Ok(Either::A(_)) | Err(_) => panic!("Expected the retry to wait out its backoff"),
+    };
+
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    assert_eq!(client.stats().queued_retries, 1);
+    drop(payment);
+    assert_eq!(client.stats().queued_retries, 0);
+}
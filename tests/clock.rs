@@ -0,0 +1,129 @@
+extern crate futures;
+extern crate swish_api;
+extern crate tokio_core;
+
+use futures::{stream, Future, Stream};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use swish_api::client::{CreatedPayment, SwishClientBuilder, PAYMENT_REQUEST_TTL};
+use swish_api::clock::{Clock, ManualClock};
+use swish_api::error::SwishClientError;
+use swish_api::event::PaymentEvent;
+use swish_api::ids::PaymentId;
+use swish_api::polling::{PaymentPoller, PollPolicy};
+use swish_api::prelude::MerchantAlias;
+use swish_api::watcher::PaymentWatcher;
+use tokio_core::reactor::Core;
+
+/// Advances the clock a second at a time, until the returned flag is set.
+fn keep_advancing(clock: &ManualClock) -> Arc<AtomicBool> {
+    let done = Arc::new(AtomicBool::new(false));
+    let (clock, stop) = (clock.clone(), done.clone());
+    thread::spawn(move || {
+        while !stop.load(Ordering::SeqCst) {
+            clock.advance(Duration::from_secs(1));
+            thread::sleep(Duration::from_millis(1));
+        }
+    });
+    done
+}
+
+#[test]
+fn test_manual_clocks_only_move_when_advanced() {
+    let started = SystemTime::now() - Duration::from_secs(3600);
+    let clock = ManualClock::starting_at(started);
+    let instant = clock.instant();
+
+    assert_eq!(clock.now(), started);
+    clock.advance(Duration::from_secs(90));
+    assert_eq!(clock.now(), started + Duration::from_secs(90));
+    assert_eq!(clock.instant(), instant + Duration::from_secs(90));
+}
+
+#[test]
+fn test_payment_expiry_follows_the_clock() {
+    let clock = ManualClock::new();
+    let created = CreatedPayment {
+        id: "AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        location: String::new(),
+        request_token: None,
+        created_at: clock.now(),
+    };
+
+    assert!(!created.is_expired_at(clock.now()));
+    clock.advance(Duration::from_secs(3 * 60));
+    assert!(created.is_expired_at(clock.now()));
+}
+
+#[test]
+fn test_awaited_payments_expire_on_the_clock() {
+    let mut core = Core::new().unwrap();
+    let clock = ManualClock::new();
+    let client = SwishClientBuilder::new(
+        MerchantAlias::new("1231181189").unwrap(),
+        "./tests/test_cert.p12",
+        "swish",
+        core.handle(),
+    )
+    .clock(clock.clone())
+    .build();
+    let policy = PollPolicy::new().interval(Duration::from_secs(600));
+
+    let started = Instant::now();
+    let done = keep_advancing(&clock);
+    let result = core.run(client.await_payment(
+        PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"),
+        stream::empty::<_, ()>(),
+        policy,
+    ));
+    done.store(true, Ordering::SeqCst);
+
+    match result {
+        Ok(PaymentEvent::Expired { .. }) => (),
+        other => panic!("Expected the payment to expire, got {:?}", other),
+    }
+    assert!(clock.elapsed() >= PAYMENT_REQUEST_TTL);
+    assert!(started.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn test_pollers_give_up_payments_on_the_clock() {
+    let mut core = Core::new().unwrap();
+    let clock = ManualClock::new();
+    let client = SwishClientBuilder::new(
+        MerchantAlias::new("1231181189").unwrap(),
+        "./tests/test_cert.p12",
+        "swish",
+        core.handle(),
+    )
+    .build();
+    let watcher = PaymentWatcher::new();
+    let poller = PaymentPoller::new(watcher.clone())
+        .policy(PollPolicy::new().interval(Duration::from_secs(600)))
+        .clock(clock.clone());
+
+    poller.track(PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"));
+    let events = watcher.watch("AB23D7406ECE4542A80152D909EF9F6B");
+    let done = keep_advancing(&clock);
+    let event = core
+        .run(
+            events
+                .into_future()
+                .map(|(event, _)| event)
+                .map_err(|_| SwishClientError::from(io::Error::other("the watcher was dropped")))
+                .select(poller.run(&client).map(|()| None))
+                .map(|(event, _)| event)
+                .map_err(|(error, _)| error),
+        )
+        .unwrap();
+    done.store(true, Ordering::SeqCst);
+
+    match event {
+        Some(PaymentEvent::Expired { .. }) => (),
+        other => panic!("Expected the payment to expire, got {:?}", other),
+    }
+    assert_eq!(poller.pending(), 0);
+}
@@ -0,0 +1,189 @@
+extern crate serde_json;
+extern crate swish_api;
+extern crate tokio_core;
+
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use swish_api::client::{Environment, SwishClient};
+use swish_api::config::{RetryConfig, SwishConfig};
+use swish_api::error::ConfigError;
+use swish_api::golden;
+use swish_api::retry::RetryPolicy;
+use tokio_core::reactor::Core;
+
+/// Responds to every request with a response of the list, in order.
+fn serve_responses(responses: Vec<String>) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}/", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let received = requests.clone();
+    thread::spawn(move || {
+        for (stream, response) in listener.incoming().zip(responses) {
+            let mut stream = stream.unwrap();
+            let mut request = vec![0; 8192];
+            let _ = stream.read(&mut request).unwrap();
+            received.fetch_add(1, Ordering::SeqCst);
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (api_url, requests)
+}
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[test]
+fn test_config_is_deserialized() {
+    let config: SwishConfig = serde_json::from_str(
+        r#"{
+            "merchant_swish_number": "1231181189",
+            "environment": "production",
+            "cert_path": "/etc/swish/cert.p12",
+            "passphrase": "swish",
+            "timeout_ms": 10000,
+            "retry": { "max_retries": 2, "backoff_ms": 100, "max_backoff_ms": 1000 }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.merchant_swish_number.as_str(), "1231181189");
+    assert_eq!(config.environment, Environment::Production);
+    assert_eq!(config.api_url, None);
+    assert_eq!(config.cert_path, Some(PathBuf::from("/etc/swish/cert.p12")));
+    assert_eq!(config.passphrase.expose_secret(), "swish");
+    assert_eq!(config.timeout_ms, Some(10000));
+    assert_eq!(
+        RetryPolicy::from(config.retry),
+        RetryPolicy::new()
+            .max_retries(2)
+            .backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(1000))
+    );
+}
+
+#[test]
+fn test_config_has_defaults() {
+    let config: SwishConfig =
+        serde_json::from_str(r#"{ "merchant_swish_number": "1231181189" }"#).unwrap();
+
+    assert_eq!(config.environment, Environment::Test);
+    assert_eq!(config.cert_path, None);
+    assert_eq!(config.timeout_ms, None);
+    assert_eq!(config.retry, RetryConfig::default());
+    assert_eq!(RetryPolicy::from(config.retry), RetryPolicy::new());
+}
+
+#[test]
+fn test_retry_config_fills_in_the_missing_settings() {
+    let retry: RetryConfig = serde_json::from_str(r#"{ "max_retries": 3 }"#).unwrap();
+
+    assert_eq!(RetryPolicy::from(retry), RetryPolicy::new().max_retries(3));
+}
+
+#[test]
+fn test_config_rejects_unknown_fields() {
+    let config = serde_json::from_str::<SwishConfig>(
+        r#"{ "merchant_swish_number": "1231181189", "timeout": 10 }"#,
+    );
+
+    let error = config.unwrap_err().to_string();
+    assert!(error.contains("unknown field `timeout`"), "{}", error);
+}
+
+#[test]
+fn test_config_rejects_an_invalid_merchant_swish_number() {
+    let config =
+        serde_json::from_str::<SwishConfig>(r#"{ "merchant_swish_number": "0701234567" }"#);
+
+    assert!(config.is_err());
+}
+
+#[test]
+fn test_config_rejects_an_unknown_environment() {
+    let config = serde_json::from_str::<SwishConfig>(
+        r#"{ "merchant_swish_number": "1231181189", "environment": "staging" }"#,
+    );
+
+    assert!(config.is_err());
+}
+
+#[test]
+fn test_config_debug_redacts_the_passphrase() {
+    let config: SwishConfig = serde_json::from_str(
+        r#"{ "merchant_swish_number": "1231181189", "passphrase": "hunter2" }"#,
+    )
+    .unwrap();
+
+    assert!(!format!("{:?}", config).contains("hunter2"));
+}
+
+#[test]
+fn test_client_from_config_uses_the_settings() {
+    let mut core = Core::new().unwrap();
+    let unavailable = response("503 Service Unavailable", "");
+    let (api_url, requests) =
+        serve_responses(vec![unavailable, response("200 OK", golden::PAYMENT)]);
+    let config: SwishConfig = serde_json::from_str(&format!(
+        r#"{{
+            "merchant_swish_number": "1231181189",
+            "api_url": "{}",
+            "timeout_ms": 5000,
+            "retry": {{ "max_retries": 1, "backoff_ms": 10 }}
+        }}"#,
+        api_url
+    ))
+    .unwrap();
+    let client = SwishClient::from_config(&config, core.handle()).unwrap();
+
+    let payment = core.run(client.get_payment("AB23D7406ECE4542A80152D909EF9F6B"));
+
+    assert!(payment.is_ok());
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_client_from_config_requires_a_certificate_without_an_api_url() {
+    let core = Core::new().unwrap();
+    let config: SwishConfig = serde_json::from_str(
+        r#"{ "merchant_swish_number": "1231181189", "environment": "production" }"#,
+    )
+    .unwrap();
+
+    let error = SwishClient::from_config(&config, core.handle()).unwrap_err();
+
+    match error {
+        ConfigError::Secret(ref error) => assert_eq!(error.kind(), io::ErrorKind::NotFound),
+        ref other => panic!("Expected Secret, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_client_from_config_fails_on_a_certificate_that_cannot_be_read() {
+    let core = Core::new().unwrap();
+    let config: SwishConfig = serde_json::from_str(
+        r#"{
+            "merchant_swish_number": "1231181189",
+            "cert_path": "./tests/no_such_cert.p12",
+            "passphrase": "swish"
+        }"#,
+    )
+    .unwrap();
+
+    let error = SwishClient::from_config(&config, core.handle()).unwrap_err();
+
+    match error {
+        ConfigError::Secret(ref error) => assert_eq!(error.kind(), io::ErrorKind::NotFound),
+        ref other => panic!("Expected Secret, got {:?}", other),
+    }
+}
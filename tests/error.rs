@@ -0,0 +1,253 @@
+extern crate hyper;
+extern crate serde_json;
+extern crate swish_api;
+
+use hyper::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use hyper::StatusCode;
+use std::error::Error;
+use std::io;
+use std::sync::Arc;
+use swish_api::active;
+use swish_api::error::{
+    CreatePaymentError, ErrorCategory, ErrorCode, ErrorResponse, GetPaymentError, Language,
+    RequestContext, RequestError, Resource, SwishClientError,
+};
+use swish_api::validation::ValidationError;
+
+fn read_certificate() -> Result<(), SwishClientError> {
+    Err(io::Error::new(io::ErrorKind::NotFound, "no certificate").into())
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    read_certificate()?;
+    Ok(())
+}
+
+#[test]
+fn test_error_source_is_the_underlying_error() {
+    let error = run().unwrap_err();
+    let source = error.source().unwrap();
+
+    assert_eq!(source.to_string(), "no certificate");
+    assert!(source.downcast_ref::<io::Error>().is_some());
+}
+
+#[test]
+fn test_validation_error_is_the_source() {
+    let error = SwishClientError::from(ValidationError::Missing {
+        field: "callbackUrl",
+    });
+
+    assert!(error
+        .source()
+        .and_then(|source| source.downcast_ref::<ValidationError>())
+        .is_some());
+}
+
+fn swish_error(http_status: StatusCode, code: Option<ErrorCode>) -> SwishClientError {
+    let mut request_error = RequestError::default();
+    request_error.http_status = http_status;
+    request_error.code = code;
+    SwishClientError::from(request_error)
+}
+
+#[test]
+fn test_errors_are_classified_by_status() {
+    let rejected = SwishClientError::from(vec![
+        swish_error(StatusCode::UNPROCESSABLE_ENTITY, Some(ErrorCode::RP03)),
+        swish_error(StatusCode::UNPROCESSABLE_ENTITY, Some(ErrorCode::AM06)),
+    ]);
+    let unavailable = swish_error(StatusCode::SERVICE_UNAVAILABLE, None);
+
+    assert_eq!(
+        rejected.http_status(),
+        Some(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+    assert_eq!(
+        rejected.swish_error_codes(),
+        vec![&ErrorCode::RP03, &ErrorCode::AM06]
+    );
+    assert!(rejected.is_client_error());
+    assert!(!rejected.is_retryable());
+    assert!(unavailable.is_server_error());
+    assert!(unavailable.is_retryable());
+}
+
+#[test]
+fn test_transport_errors_are_retryable() {
+    let error = SwishClientError::from(io::Error::new(io::ErrorKind::TimedOut, "timed out"));
+
+    assert_eq!(error.http_status(), None);
+    assert!(error.swish_error_codes().is_empty());
+    assert!(error.is_retryable());
+    assert!(!error.is_client_error());
+}
+
+#[test]
+fn test_unknown_error_codes_are_kept() {
+    let errors: Vec<RequestError> = serde_json::from_str(
+        r#"[
+            {"errorCode": "BANKIDCL", "errorMessage": "Payer cancelled BankID signing"},
+            {"errorCode": "XX99", "errorMessage": "Something new"}
+        ]"#,
+    )
+    .unwrap();
+
+    assert_eq!(errors[0].code, Some(ErrorCode::BANKIDCL));
+    assert_eq!(errors[1].code, Some(ErrorCode::Unknown("XX99".to_owned())));
+    assert_eq!(errors[1].code.as_ref().unwrap().as_str(), "XX99");
+}
+
+#[test]
+fn test_error_codes_are_described() {
+    assert_eq!(
+        ErrorCode::ACMT03.description(),
+        "The payer isn't enrolled in Swish"
+    );
+    assert_eq!(
+        ErrorCode::ACMT03.to_string(),
+        "The payer isn't enrolled in Swish (ACMT03)"
+    );
+    assert_eq!(
+        ErrorCode::Unknown("XX99".to_owned()).to_string(),
+        "An error code that isn't known (XX99)"
+    );
+}
+
+#[test]
+fn test_operation_errors_match_documented_codes() {
+    let not_enrolled = swish_error(StatusCode::FORBIDDEN, Some(ErrorCode::ACMT03));
+    let refund_code = swish_error(StatusCode::UNPROCESSABLE_ENTITY, Some(ErrorCode::RF07));
+
+    match CreatePaymentError::from(not_enrolled) {
+        CreatePaymentError::PayerNotEnrolled(err) => {
+            assert_eq!(err.http_status, StatusCode::FORBIDDEN)
+        }
+        other => panic!("Expected PayerNotEnrolled, got {:?}", other),
+    }
+    match CreatePaymentError::from(refund_code) {
+        CreatePaymentError::Other(err) => {
+            assert_eq!(err.swish_error_codes(), vec![&ErrorCode::RF07])
+        }
+        other => panic!("Expected Other, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_get_errors_tell_not_found_apart() {
+    let not_found = SwishClientError::NotFound {
+        resource: Resource::Payment,
+        id: "AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        correlation_id: None,
+    };
+    let unavailable = swish_error(StatusCode::SERVICE_UNAVAILABLE, None);
+
+    assert_eq!(
+        not_found.to_string(),
+        "the payment AB23D7406ECE4542A80152D909EF9F6B wasn't found at Swish"
+    );
+    assert_eq!(not_found.http_status(), Some(StatusCode::NOT_FOUND));
+    match GetPaymentError::from(not_found) {
+        GetPaymentError::NotFound { id } => assert_eq!(id, "AB23D7406ECE4542A80152D909EF9F6B"),
+        other => panic!("Expected NotFound, got {:?}", other),
+    }
+    assert!(match GetPaymentError::from(unavailable) {
+        GetPaymentError::NotFound { .. } => false,
+        GetPaymentError::Other(_) => true,
+        _ => false,
+    });
+}
+
+#[test]
+fn test_errors_are_displayed_with_their_cause() {
+    let error = swish_error(StatusCode::FORBIDDEN, Some(ErrorCode::ACMT03));
+    let validation_error = SwishClientError::from(ValidationError::Missing {
+        field: "callbackUrl",
+    });
+
+    assert_eq!(
+        error.to_string(),
+        "Swish responded with 403 Forbidden: The payer isn't enrolled in Swish (ACMT03)"
+    );
+    assert!(validation_error.to_string().starts_with("invalid params: "));
+}
+
+#[test]
+fn test_errors_are_grouped_into_categories() {
+    let not_enrolled = swish_error(StatusCode::FORBIDDEN, Some(ErrorCode::ACMT03));
+    let unavailable = swish_error(StatusCode::SERVICE_UNAVAILABLE, None);
+    let not_found = swish_error(StatusCode::NOT_FOUND, None);
+
+    assert_eq!(ErrorCode::RP06.category(), ErrorCategory::Duplicate);
+    assert_eq!(ErrorCode::AM02.category(), ErrorCategory::AmountLimits);
+    assert_eq!(not_enrolled.category(), Some(ErrorCategory::Enrollment));
+    assert_eq!(unavailable.category(), Some(ErrorCategory::System));
+    assert_eq!(not_found.category(), None);
+}
+
+#[test]
+fn test_errors_keep_the_raw_response() {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+    let mut request_error = RequestError::default();
+    request_error.http_status = StatusCode::BAD_GATEWAY;
+    request_error.response = Some(Arc::new(ErrorResponse {
+        headers,
+        body: "<html>Bad Gateway</html>".to_owned(),
+    }));
+    let error = SwishClientError::from(request_error);
+
+    assert_eq!(error.response_body(), Some("<html>Bad Gateway</html>"));
+    assert_eq!(error.response_headers().unwrap()[CONTENT_TYPE], "text/html");
+    assert_eq!(
+        swish_error(StatusCode::FORBIDDEN, None).response_body(),
+        None
+    );
+}
+
+#[test]
+fn test_errors_have_localized_messages() {
+    let context = RequestContext {
+        method: hyper::Method::POST,
+        path: "/swish-cpcapi/api/v1/paymentrequests".to_owned(),
+        merchant: "1231181189".to_owned(),
+        correlation_id: "abc123".to_owned(),
+        language: Language::Swedish,
+    };
+    let mut request_error = RequestError::default();
+    request_error.http_status = StatusCode::FORBIDDEN;
+    request_error.code = Some(ErrorCode::ACMT03);
+    request_error.context = Some(Arc::new(context));
+    let error = SwishClientError::from(request_error);
+
+    assert_eq!(
+        ErrorCode::ACMT03.localized_message(Language::English),
+        "The mobile number isn't connected to Swish."
+    );
+    assert_eq!(
+        error.localized_message(),
+        Some("Mobilnumret är inte anslutet till Swish.")
+    );
+    assert_eq!(
+        ErrorCode::RP03.localized_message(Language::English),
+        ErrorCode::Unknown("XX01".to_owned()).localized_message(Language::English)
+    );
+    assert_eq!(
+        swish_error(StatusCode::INTERNAL_SERVER_ERROR, None).localized_message(),
+        None
+    );
+}
+
+#[test]
+fn test_active_payment_conflicts_are_detected() {
+    let conflict = SwishClientError::from(vec![
+        swish_error(StatusCode::UNPROCESSABLE_ENTITY, Some(ErrorCode::RP03)),
+        swish_error(StatusCode::UNPROCESSABLE_ENTITY, Some(ErrorCode::RP06)),
+    ]);
+
+    assert!(active::is_active_payment_conflict(&conflict));
+    assert!(!active::is_active_payment_conflict(&swish_error(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Some(ErrorCode::RP03)
+    )));
+}
@@ -0,0 +1,107 @@
+extern crate serde_json;
+extern crate swish_api;
+
+use swish_api::amount::Amount;
+use swish_api::callback::{PaymentCallback, RefundCallback};
+use swish_api::client::{Payment, Refund, Status};
+use swish_api::error::ErrorCode;
+use swish_api::event::PaymentEvent;
+use swish_api::ids::{PaymentId, RefundId};
+
+const PAYMENT: &str = r#"{
+    "id": "AB23D7406ECE4542A80152D909EF9F6B",
+    "payeePaymentReference": "0123456789",
+    "paymentReference": "6D6CD7406ECE4542A80152D909EF9F6B",
+    "callbackUrl": "https://example.com/api/swishcb/paymentrequests",
+    "payerAlias": "46701234567",
+    "payeeAlias": "1231181189",
+    "amount": 100.0,
+    "currency": "SEK",
+    "message": "Kingston USB Flash Drive 8 GB",
+    "status": "PAID",
+    "dateCreated": "2015-02-19T22:01:53+01:00",
+    "datePaid": "2015-02-19T22:03:53+01:00",
+    "errorCode": null,
+    "errorMessage": null
+}"#;
+
+const REFUND: &str = r#"{
+    "id": "ABC2D7406ECE4542A80152D909EF9F6B",
+    "payerPaymentReference": "0123456789",
+    "originalPaymentReference": "6D6CD7406ECE4542A80152D909EF9F6B",
+    "callbackUrl": "https://example.com/api/swishcb/refunds",
+    "payerAlias": "1231181189",
+    "payeeAlias": "46701234567",
+    "amount": 100.0,
+    "currency": "SEK",
+    "message": "Refund for Kingston USB Flash Drive 8 GB",
+    "status": "DEBITED",
+    "dateCreated": "2015-02-19T22:01:53+01:00",
+    "datePaid": null,
+    "errorCode": null,
+    "errorMessage": null,
+    "additionalInformation": null
+}"#;
+
+#[test]
+fn test_callbacks_and_fetches_give_the_same_event() {
+    let payment: Payment = serde_json::from_str(PAYMENT).unwrap();
+    let callback = PaymentCallback::from_slice(PAYMENT.as_bytes()).unwrap();
+    let event = PaymentEvent::from(&payment);
+
+    assert_eq!(event, PaymentEvent::from(&callback));
+    assert_eq!(
+        event,
+        PaymentEvent::Paid {
+            payment_id: PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B"),
+            payment_reference: Some("6D6CD7406ECE4542A80152D909EF9F6B".to_owned()),
+            amount: Amount::from_kronor(100),
+            date_paid: Some("2015-02-19T22:03:53+01:00".to_owned()),
+        }
+    );
+    assert!(event.is_final());
+}
+
+#[test]
+fn test_failed_payments_carry_the_error_code() {
+    let body = PAYMENT
+        .replace(r#""PAID""#, r#""ERROR""#)
+        .replace(r#""errorCode": null"#, r#""errorCode": "RF07""#);
+    let payment: Payment = serde_json::from_str(&body).unwrap();
+
+    match PaymentEvent::from(&payment) {
+        PaymentEvent::Errored { error_code, .. } => assert_eq!(error_code, Some(ErrorCode::RF07)),
+        event => panic!("Expected an errored payment, got {:?}", event),
+    }
+}
+
+#[test]
+fn test_refund_events() {
+    let refund: Refund = serde_json::from_str(REFUND).unwrap();
+    let callback = RefundCallback::from_slice(REFUND.as_bytes()).unwrap();
+    let event = PaymentEvent::from(&callback);
+
+    assert_eq!(event, PaymentEvent::from(&refund));
+    assert_eq!(
+        event,
+        PaymentEvent::RefundDebited {
+            refund_id: RefundId::from("ABC2D7406ECE4542A80152D909EF9F6B"),
+            original_payment_reference: Some("6D6CD7406ECE4542A80152D909EF9F6B".to_owned()),
+        }
+    );
+    assert!(!event.is_final());
+}
+
+#[test]
+fn test_unknown_statuses_are_kept() {
+    let body = PAYMENT.replace(r#""PAID""#, r#""SETTLED""#);
+    let payment: Payment = serde_json::from_str(&body).unwrap();
+
+    assert_eq!(
+        PaymentEvent::from(&payment),
+        PaymentEvent::Unknown {
+            id: "AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+            status: Status::Unknown("SETTLED".to_owned()),
+        }
+    );
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "test-utils")]
+extern crate swish_api;
+
+use swish_api::client::Status;
+use swish_api::fake::FakeData;
+use swish_api::validation;
+
+#[test]
+fn test_the_same_seed_generates_the_same_data() {
+    let mut first = FakeData::new(1);
+    let mut second = FakeData::new(1);
+
+    for _ in 0..10 {
+        assert_eq!(first.any_payment(), second.any_payment());
+        assert_eq!(first.payment_params(), second.payment_params());
+    }
+    assert_ne!(
+        FakeData::new(2).any_payment(),
+        FakeData::new(1).any_payment()
+    );
+}
+
+#[test]
+fn test_params_are_accepted_by_swish() {
+    let mut fake = FakeData::random();
+
+    for _ in 0..100 {
+        let payment = fake.payment_params();
+        assert_eq!(payment.as_params().validate(), Ok(()));
+
+        let payer_alias = fake.phone_alias();
+        let payment_reference = fake.id();
+        let refund = fake.refund_params(&payment_reference, &payer_alias, payment.amount);
+        assert_eq!(refund.as_params().validate(), Ok(()));
+        assert!(refund.amount <= payment.amount);
+    }
+}
+
+#[test]
+fn test_refunds_are_of_the_payment() {
+    let mut fake = FakeData::new(7);
+
+    for _ in 0..20 {
+        let (payment, refund) = fake.any_refund();
+        let (payment, refund) = (payment.payment(), refund.refund());
+        assert_eq!(payment.status, Some(Status::Paid));
+        assert_eq!(refund.original_payment_reference, payment.payment_reference);
+        assert_eq!(refund.payee_alias, payment.payer_alias);
+        assert_eq!(refund.amount, payment.amount);
+        assert!(validation::validate_message(refund.message.as_ref().unwrap()).is_ok());
+    }
+}
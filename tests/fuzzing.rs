@@ -0,0 +1,35 @@
+#![cfg(feature = "fuzzing")]
+extern crate swish_api;
+
+use swish_api::error::SwishClientError;
+use swish_api::fuzzing;
+
+/// Inputs found to be troublesome for response handling, which must give errors and not panic.
+const INPUTS: [&[u8]; 8] = [
+    b"",
+    b"   ",
+    b"\xff\xfe\x00{",
+    b"{\"id\":\"AB23D7406ECE4542A80152D909EF9F6B\",\"amount\":",
+    b"<html><body>502 Bad Gateway</body></html>",
+    b"[{\"errorCode\":17,\"errorMessage\":null}]",
+    b"{\"id\":[],\"amount\":{},\"status\":5}",
+    b"null",
+];
+
+#[test]
+fn test_malformed_responses_are_errors() {
+    for &status in &[200, 400, 404, 422, 500] {
+        for input in &INPUTS {
+            assert!(fuzzing::fetch_payment(status, input, false).is_err());
+            assert!(fuzzing::fetch_refund(status, input, true).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_non_utf8_responses_are_utf8_errors() {
+    match fuzzing::fetch_payment(200, b"\xff\xfe\x00{", false) {
+        Err(SwishClientError::Utf8(_)) => (),
+        other => panic!("Expected a UTF-8 error, got {:?}", other),
+    }
+}
@@ -0,0 +1,67 @@
+extern crate serde;
+extern crate serde_json;
+extern crate swish_api;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use swish_api::amount::Amount;
+use swish_api::callback::{Callback, PaymentCallback, RefundCallback};
+use swish_api::client::{Payment, Refund, Status};
+use swish_api::error::{ErrorCode, RequestError};
+use swish_api::golden;
+
+/// Asserts that the model serializes back into the same JSON it was parsed from.
+fn assert_round_trips<T: Serialize + DeserializeOwned>(json: &str) -> T {
+    let model: T = serde_json::from_str(json).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_value(&model).unwrap(), expected);
+    model
+}
+
+#[test]
+fn test_payments_round_trip() {
+    let payment: Payment = assert_round_trips(golden::PAYMENT);
+    assert_eq!(payment.status, Some(Status::Paid));
+    assert_eq!(payment.amount, Amount::from_kronor(100));
+    assert!(payment.extra.is_empty());
+
+    let payment: Payment = assert_round_trips(golden::PAYMENT_ERROR);
+    assert_eq!(payment.status, Some(Status::Error));
+    assert_eq!(payment.error_code, Some(ErrorCode::TM01));
+}
+
+#[test]
+fn test_refunds_round_trip() {
+    let refund: Refund = assert_round_trips(golden::REFUND);
+    assert_eq!(refund.status, Some(Status::Paid));
+    assert_eq!(
+        refund.original_payment_reference,
+        Some("6D6CD7406ECE4542A80152D909EF9F6B".to_owned())
+    );
+    assert!(refund.extra.is_empty());
+}
+
+#[test]
+fn test_callbacks_round_trip() {
+    let payment: PaymentCallback = assert_round_trips(golden::PAYMENT_CALLBACK);
+    let refund: RefundCallback = assert_round_trips(golden::REFUND_CALLBACK);
+    assert!(payment.extra.is_empty());
+    assert!(refund.extra.is_empty());
+
+    match Callback::from_slice(golden::PAYMENT_CALLBACK.as_bytes()).unwrap() {
+        Callback::Payment(callback) => assert_eq!(callback, payment),
+        other => panic!("Expected a payment callback, got {:?}", other),
+    }
+    match Callback::from_slice(golden::REFUND_CALLBACK.as_bytes()).unwrap() {
+        Callback::Refund(callback) => assert_eq!(callback.status, Status::Debited),
+        other => panic!("Expected a refund callback, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_errors_are_parsed() {
+    let errors: Vec<RequestError> = serde_json::from_str(golden::ERRORS).unwrap();
+    let codes: Vec<_> = errors.iter().map(|error| error.code.clone()).collect();
+
+    assert_eq!(codes, vec![Some(ErrorCode::RP03), Some(ErrorCode::PA02)]);
+}
@@ -3,19 +3,23 @@ extern crate tokio_core;
 
 use std::env;
 use std::{thread, time};
+use swish_api::alias::{MerchantAlias, PhoneAlias};
+use swish_api::amount::Amount;
+use swish_api::callback::CallbackUrl;
 use swish_api::{client, error};
 use tokio_core::reactor::Core;
 
 fn get_client_and_core() -> Result<(client::SwishClient, Core), error::SwishClientError> {
     let core = tokio_core::reactor::Core::new().unwrap();
     let handle = core.handle();
+    let merchant_alias = MerchantAlias::new("1231181189").unwrap();
     let current_dir = env::current_dir()?;
     let cert_path = current_dir.join("./tests/test_cert.p12");
     let swish_client = cert_path
         .into_os_string()
         .to_str()
         .map(|cert_path_string| {
-            client::SwishClient::new("1231181189", cert_path_string, "swish", handle)
+            client::SwishClient::new(merchant_alias, cert_path_string, "swish", handle)
         }).unwrap();
 
     Ok((swish_client, core))
@@ -23,10 +27,10 @@ fn get_client_and_core() -> Result<(client::SwishClient, Core), error::SwishClie
 
 fn get_default_params<'a>() -> client::PaymentParams<'a> {
     let mut payment_params = client::PaymentParams::default();
-    payment_params.amount = 100.00;
-    payment_params.payee_alias = "1231181189";
+    payment_params.amount = Amount::from_kronor(100);
     payment_params.payee_payment_reference = Some("0123456789");
-    payment_params.callback_url = "https://example.com/api/swishcb/paymentrequests";
+    payment_params.callback_url =
+        CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").ok();
     payment_params.message = Some("Kingston USB Flash Drive 8 GB");
     payment_params
 }
@@ -35,15 +39,15 @@ fn get_default_params<'a>() -> client::PaymentParams<'a> {
 fn test_create_payment_ecommerce() {
     let (client, mut core) = get_client_and_core().unwrap();
     let mut payment_params = get_default_params();
-    payment_params.payer_alias = Some("46712345678");
+    payment_params.payer_alias = Some(PhoneAlias::new("46712345678").unwrap());
 
     let payment = client.create_payment(payment_params);
     let payment: Result<client::CreatedPayment, error::SwishClientError> = core.run(payment);
 
     assert!(payment.is_ok());
     let ok_payment = payment.unwrap();
-    assert_eq!(ok_payment.id.is_empty(), false);
-    assert_eq!(ok_payment.location.is_empty(), false);
+    assert!(!ok_payment.id.is_empty());
+    assert!(!ok_payment.location.is_empty());
     assert!(ok_payment.request_token.is_none());
 }
 
@@ -57,24 +61,16 @@ fn test_create_payment_mcommerce() {
 
     assert!(payment.is_ok());
     let ok_payment = payment.unwrap();
-    assert_eq!(ok_payment.id.is_empty(), false);
-    assert_eq!(ok_payment.location.is_empty(), false);
+    assert!(!ok_payment.id.is_empty());
+    assert!(!ok_payment.location.is_empty());
     assert!(ok_payment.request_token.is_some());
 }
 
 #[test]
-#[ignore]
 fn test_create_payment_error_for_callback_error() {
-    let (client, mut core) = get_client_and_core().unwrap();
-    let mut payment_params = get_default_params();
-    payment_params.callback_url = "http://example.com/api/swishcb/paymentrequests";
-
-    let payment = client.create_payment(payment_params);
-    let payment: Result<client::CreatedPayment, error::SwishClientError> = core.run(payment);
+    let callback_url = CallbackUrl::new("http://example.com/api/swishcb/paymentrequests");
 
-    assert_eq!(format!("{}", payment.unwrap_err()), "a");
-    // panic!(payment.unwrap_err().error_message);
-    // assert!(payment.unwrap_err());
+    assert_eq!(callback_url.unwrap_err().field(), "callbackUrl");
 }
 
 #[test]
@@ -87,17 +83,17 @@ fn test_get_payment() {
         core.run(created_payment);
 
     let payment: Result<client::Payment, error::SwishClientError> = created_payment
-        .and_then(|created_payment| core.run(client.get_payment(created_payment.id.as_str())));
+        .and_then(|created_payment| core.run(client.get_payment(&created_payment)));
 
     assert!(payment.is_ok());
     let ok_payment = payment.unwrap();
-    assert_eq!(ok_payment.id.is_empty(), false);
-    assert_eq!(ok_payment.amount, 100.00);
+    assert!(!ok_payment.id.is_empty());
+    assert_eq!(ok_payment.amount, Amount::from_kronor(100));
     assert_eq!(ok_payment.currency, client::Currency::SEK);
     assert!(ok_payment.status.is_some());
     let ok_status = ok_payment.status.unwrap();
     assert_eq!(ok_status, client::Status::Created);
-    assert_eq!(ok_payment.date_created.is_empty(), false);
+    assert!(!ok_payment.date_created.is_empty());
     assert!(ok_payment.message.is_some());
     let ok_message = ok_payment.message.unwrap();
     assert_eq!(ok_message, "Kingston USB Flash Drive 8 GB");
@@ -105,6 +101,25 @@ fn test_get_payment() {
     assert!(ok_payment.payee_payment_reference.is_some());
 }
 
+#[test]
+fn test_get_payment_with_raw() {
+    let (client, mut core) = get_client_and_core().unwrap();
+    let payment_params = get_default_params();
+
+    let created_payment = client.create_payment(payment_params);
+    let created_payment: Result<client::CreatedPayment, error::SwishClientError> =
+        core.run(created_payment);
+
+    let payment = created_payment.and_then(|created_payment| {
+        core.run(client.get_payment_with_raw(created_payment.id.as_str()))
+    });
+
+    assert!(payment.is_ok());
+    let (ok_payment, raw_payment) = payment.unwrap();
+    assert_eq!(raw_payment["id"], ok_payment.id.as_str());
+    assert_eq!(raw_payment["message"], "Kingston USB Flash Drive 8 GB");
+}
+
 #[test]
 fn test_create_refund() {
     let (client, mut core) = get_client_and_core().unwrap();
@@ -119,25 +134,25 @@ fn test_create_refund() {
     thread::sleep(five_seconds);
 
     let refund: Result<client::CreatedRefund, error::SwishClientError> = created_payment
-        .and_then(|created_payment| core.run(client.get_payment(created_payment.id.as_str())))
+        .and_then(|created_payment| core.run(client.get_payment(&created_payment)))
         .and_then(|gotten_payment| {
             let payment_reference = gotten_payment.payment_reference.unwrap();
             let mut refund_params = client::RefundParams::default();
-            refund_params.amount = 100.00;
-            refund_params.callback_url = "https://example.com/api/swishcb/refunds";
+            refund_params.amount = Amount::from_kronor(100);
+            refund_params.callback_url =
+                CallbackUrl::new("https://example.com/api/swishcb/refunds").ok();
             refund_params.original_payment_reference = payment_reference.as_str();
             refund_params.payer_payment_reference = Some("0123456789");
             refund_params.message = Some("Refund for Kingston USB Flash Drive 8 GB");
 
             let refund = client.create_refund(refund_params);
-            let refund = core.run(refund);
-            refund
+            core.run(refund)
         });
 
     assert!(refund.is_ok());
     let ok_refund = refund.unwrap();
-    assert_eq!(ok_refund.id.is_empty(), false);
-    assert_eq!(ok_refund.location.is_empty(), false);
+    assert!(!ok_refund.id.is_empty());
+    assert!(!ok_refund.location.is_empty());
 }
 
 #[test]
@@ -154,33 +169,33 @@ fn test_get_refund() {
     thread::sleep(five_seconds);
 
     let created_refund: Result<client::CreatedRefund, error::SwishClientError> = created_payment
-        .and_then(|created_payment| core.run(client.get_payment(created_payment.id.as_str())))
+        .and_then(|created_payment| core.run(client.get_payment(&created_payment)))
         .and_then(|gotten_payment| {
             let payment_reference = gotten_payment.payment_reference.unwrap();
             let mut refund_params = client::RefundParams::default();
-            refund_params.amount = 100.00;
-            refund_params.callback_url = "https://example.com/api/swishcb/refunds";
+            refund_params.amount = Amount::from_kronor(100);
+            refund_params.callback_url =
+                CallbackUrl::new("https://example.com/api/swishcb/refunds").ok();
             refund_params.original_payment_reference = payment_reference.as_str();
             refund_params.payer_payment_reference = Some("0123456789");
             refund_params.message = Some("Refund for Kingston USB Flash Drive 8 GB");
 
             let refund = client.create_refund(refund_params);
-            let refund = core.run(refund);
-            refund
+            core.run(refund)
         });
 
     let gotten_refund = created_refund
-        .and_then(|created_refund| core.run(client.get_refund(created_refund.id.as_str())));
+        .and_then(|created_refund| core.run(client.get_refund(&created_refund)));
 
     assert!(gotten_refund.is_ok());
     let ok_refund = gotten_refund.unwrap();
-    assert_eq!(ok_refund.id.is_empty(), false);
-    assert_eq!(ok_refund.amount, 100.00);
+    assert!(!ok_refund.id.is_empty());
+    assert_eq!(ok_refund.amount, Amount::from_kronor(100));
     assert_eq!(ok_refund.currency, client::Currency::SEK);
     assert!(ok_refund.status.is_some());
     let ok_status = ok_refund.status.unwrap();
     assert_eq!(ok_status, client::Status::Initiated);
-    assert_eq!(ok_refund.date_created.is_empty(), false);
+    assert!(!ok_refund.date_created.is_empty());
     assert!(ok_refund.message.is_some());
     let ok_message = ok_refund.message.unwrap();
     assert_eq!(ok_message, "Refund for Kingston USB Flash Drive 8 GB");
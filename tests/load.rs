@@ -0,0 +1,82 @@
+#![cfg(all(feature = "load-test", feature = "simulator"))]
+extern crate native_tls;
+extern crate swish_api;
+extern crate tokio_core;
+
+use native_tls::TlsConnector;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use swish_api::alias::{MerchantAlias, PhoneAlias};
+use swish_api::client::{SwishClient, SwishClientBuilder};
+use swish_api::fake::FakeData;
+use swish_api::load::LoadTest;
+use swish_api::simulator::SwishSimulator;
+use tokio_core::reactor::Core;
+
+/// Starts a simulator on a thread of its own, and gets the URL of its API.
+fn start_simulator() -> String {
+    let (url_sender, url_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut core = Core::new().unwrap();
+        let simulator =
+            SwishSimulator::bind(&"127.0.0.1:0".parse().unwrap(), &core.handle()).unwrap();
+        url_sender.send(simulator.api_url().unwrap()).unwrap();
+        core.run(simulator.serve()).unwrap();
+    });
+    url_receiver.recv().unwrap()
+}
+
+fn get_client(core: &Core, api_url: &str) -> SwishClient {
+    SwishClientBuilder::new(
+        MerchantAlias::new("1231181189").unwrap(),
+        "",
+        "",
+        core.handle(),
+    )
+    .tls_connector(TlsConnector::new().unwrap())
+    .api_url(api_url)
+    .build()
+}
+
+#[test]
+fn test_calls_are_made_at_the_rate_of_the_stages() {
+    let api_url = start_simulator();
+    let mut core = Core::new().unwrap();
+    let client = get_client(&core, &api_url);
+
+    let load_test = LoadTest::new()
+        .stage(Duration::from_millis(500), 20)
+        .stage(Duration::from_millis(500), 40);
+    let report = core.run(load_test.run(&client)).unwrap();
+
+    assert_eq!(report.sent, 30);
+    assert_eq!(report.succeeded(), 30);
+    assert_eq!(report.skipped, 0);
+    assert!(report.elapsed >= Duration::from_millis(950));
+    let p50 = report.percentile(50.0).unwrap();
+    assert!(p50 <= report.percentile(99.0).unwrap());
+    assert!(report.to_string().starts_with("30 calls in"));
+}
+
+#[test]
+fn test_errors_are_counted_by_what_they_failed_with() {
+    let api_url = start_simulator();
+    let mut core = Core::new().unwrap();
+    let client = get_client(&core, &api_url);
+    let mut fake = FakeData::new(1);
+    let payer_alias = PhoneAlias::new("46712345678").unwrap();
+
+    let load_test = LoadTest::new()
+        .stage(Duration::from_millis(500), 20)
+        .params(move |_| {
+            let mut params = fake.payment_params();
+            params.payer_alias = Some(payer_alias.clone());
+            params
+        });
+    let report = core.run(load_test.run(&client)).unwrap();
+
+    assert_eq!(report.sent, 10);
+    assert_eq!(report.succeeded(), 1);
+    assert_eq!(report.errors.get("RP06"), Some(&9));
+}
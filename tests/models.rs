@@ -0,0 +1,440 @@
+#[cfg(feature = "chrono")]
+extern crate chrono;
+extern crate serde_json;
+extern crate swish_api;
+
+use std::collections::HashSet;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use swish_api::amount::Amount;
+use swish_api::callback::{Callback, PaymentCallback, RefundCallback};
+use swish_api::client::{
+    CreatedMCommercePayment, CreatedPayment, CreatedRefund, Currency, Payment, Refund,
+    RefundParams, Status, PAYMENT_REQUEST_TTL,
+};
+use swish_api::error::ErrorCode;
+use swish_api::ids::{PaymentId, PaymentRef};
+use swish_api::params::OwnedRefundParams;
+
+const PAYMENT: &str = r#"{
+    "id": "AB23D7406ECE4542A80152D909EF9F6B",
+    "payeePaymentReference": "0123456789",
+    "paymentReference": "6D6CD7406ECE4542A80152D909EF9F6B",
+    "callbackUrl": "https://example.com/api/swishcb/paymentrequests",
+    "payerAlias": "46701234567",
+    "payeeAlias": "1231181189",
+    "amount": 100.0,
+    "currency": "SEK",
+    "message": "Kingston USB Flash Drive 8 GB",
+    "status": "PAID",
+    "dateCreated": "2015-02-19T22:01:53+01:00",
+    "datePaid": "2015-02-19T22:03:53+01:00",
+    "errorCode": null,
+    "errorMessage": null,
+    "settlementDate": "2015-02-20"
+}"#;
+
+const REFUND: &str = r#"{
+    "id": "ABC2D7406ECE4542A80152D909EF9F6B",
+    "payerPaymentReference": "0123456789",
+    "originalPaymentReference": "6D6CD7406ECE4542A80152D909EF9F6B",
+    "callbackUrl": "https://example.com/api/swishcb/refunds",
+    "payerAlias": "1231181189",
+    "payeeAlias": "46701234567",
+    "amount": 100.0,
+    "currency": "SEK",
+    "message": "Refund for Kingston USB Flash Drive 8 GB",
+    "status": "PAID",
+    "dateCreated": "2015-02-19T22:01:53+01:00",
+    "datePaid": null,
+    "errorCode": null,
+    "errorMessage": null,
+    "additionalInformation": null,
+    "settlementDate": "2015-02-20"
+}"#;
+
+#[test]
+fn test_payment_keeps_unknown_fields() {
+    let payment: Payment = serde_json::from_str(PAYMENT).unwrap();
+
+    assert_eq!(payment.id, "AB23D7406ECE4542A80152D909EF9F6B");
+    assert_eq!(payment.amount, Amount::from_kronor(100));
+    assert_eq!(payment.extra.len(), 1);
+    assert_eq!(payment.extra["settlementDate"], "2015-02-20");
+}
+
+#[test]
+fn test_refund_keeps_unknown_fields() {
+    let refund: Refund = serde_json::from_str(REFUND).unwrap();
+
+    assert_eq!(refund.id, "ABC2D7406ECE4542A80152D909EF9F6B");
+    assert_eq!(refund.extra["settlementDate"], "2015-02-20");
+    assert!(!refund.extra.contains_key("callbackUrl"));
+}
+
+#[test]
+fn test_refund_original_payment_reference_is_read_in_both_spellings() {
+    let camel_cased: Refund = serde_json::from_str(REFUND).unwrap();
+    let odd_cased: Refund = serde_json::from_str(
+        &REFUND.replace("originalPaymentReference", "originalpaymentReference"),
+    )
+    .unwrap();
+
+    for refund in &[camel_cased, odd_cased] {
+        assert_eq!(
+            refund.original_payment_reference.as_deref(),
+            Some("6D6CD7406ECE4542A80152D909EF9F6B")
+        );
+        assert_eq!(refund.extra.len(), 1);
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_payment_timestamps_keep_their_timezone() {
+    use chrono::{TimeZone, Utc};
+
+    let payment: Payment = serde_json::from_str(PAYMENT).unwrap();
+    let created_at = payment.created_at().unwrap();
+    let paid_at = payment.paid_at().unwrap().unwrap();
+
+    assert_eq!(created_at.offset().local_minus_utc(), 3600);
+    assert_eq!(
+        created_at,
+        Utc.with_ymd_and_hms(2015, 2, 19, 21, 1, 53).unwrap()
+    );
+    assert_eq!((paid_at - created_at).num_minutes(), 2);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_refund_without_date_paid_has_no_paid_at() {
+    let refund: Refund = serde_json::from_str(REFUND).unwrap();
+
+    assert!(refund.created_at().is_ok());
+    assert_eq!(refund.paid_at().unwrap(), None);
+}
+
+#[test]
+fn test_amount_is_read_as_ore() {
+    let amount: Amount = serde_json::from_str("100.29").unwrap();
+
+    assert_eq!(amount.as_ore(), 10029);
+    assert_eq!(serde_json::to_string(&amount).unwrap(), "100.29");
+}
+
+#[test]
+fn test_amount_is_read_from_numbers_and_strings() {
+    let amounts: Vec<Amount> =
+        serde_json::from_str(r#"[100, 100.5, "100", "100.50", "100,5", " 100.500 "]"#).unwrap();
+    let payment: Payment = serde_json::from_str(&PAYMENT.replace("100.0", r#""100.00""#)).unwrap();
+
+    assert_eq!(
+        amounts.iter().map(Amount::as_ore).collect::<Vec<_>>(),
+        vec![10000, 10050, 10000, 10050, 10050, 10050]
+    );
+    assert_eq!(payment.amount, Amount::from_kronor(100));
+    assert!(serde_json::from_str::<Amount>(r#""100.505""#).is_err());
+    assert!(serde_json::from_str::<Amount>(r#""SEK 100""#).is_err());
+}
+
+#[test]
+fn test_owned_refund_params_round_trip() {
+    let mut refund_params = RefundParams::default();
+    refund_params.original_payment_reference = "6D6CD7406ECE4542A80152D909EF9F6B";
+    refund_params.amount = Amount::from_kronor(100);
+    let owned_params = OwnedRefundParams::from(refund_params);
+
+    let moved_params = thread::spawn(move || owned_params).join().unwrap();
+    let refund_params = moved_params.as_params();
+
+    assert_eq!(
+        refund_params.original_payment_reference,
+        "6D6CD7406ECE4542A80152D909EF9F6B"
+    );
+    assert_eq!(refund_params.amount, Amount::from_kronor(100));
+}
+
+#[test]
+fn test_created_mcommerce_payment_has_a_plain_token() {
+    let payment: CreatedMCommercePayment = serde_json::from_str(
+        r#"{"id":"AB23D7406ECE4542A80152D909EF9F6B","location":"https://mss.cpc.getswish.net/swish-cpcapi/api/v1/paymentrequests/AB23D7406ECE4542A80152D909EF9F6B","request_token":"c28a4061470f4af48973bd2a4642b4fa","created_at":{"secs_since_epoch":1424419200,"nanos_since_epoch":0}}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        payment.request_token.as_str(),
+        "c28a4061470f4af48973bd2a4642b4fa"
+    );
+}
+
+#[test]
+fn test_payment_round_trips_through_json() {
+    let payment: Payment = serde_json::from_str(PAYMENT).unwrap();
+    let stored = serde_json::to_value(&payment).unwrap();
+
+    assert_eq!(
+        stored,
+        serde_json::from_str::<serde_json::Value>(PAYMENT).unwrap()
+    );
+    assert_eq!(
+        serde_json::from_value::<Payment>(stored).unwrap().status,
+        Some(Status::Paid)
+    );
+}
+
+#[test]
+fn test_refund_is_stored_with_the_swish_field_names() {
+    let refund: Refund = serde_json::from_str(REFUND).unwrap();
+    let stored = serde_json::to_value(&refund).unwrap();
+
+    assert_eq!(stored["payerPaymentReference"], "0123456789");
+    assert_eq!(stored["status"], "PAID");
+    assert_eq!(stored["settlementDate"], "2015-02-20");
+}
+
+#[test]
+fn test_created_refunds_can_be_compared_and_hashed() {
+    let created_refund = CreatedRefund {
+        id: "ABC2D7406ECE4542A80152D909EF9F6B".to_owned(),
+        location: "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/refunds/ABC2D7406ECE4542A80152D909EF9F6B".to_owned(),
+    };
+    let mut created_refunds = HashSet::new();
+
+    created_refunds.insert(created_refund.clone());
+    created_refunds.insert(created_refund.clone());
+
+    assert_eq!(created_refunds.len(), 1);
+    assert!(created_refunds.contains(&created_refund));
+}
+
+#[test]
+fn test_created_payments_are_compared_and_hashed_without_their_creation_time() {
+    let json = r#"{
+        "id": "AB23D7406ECE4542A80152D909EF9F6B",
+        "location": "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/paymentrequests/AB23D7406ECE4542A80152D909EF9F6B",
+        "request_token": "f34DS34lfd0d03fdDselkfd3ffk21",
+        "created_at": {"secs_since_epoch": 1424419200, "nanos_since_epoch": 0}
+    }"#;
+    let created_payment: CreatedMCommercePayment = serde_json::from_str(json).unwrap();
+    let deserialized_again = CreatedMCommercePayment {
+        created_at: created_payment.created_at - Duration::from_secs(1),
+        ..serde_json::from_str(json).unwrap()
+    };
+    let mut created_payments = HashSet::new();
+
+    created_payments.insert(created_payment.clone());
+    created_payments.insert(deserialized_again.clone());
+
+    assert_eq!(created_payment, deserialized_again);
+    assert_eq!(created_payments.len(), 1);
+    let created_payment: CreatedPayment = serde_json::from_str(json).unwrap();
+    let other_payment = CreatedPayment {
+        id: "BC23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        ..created_payment.clone()
+    };
+    assert_ne!(created_payment, other_payment);
+}
+
+#[test]
+fn test_status_and_currency_are_displayed_and_parsed() {
+    assert_eq!(Status::Paid.to_string(), "PAID");
+    assert_eq!("initiated".parse::<Status>(), Ok(Status::Initiated));
+    assert!("SETTLED".parse::<Status>().is_err());
+    assert_eq!(Currency::SEK.to_string(), "SEK");
+    assert_eq!("sek".parse::<Currency>(), Ok(Currency::SEK));
+    assert!("EUR".parse::<Currency>().is_err());
+}
+
+#[test]
+fn test_payment_refs_are_made_from_any_id() {
+    let created_payment = CreatedPayment {
+        id: "AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        location: "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/paymentrequests/AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        request_token: None,
+        created_at: SystemTime::now(),
+    };
+    let payment_id = PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B");
+
+    assert_eq!(
+        PaymentRef::from(&created_payment),
+        PaymentRef::from(&payment_id)
+    );
+    assert_eq!(
+        PaymentRef::from(&created_payment.id).as_str(),
+        "AB23D7406ECE4542A80152D909EF9F6B"
+    );
+}
+
+#[test]
+fn test_created_payments_expire() {
+    let now = SystemTime::now();
+    let created_at = now - Duration::from_secs(4 * 60);
+    let created_payment = CreatedPayment {
+        id: "AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        location: "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/paymentrequests/AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        request_token: None,
+        created_at: now,
+    };
+    assert!(!created_payment.is_expired_at(now));
+
+    let created_payment = CreatedPayment {
+        created_at,
+        ..created_payment
+    };
+    assert!(created_payment.is_expired_at(now));
+    assert_eq!(
+        created_payment.expires_at(),
+        created_at + PAYMENT_REQUEST_TTL
+    );
+}
+
+#[test]
+fn test_new_statuses_are_parsed() {
+    let declined: Payment =
+        serde_json::from_str(&PAYMENT.replace(r#""PAID""#, r#""DECLINED""#)).unwrap();
+    let settled: Payment =
+        serde_json::from_str(&PAYMENT.replace(r#""PAID""#, r#""SETTLED""#)).unwrap();
+
+    assert_eq!(declined.status, Some(Status::Declined));
+    assert_eq!(settled.status, Some(Status::Unknown("SETTLED".to_owned())));
+    assert_eq!(serde_json::to_value(&settled).unwrap()["status"], "SETTLED");
+    assert_eq!("debited".parse::<Status>(), Ok(Status::Debited));
+}
+
+#[test]
+fn test_unsupported_currencies_are_parsed() {
+    let payment: Payment = serde_json::from_str(&PAYMENT.replace(r#""SEK""#, r#""EUR""#)).unwrap();
+
+    assert_eq!(payment.currency, Currency::Other("EUR".to_owned()));
+    assert_eq!(serde_json::to_value(&payment).unwrap()["currency"], "EUR");
+}
+
+#[test]
+fn test_fetched_error_codes_are_parsed() {
+    let payment: Payment = serde_json::from_str(
+        &PAYMENT
+            .replace(r#""PAID""#, r#""ERROR""#)
+            .replace(r#""errorCode": null"#, r#""errorCode": "TM01""#),
+    )
+    .unwrap();
+    let refund: Refund =
+        serde_json::from_str(&REFUND.replace(r#""errorCode": null"#, r#""errorCode": "XX99""#))
+            .unwrap();
+
+    assert_eq!(payment.error_code, Some(ErrorCode::TM01));
+    assert_eq!(
+        refund.error_code,
+        Some(ErrorCode::Unknown("XX99".to_owned()))
+    );
+    assert_eq!(serde_json::to_value(&refund).unwrap()["errorCode"], "XX99");
+}
+
+#[test]
+fn test_payment_callback_with_an_error_is_parsed() {
+    let body = PAYMENT
+        .replace(r#""PAID""#, r#""ERROR""#)
+        .replace(r#""errorCode": null"#, r#""errorCode": "TM01""#);
+
+    let callback = PaymentCallback::from_slice(body.as_bytes()).unwrap();
+
+    assert_eq!(callback.status, Status::Error);
+    assert_eq!(callback.error_code, Some(ErrorCode::TM01));
+    assert_eq!(callback.amount, Amount::from_kronor(100));
+    assert_eq!(callback.extra["settlementDate"], "2015-02-20");
+}
+
+#[test]
+fn test_refund_callback_is_parsed() {
+    let body = REFUND
+        .replace("originalPaymentReference", "originalpaymentReference")
+        .replace(r#""PAID""#, r#""DEBITED""#);
+
+    let callback = RefundCallback::from_slice(body.as_bytes()).unwrap();
+
+    assert_eq!(callback.status, Status::Debited);
+    assert_eq!(
+        callback.original_payment_reference.as_deref(),
+        Some("6D6CD7406ECE4542A80152D909EF9F6B")
+    );
+    assert!(RefundCallback::from_slice(b"{}").is_err());
+}
+
+#[test]
+fn test_callbacks_are_told_apart() {
+    match Callback::from_slice(PAYMENT.as_bytes()).unwrap() {
+        Callback::Payment(callback) => assert_eq!(callback.status, Status::Paid),
+        callback => panic!("Unexpected callback {:?}", callback),
+    }
+    match Callback::from_slice(REFUND.as_bytes()).unwrap() {
+        Callback::Refund(callback) => assert_eq!(callback.payer_alias.as_deref(), Some("1231181189")),
+        callback => panic!("Unexpected callback {:?}", callback),
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_refund_eligibility_is_explained() {
+    use chrono::{TimeZone, Utc};
+    use swish_api::eligibility::{self, RefundIneligibility};
+
+    let payment: Payment = serde_json::from_str(PAYMENT).unwrap();
+    let paid_at = Utc.with_ymd_and_hms(2015, 2, 19, 21, 3, 53).unwrap();
+    let in_a_year = Utc.with_ymd_and_hms(2016, 2, 19, 0, 0, 0).unwrap();
+
+    assert_eq!(
+        eligibility::check_refund_at(
+            &payment,
+            Amount::from_kronor(60),
+            Amount::from_kronor(40),
+            in_a_year
+        ),
+        Ok(())
+    );
+
+    let reasons = eligibility::check_refund_at(
+        &payment,
+        Amount::from_kronor(60),
+        Amount::from_kronor(50),
+        Utc.with_ymd_and_hms(2016, 3, 20, 0, 0, 0).unwrap(),
+    )
+    .unwrap_err();
+    assert_eq!(reasons.len(), 2);
+    match reasons[0] {
+        RefundIneligibility::TooOld { refundable_until } => {
+            assert_eq!(refundable_until, paid_at + chrono::Months::new(13));
+            assert_eq!(reasons[0].error_code(), Some(ErrorCode::RF02));
+        }
+        ref reason => panic!("Unexpected reason {:?}", reason),
+    }
+    assert_eq!(
+        reasons[1],
+        RefundIneligibility::ExceedsRemaining {
+            requested: Amount::from_kronor(60),
+            remaining: Amount::from_kronor(50),
+        }
+    );
+}
+
+#[test]
+fn test_created_payments_keep_their_creation_time_in_json() {
+    let created_at = SystemTime::now() - Duration::from_secs(4 * 60);
+    let created_payment = CreatedPayment {
+        id: "AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        location: "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/paymentrequests/AB23D7406ECE4542A80152D909EF9F6B".to_owned(),
+        request_token: None,
+        created_at,
+    };
+
+    let stored = serde_json::to_string(&created_payment).unwrap();
+    let restored: CreatedPayment = serde_json::from_str(&stored).unwrap();
+    assert_eq!(restored.created_at, created_at);
+    assert!(restored.is_expired_at(SystemTime::now()));
+
+    let without_created_at = r#"{
+        "id": "AB23D7406ECE4542A80152D909EF9F6B",
+        "location": "https://mss.cpc.getswish.net/swish-cpcapi/api/v1/paymentrequests/AB23D7406ECE4542A80152D909EF9F6B",
+        "request_token": null
+    }"#;
+    assert!(serde_json::from_str::<CreatedPayment>(without_created_at).is_err());
+}
@@ -0,0 +1,55 @@
+extern crate swish_api;
+
+use swish_api::amount::Amount;
+use swish_api::client::{PaymentParams, RefundParams, Status};
+use swish_api::error::ErrorCode;
+use swish_api::mss::Simulation;
+
+#[test]
+fn test_simulations_are_configured_by_message() {
+    let mut params = PaymentParams::default();
+    params.amount = Amount::from_kronor(100);
+    params.message = Some("Kingston USB Flash Drive 8 GB");
+
+    assert_eq!(
+        Simulation::Paid.payment_params(params.clone()).message,
+        None
+    );
+    assert_eq!(
+        Simulation::Declined.payment_params(params.clone()).message,
+        Some("DECLINED")
+    );
+    let simulation = Simulation::Error(ErrorCode::RF07);
+    assert_eq!(
+        simulation.refund_params(RefundParams::default()).message,
+        Some("RF07")
+    );
+}
+
+#[test]
+fn test_simulations_are_parsed_from_messages() {
+    for simulation in &[
+        Simulation::Paid,
+        Simulation::Declined,
+        Simulation::Error(ErrorCode::BANKIDCL),
+        Simulation::Error(ErrorCode::ACMT03),
+    ] {
+        assert_eq!(Simulation::from_message(simulation.message()), *simulation);
+    }
+    assert_eq!(
+        Simulation::from_message(Some("Kingston USB Flash Drive 8 GB")),
+        Simulation::Paid
+    );
+}
+
+#[test]
+fn test_simulated_errors_are_rejected_or_fail_later() {
+    assert!(Simulation::Error(ErrorCode::FF08).is_rejected());
+    assert_eq!(Simulation::Error(ErrorCode::FF08).final_status(), None);
+    assert!(!Simulation::Error(ErrorCode::TM01).is_rejected());
+    assert_eq!(
+        Simulation::Error(ErrorCode::TM01).final_status(),
+        Some(Status::Error)
+    );
+    assert_eq!(Simulation::Declined.final_status(), Some(Status::Declined));
+}
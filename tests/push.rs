@@ -0,0 +1,55 @@
+extern crate futures;
+extern crate swish_api;
+
+use futures::Stream;
+use swish_api::amount::Amount;
+use swish_api::error::ErrorCode;
+use swish_api::event::PaymentEvent;
+use swish_api::ids::{PaymentId, RefundId};
+use swish_api::push::{StatusBridge, StatusFrame};
+use swish_api::watcher::PaymentWatcher;
+
+#[test]
+fn test_events_become_frames() {
+    let paid = StatusFrame::from(&PaymentEvent::Paid {
+        payment_id: PaymentId::from("111"),
+        payment_reference: Some("6D6CD7406ECE4542A80152D909EF9F6B".to_owned()),
+        amount: Amount::from_kronor(100),
+        date_paid: None,
+    });
+    assert_eq!(
+        paid.to_json(),
+        r#"{"kind":"payment","id":"111","status":"PAID","final":true,"amount":100.0}"#
+    );
+
+    let refund = StatusFrame::from(&PaymentEvent::RefundErrored {
+        refund_id: RefundId::from("999"),
+        original_payment_reference: None,
+        error_code: Some(ErrorCode::RF07),
+        error_message: Some("Transaction declined".to_owned()),
+    });
+    assert_eq!(
+        refund.to_sse(),
+        "event: refund\ndata: {\"kind\":\"refund\",\"id\":\"999\",\"status\":\"ERROR\",\
+         \"final\":true,\"errorCode\":\"RF07\",\"errorMessage\":\"Transaction declined\"}\n\n"
+    );
+}
+
+#[test]
+fn test_bridges_push_the_frames_of_a_payment() {
+    let watcher = PaymentWatcher::new();
+    let bridge = StatusBridge::new(watcher.clone());
+    watcher.notify(PaymentEvent::Created {
+        payment_id: PaymentId::from("111"),
+    });
+
+    let frames = bridge.frames("111");
+    let json = bridge.json("111");
+    watcher.notify(PaymentEvent::Expired {
+        payment_id: PaymentId::from("111"),
+    });
+
+    let statuses: Vec<_> = frames.wait().map(|frame| frame.unwrap().status).collect();
+    assert_eq!(statuses, vec!["CREATED", "EXPIRED"]);
+    assert_eq!(json.wait().count(), 2);
+}
@@ -0,0 +1,46 @@
+extern crate hyper;
+extern crate swish_api;
+
+use hyper::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use swish_api::redact;
+
+#[test]
+fn test_redact_body_masks_payer_alias() {
+    let body = r#"{"payerAlias":"46712345678","payeeAlias":"1231181189","amount":100}"#;
+    let redacted = redact::redact_body(body);
+
+    assert!(!redacted.contains("46712345678"));
+    assert!(redacted.contains("*********78"));
+    assert!(redacted.contains("1231181189"));
+}
+
+#[test]
+fn test_redact_body_masks_nested_errors() {
+    let body = r#"[{"errorCode":"BE18","errorMessage":"Payer alias 46712345678 is invalid"}]"#;
+    let redacted = redact::redact_body(body);
+
+    assert!(!redacted.contains("46712345678"));
+    assert!(redacted.contains("BE18"));
+}
+
+#[test]
+fn test_redact_text_masks_long_digit_sequences() {
+    assert_eq!(
+        redact::redact_text("Payer 197001011234 not enrolled, code 03"),
+        "Payer **********34 not enrolled, code 03"
+    );
+}
+
+#[test]
+fn test_redact_headers_masks_payment_request_token() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "paymentrequesttoken",
+        HeaderValue::from_static("c28a4061470f4af48973bd2a4642b4fa"),
+    );
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let redacted = redact::redact_headers(&headers);
+
+    assert!(!redacted.contains("c28a4061470f4af48973bd2a4642b4fa"));
+    assert!(redacted.contains("content-type: application/json"));
+}
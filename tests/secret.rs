@@ -0,0 +1,29 @@
+extern crate swish_api;
+
+use std::io;
+use swish_api::secret::{FileSecretProvider, SecretProvider, SecretString};
+
+#[test]
+fn test_secret_string_is_redacted_from_debug() {
+    let secret = SecretString::from("swish");
+
+    assert_eq!(format!("{:?}", secret), "SecretString([REDACTED])");
+    assert_eq!(secret.expose_secret(), "swish");
+}
+
+#[test]
+fn test_file_secret_provider_reports_missing_certificate_path() {
+    let provider = FileSecretProvider::new("./tests/missing_cert.p12", "swish");
+    let error = provider.certificate().unwrap_err();
+
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    assert!(error.to_string().contains("./tests/missing_cert.p12"));
+}
+
+#[test]
+fn test_file_secret_provider_reads_certificate() {
+    let provider = FileSecretProvider::new("./tests/test_cert.p12", "swish");
+    let certificate = provider.certificate().unwrap();
+
+    assert!(!certificate.is_empty());
+}
@@ -0,0 +1,187 @@
+#![cfg(feature = "simulator")]
+extern crate futures;
+extern crate native_tls;
+extern crate swish_api;
+extern crate tokio_core;
+
+use futures::Future;
+use native_tls::TlsConnector;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+use swish_api::alias::{MerchantAlias, PhoneAlias};
+use swish_api::amount::Amount;
+use swish_api::callback::{Callback, CallbackUrl};
+use swish_api::client::{PaymentParams, RefundParams, Status, SwishClient, SwishClientBuilder};
+use swish_api::error::{ErrorCode, SwishClientError};
+use swish_api::mss::Simulation;
+use swish_api::simulator::SwishSimulator;
+use tokio_core::reactor::Core;
+
+/// Starts a simulator on a thread of its own, which settles after `settle_after`
+/// and delivers its callbacks to the returned receiver.
+fn start_simulator(settle_after: Duration) -> (String, Receiver<Callback>) {
+    let callbacks = TcpListener::bind("127.0.0.1:0").unwrap();
+    let callback_uri = format!("http://{}/callbacks", callbacks.local_addr().unwrap());
+    let (callback_sender, callback_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in callbacks.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = vec![0; 8192];
+            let read = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..read]).into_owned();
+            let body = request.split("\r\n\r\n").nth(1).unwrap_or("");
+            let _ = callback_sender.send(Callback::from_slice(body.as_bytes()).unwrap());
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+        }
+    });
+
+    let (url_sender, url_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut core = Core::new().unwrap();
+        let simulator = SwishSimulator::bind(&"127.0.0.1:0".parse().unwrap(), &core.handle())
+            .unwrap()
+            .settle_after(settle_after)
+            .deliver_callbacks_to(callback_uri.parse().unwrap());
+        url_sender.send(simulator.api_url().unwrap()).unwrap();
+        core.run(simulator.serve()).unwrap();
+    });
+
+    (url_receiver.recv().unwrap(), callback_receiver)
+}
+
+fn get_client(core: &Core, api_url: &str) -> SwishClient {
+    let merchant_alias = MerchantAlias::new("1231181189").unwrap();
+    SwishClientBuilder::new(merchant_alias, "", "", core.handle())
+        .tls_connector(TlsConnector::new().unwrap())
+        .api_url(api_url)
+        .default_callback_url(
+            CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").unwrap(),
+        )
+        .build()
+}
+
+fn get_payment_params<'a>() -> PaymentParams<'a> {
+    let mut params = PaymentParams::default();
+    params.amount = Amount::from_kronor(100);
+    params.payee_payment_reference = Some("0123456789");
+    params.message = Some("Kingston USB Flash Drive 8 GB");
+    params
+}
+
+#[test]
+fn test_payments_are_paid_once_settled() {
+    let (api_url, callbacks) = start_simulator(Duration::from_millis(200));
+    let mut core = Core::new().unwrap();
+    let client = get_client(&core, &api_url);
+
+    let created = core
+        .run(client.create_ecommerce_payment(
+            PhoneAlias::new("46712345678").unwrap(),
+            get_payment_params(),
+        ))
+        .unwrap();
+    let payment = core.run(client.get_payment(&created)).unwrap();
+    assert_eq!(payment.status, Some(Status::Created));
+    assert_eq!(payment.amount, Amount::from_kronor(100));
+
+    let callback = callbacks.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(callback.id(), created.id);
+    let payment = core.run(client.get_payment(&created)).unwrap();
+    assert_eq!(payment.status, Some(Status::Paid));
+    let payment_reference = payment.payment_reference.unwrap();
+
+    let mut refund_params = RefundParams::default();
+    refund_params.original_payment_reference = &payment_reference;
+    refund_params.payee_alias = "46712345678";
+    refund_params.amount = Amount::from_kronor(60);
+    let refund = core
+        .run(client.create_refund(refund_params.clone()))
+        .unwrap();
+    match core.run(client.create_refund(refund_params)) {
+        Err(ref error) => assert_eq!(error.swish_error_codes(), vec![&ErrorCode::RF08]),
+        other => panic!("Expected an over-refund to fail, got {:?}", other),
+    }
+
+    match callbacks.recv_timeout(Duration::from_secs(5)).unwrap() {
+        Callback::Refund(callback) => {
+            assert_eq!(callback.id, refund.id);
+            assert_eq!(callback.status, Status::Paid);
+        }
+        other => panic!("Expected a refund callback, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_mcommerce_payments_can_be_cancelled() {
+    let (api_url, callbacks) = start_simulator(Duration::from_millis(200));
+    let mut core = Core::new().unwrap();
+    let client = get_client(&core, &api_url);
+
+    let created = core
+        .run(client.create_mcommerce_payment(get_payment_params()))
+        .unwrap();
+    let cancelled = core.run(client.cancel_payment(&created)).unwrap();
+
+    assert_eq!(cancelled.status, Some(Status::Cancelled));
+    assert!(callbacks.recv_timeout(Duration::from_millis(500)).is_err());
+}
+
+#[test]
+fn test_invalid_requests_are_rejected_like_swish_does() {
+    let (api_url, _) = start_simulator(Duration::from_secs(60));
+    let mut core = Core::new().unwrap();
+    let client = get_client(&core, &api_url);
+    let payer_alias = PhoneAlias::new("46712345678").unwrap();
+
+    core.run(client.create_ecommerce_payment(payer_alias.clone(), get_payment_params()))
+        .unwrap();
+    let second = core.run(client.create_ecommerce_payment(payer_alias, get_payment_params()));
+    assert_eq!(
+        second.unwrap_err().swish_error_codes(),
+        vec![&ErrorCode::RP06]
+    );
+
+    match core.run(client.get_refund("111").map(|_| ())) {
+        Err(SwishClientError::NotFound { .. }) => (),
+        other => panic!("Expected the refund not to be found, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_simulations_are_honored() {
+    let (api_url, callbacks) = start_simulator(Duration::from_millis(100));
+    let mut core = Core::new().unwrap();
+    let client = get_client(&core, &api_url);
+
+    let declined = Simulation::Declined;
+    let created = core
+        .run(client.create_mcommerce_payment(declined.payment_params(get_payment_params())))
+        .unwrap();
+    match callbacks.recv_timeout(Duration::from_secs(5)).unwrap() {
+        Callback::Payment(callback) => {
+            assert_eq!(callback.id, created.id);
+            assert_eq!(Some(callback.status), declined.final_status());
+        }
+        other => panic!("Expected a payment callback, got {:?}", other),
+    }
+
+    let timed_out = Simulation::Error(ErrorCode::TM01);
+    let created = core
+        .run(client.create_mcommerce_payment(timed_out.payment_params(get_payment_params())))
+        .unwrap();
+    callbacks.recv_timeout(Duration::from_secs(5)).unwrap();
+    let payment = core.run(client.get_payment(&created)).unwrap();
+    assert_eq!(payment.status, timed_out.final_status());
+    assert_eq!(payment.error_code, Some(ErrorCode::TM01));
+
+    let rejected = Simulation::Error(ErrorCode::ACMT03);
+    let created =
+        core.run(client.create_mcommerce_payment(rejected.payment_params(get_payment_params())));
+    assert_eq!(
+        created.unwrap_err().swish_error_codes(),
+        vec![&ErrorCode::ACMT03]
+    );
+}
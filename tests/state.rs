@@ -0,0 +1,86 @@
+extern crate swish_api;
+
+use swish_api::error::Resource;
+use swish_api::event::PaymentEvent;
+use swish_api::ids::{PaymentId, RefundId};
+use swish_api::state::{PaymentState, RefundState};
+
+#[test]
+fn test_payments_only_move_forward() {
+    for to in &[
+        PaymentState::Paid,
+        PaymentState::Declined,
+        PaymentState::Cancelled,
+        PaymentState::Error,
+        PaymentState::Expired,
+    ] {
+        assert_eq!(PaymentState::Created.transition(*to), Ok(*to));
+    }
+
+    assert_eq!(
+        PaymentState::Paid.transition(PaymentState::Paid),
+        Ok(PaymentState::Paid)
+    );
+    assert_eq!(
+        PaymentState::Expired.transition(PaymentState::Paid),
+        Ok(PaymentState::Paid)
+    );
+
+    let error = PaymentState::Paid
+        .transition(PaymentState::Created)
+        .unwrap_err();
+    assert_eq!(error.resource, Resource::Payment);
+    assert_eq!(
+        error.to_string(),
+        "a payment can't move from PAID to CREATED"
+    );
+    assert!(PaymentState::Cancelled
+        .transition(PaymentState::Paid)
+        .is_err());
+    assert!(PaymentState::Expired
+        .transition(PaymentState::Created)
+        .is_err());
+    assert!(PaymentState::Expired.is_final());
+    assert!(!PaymentState::Created.is_final());
+}
+
+#[test]
+fn test_refunds_only_move_forward() {
+    let state = RefundState::Initiated
+        .transition(RefundState::Debited)
+        .and_then(|state| state.transition(RefundState::Paid));
+    assert_eq!(state, Ok(RefundState::Paid));
+
+    assert_eq!(
+        RefundState::Initiated.transition(RefundState::Paid),
+        Ok(RefundState::Paid)
+    );
+    assert!(RefundState::Debited
+        .transition(RefundState::Initiated)
+        .is_err());
+    assert!(RefundState::Paid.transition(RefundState::Error).is_err());
+}
+
+#[test]
+fn test_events_are_applied() {
+    let payment_id = PaymentId::from("AB23D7406ECE4542A80152D909EF9F6B");
+    let state = PaymentState::Created.apply(&PaymentEvent::Declined {
+        payment_id: payment_id.clone(),
+    });
+    assert_eq!(state, Ok(PaymentState::Declined));
+
+    let refund = PaymentEvent::RefundDebited {
+        refund_id: RefundId::from("ABC2D7406ECE4542A80152D909EF9F6B"),
+        original_payment_reference: None,
+    };
+    assert_eq!(
+        RefundState::Initiated.apply(&refund),
+        Ok(RefundState::Debited)
+    );
+
+    let error = PaymentState::Created.apply(&refund).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "a payment can't move from CREATED to REFUND DEBITED"
+    );
+}
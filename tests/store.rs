@@ -0,0 +1,315 @@
+extern crate futures;
+extern crate swish_api;
+
+use futures::Stream;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use swish_api::alias::PhoneAlias;
+use swish_api::amount::Amount;
+use swish_api::event::PaymentEvent;
+use swish_api::ids::{PaymentId, RefundId};
+use swish_api::polling::PaymentPoller;
+use swish_api::state::{PaymentState, RefundState};
+use swish_api::store::{InMemoryStore, StoreError, StoredPayment, StoredRefund, SwishStore};
+use swish_api::watcher::PaymentWatcher;
+
+fn payment(id: &str, reference: &str, age: u64) -> StoredPayment {
+    StoredPayment {
+        id: PaymentId::from(id),
+        payee_payment_reference: Some(reference.to_owned()),
+        payment_reference: None,
+        payer_alias: None,
+        amount: Amount::from_kronor(100),
+        state: PaymentState::Created,
+        created_at: SystemTime::now() - Duration::from_secs(age),
+    }
+}
+
+fn cancelled(id: &str) -> PaymentEvent {
+    PaymentEvent::Cancelled {
+        payment_id: PaymentId::from(id),
+    }
+}
+
+#[test]
+fn test_payments_are_stored() {
+    let store = InMemoryStore::new();
+    store.save_payment(payment("111", "order-1", 10)).unwrap();
+    store.save_payment(payment("222", "order-2", 20)).unwrap();
+    store.save_payment(payment("333", "order-3", 5)).unwrap();
+    store.save_payment(payment("555", "order-5", 200)).unwrap();
+
+    store.update_status(&cancelled("333")).unwrap();
+    store
+        .update_status(&PaymentEvent::Expired {
+            payment_id: PaymentId::from("555"),
+        })
+        .unwrap();
+
+    let found = store.find_by_payee_reference("order-1").unwrap().unwrap();
+    assert_eq!(found.id.as_str(), "111");
+    assert!(store.find_by_payee_reference("order-4").unwrap().is_none());
+
+    let pending: Vec<_> = store
+        .pending_payments()
+        .unwrap()
+        .into_iter()
+        .map(|payment| payment.id)
+        .collect();
+    assert_eq!(
+        pending,
+        vec![PaymentId::from("222"), PaymentId::from("111")]
+    );
+
+    match store.update_status(&PaymentEvent::Created {
+        payment_id: PaymentId::from("333"),
+    }) {
+        Err(StoreError::Transition(_)) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+    match store.update_status(&cancelled("444")) {
+        Err(StoreError::NotFound(id)) => assert_eq!(id, "444"),
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_active_payments_are_found_by_payer() {
+    let payer = PhoneAlias::new("46701234567").unwrap();
+    let store = InMemoryStore::new();
+    for (id, age) in &[("111", 30), ("222", 10), ("333", 600)] {
+        store
+            .save_payment(StoredPayment {
+                payer_alias: Some(payer.clone()),
+                ..payment(id, id, *age)
+            })
+            .unwrap();
+    }
+    store.save_payment(payment("444", "444", 0)).unwrap();
+    let now = SystemTime::now();
+
+    let active = store.active_payment_for(&payer, now).unwrap().unwrap();
+    assert_eq!(active.id.as_str(), "222");
+    let later = now + Duration::from_secs(160);
+    let active = store.active_payment_for(&payer, later).unwrap().unwrap();
+    assert_eq!(active.id.as_str(), "222");
+    let expired = now + Duration::from_secs(171);
+    assert!(store.active_payment_for(&payer, expired).unwrap().is_none());
+
+    store.update_status(&cancelled("222")).unwrap();
+    let active = store.active_payment_for(&payer, now).unwrap().unwrap();
+    assert_eq!(active.id.as_str(), "111");
+
+    store.update_status(&cancelled("111")).unwrap();
+    assert!(store.active_payment_for(&payer, now).unwrap().is_none());
+}
+
+#[test]
+fn test_refunds_are_stored() {
+    let store = InMemoryStore::new();
+    store
+        .save_refund(refund("999", 50, RefundState::Initiated))
+        .unwrap();
+
+    store
+        .update_status(&PaymentEvent::RefundDebited {
+            refund_id: RefundId::from("999"),
+            original_payment_reference: None,
+        })
+        .unwrap();
+
+    let pending = store.pending_refunds().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].state, RefundState::Debited);
+}
+
+fn refund(id: &str, kronor: i64, state: RefundState) -> StoredRefund {
+    StoredRefund {
+        id: RefundId::from(id),
+        original_payment_reference: "6D6CD7406ECE4542A80152D909EF9F6B".to_owned(),
+        amount: Amount::from_kronor(kronor),
+        state,
+        created_at: SystemTime::now(),
+    }
+}
+
+#[test]
+fn test_refunds_are_counted_per_payment() {
+    let store = InMemoryStore::new();
+    store.save_payment(payment("111", "order-1", 0)).unwrap();
+    store
+        .update_status(&PaymentEvent::Paid {
+            payment_id: PaymentId::from("111"),
+            payment_reference: Some("6D6CD7406ECE4542A80152D909EF9F6B".to_owned()),
+            amount: Amount::from_kronor(100),
+            date_paid: None,
+        })
+        .unwrap();
+    let paid = store
+        .find_by_payment_reference("6D6CD7406ECE4542A80152D909EF9F6B")
+        .unwrap()
+        .unwrap();
+    assert_eq!(paid.id.as_str(), "111");
+
+    store
+        .save_refund(refund("901", 30, RefundState::Paid))
+        .unwrap();
+    store
+        .save_refund(refund("902", 20, RefundState::Initiated))
+        .unwrap();
+    store
+        .save_refund(refund("903", 40, RefundState::Error))
+        .unwrap();
+
+    assert_eq!(store.refunded(&paid).unwrap(), Amount::from_kronor(50));
+    assert_eq!(
+        store.remaining_refundable(&paid).unwrap(),
+        Amount::from_kronor(50)
+    );
+    assert!(store.check_refund(&paid, Amount::from_kronor(50)).is_ok());
+    match store.check_refund(&paid, Amount::from_kronor(51)) {
+        Err(StoreError::OverRefund {
+            requested,
+            remaining,
+            ..
+        }) => {
+            assert_eq!(requested, Amount::from_kronor(51));
+            assert_eq!(remaining, Amount::from_kronor(50));
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+/// A store that answers the queries itself, like a database would in SQL,
+/// here with fixed answers.
+#[derive(Debug)]
+struct QueryingStore(InMemoryStore);
+
+impl SwishStore for QueryingStore {
+    fn save_payment(&self, payment: StoredPayment) -> Result<(), StoreError> {
+        self.0.save_payment(payment)
+    }
+
+    fn save_refund(&self, refund: StoredRefund) -> Result<(), StoreError> {
+        self.0.save_refund(refund)
+    }
+
+    fn update_status(&self, event: &PaymentEvent) -> Result<(), StoreError> {
+        self.0.update_status(event)
+    }
+
+    fn find_by_payee_reference(
+        &self,
+        reference: &str,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        self.0.find_by_payee_reference(reference)
+    }
+
+    fn find_by_payment_reference(
+        &self,
+        reference: &str,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        self.0.find_by_payment_reference(reference)
+    }
+
+    fn refunds_of(
+        &self,
+        original_payment_reference: &str,
+    ) -> Result<Vec<StoredRefund>, StoreError> {
+        self.0.refunds_of(original_payment_reference)
+    }
+
+    fn pending_payments(&self) -> Result<Vec<StoredPayment>, StoreError> {
+        self.0.pending_payments()
+    }
+
+    fn pending_refunds(&self) -> Result<Vec<StoredRefund>, StoreError> {
+        self.0.pending_refunds()
+    }
+
+    fn active_payment_for(
+        &self,
+        _payer_alias: &PhoneAlias,
+        _now: SystemTime,
+    ) -> Result<Option<StoredPayment>, StoreError> {
+        Ok(Some(payment("555", "active", 0)))
+    }
+
+    fn refunded(&self, _payment: &StoredPayment) -> Result<Amount, StoreError> {
+        Ok(Amount::from_kronor(80))
+    }
+
+    fn remaining_refundable(&self, _payment: &StoredPayment) -> Result<Amount, StoreError> {
+        Ok(Amount::from_kronor(20))
+    }
+
+    fn check_refund(&self, payment: &StoredPayment, amount: Amount) -> Result<(), StoreError> {
+        Err(StoreError::OverRefund {
+            payment_reference: payment.payment_reference.clone().unwrap_or_default(),
+            requested: amount,
+            remaining: Amount::from_kronor(20),
+        })
+    }
+}
+
+#[test]
+fn test_shared_stores_count_the_refunds_like_the_store() {
+    let store: Arc<dyn SwishStore> = Arc::new(QueryingStore(InMemoryStore::new()));
+    let paid = payment("111", "order-1", 0);
+
+    assert_eq!(store.refunded(&paid).unwrap(), Amount::from_kronor(80));
+    assert_eq!(
+        store.remaining_refundable(&paid).unwrap(),
+        Amount::from_kronor(20)
+    );
+    assert!(store.check_refund(&paid, Amount::from_kronor(10)).is_err());
+}
+
+#[test]
+fn test_shared_stores_find_the_active_payments_like_the_store() {
+    let store: Arc<dyn SwishStore> = Arc::new(QueryingStore(InMemoryStore::new()));
+    let payer = PhoneAlias::new("46701234567").unwrap();
+
+    let active = store
+        .active_payment_for(&payer, SystemTime::now())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(active.id.as_str(), "555");
+}
+
+#[test]
+fn test_watchers_update_the_store() {
+    let store = Arc::new(InMemoryStore::new());
+    store.save_payment(payment("111", "order-1", 0)).unwrap();
+    let watcher = PaymentWatcher::new().store(store.clone());
+    let events = watcher.watch("111");
+
+    watcher.notify(cancelled("111"));
+    watcher.notify(PaymentEvent::Created {
+        payment_id: PaymentId::from("111"),
+    });
+
+    assert_eq!(events.wait().count(), 1);
+    assert_eq!(watcher.latest("111"), Some(cancelled("111")));
+    let stored = store.find_by_payee_reference("order-1").unwrap().unwrap();
+    assert_eq!(stored.state, PaymentState::Cancelled);
+}
+
+#[test]
+fn test_pollers_resume_the_pending_payments() {
+    let store = InMemoryStore::new();
+    store.save_payment(payment("111", "order-1", 0)).unwrap();
+    store.save_payment(payment("222", "order-2", 0)).unwrap();
+    store.update_status(&cancelled("222")).unwrap();
+    store.save_payment(payment("333", "order-3", 200)).unwrap();
+    store
+        .update_status(&PaymentEvent::Expired {
+            payment_id: PaymentId::from("333"),
+        })
+        .unwrap();
+
+    let poller = PaymentPoller::new(PaymentWatcher::new());
+    assert_eq!(poller.resume(&store).unwrap(), 1);
+    assert_eq!(poller.pending(), 1);
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "test-utils")]
+extern crate serde_json;
+extern crate swish_api;
+
+use std::time::SystemTime;
+use swish_api::callback::{Callback, PaymentCallback, RefundCallback};
+use swish_api::client::{Payment, Refund, Status};
+use swish_api::error::ErrorCode;
+use swish_api::event::PaymentEvent;
+use swish_api::test_utils::{
+    CreatedPaymentFixture, PaymentFixture, RefundFixture, PAYMENT_STATUSES, REFUND_STATUSES,
+};
+
+#[test]
+fn test_payment_fixtures_parse_like_swish_responses() {
+    for status in PAYMENT_STATUSES.iter() {
+        let fixture = PaymentFixture::new(status.clone());
+
+        let payment: Payment = serde_json::from_str(&fixture.to_json()).unwrap();
+        let callback = PaymentCallback::from_slice(&fixture.callback_body()).unwrap();
+
+        assert_eq!(payment, fixture.payment());
+        assert_eq!(callback, fixture.callback());
+        assert_eq!(PaymentEvent::from(&payment), PaymentEvent::from(&callback));
+    }
+}
+
+#[test]
+fn test_refund_fixtures_parse_like_swish_responses() {
+    for status in REFUND_STATUSES.iter() {
+        let fixture = RefundFixture::new(status.clone());
+
+        let refund: Refund = serde_json::from_str(&fixture.to_json()).unwrap();
+        let callback = Callback::from_slice(&fixture.callback_body()).unwrap();
+
+        assert_eq!(refund, fixture.refund());
+        assert_eq!(callback, Callback::Refund(fixture.callback()));
+        assert_eq!(
+            PaymentEvent::from(&refund),
+            PaymentEvent::from(&RefundCallback::from_slice(&fixture.callback_body()).unwrap())
+        );
+    }
+}
+
+#[test]
+fn test_fixtures_are_filled_in_by_status() {
+    let paid = PaymentFixture::new(Status::Paid).payment();
+    assert!(paid.payment_reference.is_some());
+    assert!(paid.date_paid.is_some());
+
+    let failed = PaymentFixture::new(Status::Error)
+        .mcommerce()
+        .error(ErrorCode::BANKIDCL, "Payer cancelled BankId signing")
+        .callback();
+    assert_eq!(failed.error_code, Some(ErrorCode::BANKIDCL));
+    assert!(failed.payer_alias.is_none());
+    assert!(failed.date_paid.is_none());
+
+    let refund = RefundFixture::new(Status::Error).refund();
+    assert_eq!(refund.error_code, Some(ErrorCode::RF07));
+}
+
+#[test]
+fn test_created_payment_fixtures() {
+    let created = CreatedPaymentFixture::new().id("111").created_payment();
+    assert!(created.location.ends_with("/paymentrequests/111"));
+    assert!(!created.is_expired_at(SystemTime::now()));
+
+    let expired = CreatedPaymentFixture::new()
+        .expired()
+        .created_mcommerce_payment();
+    assert!(expired.is_expired_at(SystemTime::now()));
+    assert!(!expired.request_token.as_str().is_empty());
+}
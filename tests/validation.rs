@@ -0,0 +1,210 @@
+extern crate swish_api;
+
+use swish_api::alias::{MerchantAlias, PhoneAlias};
+use swish_api::amount::Amount;
+use swish_api::callback::CallbackUrl;
+use swish_api::client::{PaymentParams, RefundParams};
+use swish_api::ids::InstructionUuid;
+use swish_api::validation::{self, ValidationError};
+
+#[test]
+fn test_generated_instruction_uuid_is_valid() {
+    let generated = InstructionUuid::new();
+
+    assert_eq!(InstructionUuid::parse(generated.as_str()), Ok(generated));
+}
+
+#[test]
+fn test_instruction_uuid_rejects_lowercase_and_dashes() {
+    let lowercase = InstructionUuid::parse("11a86be70ea346e4b1c39c874173f088");
+    let dashed = InstructionUuid::parse("11A86BE7-0EA3-46E4-B1C3-9C874173F088");
+
+    assert!(dashed.is_err());
+    match lowercase {
+        Err(ValidationError::InvalidFormat { field, .. }) => assert_eq!(field, "instructionUUID"),
+        other => panic!("Expected an invalid format, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_phone_alias_is_normalized() {
+    let aliases = [
+        "0701234567",
+        "070-123 45 67",
+        "+46701234567",
+        "0046701234567",
+    ];
+
+    for alias in &aliases {
+        assert_eq!(PhoneAlias::new(alias).unwrap().as_str(), "46701234567");
+    }
+}
+
+#[test]
+fn test_phone_alias_rejects_invalid_numbers() {
+    assert!(PhoneAlias::new("").is_err());
+    assert!(PhoneAlias::new("0701").is_err());
+    assert!(PhoneAlias::new("46 70 123 45 67 89 012").is_err());
+    assert_eq!(
+        PhoneAlias::new("0701abc567").unwrap_err().field(),
+        "payerAlias"
+    );
+}
+
+#[test]
+fn test_merchant_alias_requires_a_swish_number() {
+    assert!(MerchantAlias::new("1231181189").is_ok());
+    assert!(MerchantAlias::new("123118118").is_err());
+    assert!(MerchantAlias::new("9871181189").is_err());
+    assert_eq!(
+        MerchantAlias::new("123 118 11 89").unwrap_err().field(),
+        "payeeAlias"
+    );
+}
+
+#[test]
+fn test_message_longer_than_50_characters_is_rejected() {
+    let message = "å".repeat(51);
+
+    assert!(validation::validate_message(&message[..100]).is_ok());
+    assert_eq!(
+        validation::validate_message(&message),
+        Err(ValidationError::TooLong {
+            field: "message",
+            max: 50,
+        })
+    );
+}
+
+#[test]
+fn test_payee_payment_reference_is_validated() {
+    assert!(validation::validate_payee_payment_reference("0123456789").is_ok());
+    assert_eq!(
+        validation::validate_payee_payment_reference(""),
+        Err(ValidationError::Empty {
+            field: "payeePaymentReference",
+        })
+    );
+    assert_eq!(
+        validation::validate_payee_payment_reference("order-1"),
+        Err(ValidationError::InvalidCharacter {
+            field: "payeePaymentReference",
+            character: '-',
+        })
+    );
+    assert!(validation::validate_payee_payment_reference(&"1".repeat(36)).is_err());
+}
+
+#[test]
+fn test_callback_url_must_be_https() {
+    assert!(CallbackUrl::new("https://example.com/api/swishcb/refunds").is_ok());
+    assert!(CallbackUrl::new("example.com/api/swishcb/refunds").is_err());
+    assert!(CallbackUrl::new("ftp://example.com/api/swishcb/refunds").is_err());
+    assert_eq!(
+        CallbackUrl::new(&format!("https://example.com/{}", "a".repeat(2048))),
+        Err(ValidationError::TooLong {
+            field: "callbackUrl",
+            max: 2048,
+        })
+    );
+}
+
+#[test]
+fn test_amount_must_be_within_the_limits() {
+    let too_small = validation::validate_amount(Amount::from_ore(99));
+
+    assert!(validation::validate_amount(Amount::from_kronor(1)).is_ok());
+    assert!(validation::validate_amount(Amount::from_ore(99_999_999_999_999)).is_ok());
+    assert!(validation::validate_amount(Amount::from_ore(100_000_000_000_000)).is_err());
+    assert_eq!(
+        too_small.unwrap_err().to_string(),
+        "amount must be between 1.00 and 999999999999.99 SEK"
+    );
+}
+
+#[test]
+fn test_validate_collects_every_error() {
+    let mut payment_params = PaymentParams::default();
+    payment_params.amount = Amount::from_kronor(100);
+    payment_params.payee_payment_reference = Some("");
+    payment_params.message = Some("Kingston USB Flash Drive 8 GB");
+    let refund_params = RefundParams::default();
+
+    let payment_fields: Vec<_> = payment_params
+        .validate()
+        .unwrap_err()
+        .iter()
+        .map(ValidationError::field)
+        .collect();
+    let refund_fields: Vec<_> = refund_params
+        .validate()
+        .unwrap_err()
+        .iter()
+        .map(ValidationError::field)
+        .collect();
+
+    assert_eq!(payment_fields, vec!["payeePaymentReference"]);
+    assert_eq!(refund_fields, vec!["originalPaymentReference", "amount"]);
+}
+
+#[test]
+fn test_payment_params_builder_validates_on_build() {
+    let callback_url = CallbackUrl::new("https://example.com/api/swishcb/paymentrequests").unwrap();
+    let payer_alias = PhoneAlias::new("0712345678").unwrap();
+
+    let payment_params = PaymentParams::builder()
+        .amount(Amount::from_kronor(100))
+        .callback_url(callback_url.clone())
+        .ecommerce(payer_alias.clone())
+        .build()
+        .unwrap();
+    let invalid = PaymentParams::builder()
+        .mcommerce()
+        .callback_url(callback_url)
+        .amount(Amount::from_kronor(100))
+        .message("100%")
+        .build();
+
+    assert_eq!(payment_params.payer_alias, Some(payer_alias));
+    assert_eq!(invalid.unwrap_err()[0].field(), "message");
+}
+
+#[test]
+fn test_params_builders_leave_the_callback_url_to_the_client() {
+    let payment_params = PaymentParams::builder()
+        .amount(Amount::from_kronor(100))
+        .mcommerce()
+        .build()
+        .unwrap();
+    let refund_params = RefundParams::builder()
+        .original_payment_reference("6D6CD7406ECE4542A80152D909EF9F6B")
+        .amount(Amount::from_kronor(100))
+        .build()
+        .unwrap();
+
+    assert_eq!(payment_params.callback_url, None);
+    assert_eq!(refund_params.callback_url, None);
+}
+
+#[test]
+fn test_refund_params_builder_validates_on_build() {
+    let callback_url = CallbackUrl::new("https://example.com/api/swishcb/refunds").unwrap();
+
+    let refund_params = RefundParams::builder()
+        .original_payment_reference("6D6CD7406ECE4542A80152D909EF9F6B")
+        .callback_url(callback_url.clone())
+        .amount(Amount::from_kronor(100))
+        .build()
+        .unwrap();
+    let invalid = RefundParams::builder()
+        .amount(Amount::from_ore(50))
+        .callback_url(callback_url)
+        .original_payment_reference("6D6CD7406ECE4542A80152D909EF9F6B")
+        .build();
+
+    assert_eq!(
+        refund_params.original_payment_reference,
+        "6D6CD7406ECE4542A80152D909EF9F6B"
+    );
+    assert_eq!(invalid.unwrap_err()[0].field(), "amount");
+}
@@ -0,0 +1,67 @@
+extern crate futures;
+extern crate swish_api;
+
+use futures::Stream;
+use swish_api::amount::Amount;
+use swish_api::event::PaymentEvent;
+use swish_api::ids::PaymentId;
+use swish_api::watcher::PaymentWatcher;
+
+fn created(id: &str) -> PaymentEvent {
+    PaymentEvent::Created {
+        payment_id: PaymentId::from(id),
+    }
+}
+
+fn paid(id: &str) -> PaymentEvent {
+    PaymentEvent::Paid {
+        payment_id: PaymentId::from(id),
+        payment_reference: None,
+        amount: Amount::from_kronor(100),
+        date_paid: None,
+    }
+}
+
+#[test]
+fn test_watchers_get_the_events_of_their_payment() {
+    let watcher = PaymentWatcher::new();
+    let first = watcher.watch("111");
+    let second = watcher.watch("111");
+    let other = watcher.watch("222");
+    assert_eq!(watcher.watched(), 2);
+
+    watcher.notify(created("111"));
+    watcher.notify(created("111"));
+    watcher.notify(paid("111"));
+
+    let expected = vec![Ok(created("111")), Ok(paid("111"))];
+    assert_eq!(first.wait().collect::<Vec<_>>(), expected);
+    assert_eq!(second.wait().collect::<Vec<_>>(), expected);
+    assert_eq!(watcher.watched(), 1);
+    drop(other);
+}
+
+#[test]
+fn test_late_watchers_get_the_latest_event() {
+    let watcher = PaymentWatcher::new();
+    watcher.notify(created("111"));
+    watcher.notify(paid("111"));
+
+    assert_eq!(
+        watcher.watch("111").wait().collect::<Vec<_>>(),
+        vec![Ok(paid("111"))]
+    );
+    assert_eq!(watcher.watched(), 0);
+}
+
+#[test]
+fn test_the_oldest_events_are_forgotten() {
+    let watcher = PaymentWatcher::with_capacity(1);
+    watcher.notify(paid("111"));
+    watcher.notify(paid("222"));
+
+    let events = watcher.watch("111");
+    watcher.notify(paid("111"));
+
+    assert_eq!(events.wait().collect::<Vec<_>>(), vec![Ok(paid("111"))]);
+}
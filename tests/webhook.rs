@@ -0,0 +1,78 @@
+#![cfg(feature = "test-utils")]
+extern crate futures;
+extern crate hyper;
+extern crate swish_api;
+extern crate tokio_core;
+
+use hyper::StatusCode;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+use swish_api::callback::Callback;
+use swish_api::client::Status;
+use swish_api::test_utils::{PaymentFixture, RefundFixture};
+use swish_api::webhook::WebhookHarness;
+use tokio_core::reactor::Core;
+
+/// Starts a callback handler responding with `status`, which hands
+/// the bodies of the callbacks it receives to the returned receiver.
+fn start_handler(status: &'static str) -> (String, Receiver<Vec<u8>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/callbacks", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = vec![0; 8192];
+            let read = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..read]).into_owned();
+            let body = request.split("\r\n\r\n").nth(1).unwrap_or("");
+            let _ = sender.send(body.as_bytes().to_vec());
+            let response = format!("HTTP/1.1 {}\r\ncontent-length: 0\r\n\r\n", status);
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (url, receiver)
+}
+
+#[test]
+fn test_callbacks_are_delivered_with_duplicates() {
+    let (url, bodies) = start_handler("200 OK");
+    let mut core = Core::new().unwrap();
+    let harness = WebhookHarness::new(&url, &core.handle())
+        .unwrap()
+        .duplicates(2);
+    let fixture = PaymentFixture::new(Status::Paid);
+
+    let statuses = core
+        .run(harness.deliver_callback(&Callback::Payment(fixture.callback())))
+        .unwrap();
+
+    assert_eq!(statuses, vec![StatusCode::OK; 3]);
+    for _ in 0..3 {
+        let body = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            Callback::from_slice(&body).unwrap(),
+            Callback::Payment(fixture.callback())
+        );
+    }
+}
+
+#[test]
+fn test_callbacks_are_delayed_and_report_failures() {
+    let (url, bodies) = start_handler("500 Internal Server Error");
+    let mut core = Core::new().unwrap();
+    let harness = WebhookHarness::new(&url, &core.handle())
+        .unwrap()
+        .delay(Duration::from_millis(200));
+    let body = RefundFixture::new(Status::Error).callback_body();
+
+    let started = Instant::now();
+    let statuses = core.run(harness.deliver(body.clone())).unwrap();
+
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert_eq!(statuses, vec![StatusCode::INTERNAL_SERVER_ERROR]);
+    assert_eq!(bodies.recv_timeout(Duration::from_secs(5)).unwrap(), body);
+}